@@ -1,3 +1,46 @@
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use std::path::Path;
+
 fn main() {
+    write_binary_manifest();
     tauri_build::build()
 }
+
+/// Bakes the SHA-256 of every sidecar bundled for this target into the app,
+/// so tampered or corrupted binaries can be refused at launch.
+fn write_binary_manifest() {
+    println!("cargo:rerun-if-changed=binaries");
+    let target = std::env::var("TARGET").unwrap_or_default();
+    let suffix = format!("-{}", target);
+
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir("binaries").into_iter().flatten().flatten() {
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let stem = file_name.strip_suffix(".exe").unwrap_or(&file_name);
+        let Some(name) = stem.strip_suffix(&suffix) else {
+            continue;
+        };
+        // Empty placeholders let `tauri_build` pass in checkouts without the real binaries.
+        let Ok(contents) = std::fs::read(&path) else {
+            continue;
+        };
+        if contents.is_empty() {
+            continue;
+        }
+        println!("cargo:rerun-if-changed={}", path.display());
+        entries.push((name.to_string(), format!("{:x}", Sha256::digest(&contents))));
+    }
+    entries.sort();
+
+    let mut source = String::from("pub const BUNDLED_BINARIES: &[(&str, &str)] = &[\n");
+    for (name, digest) in entries {
+        let _ = writeln!(source, "    ({:?}, {:?}),", name, digest);
+    }
+    source.push_str("];\n");
+
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    std::fs::write(Path::new(&out_dir).join("binary_manifest.rs"), source)
+        .expect("failed to write binary manifest");
+}
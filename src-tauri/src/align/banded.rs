@@ -0,0 +1,616 @@
+//! Affine-gap dynamic programming (Gotoh) restricted to a band of
+//! diagonals. Rows are query bases, columns reference bases.
+//!
+//! Each row is filled in two passes: the diagonal and vertical terms depend
+//! only on the previous row, so that pass has no dependency between cells
+//! and runs eight (AVX2) or four (NEON) columns at a time; only the
+//! horizontal gap term is then carried left to right. Scores are `i32`, kept
+//! from overflowing by the bounds [`Scoring`] is checked against, and the
+//! traceback takes one byte per cell in the band.
+
+use super::{AlignMode, Scoring};
+
+const NEG: i32 = i32::MIN / 4;
+
+// Traceback byte: where H came from, plus whether E and F were extended.
+const FROM_DIAG: u8 = 0;
+const FROM_LEFT: u8 = 1;
+const FROM_UP: u8 = 2;
+const FROM_NONE: u8 = 3;
+const SOURCE: u8 = 0b11;
+const E_EXTENDED: u8 = 0b100;
+const F_EXTENDED: u8 = 0b1000;
+
+/// Columns `lo..=hi` of each row that are computed.
+pub struct Band {
+    rows: Vec<(usize, usize)>,
+}
+
+impl Band {
+    /// Every cell.
+    pub fn full(query_len: usize, reference_len: usize) -> Self {
+        Self {
+            rows: vec![(0, reference_len); query_len + 1],
+        }
+    }
+
+    /// `width` columns either side of the line through row 0 at column
+    /// `start` with `slope` columns per row. Never empty, and moves right
+    /// monotonically as the fill requires.
+    pub fn around(
+        query_len: usize,
+        reference_len: usize,
+        start: f64,
+        slope: f64,
+        width: usize,
+    ) -> Self {
+        let rows = (0..=query_len)
+            .map(|row| {
+                let center = (start + slope * row as f64).round();
+                let lo = (center - width as f64).clamp(0.0, reference_len as f64) as usize;
+                let hi = (center + width as f64).clamp(0.0, reference_len as f64) as usize;
+                (lo, hi.max(lo))
+            })
+            .collect();
+        Self { rows }
+    }
+
+    pub fn cells(&self) -> usize {
+        self.rows.iter().map(|(lo, hi)| hi - lo + 1).sum()
+    }
+}
+
+/// A path through the matrix: where it starts and ends and the operations
+/// along it, first to last.
+pub struct Path {
+    pub score: i32,
+    pub query_start: usize,
+    pub query_end: usize,
+    pub reference_start: usize,
+    pub reference_end: usize,
+    pub ops: Vec<Op>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    /// Match or mismatch.
+    Aligned,
+    /// A query base against a gap.
+    Insertion,
+    /// A reference base against a gap.
+    Deletion,
+}
+
+fn substitution(scoring: &Scoring, query: u8, reference: u8) -> i32 {
+    let (query, reference) = (query.to_ascii_uppercase(), reference.to_ascii_uppercase());
+    if query == b'N' || reference == b'N' {
+        0
+    } else if query == reference {
+        scoring.match_score
+    } else {
+        scoring.mismatch
+    }
+}
+
+/// Score of a gap of `length` bases.
+fn gap(scoring: &Scoring, length: usize) -> i32 {
+    if length == 0 {
+        return 0;
+    }
+    scoring.gap_open + scoring.gap_extend * (length as i32 - 1)
+}
+
+/// The diagonal and vertical terms of one row over the band's columns, from
+/// the row above at the same column (`up`) and the one before (`up_left`).
+/// All slices have the band's width.
+struct Vertical<'a> {
+    up: &'a [i32],
+    up_left: &'a [i32],
+    substitutions: &'a [i32],
+    f: &'a mut [i32],
+    f_extended: &'a mut [i32],
+    diagonal: &'a mut [i32],
+}
+
+impl Vertical<'_> {
+    fn fill(&mut self, gap_open: i32, gap_extend: i32) {
+        let done = self.fill_lanes(gap_open, gap_extend);
+        self.fill_scalar(done, gap_open, gap_extend);
+    }
+
+    /// Columns `from..` one at a time.
+    fn fill_scalar(&mut self, from: usize, gap_open: i32, gap_extend: i32) {
+        for column in from..self.up.len() {
+            let open = self.up[column] + gap_open;
+            let extend = self.f[column] + gap_extend;
+            self.f_extended[column] = -((extend > open) as i32);
+            self.f[column] = open.max(extend);
+            self.diagonal[column] = self.up_left[column] + self.substitutions[column];
+        }
+    }
+
+    /// Fills as many whole vectors of columns from the start as fit, and
+    /// returns how many columns that was.
+    #[cfg(target_arch = "x86_64")]
+    fn fill_lanes(&mut self, gap_open: i32, gap_extend: i32) -> usize {
+        if !is_x86_feature_detected!("avx2") {
+            return 0;
+        }
+        // SAFETY: AVX2 was detected just above.
+        unsafe { self.fill_avx2(gap_open, gap_extend) }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    fn fill_lanes(&mut self, gap_open: i32, gap_extend: i32) -> usize {
+        // SAFETY: NEON is part of every aarch64 target.
+        unsafe { self.fill_neon(gap_open, gap_extend) }
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    fn fill_lanes(&mut self, _gap_open: i32, _gap_extend: i32) -> usize {
+        0
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn fill_avx2(&mut self, gap_open: i32, gap_extend: i32) -> usize {
+        use std::arch::x86_64::*;
+        const LANES: usize = 8;
+
+        let len = self.up.len();
+        let gap_open = _mm256_set1_epi32(gap_open);
+        let gap_extend = _mm256_set1_epi32(gap_extend);
+        let mut column = 0;
+        // Loads and stores are unaligned and stay within `column..column + LANES`,
+        // inside every slice since they all have length `len`.
+        while column + LANES <= len {
+            let up = _mm256_loadu_si256(self.up.as_ptr().add(column).cast());
+            let up_left = _mm256_loadu_si256(self.up_left.as_ptr().add(column).cast());
+            let substitutions = _mm256_loadu_si256(self.substitutions.as_ptr().add(column).cast());
+            let f = _mm256_loadu_si256(self.f.as_ptr().add(column).cast());
+
+            let open = _mm256_add_epi32(up, gap_open);
+            let extend = _mm256_add_epi32(f, gap_extend);
+            _mm256_storeu_si256(
+                self.f_extended.as_mut_ptr().add(column).cast(),
+                _mm256_cmpgt_epi32(extend, open),
+            );
+            _mm256_storeu_si256(
+                self.f.as_mut_ptr().add(column).cast(),
+                _mm256_max_epi32(open, extend),
+            );
+            _mm256_storeu_si256(
+                self.diagonal.as_mut_ptr().add(column).cast(),
+                _mm256_add_epi32(up_left, substitutions),
+            );
+            column += LANES;
+        }
+        column
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[target_feature(enable = "neon")]
+    unsafe fn fill_neon(&mut self, gap_open: i32, gap_extend: i32) -> usize {
+        use std::arch::aarch64::*;
+        const LANES: usize = 4;
+
+        let len = self.up.len();
+        let gap_open = vdupq_n_s32(gap_open);
+        let gap_extend = vdupq_n_s32(gap_extend);
+        let mut column = 0;
+        // As in `fill_avx2`, every access stays within `column..column + LANES`.
+        while column + LANES <= len {
+            let up = vld1q_s32(self.up.as_ptr().add(column));
+            let up_left = vld1q_s32(self.up_left.as_ptr().add(column));
+            let substitutions = vld1q_s32(self.substitutions.as_ptr().add(column));
+            let f = vld1q_s32(self.f.as_ptr().add(column));
+
+            let open = vaddq_s32(up, gap_open);
+            let extend = vaddq_s32(f, gap_extend);
+            vst1q_s32(
+                self.f_extended.as_mut_ptr().add(column),
+                vreinterpretq_s32_u32(vcgtq_s32(extend, open)),
+            );
+            vst1q_s32(self.f.as_mut_ptr().add(column), vmaxq_s32(open, extend));
+            vst1q_s32(
+                self.diagonal.as_mut_ptr().add(column),
+                vaddq_s32(up_left, substitutions),
+            );
+            column += LANES;
+        }
+        column
+    }
+}
+
+/// Aligns `query` to `reference` within `band`. `None` if no path through
+/// the band reaches an allowed end.
+pub fn align(
+    query: &[u8],
+    reference: &[u8],
+    mode: AlignMode,
+    scoring: &Scoring,
+    band: &Band,
+) -> Option<Path> {
+    let (n, m) = (query.len(), reference.len());
+    let local = mode == AlignMode::Local;
+    let floor = if local { 0 } else { NEG };
+
+    // Substitution scores per query base, looked up by reference position.
+    let mut profile: [Vec<i32>; 5] = Default::default();
+    for (code, base) in [b'A', b'C', b'G', b'T', b'N'].into_iter().enumerate() {
+        profile[code] = reference
+            .iter()
+            .map(|&reference| substitution(scoring, base, reference))
+            .collect();
+    }
+    let code = |base: u8| match base.to_ascii_uppercase() {
+        b'A' => 0,
+        b'C' => 1,
+        b'G' => 2,
+        b'T' | b'U' => 3,
+        _ => 4,
+    };
+
+    let mut previous = vec![NEG; m + 1];
+    let mut current = vec![NEG; m + 1];
+    // The columns each buffer last held, to clear before it is reused.
+    let mut previous_range = (0, m);
+    let mut current_range = (0, m);
+    let mut f = vec![NEG; m + 1];
+    let mut diagonal = vec![NEG; m + 1];
+    // All ones where F was extended rather than opened, as lanes compare.
+    let mut f_extended = vec![0i32; m + 1];
+    let mut trace: Vec<Vec<u8>> = Vec::with_capacity(n + 1);
+
+    let (lo, hi) = band.rows[0];
+    for (column, cell) in previous.iter_mut().enumerate().take(hi + 1).skip(lo) {
+        *cell = match mode {
+            AlignMode::Global => gap(scoring, column),
+            AlignMode::Glocal | AlignMode::Local => 0,
+        };
+    }
+    trace.push(vec![FROM_LEFT; hi - lo + 1]);
+
+    let mut best = (NEG, 0, 0);
+    if local {
+        best = (0, 0, 0);
+    }
+
+    for row in 1..=n {
+        let (lo, hi) = band.rows[row];
+        let scores = &profile[code(query[row - 1])];
+        current[current_range.0..=current_range.1].fill(NEG);
+        current_range = (lo, hi);
+        let mut cells = vec![0u8; hi - lo + 1];
+
+        let first = if lo == 0 {
+            current[0] = if local { 0 } else { gap(scoring, row) };
+            cells[0] = if local {
+                FROM_NONE
+            } else {
+                FROM_UP | F_EXTENDED
+            };
+            f[0] = current[0];
+            1
+        } else {
+            lo
+        };
+
+        // Diagonal and vertical terms: no dependency between columns.
+        Vertical {
+            up: &previous[first..=hi],
+            up_left: &previous[first - 1..hi],
+            substitutions: &scores[first - 1..hi],
+            f: &mut f[first..=hi],
+            f_extended: &mut f_extended[first..=hi],
+            diagonal: &mut diagonal[first..=hi],
+        }
+        .fill(scoring.gap_open, scoring.gap_extend);
+
+        // Horizontal term, carried along the row.
+        let mut e = NEG;
+        let mut left = if first == 1 { current[0] } else { NEG };
+        for column in first..=hi {
+            let open = left + scoring.gap_open;
+            let extend = e + scoring.gap_extend;
+            let mut flags = 0;
+            if extend > open {
+                flags |= E_EXTENDED;
+            }
+            e = open.max(extend);
+            if f_extended[column] != 0 {
+                flags |= F_EXTENDED;
+            }
+            let (d, v) = (diagonal[column], f[column]);
+            let h = d.max(v).max(e).max(floor);
+            // A local path starts afresh wherever its score would drop to
+            // zero, so the traceback stops there.
+            flags |= if local && h == 0 {
+                FROM_NONE
+            } else if h == d {
+                FROM_DIAG
+            } else if h == v {
+                FROM_UP
+            } else if h == e {
+                FROM_LEFT
+            } else {
+                FROM_NONE
+            };
+            current[column] = h;
+            cells[column - lo] = flags;
+            left = h;
+            if local && h > best.0 {
+                best = (h, row, column);
+            }
+        }
+        trace.push(cells);
+        std::mem::swap(&mut previous, &mut current);
+        std::mem::swap(&mut previous_range, &mut current_range);
+    }
+
+    // `previous` now holds the last row.
+    let (score, end_row, end_column) = match mode {
+        AlignMode::Local => best,
+        AlignMode::Global => {
+            let (_, hi) = band.rows[n];
+            if hi != m {
+                return None;
+            }
+            (previous[m], n, m)
+        }
+        AlignMode::Glocal => {
+            let (lo, hi) = band.rows[n];
+            let column =
+                (lo..=hi).max_by_key(|&column| (previous[column], std::cmp::Reverse(column)))?;
+            (previous[column], n, column)
+        }
+    };
+    if score <= NEG / 2 {
+        return None;
+    }
+
+    let flags = |row: usize, column: usize| -> Option<u8> {
+        let (lo, hi) = band.rows[row];
+        (lo..=hi).contains(&column).then(|| trace[row][column - lo])
+    };
+    let (mut row, mut column) = (end_row, end_column);
+    let mut ops = Vec::new();
+    // Which matrix the path is in: H, E (left) or F (up).
+    let mut state = FROM_DIAG;
+    loop {
+        if row == 0 {
+            if mode == AlignMode::Global {
+                ops.extend(std::iter::repeat_n(Op::Deletion, column));
+                column = 0;
+            }
+            break;
+        }
+        if column == 0 && !local {
+            ops.extend(std::iter::repeat_n(Op::Insertion, row));
+            row = 0;
+            break;
+        }
+        let cell = flags(row, column)?;
+        if state == FROM_DIAG {
+            state = cell & SOURCE;
+            if state == FROM_NONE {
+                break;
+            }
+        }
+        match state {
+            FROM_DIAG => {
+                ops.push(Op::Aligned);
+                row -= 1;
+                column -= 1;
+            }
+            FROM_LEFT => {
+                ops.push(Op::Deletion);
+                if cell & E_EXTENDED == 0 {
+                    state = FROM_DIAG;
+                }
+                column -= 1;
+            }
+            _ => {
+                ops.push(Op::Insertion);
+                if cell & F_EXTENDED == 0 {
+                    state = FROM_DIAG;
+                }
+                row -= 1;
+            }
+        }
+    }
+    ops.reverse();
+    Some(Path {
+        score,
+        query_start: row,
+        query_end: end_row,
+        reference_start: column,
+        reference_end: end_column,
+        ops,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full(query: &[u8], reference: &[u8], mode: AlignMode, scoring: &Scoring) -> Path {
+        let band = Band::full(query.len(), reference.len());
+        align(query, reference, mode, scoring, &band).expect("a full matrix always aligns")
+    }
+
+    /// Bases from a fixed linear congruential generator.
+    fn bases(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                b"ACGT"[(state >> 62) as usize]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn global_identical() {
+        let path = full(b"ACGT", b"ACGT", AlignMode::Global, &Scoring::default());
+        assert_eq!(path.score, 12);
+        assert_eq!(path.ops, vec![Op::Aligned; 4]);
+        assert_eq!((path.query_start, path.query_end), (0, 4));
+        assert_eq!((path.reference_start, path.reference_end), (0, 4));
+    }
+
+    #[test]
+    fn global_deletion() {
+        // ACG-T over ACGGT: four matches and a gap of one.
+        let path = full(b"ACGT", b"ACGGT", AlignMode::Global, &Scoring::default());
+        assert_eq!(path.score, 4 * 3 - 10);
+        assert_eq!(path.ops.len(), 5);
+        assert_eq!(path.ops.iter().filter(|&&op| op == Op::Deletion).count(), 1);
+        assert_eq!(path.ops.iter().filter(|&&op| op == Op::Aligned).count(), 4);
+        assert_eq!((path.reference_start, path.reference_end), (0, 5));
+    }
+
+    #[test]
+    fn global_leading_gaps() {
+        // The query's missing first bases are a gap at the very start.
+        let path = full(b"GT", b"ACGT", AlignMode::Global, &Scoring::default());
+        assert_eq!(path.score, 2 * 3 + (-10 - 4));
+        assert_eq!(
+            path.ops,
+            vec![Op::Deletion, Op::Deletion, Op::Aligned, Op::Aligned]
+        );
+        assert_eq!(path.reference_start, 0);
+    }
+
+    #[test]
+    fn glocal_inside_reference() {
+        let path = full(
+            b"ACGT",
+            b"TTTACGTTTT",
+            AlignMode::Glocal,
+            &Scoring::default(),
+        );
+        assert_eq!(path.score, 12);
+        assert_eq!((path.query_start, path.query_end), (0, 4));
+        assert_eq!((path.reference_start, path.reference_end), (3, 7));
+        assert_eq!(path.ops, vec![Op::Aligned; 4]);
+    }
+
+    #[test]
+    fn local_best_part() {
+        let path = full(
+            b"TTACGTACTT",
+            b"GGACGTACGG",
+            AlignMode::Local,
+            &Scoring::default(),
+        );
+        assert_eq!(path.score, 18);
+        assert_eq!((path.query_start, path.query_end), (2, 8));
+        assert_eq!((path.reference_start, path.reference_end), (2, 8));
+        assert_eq!(path.ops, vec![Op::Aligned; 6]);
+    }
+
+    #[test]
+    fn local_stops_at_zero() {
+        // A match and a mismatch of equal weight sum to zero; the path must
+        // start after them rather than run through the zero.
+        let scoring = Scoring {
+            match_score: 5,
+            mismatch: -5,
+            gap_open: -10,
+            gap_extend: -10,
+        };
+        let path = full(b"ACGAAA", b"ATGAAA", AlignMode::Local, &scoring);
+        assert_eq!(path.score, 20);
+        assert_eq!((path.query_start, path.query_end), (2, 6));
+        assert_eq!((path.reference_start, path.reference_end), (2, 6));
+        assert_eq!(path.ops, vec![Op::Aligned; 4]);
+    }
+
+    #[test]
+    fn band_matches_full_matrix() {
+        let scoring = Scoring::default();
+        let reference = bases(1, 300);
+        // A handful of substitutions and one indel keep the best path close
+        // to the main diagonal.
+        let mut query = reference.clone();
+        for position in [17, 80, 81, 150, 222] {
+            query[position] = if query[position] == b'A' { b'C' } else { b'A' };
+        }
+        query.remove(120);
+        let (n, m) = (query.len(), reference.len());
+
+        for mode in [AlignMode::Global, AlignMode::Glocal, AlignMode::Local] {
+            let expected = full(&query, &reference, mode, &scoring);
+            let band = Band::around(n, m, 0.0, m as f64 / n as f64, 8);
+            assert!(band.cells() < (n + 1) * (m + 1) / 4);
+            let banded = align(&query, &reference, mode, &scoring, &band).unwrap();
+            assert_eq!(banded.score, expected.score, "{:?}", mode);
+            assert_eq!(banded.ops, expected.ops, "{:?}", mode);
+            assert_eq!(
+                (banded.query_start, banded.reference_start),
+                (expected.query_start, expected.reference_start),
+                "{:?}",
+                mode
+            );
+        }
+    }
+
+    #[test]
+    fn ops_consume_both_sequences() {
+        let scoring = Scoring::default();
+        let reference = bases(7, 120);
+        let query = bases(8, 90);
+        for mode in [AlignMode::Global, AlignMode::Glocal, AlignMode::Local] {
+            let path = full(&query, &reference, mode, &scoring);
+            let query_bases = path.ops.iter().filter(|&&op| op != Op::Deletion).count();
+            let reference_bases = path.ops.iter().filter(|&&op| op != Op::Insertion).count();
+            assert_eq!(query_bases, path.query_end - path.query_start, "{:?}", mode);
+            assert_eq!(
+                reference_bases,
+                path.reference_end - path.reference_start,
+                "{:?}",
+                mode
+            );
+        }
+    }
+
+    #[test]
+    fn lanes_match_scalar() {
+        let (gap_open, gap_extend) = (-10, -4);
+        for len in [0, 1, 3, 4, 7, 8, 9, 31, 64, 65] {
+            let values = |seed: u64| -> Vec<i32> {
+                bases(seed, len)
+                    .iter()
+                    .map(|&base| base as i32 * 7 - 400)
+                    .collect()
+            };
+            let (up, up_left, substitutions) = (values(1), values(2), values(3));
+            let mut lanes = (values(4), vec![0; len], vec![0; len]);
+            let mut scalar = lanes.clone();
+
+            Vertical {
+                up: &up,
+                up_left: &up_left,
+                substitutions: &substitutions,
+                f: &mut lanes.0,
+                f_extended: &mut lanes.1,
+                diagonal: &mut lanes.2,
+            }
+            .fill(gap_open, gap_extend);
+            Vertical {
+                up: &up,
+                up_left: &up_left,
+                substitutions: &substitutions,
+                f: &mut scalar.0,
+                f_extended: &mut scalar.1,
+                diagonal: &mut scalar.2,
+            }
+            .fill_scalar(0, gap_open, gap_extend);
+
+            assert_eq!(lanes, scalar, "{} columns", len);
+        }
+    }
+}
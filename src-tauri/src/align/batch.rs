@@ -0,0 +1,75 @@
+//! Aligns every sample of a plate to one reference at once, on a rayon pool
+//! using all but one core. The engine aligns a batch one sample after the
+//! other, which for 96 wells is most of a run's wall time.
+
+use super::{align, bases, AlignOptions, PairwiseAlignment};
+use crate::error::AppError;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BatchSample {
+    /// Well or patient id, echoed back in the results and progress.
+    pub sample: String,
+    /// Bases, FASTA or GenBank text.
+    pub sequence: String,
+}
+
+/// One sample's outcome; a sample that cannot be aligned does not fail the
+/// others.
+#[derive(Clone, Debug, Serialize)]
+pub struct BatchAlignment {
+    pub sample: String,
+    pub alignment: Option<PairwiseAlignment>,
+    pub error: Option<String>,
+}
+
+fn pool() -> Result<&'static ThreadPool, AppError> {
+    static POOL: OnceLock<Result<ThreadPool, String>> = OnceLock::new();
+    POOL.get_or_init(|| {
+        // One core is left to the UI and the engine.
+        let threads = std::thread::available_parallelism()
+            .map_or(1, |cores| cores.get().saturating_sub(1).max(1));
+        ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("align-{}", index))
+            .build()
+            .map_err(|e| e.to_string())
+    })
+    .as_ref()
+    .map_err(|e| AppError::Alignment(format!("no alignment threads: {}", e)))
+}
+
+/// Aligns each of `samples` to `reference` (bare bases), returning results
+/// in the order of `samples`. `progress` is called from the pool's threads
+/// with each finished sample and how many are finished so far.
+pub fn align_all(
+    samples: &[BatchSample],
+    reference: &str,
+    options: &AlignOptions,
+    progress: impl Fn(&str, usize) + Sync,
+) -> Result<Vec<BatchAlignment>, AppError> {
+    let done = AtomicUsize::new(0);
+    Ok(pool()?.install(|| {
+        samples
+            .par_iter()
+            .map(|sample| {
+                let result = bases(&sample.sequence, "sample")
+                    .and_then(|query| align(&query, reference, options));
+                progress(&sample.sample, done.fetch_add(1, Ordering::Relaxed) + 1);
+                let (alignment, error) = match result {
+                    Ok(alignment) => (Some(alignment), None),
+                    Err(e) => (None, Some(e.to_string())),
+                };
+                BatchAlignment {
+                    sample: sample.sample.clone(),
+                    alignment,
+                    error,
+                }
+            })
+            .collect()
+    }))
+}
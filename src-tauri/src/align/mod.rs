@@ -0,0 +1,389 @@
+//! Pairwise alignment in Rust, so aligning a trace to its reference still
+//! works while the engine is down, and short alignments skip the round trip
+//! to it. Not a replacement for the engine's aligner: no trace-aware
+//! scoring, just bases.
+//!
+//! Small problems are aligned over the full matrix. Larger ones are banded:
+//! global alignments around the main diagonal, local and glocal ones around
+//! the diagonal most shared k-mers agree on. Whole plates are aligned in
+//! parallel by [`batch`].
+
+mod banded;
+mod batch;
+
+pub use batch::{BatchAlignment, BatchSample};
+
+use crate::error::AppError;
+use crate::progress::{AnalysisProgress, ANALYSIS_PROGRESS_EVENT};
+use crate::sequence;
+use banded::{Band, Op};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter};
+
+/// Cells aligned without a band when the caller does not ask for one.
+const FULL_MATRIX_CELLS: usize = 16_000_000;
+/// Upper bound on the cells of any alignment; the traceback takes a byte each.
+const MAX_CELLS: usize = 200_000_000;
+/// Bound on any score a path can sum to, either way. The matrix fill starts
+/// from `i32::MIN / 4`, so this leaves as much room again below that.
+const MAX_SCORE: i64 = (i32::MAX / 4) as i64;
+const DEFAULT_BAND: usize = 64;
+const SEED_LEN: usize = 11;
+/// Reference positions kept per k-mer, so repeats do not dominate the vote.
+const MAX_SEED_HITS: usize = 16;
+/// `stage` of the progress events of [`align_batch`].
+const BATCH_STAGE: &str = "aligning";
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlignMode {
+    /// End to end on both sequences.
+    Global,
+    /// The whole query somewhere in the reference: gaps before and after it
+    /// on the reference are free. The usual case for a read.
+    #[default]
+    Glocal,
+    /// The best-scoring part of each.
+    Local,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scoring {
+    pub match_score: i32,
+    pub mismatch: i32,
+    /// Score of a gap's first base.
+    pub gap_open: i32,
+    /// Score of each further base of a gap.
+    pub gap_extend: i32,
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        // tracy's defaults.
+        Self {
+            match_score: 3,
+            mismatch: -5,
+            gap_open: -10,
+            gap_extend: -4,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlignOptions {
+    pub mode: AlignMode,
+    pub scoring: Scoring,
+    /// Diagonals either side of the band's centre; chosen automatically when
+    /// unset.
+    pub band: Option<usize>,
+    /// Also try the query's reverse complement and keep the better strand.
+    pub both_strands: bool,
+}
+
+impl Default for AlignOptions {
+    fn default() -> Self {
+        Self {
+            mode: AlignMode::default(),
+            scoring: Scoring::default(),
+            band: None,
+            both_strands: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PairwiseAlignment {
+    pub score: i32,
+    /// Whether it is the query's reverse complement that aligned.
+    pub reverse: bool,
+    /// Aligned part of the query (on the aligned strand) and the reference,
+    /// 0-based, end exclusive.
+    pub query_start: usize,
+    pub query_end: usize,
+    pub reference_start: usize,
+    pub reference_end: usize,
+    /// `=`, `X`, `I` and `D` operations.
+    pub cigar: String,
+    /// Both sequences over the aligned part, gapped with `-` to equal length.
+    pub query_aligned: String,
+    pub reference_aligned: String,
+    pub matches: usize,
+    pub mismatches: usize,
+    /// Alignment columns that are a gap in either sequence.
+    pub gaps: usize,
+    /// Matches over alignment columns.
+    pub identity: f64,
+}
+
+impl Scoring {
+    /// Fails if a path of `length` steps could take a score past
+    /// [`MAX_SCORE`] and overflow the `i32` fill.
+    fn check(&self, length: usize) -> Result<(), AppError> {
+        let largest = [
+            self.match_score,
+            self.mismatch,
+            self.gap_open,
+            self.gap_extend,
+        ]
+        .into_iter()
+        .map(|score| i64::from(score).abs())
+        .max()
+        .unwrap_or(0);
+        if largest.saturating_mul(length as i64) > MAX_SCORE {
+            return Err(AppError::Alignment(format!(
+                "scores up to {} could overflow over sequences this long",
+                largest
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn join_error(e: impl std::fmt::Display) -> AppError {
+    AppError::Io(std::io::Error::other(e.to_string()))
+}
+
+/// Bases of `text` (plain, FASTA or GenBank) without gaps.
+fn bases(text: &str, what: &str) -> Result<String, AppError> {
+    let bases: String = sequence::sanitize(text)?
+        .sequence
+        .chars()
+        .filter(|&base| base != '-')
+        .collect();
+    if bases.is_empty() {
+        return Err(AppError::InvalidSequence(format!("the {} is empty", what)));
+    }
+    Ok(bases)
+}
+
+fn kmer(bases: &[u8]) -> Option<u64> {
+    bases.iter().try_fold(0u64, |kmer, base| {
+        let code = match base.to_ascii_uppercase() {
+            b'A' => 0,
+            b'C' => 1,
+            b'G' => 2,
+            b'T' | b'U' => 3,
+            _ => return None,
+        };
+        Some(kmer << 2 | code)
+    })
+}
+
+/// The diagonal (reference minus query position) most shared k-mers lie on.
+fn seed_diagonal(query: &[u8], reference: &[u8]) -> Option<isize> {
+    if query.len() < SEED_LEN || reference.len() < SEED_LEN {
+        return None;
+    }
+    let mut index: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (position, window) in reference.windows(SEED_LEN).enumerate() {
+        if let Some(kmer) = kmer(window) {
+            let hits = index.entry(kmer).or_default();
+            if hits.len() < MAX_SEED_HITS {
+                hits.push(position);
+            }
+        }
+    }
+    let mut votes: HashMap<isize, usize> = HashMap::new();
+    for (position, window) in query.windows(SEED_LEN).enumerate() {
+        let Some(hits) = kmer(window).and_then(|kmer| index.get(&kmer)) else {
+            continue;
+        };
+        for &hit in hits {
+            *votes.entry(hit as isize - position as isize).or_default() += 1;
+        }
+    }
+    votes
+        .into_iter()
+        .max_by_key(|&(diagonal, count)| (count, std::cmp::Reverse(diagonal)))
+        .map(|(diagonal, _)| diagonal)
+}
+
+fn band(query: &[u8], reference: &[u8], options: &AlignOptions) -> Result<Band, AppError> {
+    let (n, m) = (query.len(), reference.len());
+    let full = (n + 1) * (m + 1);
+    if options.band.is_none() && full <= FULL_MATRIX_CELLS {
+        return Ok(Band::full(n, m));
+    }
+    let width = options.band.unwrap_or(DEFAULT_BAND).max(1);
+    let band = match options.mode {
+        AlignMode::Global => Band::around(n, m, 0.0, m as f64 / n as f64, width),
+        AlignMode::Glocal | AlignMode::Local => match seed_diagonal(query, reference) {
+            Some(diagonal) => Band::around(n, m, diagonal as f64, 1.0, width),
+            None if full <= MAX_CELLS => Band::full(n, m),
+            None => {
+                return Err(AppError::Alignment(
+                    "the sequences share no k-mer to anchor the alignment on".to_string(),
+                ))
+            }
+        },
+    };
+    if band.cells() > MAX_CELLS {
+        return Err(AppError::Alignment(
+            "the sequences are too long for the band; narrow it".to_string(),
+        ));
+    }
+    Ok(band)
+}
+
+fn summarize(
+    path: banded::Path,
+    query: &[u8],
+    reference: &[u8],
+    reverse: bool,
+) -> PairwiseAlignment {
+    let mut alignment = PairwiseAlignment {
+        score: path.score,
+        reverse,
+        query_start: path.query_start,
+        query_end: path.query_end,
+        reference_start: path.reference_start,
+        reference_end: path.reference_end,
+        cigar: String::new(),
+        query_aligned: String::with_capacity(path.ops.len()),
+        reference_aligned: String::with_capacity(path.ops.len()),
+        matches: 0,
+        mismatches: 0,
+        gaps: 0,
+        identity: 0.0,
+    };
+    let (mut q, mut r) = (path.query_start, path.reference_start);
+    let mut run: Option<(char, usize)> = None;
+    for op in &path.ops {
+        let code = match op {
+            Op::Aligned => {
+                let (query_base, reference_base) = (query[q] as char, reference[r] as char);
+                alignment.query_aligned.push(query_base);
+                alignment.reference_aligned.push(reference_base);
+                q += 1;
+                r += 1;
+                if query_base.eq_ignore_ascii_case(&reference_base) {
+                    alignment.matches += 1;
+                    '='
+                } else {
+                    alignment.mismatches += 1;
+                    'X'
+                }
+            }
+            Op::Insertion => {
+                alignment.query_aligned.push(query[q] as char);
+                alignment.reference_aligned.push('-');
+                q += 1;
+                alignment.gaps += 1;
+                'I'
+            }
+            Op::Deletion => {
+                alignment.query_aligned.push('-');
+                alignment.reference_aligned.push(reference[r] as char);
+                r += 1;
+                alignment.gaps += 1;
+                'D'
+            }
+        };
+        run = match run {
+            Some((previous, length)) if previous == code => Some((code, length + 1)),
+            Some((previous, length)) => {
+                alignment.cigar.push_str(&format!("{}{}", length, previous));
+                Some((code, 1))
+            }
+            None => Some((code, 1)),
+        };
+    }
+    if let Some((code, length)) = run {
+        alignment.cigar.push_str(&format!("{}{}", length, code));
+    }
+    if !path.ops.is_empty() {
+        alignment.identity = alignment.matches as f64 / path.ops.len() as f64;
+    }
+    alignment
+}
+
+/// Aligns `query` to `reference`, both bare bases.
+pub fn align(
+    query: &str,
+    reference: &str,
+    options: &AlignOptions,
+) -> Result<PairwiseAlignment, AppError> {
+    let reference = reference.as_bytes();
+    // Every step of a path consumes a base of one sequence or both.
+    options.scoring.check(query.len() + reference.len() + 1)?;
+    let mut strands = vec![(query.to_string(), false)];
+    if options.both_strands {
+        strands.push((sequence::reverse_complement(query), true));
+    }
+    let mut best: Option<PairwiseAlignment> = None;
+    for (query, reverse) in strands {
+        let query = query.as_bytes();
+        let band = band(query, reference, options)?;
+        let Some(path) = banded::align(query, reference, options.mode, &options.scoring, &band)
+        else {
+            continue;
+        };
+        if best.as_ref().is_some_and(|best| best.score >= path.score) {
+            continue;
+        }
+        best = Some(summarize(path, query, reference, reverse));
+    }
+    best.ok_or_else(|| AppError::Alignment("no alignment fits in the band; widen it".to_string()))
+}
+
+/// Aligns `query` (e.g. a consensus) to `reference`, both given as bases,
+/// FASTA or GenBank text.
+#[tauri::command]
+pub async fn align_sequences(
+    query: String,
+    reference: String,
+    options: Option<AlignOptions>,
+) -> Result<PairwiseAlignment, AppError> {
+    let query = bases(&query, "query")?;
+    let reference = bases(&reference, "reference")?;
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || align(&query, &reference, &options))
+        .await
+        .map_err(join_error)?
+}
+
+/// Aligns every sample of a plate to `reference`, in parallel, returning
+/// results in the order of `samples`. Progress is emitted as
+/// [`ANALYSIS_PROGRESS_EVENT`]s with `batch_id` as their `job_id`, like an
+/// engine batch, one per finished sample.
+#[tauri::command]
+pub async fn align_batch(
+    app_handle: AppHandle,
+    batch_id: String,
+    reference: String,
+    samples: Vec<BatchSample>,
+    options: Option<AlignOptions>,
+) -> Result<Vec<BatchAlignment>, AppError> {
+    let reference = bases(&reference, "reference")?;
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        let total = samples.len();
+        let progress = |sample: Option<&str>, done: usize| {
+            let _ = app_handle.emit(
+                ANALYSIS_PROGRESS_EVENT,
+                AnalysisProgress {
+                    job_id: batch_id.clone(),
+                    job: None,
+                    stage: BATCH_STAGE.to_string(),
+                    percent: Some(if total == 0 {
+                        100.0
+                    } else {
+                        done as f64 * 100.0 / total as f64
+                    }),
+                    message: Some(format!("{} of {} samples aligned", done, total)),
+                    sample: sample.map(str::to_string),
+                    output_paths: Vec::new(),
+                },
+            );
+        };
+        progress(None, 0);
+        batch::align_all(&samples, &reference, &options, |sample, done| {
+            progress(Some(sample), done)
+        })
+    })
+    .await
+    .map_err(join_error)?
+}
@@ -0,0 +1,160 @@
+//! Importing trace sets delivered as zip archives, as sequencing providers
+//! send them. Supported files are extracted one at a time into the app's
+//! data folder, keeping the folders they were in, and the result is planned
+//! like an imported folder: the user reviews it and `commit_import` hands
+//! the files to the normal intake.
+//!
+//! Entries whose names would land outside the extraction folder (absolute
+//! paths, `..`) and symlinks are never written, only reported.
+
+use crate::disk;
+use crate::error::AppError;
+use crate::file_intake;
+use crate::folder_import::{self, ImportOptions, ImportPlan};
+use serde::Serialize;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tracing::{info, warn};
+use zip::ZipArchive;
+
+const IMPORTS_DIR: &str = "imports";
+/// Folders archivers add that never hold data.
+const IGNORED_FOLDERS: [&str; 1] = ["__MACOSX"];
+/// Unix file type bits of a symlink.
+const SYMLINK_MODE: u32 = 0o120000;
+const FILE_TYPE_MASK: u32 = 0o170000;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ArchiveImport {
+    pub archive: PathBuf,
+    /// Where the files were extracted; they stay until the user removes them.
+    pub extracted_to: PathBuf,
+    pub plan: ImportPlan,
+    /// Entries refused as unsafe, by their name in the archive.
+    pub rejected: Vec<String>,
+}
+
+fn invalid(archive: &Path, detail: impl std::fmt::Display) -> AppError {
+    AppError::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("{:?}: {}", archive, detail),
+    ))
+}
+
+/// The archive's supported files, as (index, safe relative path, size);
+/// unsafe entries go to `rejected`.
+fn entries(
+    zip: &mut ZipArchive<File>,
+    rejected: &mut Vec<String>,
+) -> Result<Vec<(usize, PathBuf, u64)>, AppError> {
+    let mut entries = Vec::new();
+    for index in 0..zip.len() {
+        let entry = zip.by_index_raw(index)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let is_symlink = entry
+            .unix_mode()
+            .is_some_and(|mode| mode & FILE_TYPE_MASK == SYMLINK_MODE);
+        let Some(relative) = entry.enclosed_name().filter(|_| !is_symlink) else {
+            warn!("Refusing unsafe archive entry {:?}", entry.name());
+            rejected.push(entry.name().to_string());
+            continue;
+        };
+        let hidden = relative.components().any(|component| {
+            let name = component.as_os_str().to_string_lossy();
+            name.starts_with('.') || IGNORED_FOLDERS.contains(&name.as_ref())
+        });
+        if hidden || !file_intake::is_supported(&relative) {
+            continue;
+        }
+        entries.push((index, relative, entry.size()));
+    }
+    Ok(entries)
+}
+
+/// Extracts the supported files of `archive` into `target`.
+fn extract(archive: &Path, target: &Path) -> Result<Vec<String>, AppError> {
+    let mut zip = ZipArchive::new(File::open(archive)?)?;
+    let mut rejected = Vec::new();
+    let entries = entries(&mut zip, &mut rejected)?;
+
+    let needed: u64 = entries.iter().map(|(_, _, size)| size).sum();
+    if let Some(available) = disk::available_space(target) {
+        if needed > available {
+            return Err(invalid(
+                archive,
+                format!("{} bytes to extract but only {} free", needed, available),
+            ));
+        }
+    }
+
+    for (index, relative, size) in entries {
+        let destination = target.join(&relative);
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let entry = zip.by_index(index)?;
+        let mut file = File::create(&destination)?;
+        // Sizes in the archive are trusted no further than this.
+        let copied = std::io::copy(&mut entry.take(size + 1), &mut file)?;
+        if copied > size {
+            return Err(invalid(
+                archive,
+                format!("{:?} is larger than the archive says", relative),
+            ));
+        }
+    }
+    Ok(rejected)
+}
+
+/// Extracts the traces and references in the zip at `path` and plans their
+/// import like `import_directory` does; nothing is opened until
+/// `commit_import`.
+#[tauri::command]
+pub async fn import_archive(
+    app_handle: AppHandle,
+    path: PathBuf,
+    options: Option<ImportOptions>,
+) -> Result<ArchiveImport, AppError> {
+    let options = options.unwrap_or_default();
+    let stem = path.file_stem().map_or_else(
+        || "archive".to_string(),
+        |stem| stem.to_string_lossy().to_string(),
+    );
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let target = crate::data_dir::data(&app_handle)?
+        .join(IMPORTS_DIR)
+        .join(format!("{}-{}", stem, stamp));
+
+    tauri::async_runtime::spawn_blocking(move || {
+        std::fs::create_dir_all(&target)?;
+        let rejected = match extract(&path, &target) {
+            Ok(rejected) => rejected,
+            Err(e) => {
+                let _ = std::fs::remove_dir_all(&target);
+                return Err(e);
+            }
+        };
+        let plan = folder_import::plan(&target, &options)?;
+        info!(
+            "Extracted {} file(s) from {:?} to {:?}",
+            plan.files.len(),
+            path,
+            target
+        );
+        Ok(ArchiveImport {
+            archive: path,
+            extracted_to: plan.root.clone(),
+            plan,
+            rejected,
+        })
+    })
+    .await
+    .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))?
+}
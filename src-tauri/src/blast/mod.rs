@@ -0,0 +1,270 @@
+//! Local BLAST, for identifying what a sample is without sending it
+//! anywhere: databases are built with `makeblastdb` from the user's
+//! references and searched with `blastn`, both downloaded on first use.
+//! Each database lives in its own folder under `blast/` in the data folder,
+//! next to a `database.json` describing it.
+
+mod tabular;
+
+pub use tabular::BlastHit;
+
+use crate::error::AppError;
+use crate::import;
+use crate::sequence::{self, Records};
+use crate::supervisor;
+use crate::temp::TempStore;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tracing::info;
+
+const BLASTN: &str = "blastn";
+const MAKEBLASTDB: &str = "makeblastdb";
+const BLAST_DIR: &str = "blast";
+const INFO_FILE: &str = "database.json";
+const SOURCE_FILE: &str = "sequences.fasta";
+/// Base name of the files `makeblastdb` writes.
+const DB_NAME: &str = "db";
+const MAX_NAME_LEN: usize = 64;
+const DEFAULT_MAX_HITS: u32 = 10;
+const DEFAULT_EVALUE: f64 = 1e-10;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlastDatabase {
+    pub name: String,
+    /// The files it was built from.
+    pub references: Vec<PathBuf>,
+    pub sequences: usize,
+    pub bases: u64,
+    /// Seconds since the Unix epoch.
+    pub built_at: u64,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn join_error(e: impl std::fmt::Display) -> AppError {
+    AppError::Io(std::io::Error::other(e.to_string()))
+}
+
+/// `name` if it can be a folder name on every platform.
+fn checked_name(name: &str) -> Result<&str, AppError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return Err(AppError::Tool(format!(
+            "{:?} is not a database name; use letters, digits, `_` and `-`",
+            name
+        )));
+    }
+    Ok(name)
+}
+
+fn databases_dir(app_handle: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(crate::data_dir::data(app_handle)?.join(BLAST_DIR))
+}
+
+/// Whether the file at `path` is FASTA or FASTQ rather than an annotated
+/// format, going by its first character.
+fn is_fastx(path: &Path) -> Result<bool, AppError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let first = reader
+        .fill_buf()?
+        .iter()
+        .find(|byte| !byte.is_ascii_whitespace())
+        .copied();
+    Ok(matches!(first, Some(b'>' | b'@')))
+}
+
+/// Writes every sequence of `references` to `target` as FASTA; returns how
+/// many sequences and bases were written.
+fn write_sources(references: &[PathBuf], target: &Path) -> Result<(usize, u64), AppError> {
+    let mut out = BufWriter::new(File::create(target)?);
+    let (mut sequences, mut bases) = (0, 0);
+    let mut add = |out: &mut BufWriter<File>, name: &str, sequence: &str| -> std::io::Result<()> {
+        sequences += 1;
+        bases += sequence.len() as u64;
+        out.write_all(sequence::to_fasta(name, sequence, 60).as_bytes())
+    };
+    for reference in references {
+        if is_fastx(reference)? {
+            for record in Records::new(BufReader::new(File::open(reference)?)) {
+                let record = record?;
+                let title = match &record.description {
+                    Some(description) => format!("{} {}", record.name, description),
+                    None => record.name.clone(),
+                };
+                add(&mut out, &title, &record.sequence)?;
+            }
+        } else {
+            let parsed = import::read(reference)?;
+            add(&mut out, &parsed.name, &parsed.sequence)?;
+        }
+    }
+    out.flush()?;
+    if sequences == 0 {
+        return Err(AppError::Tool("the references hold no sequences".into()));
+    }
+    Ok((sequences, bases))
+}
+
+fn read_info(dir: &Path) -> Option<BlastDatabase> {
+    let text = std::fs::read_to_string(dir.join(INFO_FILE)).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+/// Builds the nucleotide database `name` from `references` (FASTA, GenBank,
+/// EMBL or SnapGene files), replacing any database of that name.
+#[tauri::command]
+pub async fn build_blast_db(
+    app_handle: AppHandle,
+    name: String,
+    references: Vec<PathBuf>,
+) -> Result<BlastDatabase, AppError> {
+    let name = checked_name(&name)?.to_string();
+    let root = databases_dir(&app_handle)?;
+    let dir = root.join(&name);
+    let staging = root.join(format!("{}.building", name));
+    let _ = std::fs::remove_dir_all(&staging);
+    std::fs::create_dir_all(&staging)?;
+
+    let built = async {
+        let sources = staging.join(SOURCE_FILE);
+        let (target, files) = (sources.clone(), references.clone());
+        let counts = tauri::async_runtime::spawn_blocking(move || write_sources(&files, &target))
+            .await
+            .map_err(join_error)??;
+        let args = vec![
+            "-in".into(),
+            sources.to_string_lossy().into_owned(),
+            "-dbtype".into(),
+            "nucl".into(),
+            "-title".into(),
+            name.clone(),
+            "-out".into(),
+            staging.join(DB_NAME).to_string_lossy().into_owned(),
+        ];
+        supervisor::run(&app_handle, MAKEBLASTDB, args).await?;
+        Ok::<_, AppError>(counts)
+    }
+    .await;
+    let (sequences, bases) = match built {
+        Ok(counts) => counts,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+    };
+
+    let database = BlastDatabase {
+        name: name.clone(),
+        references,
+        sequences,
+        bases,
+        built_at: now(),
+    };
+    std::fs::write(
+        staging.join(INFO_FILE),
+        serde_json::to_vec_pretty(&database).unwrap_or_default(),
+    )?;
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::rename(&staging, &dir)?;
+    info!(
+        "Built BLAST database {} ({} sequences, {} bp)",
+        name, sequences, bases
+    );
+    Ok(database)
+}
+
+/// The databases built so far, by name.
+#[tauri::command]
+pub fn list_blast_dbs(app_handle: AppHandle) -> Result<Vec<BlastDatabase>, AppError> {
+    let root = databases_dir(&app_handle)?;
+    let mut databases: Vec<BlastDatabase> = std::fs::read_dir(&root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| read_info(&entry.path()))
+        .collect();
+    databases.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(databases)
+}
+
+#[tauri::command]
+pub fn delete_blast_db(app_handle: AppHandle, name: String) -> Result<(), AppError> {
+    let dir = databases_dir(&app_handle)?.join(checked_name(&name)?);
+    match std::fs::remove_dir_all(&dir) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Searches `sequence` (e.g. a consensus) against `database` with blastn and
+/// returns up to `max_hits` hits with an E-value of at most `evalue`, best
+/// first.
+#[tauri::command]
+pub async fn blast_search(
+    app_handle: AppHandle,
+    database: String,
+    sequence: String,
+    max_hits: Option<u32>,
+    evalue: Option<f64>,
+) -> Result<Vec<BlastHit>, AppError> {
+    let dir = databases_dir(&app_handle)?.join(checked_name(&database)?);
+    if read_info(&dir).is_none() {
+        return Err(AppError::Tool(format!(
+            "there is no BLAST database {:?}",
+            database
+        )));
+    }
+    let query = sequence::sanitize(&sequence)?;
+    let bases: String = query.sequence.chars().filter(|&base| base != '-').collect();
+    let name = query.name.unwrap_or_else(|| "query".to_string());
+
+    let temp = app_handle.state::<TempStore>();
+    let scratch_name = format!(
+        "blast-{}",
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos())
+    );
+    let scratch = temp.create(&app_handle, &scratch_name)?;
+    let query_path = scratch.join("query.fasta");
+    let hits_path = scratch.join("hits.tsv");
+    let searched = async {
+        std::fs::write(&query_path, sequence::to_fasta(&name, &bases, 60))?;
+        supervisor::run(
+            &app_handle,
+            BLASTN,
+            vec![
+                "-db".into(),
+                dir.join(DB_NAME).to_string_lossy().into_owned(),
+                "-query".into(),
+                query_path.to_string_lossy().into_owned(),
+                "-outfmt".into(),
+                tabular::OUTFMT.into(),
+                "-max_target_seqs".into(),
+                max_hits.unwrap_or(DEFAULT_MAX_HITS).max(1).to_string(),
+                "-evalue".into(),
+                evalue.unwrap_or(DEFAULT_EVALUE).to_string(),
+                "-out".into(),
+                hits_path.to_string_lossy().into_owned(),
+            ],
+        )
+        .await?;
+        tabular::parse(&std::fs::read_to_string(&hits_path)?)
+    }
+    .await;
+    temp.remove(&scratch_name);
+    searched
+}
@@ -0,0 +1,79 @@
+//! BLAST tabular output (`-outfmt 6`) with the columns in [`OUTFMT`].
+
+use crate::error::AppError;
+use serde::Serialize;
+
+/// The `-outfmt` argument; [`parse`] expects exactly these columns.
+pub const OUTFMT: &str =
+    "6 qseqid sseqid pident length mismatch gapopen qstart qend sstart send evalue bitscore qcovs sstrand stitle";
+const COLUMNS: usize = 15;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BlastHit {
+    pub query: String,
+    pub subject: String,
+    /// The subject's FASTA title.
+    pub title: String,
+    pub percent_identity: f64,
+    pub alignment_length: u64,
+    pub mismatches: u64,
+    pub gap_opens: u64,
+    /// 1-based, inclusive.
+    pub query_start: u64,
+    pub query_end: u64,
+    /// 1-based, inclusive; `subject_start > subject_end` on the minus strand.
+    pub subject_start: u64,
+    pub subject_end: u64,
+    pub evalue: f64,
+    pub bit_score: f64,
+    /// Percent of the query covered by all hits to this subject.
+    pub query_coverage: f64,
+    pub minus_strand: bool,
+}
+
+fn field<T: std::str::FromStr>(fields: &[&str], index: usize, line: usize) -> Result<T, AppError> {
+    fields[index].trim().parse().map_err(|_| {
+        AppError::Tool(format!(
+            "blastn output line {}: {:?} is not a number",
+            line, fields[index]
+        ))
+    })
+}
+
+/// Hits in `output`, in the order BLAST ranked them.
+pub fn parse(output: &str) -> Result<Vec<BlastHit>, AppError> {
+    let mut hits = Vec::new();
+    for (index, line) in output.lines().enumerate() {
+        let line_number = index + 1;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.splitn(COLUMNS, '\t').collect();
+        if fields.len() != COLUMNS {
+            return Err(AppError::Tool(format!(
+                "blastn output line {} has {} columns, expected {}",
+                line_number,
+                fields.len(),
+                COLUMNS
+            )));
+        }
+        hits.push(BlastHit {
+            query: fields[0].to_string(),
+            subject: fields[1].to_string(),
+            percent_identity: field(&fields, 2, line_number)?,
+            alignment_length: field(&fields, 3, line_number)?,
+            mismatches: field(&fields, 4, line_number)?,
+            gap_opens: field(&fields, 5, line_number)?,
+            query_start: field(&fields, 6, line_number)?,
+            query_end: field(&fields, 7, line_number)?,
+            subject_start: field(&fields, 8, line_number)?,
+            subject_end: field(&fields, 9, line_number)?,
+            evalue: field(&fields, 10, line_number)?,
+            bit_score: field(&fields, 11, line_number)?,
+            query_coverage: field(&fields, 12, line_number)?,
+            minus_strand: fields[13].trim() == "minus",
+            title: fields[14].trim().to_string(),
+        });
+    }
+    Ok(hits)
+}
@@ -0,0 +1,107 @@
+//! Large numeric blobs handed over as files instead of through the IPC bridge.
+//!
+//! Rust writes the data into a memory-mapped file under the app cache and
+//! returns a [`BlobHandle`]. The frontend fetches `convertFileSrc(path)` over
+//! the asset protocol (scoped to the blob directory); the engine can map the
+//! path directly. Blobs live until `release_blob` or the app exits.
+
+use crate::error::AppError;
+use memmap2::MmapMut;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tracing::{debug, warn};
+
+const BLOB_DIR: &str = "blobs";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BlobHandle {
+    pub id: String,
+    pub path: PathBuf,
+    pub len: u64,
+}
+
+pub struct BlobStore {
+    dir: Option<PathBuf>,
+    next_id: AtomicU64,
+    live: Mutex<HashSet<String>>,
+}
+
+impl BlobStore {
+    /// Clears blobs left behind by a previous session.
+    pub fn open(app_handle: &AppHandle) -> Self {
+        let dir = crate::data_dir::cache(app_handle).ok().map(|dir| dir.join(BLOB_DIR));
+        if let Some(dir) = &dir {
+            let _ = std::fs::remove_dir_all(dir);
+            // The config's scope only covers the default cache dir.
+            let _ = app_handle.asset_protocol_scope().allow_directory(dir, true);
+        }
+        Self {
+            dir,
+            next_id: AtomicU64::new(1),
+            live: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Creates a blob of `len` bytes and lets `fill` write it in place.
+    pub fn write(&self, len: u64, fill: impl FnOnce(&mut [u8])) -> Result<BlobHandle, AppError> {
+        let dir = self
+            .dir
+            .as_ref()
+            .ok_or_else(|| AppError::Io(std::io::Error::other("no cache directory for blobs")))?;
+        std::fs::create_dir_all(dir)?;
+
+        let id = format!("{}-{}", std::process::id(), self.next_id.fetch_add(1, Ordering::SeqCst));
+        let path = dir.join(format!("{}.bin", id));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        file.set_len(len)?;
+        if len > 0 {
+            // SAFETY: the file was just created by us and is not shared until
+            // the handle is returned, so nothing else can resize it while mapped.
+            let mut map = unsafe { MmapMut::map_mut(&file)? };
+            fill(&mut map);
+            map.flush()?;
+        }
+
+        self.live.lock().unwrap().insert(id.clone());
+        debug!("Wrote blob {} ({} bytes)", id, len);
+        Ok(BlobHandle { id, path, len })
+    }
+
+    pub fn write_bytes(&self, bytes: &[u8]) -> Result<BlobHandle, AppError> {
+        self.write(bytes.len() as u64, |target| target.copy_from_slice(bytes))
+    }
+
+    /// Deletes a blob. Unknown ids are ignored, so only our own files can be removed.
+    pub fn release(&self, id: &str) {
+        if !self.live.lock().unwrap().remove(id) {
+            return;
+        }
+        if let Some(dir) = &self.dir {
+            if let Err(e) = std::fs::remove_file(dir.join(format!("{}.bin", id))) {
+                warn!("Could not delete blob {}: {}", id, e);
+            }
+        }
+    }
+
+    /// Deletes every blob; called on exit.
+    pub fn clear(&self) {
+        self.live.lock().unwrap().clear();
+        if let Some(dir) = &self.dir {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn release_blob(store: tauri::State<BlobStore>, id: String) {
+    store.release(&id);
+}
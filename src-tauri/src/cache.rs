@@ -0,0 +1,185 @@
+//! Cache of finished analysis results, so running an identical analysis again
+//! (or reopening its project) needs no engine work at all.
+//!
+//! Entries are keyed by a SHA-256 over the engine version, the analysis
+//! request and the contents of every input file it names; any change to one
+//! of them is a different key. Results are stored zstd-compressed under the
+//! app cache dir. Once they take up more than the `result_cache_max_gb`
+//! setting, the least recently used are evicted.
+
+use crate::error::AppError;
+use crate::settings::SettingsStore;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs::{File, FileTimes, OpenOptions};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager};
+use tracing::{debug, info, warn};
+
+const CACHE_DIR: &str = "results";
+const EXTENSION: &str = "json.zst";
+const COMPRESSION_LEVEL: i32 = 9;
+const GIGABYTE: u64 = 1024 * 1024 * 1024;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub bytes: u64,
+}
+
+pub struct ResultCache {
+    dir: Option<PathBuf>,
+}
+
+impl ResultCache {
+    pub fn open(app_handle: &AppHandle) -> Self {
+        let dir = crate::data_dir::cache(app_handle).ok().map(|dir| dir.join(CACHE_DIR));
+        Self { dir }
+    }
+
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(format!("{}.{}", key, EXTENSION)))
+    }
+
+    /// The cached result for `key`, decompressed.
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let path = self.path(key)?;
+        let file = OpenOptions::new().read(true).write(true).open(&path).ok()?;
+        // Marks the entry as recently used for eviction.
+        let _ = file.set_times(FileTimes::new().set_modified(SystemTime::now()));
+        match zstd::decode_all(file) {
+            Ok(bytes) => Some(bytes),
+            Err(e) => {
+                warn!("Dropping unreadable cached result {:?}: {}", path, e);
+                let _ = std::fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    /// Stores `result` under `key`, then evicts old entries past `max_bytes`.
+    pub fn put(&self, key: &str, result: &[u8], max_bytes: Option<u64>) -> Result<(), AppError> {
+        let Some(path) = self.path(key) else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let compressed = zstd::encode_all(result, COMPRESSION_LEVEL)?;
+        let staging = path.with_extension("tmp");
+        std::fs::write(&staging, compressed)?;
+        std::fs::rename(&staging, &path)?;
+        debug!("Cached result {} ({} bytes)", key, result.len());
+        if let Some(max_bytes) = max_bytes {
+            self.evict(max_bytes);
+        }
+        Ok(())
+    }
+
+    /// Every entry with its last use and size, least recently used first.
+    fn entries(&self) -> Vec<(PathBuf, SystemTime, u64)> {
+        let Some(dir) = &self.dir else {
+            return Vec::new();
+        };
+        let mut entries: Vec<_> = std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                Some((entry.path(), modified, metadata.len()))
+            })
+            .collect();
+        entries.sort_by_key(|(_, modified, _)| *modified);
+        entries
+    }
+
+    fn evict(&self, max_bytes: u64) {
+        let entries = self.entries();
+        let mut total: u64 = entries.iter().map(|(_, _, bytes)| bytes).sum();
+        for (path, _, bytes) in entries {
+            if total <= max_bytes {
+                break;
+            }
+            if std::fs::remove_file(&path).is_ok() {
+                total -= bytes;
+            }
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let entries = self.entries();
+        CacheStats {
+            entries: entries.len(),
+            bytes: entries.iter().map(|(_, _, bytes)| bytes).sum(),
+        }
+    }
+
+    pub fn clear(&self) {
+        if let Some(dir) = &self.dir {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+}
+
+/// The cache key of `request` run on engine `engine_version`. Reads every input
+/// file, so call it off the async runtime for large inputs.
+pub fn key(engine_version: &str, request: &Value) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(engine_version.as_bytes());
+    hasher.update([0]);
+    // Object keys serialise sorted, so equal requests hash equally.
+    hasher.update(request.to_string().as_bytes());
+    for path in input_files(request) {
+        hasher.update([0]);
+        hasher.update(path.to_string_lossy().as_bytes());
+        let mut file = File::open(path)?;
+        let mut buffer = vec![0u8; 1024 * 1024];
+        loop {
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Absolute paths of existing files named anywhere in `request`.
+fn input_files(request: &Value) -> Vec<&Path> {
+    match request {
+        Value::String(text) => {
+            let path = Path::new(text);
+            if path.is_absolute() && path.is_file() {
+                vec![path]
+            } else {
+                Vec::new()
+            }
+        }
+        Value::Array(items) => items.iter().flat_map(input_files).collect(),
+        Value::Object(fields) => fields.values().flat_map(input_files).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The configured size cap in bytes.
+pub fn max_bytes(app_handle: &AppHandle) -> Option<u64> {
+    let settings = app_handle.state::<SettingsStore>().get();
+    settings.result_cache_max_gb.map(|gb| u64::from(gb) * GIGABYTE)
+}
+
+#[tauri::command]
+pub fn get_result_cache_stats(cache: tauri::State<ResultCache>) -> CacheStats {
+    cache.stats()
+}
+
+#[tauri::command]
+pub fn clear_result_cache(cache: tauri::State<ResultCache>) {
+    cache.clear();
+    info!("Cleared the result cache");
+}
@@ -0,0 +1,60 @@
+//! Copying sequences to and pasting them from the system clipboard in a
+//! form other tools accept: whatever is copied is sanitized first (see
+//! [`sequence::sanitize`]), so numbering, spaces and line breaks picked up
+//! from GenBank views or alignments never reach the analysis or the next tool.
+
+use crate::error::AppError;
+use crate::sequence::{self, Sanitized};
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// Bases per line of copied FASTA, unless asked otherwise.
+const FASTA_WIDTH: usize = 60;
+
+fn write(app_handle: &AppHandle, text: String) -> Result<(), AppError> {
+    app_handle
+        .clipboard()
+        .write_text(text)
+        .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))
+}
+
+/// Copies `sequence` as a FASTA record named `name` (or the header it
+/// already has), wrapped at `line_width` bases.
+#[tauri::command]
+pub fn copy_as_fasta(
+    app_handle: AppHandle,
+    sequence: String,
+    name: Option<String>,
+    line_width: Option<usize>,
+) -> Result<(), AppError> {
+    let sanitized = sequence::sanitize(&sequence)?;
+    let name = name
+        .or(sanitized.name)
+        .unwrap_or_else(|| "sequence".to_string());
+    let fasta = sequence::to_fasta(
+        &name,
+        &sanitized.sequence,
+        line_width.unwrap_or(FASTA_WIDTH),
+    );
+    write(&app_handle, fasta)
+}
+
+/// Copies the reverse complement of `sequence` as bare bases.
+#[tauri::command]
+pub fn copy_reverse_complement(app_handle: AppHandle, sequence: String) -> Result<(), AppError> {
+    let sanitized = sequence::sanitize(&sequence)?;
+    write(
+        &app_handle,
+        sequence::reverse_complement(&sanitized.sequence),
+    )
+}
+
+/// The sequence on the clipboard, sanitized, with its name if it had one.
+#[tauri::command]
+pub fn paste_sequence(app_handle: AppHandle) -> Result<Sanitized, AppError> {
+    let text = app_handle
+        .clipboard()
+        .read_text()
+        .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))?;
+    sequence::sanitize(&text)
+}
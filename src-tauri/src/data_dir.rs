@@ -0,0 +1,366 @@
+//! Where the bulky app data lives: the job history, checkpoints, autosaved
+//! sessions and the caches (results, sequence indexes, extracted projects).
+//! By default these are the platform's app data and cache folders; the
+//! `data_dir` setting puts them in `data/` and `cache/` under a folder of the
+//! user's choosing, e.g. on a larger disk or a network drive. Settings, logs
+//! and small machine-specific state always stay in the platform folders.
+//!
+//! `move_data_dir` checks that the new folder is writable and has room, then
+//! saves the setting and leaves a note for the next launch, which moves the
+//! existing data over before anything opens it. A folder that is gone at
+//! launch (an unmounted drive, say) is reported and the platform folders are
+//! used for that session.
+
+use crate::disk;
+use crate::error::AppError;
+use crate::portable;
+use crate::settings::SettingsStore;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tracing::{error, info, warn};
+
+/// The pending move, written to the app config dir by `move_data_dir`.
+const MOVE_FILE: &str = "data-dir-move.json";
+const PROBE_FILE: &str = ".ps-analyzer-write-test";
+
+/// What is moved from the data and cache folders. Anything else in them
+/// (settings on Windows and macOS, where config and data share a folder) stays.
+const DATA_ENTRIES: [&str; 6] = [
+    "jobs.sqlite",
+    "checkpoints",
+    "session",
+    "imports",
+    "blast",
+    "references",
+];
+/// Blobs and staged drops do not outlive a session and are not moved.
+const CACHE_ENTRIES: [&str; 4] = ["results", "fai", "projects", "accessions"];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Dirs {
+    data: PathBuf,
+    cache: PathBuf,
+}
+
+impl Dirs {
+    fn defaults(app_handle: &AppHandle) -> tauri::Result<Self> {
+        Ok(Self {
+            data: portable::app_data_dir(app_handle)?,
+            cache: portable::app_cache_dir(app_handle)?,
+        })
+    }
+
+    fn under(root: &Path) -> Self {
+        Self {
+            data: root.join("data"),
+            cache: root.join("cache"),
+        }
+    }
+
+    fn entries(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        let data = DATA_ENTRIES.iter().map(|name| self.data.join(name));
+        let cache = CACHE_ENTRIES.iter().map(|name| self.cache.join(name));
+        data.chain(cache)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PendingMove {
+    from: Dirs,
+    to: Dirs,
+    /// The `data_dir` setting before the move, restored if it fails.
+    previous_setting: Option<PathBuf>,
+}
+
+/// Managed state: the folders in use this session.
+pub struct DataDirs {
+    dirs: Option<Dirs>,
+    /// Why the configured folder is not in use, or the last move failed.
+    problem: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DataDirStatus {
+    pub data_dir: Option<PathBuf>,
+    pub cache_dir: Option<PathBuf>,
+    /// The `data_dir` setting; the platform folders are used when unset.
+    pub configured: Option<PathBuf>,
+    /// A move waits for the next launch.
+    pub move_pending: bool,
+    pub problem: Option<String>,
+}
+
+fn current(app_handle: &AppHandle) -> Option<Dirs> {
+    app_handle.try_state::<DataDirs>()?.dirs.clone()
+}
+
+/// The folder for app data. Falls back to the platform's before [`DataDirs`]
+/// is managed.
+pub fn data(app_handle: &AppHandle) -> tauri::Result<PathBuf> {
+    match current(app_handle) {
+        Some(dirs) => Ok(dirs.data),
+        None => portable::app_data_dir(app_handle),
+    }
+}
+
+/// The folder for caches. Falls back to the platform's before [`DataDirs`]
+/// is managed.
+pub fn cache(app_handle: &AppHandle) -> tauri::Result<PathBuf> {
+    match current(app_handle) {
+        Some(dirs) => Ok(dirs.cache),
+        None => portable::app_cache_dir(app_handle),
+    }
+}
+
+/// Creates `dir` if needed and checks a file can be written to it.
+fn check_writable(dir: &Path) -> Result<(), AppError> {
+    let unwritable =
+        |e: std::io::Error| AppError::DataDir(format!("{:?} is not writable: {}", dir, e));
+    std::fs::create_dir_all(dir).map_err(unwritable)?;
+    let probe = dir.join(PROBE_FILE);
+    std::fs::write(&probe, b"ok").map_err(unwritable)?;
+    std::fs::remove_file(&probe).map_err(unwritable)
+}
+
+fn move_file_path(app_handle: &AppHandle) -> Option<PathBuf> {
+    portable::app_config_dir(app_handle)
+        .ok()
+        .map(|dir| dir.join(MOVE_FILE))
+}
+
+fn copy_recursively(from: &Path, to: &Path) -> std::io::Result<()> {
+    if from.is_dir() {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursively(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        std::fs::copy(from, to).map(|_| ())
+    }
+}
+
+fn remove(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Moves each entry of `from` to `to`: renamed where both are on one volume,
+/// otherwise copied and then removed once everything has been copied. A
+/// failed copy undoes the renames and copies before it, leaving `from` as it was.
+fn move_entries(from: &Dirs, to: &Dirs) -> std::io::Result<()> {
+    let pairs: Vec<(PathBuf, PathBuf)> = from
+        .entries()
+        .zip(to.entries())
+        .filter(|(source, _)| source.exists())
+        .collect();
+    std::fs::create_dir_all(&to.data)?;
+    std::fs::create_dir_all(&to.cache)?;
+
+    let mut renamed = Vec::new();
+    let mut copied = Vec::new();
+    for (source, target) in &pairs {
+        if std::fs::rename(source, target).is_ok() {
+            renamed.push((source, target));
+            continue;
+        }
+        if let Err(e) = copy_recursively(source, target) {
+            let _ = remove(target);
+            for target in copied {
+                let _ = remove(target);
+            }
+            for (source, target) in renamed {
+                let _ = std::fs::rename(target, source);
+            }
+            return Err(e);
+        }
+        copied.push(target);
+    }
+    for (source, target) in &pairs {
+        if copied.contains(&target) {
+            if let Err(e) = remove(source) {
+                warn!("Could not remove {:?} after moving it: {}", source, e);
+            }
+        }
+    }
+    Ok(())
+}
+
+impl DataDirs {
+    /// Works out the folders for this session, first finishing a move
+    /// `move_data_dir` left for this launch. Called once at startup, before
+    /// anything under those folders is opened.
+    pub fn resolve(app_handle: &AppHandle) -> Self {
+        let mut problem = None;
+        if let Some(path) = move_file_path(app_handle).filter(|path| path.exists()) {
+            problem = finish_move(app_handle, &path).err();
+        }
+
+        let defaults = Dirs::defaults(app_handle).ok();
+        let Some(root) = app_handle.state::<SettingsStore>().get().data_dir else {
+            return Self {
+                dirs: defaults,
+                problem,
+            };
+        };
+        let custom = Dirs::under(&root);
+        // Only `data/` and `cache/` are created: recreating the chosen folder
+        // itself would put an empty data folder on the local disk in place of
+        // an unmounted drive.
+        let usable = if root.is_dir() {
+            check_writable(&custom.data).and_then(|()| check_writable(&custom.cache))
+        } else {
+            Err(AppError::DataDir(format!("{:?} does not exist", root)))
+        };
+        match usable {
+            Ok(()) => {
+                info!("Using data folder {:?}", root);
+                Self {
+                    dirs: Some(custom),
+                    problem,
+                }
+            }
+            Err(e) => {
+                error!(
+                    "Data folder unavailable, using the default folders this session: {}",
+                    e
+                );
+                Self {
+                    dirs: defaults,
+                    problem: Some(e.to_string()),
+                }
+            }
+        }
+    }
+}
+
+/// Carries out the pending move in `path`. On failure the setting goes back
+/// to the folder the data is still in.
+fn finish_move(app_handle: &AppHandle, path: &Path) -> Result<(), String> {
+    let pending: PendingMove = std::fs::read(path)
+        .ok()
+        .and_then(|contents| serde_json::from_slice(&contents).ok())
+        .ok_or_else(|| format!("the pending data folder move in {:?} is unreadable", path))?;
+    let _ = std::fs::remove_file(path);
+
+    info!(
+        "Moving app data from {:?} to {:?}",
+        pending.from, pending.to
+    );
+    match move_entries(&pending.from, &pending.to) {
+        Ok(()) => {
+            info!("Moved app data to {:?}", pending.to);
+            Ok(())
+        }
+        Err(e) => {
+            let message = format!(
+                "Could not move the app data to {:?}: {}",
+                pending.to.data, e
+            );
+            error!("{}", message);
+            let previous = pending.previous_setting;
+            if let Err(e) = app_handle
+                .state::<SettingsStore>()
+                .update(|settings| settings.data_dir = previous)
+            {
+                error!("Could not restore the data_dir setting: {}", e);
+            }
+            Err(message)
+        }
+    }
+}
+
+/// Moves the app data to `target`, or back to the platform folders when
+/// `None`. The folder is checked now; the data itself moves at the next
+/// launch, which the frontend should offer.
+#[tauri::command]
+pub async fn move_data_dir(
+    app_handle: AppHandle,
+    target: Option<PathBuf>,
+) -> Result<DataDirStatus, AppError> {
+    if let Some(target) = &target {
+        if !target.is_absolute() {
+            return Err(AppError::DataDir(format!(
+                "{:?} is not an absolute path",
+                target
+            )));
+        }
+    }
+    let from = app_handle
+        .state::<DataDirs>()
+        .dirs
+        .clone()
+        .ok_or_else(|| AppError::DataDir("the current data folder is unknown".to_string()))?;
+    let to = match &target {
+        Some(root) => Dirs::under(root),
+        None => Dirs::defaults(&app_handle)?,
+    };
+    if to.data == from.data && to.cache == from.cache {
+        return Ok(get_data_dir_status(app_handle));
+    }
+
+    let (checked_from, checked_to) = (from.clone(), to.clone());
+    tauri::async_runtime::spawn_blocking(move || -> Result<(), AppError> {
+        check_writable(&checked_to.data)?;
+        check_writable(&checked_to.cache)?;
+        if let Some(taken) = checked_to.entries().find(|entry| entry.exists()) {
+            return Err(AppError::DataDir(format!(
+                "{:?} already holds app data; choose an empty folder",
+                taken
+            )));
+        }
+        let needed: u64 = checked_from
+            .entries()
+            .map(|entry| disk::usage(&entry))
+            .sum();
+        if let Some(available) = disk::available_space(&checked_to.data) {
+            if available < needed {
+                return Err(AppError::InsufficientSpace {
+                    path: checked_to.data,
+                    needed,
+                    available,
+                });
+            }
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))??;
+
+    let store = app_handle.state::<SettingsStore>();
+    let pending = PendingMove {
+        from,
+        to,
+        previous_setting: store.get().data_dir,
+    };
+    let path = move_file_path(&app_handle)
+        .ok_or_else(|| AppError::DataDir("no config folder to record the move in".to_string()))?;
+    std::fs::write(
+        &path,
+        serde_json::to_vec_pretty(&pending).unwrap_or_default(),
+    )?;
+    if let Err(e) = store.update(|settings| settings.data_dir = target) {
+        let _ = std::fs::remove_file(&path);
+        return Err(e);
+    }
+    info!("App data will move to {:?} at the next launch", pending.to);
+    Ok(get_data_dir_status(app_handle))
+}
+
+/// The folders in use, the configured one and any problem with it.
+#[tauri::command]
+pub fn get_data_dir_status(app_handle: AppHandle) -> DataDirStatus {
+    let dirs = app_handle.state::<DataDirs>();
+    DataDirStatus {
+        data_dir: dirs.dirs.as_ref().map(|dirs| dirs.data.clone()),
+        cache_dir: dirs.dirs.as_ref().map(|dirs| dirs.cache.clone()),
+        configured: app_handle.state::<SettingsStore>().get().data_dir,
+        move_pending: move_file_path(&app_handle).is_some_and(|path| path.exists()),
+        problem: dirs.problem.clone(),
+    }
+}
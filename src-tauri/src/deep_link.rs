@@ -0,0 +1,115 @@
+//! `psanalyzer://` links, so LIMS pages can hand files or accessions to the
+//! desktop app:
+//!
+//! - `psanalyzer://open?path=/data/run1/A01.ab1&path=...`
+//! - `psanalyzer://analyze?accession=NM_000546.6`
+
+use crate::engine::EngineManager;
+use crate::file_intake;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tracing::{info, warn};
+
+pub const SCHEME: &str = "psanalyzer";
+
+/// Emitted, once the engine is ready, with the accession to analyse.
+pub const ANALYZE_ACCESSION_EVENT: &str = "analyze-accession";
+
+const MAX_ACCESSION_LEN: usize = 64;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AnalyzeAccessionPayload {
+    pub accession: String,
+}
+
+/// Accessions received before the engine was ready.
+#[derive(Default)]
+pub struct PendingAccessions(Mutex<Vec<String>>);
+
+/// Handles links the app was launched with and listens for later ones.
+pub fn init(app_handle: &AppHandle) {
+    let handle = app_handle.clone();
+    app_handle.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            handle_url(&handle, &url);
+        }
+    });
+
+    if let Ok(Some(urls)) = app_handle.deep_link().get_current() {
+        for url in urls {
+            handle_url(app_handle, &url);
+        }
+    }
+
+    // Installers register the scheme; this covers unpackaged dev builds and AppImages.
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = app_handle.deep_link().register_all() {
+        warn!("Could not register the {}:// scheme: {}", SCHEME, e);
+    }
+}
+
+fn handle_url(app_handle: &AppHandle, url: &Url) {
+    // macOS also reports files opened through file associations here.
+    if url.scheme() != SCHEME {
+        return;
+    }
+    info!("Deep link: {}", url);
+    crate::tray::restore_from_background(app_handle);
+
+    match url.host_str() {
+        Some("open") => {
+            let paths = url
+                .query_pairs()
+                .filter(|(key, _)| key == "path")
+                .map(|(_, value)| PathBuf::from(value.as_ref()));
+            file_intake::intake(app_handle, paths);
+        }
+        Some("analyze") => {
+            let accessions = url
+                .query_pairs()
+                .filter(|(key, _)| key == "accession")
+                .map(|(_, value)| value.into_owned());
+            for accession in accessions {
+                if is_valid_accession(&accession) {
+                    queue_accession(app_handle, accession);
+                } else {
+                    warn!("Ignoring malformed accession {:?}", accession);
+                }
+            }
+        }
+        other => warn!("Ignoring deep link with unknown action {:?}", other),
+    }
+}
+
+/// Accessions come from web pages, so only plain identifiers such as
+/// `NM_000546.6` or `NC_000017.11` are let through.
+fn is_valid_accession(accession: &str) -> bool {
+    !accession.is_empty()
+        && accession.len() <= MAX_ACCESSION_LEN
+        && accession
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-')
+}
+
+fn queue_accession(app_handle: &AppHandle, accession: String) {
+    app_handle
+        .state::<PendingAccessions>()
+        .0
+        .lock()
+        .unwrap()
+        .push(accession);
+    if app_handle.state::<EngineManager>().is_ready() {
+        flush(app_handle);
+    }
+}
+
+/// Emits every queued accession. Called whenever the engine becomes ready.
+pub fn flush(app_handle: &AppHandle) {
+    let accessions = std::mem::take(&mut *app_handle.state::<PendingAccessions>().0.lock().unwrap());
+    for accession in accessions {
+        let _ = app_handle.emit(ANALYZE_ACCESSION_EVENT, AnalyzeAccessionPayload { accession });
+    }
+}
@@ -0,0 +1,142 @@
+//! Free-space checks before a job or import starts, so running out of disk is
+//! reported up front instead of as a traceback from the middle of a run.
+//!
+//! The space needed is estimated from the input sizes: tracy's intermediate
+//! files take several times the size of the traces, the results somewhat less.
+
+use crate::error::AppError;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use sysinfo::Disks;
+use tauri::{AppHandle, Emitter};
+use tracing::warn;
+
+/// Emitted with a [`DiskSpaceWarning`] when a volume is (nearly) too full.
+pub const DISK_SPACE_LOW_EVENT: &str = "disk-space-low";
+
+/// Intermediate files per byte of input.
+const TEMP_FACTOR: u64 = 10;
+/// Results per byte of input.
+const OUTPUT_FACTOR: u64 = 4;
+/// Needed however small the inputs are.
+const MIN_BYTES: u64 = 256 * 1024 * 1024;
+/// Below this multiple of the estimate the user is warned but the work starts.
+const WARN_MARGIN: u64 = 2;
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Volume {
+    Temp,
+    Output,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DiskSpaceWarning {
+    pub volume: Volume,
+    pub path: PathBuf,
+    pub needed_bytes: u64,
+    pub available_bytes: u64,
+    /// The work was refused rather than started anyway.
+    pub blocking: bool,
+}
+
+/// Free bytes on the volume holding `path`, if it can be found.
+pub fn available_space(path: &Path) -> Option<u64> {
+    let path = existing_ancestor(path)?.canonicalize().ok()?;
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+/// Bytes used by a file or, recursively, a directory.
+pub fn usage(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| usage(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+/// Output directories may not have been created yet.
+fn existing_ancestor(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|ancestor| ancestor.exists())
+}
+
+/// Total size of the input files named in a job request: every string that is
+/// the absolute path of an existing file.
+pub fn request_input_bytes(request: &Value) -> u64 {
+    match request {
+        Value::String(text) => {
+            let path = Path::new(text);
+            if path.is_absolute() {
+                std::fs::metadata(path)
+                    .ok()
+                    .filter(|metadata| metadata.is_file())
+                    .map_or(0, |metadata| metadata.len())
+            } else {
+                0
+            }
+        }
+        Value::Array(items) => items.iter().map(request_input_bytes).sum(),
+        Value::Object(fields) => fields.values().map(request_input_bytes).sum(),
+        _ => 0,
+    }
+}
+
+/// Checks that `temp` and, if given, `output` have room for work on
+/// `input_bytes` of input. Emits `disk-space-low` when either is short, and
+/// fails if either cannot hold the estimate at all. A volume that cannot be
+/// inspected is not held against the work.
+pub fn preflight(
+    app_handle: &AppHandle,
+    input_bytes: u64,
+    temp: &Path,
+    output: Option<&Path>,
+) -> Result<(), AppError> {
+    let mut checks = vec![(Volume::Temp, temp, input_bytes.saturating_mul(TEMP_FACTOR))];
+    if let Some(output) = output {
+        checks.push((Volume::Output, output, input_bytes.saturating_mul(OUTPUT_FACTOR)));
+    }
+
+    for (volume, path, estimate) in checks {
+        let needed = estimate.max(MIN_BYTES);
+        let Some(available) = available_space(path) else {
+            continue;
+        };
+        if available >= needed.saturating_mul(WARN_MARGIN) {
+            continue;
+        }
+        let blocking = available < needed;
+        warn!(
+            "Low disk space on the {:?} volume at {:?}: {} bytes free, about {} needed",
+            volume, path, available, needed
+        );
+        let _ = app_handle.emit(
+            DISK_SPACE_LOW_EVENT,
+            DiskSpaceWarning {
+                volume,
+                path: path.to_path_buf(),
+                needed_bytes: needed,
+                available_bytes: available,
+                blocking,
+            },
+        );
+        if blocking {
+            return Err(AppError::InsufficientSpace {
+                path: path.to_path_buf(),
+                needed,
+                available,
+            });
+        }
+    }
+    Ok(())
+}
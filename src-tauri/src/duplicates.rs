@@ -0,0 +1,100 @@
+//! Spotting the same trace imported twice under different names, which
+//! would otherwise count one sample twice in batch statistics. Files are
+//! compared by BLAKE3 hash of their contents, within an import and against
+//! the files already imported this session.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::debug;
+
+/// Files with the same contents; the first is the one kept.
+#[derive(Clone, Debug, Serialize)]
+pub struct DuplicateGroup {
+    pub hash: String,
+    pub paths: Vec<PathBuf>,
+}
+
+/// A file left out of an import because its contents were already there.
+#[derive(Clone, Debug, Serialize)]
+pub struct SkippedDuplicate {
+    pub path: PathBuf,
+    pub duplicate_of: PathBuf,
+}
+
+/// BLAKE3 hash of the file's contents, in hex.
+pub fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    std::io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Groups of `paths` with the same contents, in the order first seen.
+/// Unreadable files are left out.
+pub fn find<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Vec<DuplicateGroup> {
+    let mut groups: Vec<DuplicateGroup> = Vec::new();
+    let mut by_hash: HashMap<String, usize> = HashMap::new();
+    for path in paths {
+        let hash = match hash_file(path) {
+            Ok(hash) => hash,
+            Err(e) => {
+                debug!("Not checking {:?} for duplicates: {}", path, e);
+                continue;
+            }
+        };
+        match by_hash.get(&hash) {
+            Some(&index) => groups[index].paths.push(path.to_path_buf()),
+            None => {
+                by_hash.insert(hash.clone(), groups.len());
+                groups.push(DuplicateGroup {
+                    hash,
+                    paths: vec![path.to_path_buf()],
+                });
+            }
+        }
+    }
+    groups.retain(|group| group.paths.len() > 1);
+    groups
+}
+
+/// Hashes of the files imported this session.
+#[derive(Default)]
+pub struct ImportedFiles(Mutex<HashMap<String, PathBuf>>);
+
+impl ImportedFiles {
+    /// Records `paths` as imported and returns them without the ones whose
+    /// contents were imported before, which are listed in the second half.
+    /// With `allow_duplicates`, every path is kept.
+    pub fn admit(
+        &self,
+        paths: Vec<PathBuf>,
+        allow_duplicates: bool,
+    ) -> (Vec<PathBuf>, Vec<SkippedDuplicate>) {
+        let mut imported = self.0.lock().unwrap();
+        let mut admitted = Vec::new();
+        let mut skipped = Vec::new();
+        for path in paths {
+            let Ok(hash) = hash_file(&path) else {
+                // Intake reports unreadable files itself.
+                admitted.push(path);
+                continue;
+            };
+            match imported.get(&hash) {
+                Some(original) if *original != path && !allow_duplicates => {
+                    skipped.push(SkippedDuplicate {
+                        path,
+                        duplicate_of: original.clone(),
+                    });
+                }
+                Some(_) => admitted.push(path),
+                None => {
+                    imported.insert(hash, path.clone());
+                    admitted.push(path);
+                }
+            }
+        }
+        (admitted, skipped)
+    }
+}
@@ -0,0 +1,35 @@
+//! A per-run shared secret between the shell and the engine, so other local
+//! processes cannot drive the engine's HTTP API. The engine reads the token
+//! from its environment and rejects requests that do not present it.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use tauri_plugin_http::reqwest::{self, header};
+
+/// Environment variable the engine reads its expected token from.
+pub const AUTH_TOKEN_ENV: &str = "BIO_AUTH_TOKEN";
+
+const TOKEN_BYTES: usize = 32;
+
+/// A fresh random token, URL-safe so it can go into a header as is.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    getrandom::fill(&mut bytes).expect("the OS random number generator is unavailable");
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// A builder for clients talking to the engine, sending `token` as a bearer
+/// token with every request.
+pub fn client_builder(token: &str) -> reqwest::ClientBuilder {
+    let mut headers = header::HeaderMap::new();
+    headers.insert(header::AUTHORIZATION, authorization(token));
+    reqwest::Client::builder().default_headers(headers)
+}
+
+/// The `Authorization` header value carrying `token`.
+pub fn authorization(token: &str) -> header::HeaderValue {
+    let mut value = header::HeaderValue::from_str(&format!("Bearer {}", token))
+        .expect("tokens are generated or validated as header-safe");
+    value.set_sensitive(true);
+    value
+}
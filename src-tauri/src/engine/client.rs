@@ -0,0 +1,196 @@
+//! The one HTTP client for talking to the engine, shared by the frontend
+//! proxy (`engine_request`) and Rust-side callers, so timeouts, retries and
+//! error mapping are the same everywhere.
+
+use super::transport::{self, Endpoint};
+use super::{auth, EngineManager};
+use crate::error::AppError;
+use serde::Deserialize;
+use std::time::Duration;
+use tauri::ipc::Response;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_http::reqwest::{self, header, Method, RequestBuilder};
+use tracing::{debug, warn};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Attempts for idempotent requests that fail to connect or time out, e.g.
+/// while the engine is restarting.
+const ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+pub struct EngineClient {
+    http: reqwest::Client,
+    endpoint: Endpoint,
+    token: String,
+}
+
+/// FastAPI-style error body.
+#[derive(Deserialize)]
+struct ErrorBody {
+    detail: serde_json::Value,
+}
+
+impl EngineClient {
+    pub fn new(endpoint: Endpoint, token: &str) -> Result<Self, AppError> {
+        let mut builder = auth::client_builder(token).timeout(DEFAULT_TIMEOUT);
+        // The user may have configured a proxy for the engine's own downloads; a
+        // local engine must never be reached through it, a remote one may need it.
+        if !matches!(endpoint, Endpoint::Remote(_)) {
+            builder = builder.no_proxy();
+        }
+        let http = builder.build()?;
+        Ok(Self {
+            http,
+            endpoint,
+            token: token.to_string(),
+        })
+    }
+
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
+    }
+
+    /// The `Authorization` header for connections made outside this client.
+    pub fn authorization(&self) -> header::HeaderValue {
+        auth::authorization(&self.token)
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.endpoint.base_url(), path)
+    }
+
+    /// A request to `path` on the engine, e.g. `/jobs`.
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http.request(method, self.url(path))
+    }
+
+    /// Sends `request` once and returns whatever the engine answered, error
+    /// statuses included.
+    pub async fn execute(&self, request: RequestBuilder) -> Result<reqwest::Response, AppError> {
+        self.execute_request(request.build()?).await
+    }
+
+    async fn execute_request(&self, mut request: reqwest::Request) -> Result<reqwest::Response, AppError> {
+        match &self.endpoint {
+            Endpoint::Tcp(_) | Endpoint::Remote(_) => Ok(self.http.execute(request).await?),
+            Endpoint::Socket(path) => {
+                // Neither the client's timeout nor its default headers apply here.
+                let timeout = request.timeout().copied().unwrap_or(DEFAULT_TIMEOUT);
+                request
+                    .headers_mut()
+                    .insert(header::AUTHORIZATION, auth::authorization(&self.token));
+                tokio::time::timeout(timeout, transport::execute(path, request))
+                    .await
+                    .map_err(|_| AppError::EngineUnreachable(format!("no answer within {:?}", timeout)))?
+            }
+        }
+    }
+
+    /// Sends `request`, retrying idempotent methods that could not connect or
+    /// timed out, and turns error statuses into [`AppError::EngineRequest`].
+    pub async fn send(&self, request: RequestBuilder) -> Result<reqwest::Response, AppError> {
+        let request = request.build()?;
+        let idempotent = matches!(
+            *request.method(),
+            Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+        );
+        let label = format!("{} {}", request.method(), request.url().path());
+
+        // Streaming bodies cannot be cloned, and so are never retried.
+        let Some(retryable) = request.try_clone().filter(|_| idempotent) else {
+            let response = self.execute_request(request).await?;
+            return check_status(&label, response).await;
+        };
+
+        let mut attempt = 1;
+        loop {
+            let current = retryable.try_clone().expect("body was cloned once already");
+            match self.execute_request(current).await {
+                Ok(response) => return check_status(&label, response).await,
+                Err(e) if attempt < ATTEMPTS && is_transient(&e) => {
+                    warn!("{} failed (attempt {}), retrying: {}", label, attempt, e);
+                    tokio::time::sleep(RETRY_DELAY * attempt).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Whether `error` means the engine could not be reached at all, rather than
+/// that it failed the request.
+fn is_transient(error: &AppError) -> bool {
+    match error {
+        AppError::Http(e) => e.is_connect() || e.is_timeout(),
+        AppError::EngineUnreachable(_) => true,
+        _ => false,
+    }
+}
+
+async fn check_status(label: &str, response: reqwest::Response) -> Result<reqwest::Response, AppError> {
+    let status = response.status();
+    if status.is_success() {
+        debug!("{} -> {}", label, status);
+        return Ok(response);
+    }
+    let body = response.bytes().await.unwrap_or_default();
+    let message = match serde_json::from_slice::<ErrorBody>(&body) {
+        Ok(ErrorBody {
+            detail: serde_json::Value::String(detail),
+        }) => detail,
+        Ok(ErrorBody { detail }) => detail.to_string(),
+        Err(_) => String::from_utf8_lossy(&body).trim().to_string(),
+    };
+    Err(AppError::EngineRequest {
+        status: status.as_u16(),
+        message,
+    })
+}
+
+/// Sends a request from the frontend to the engine, see `engine_request`.
+pub async fn proxy(
+    app_handle: &AppHandle,
+    method: String,
+    path: String,
+    body: Option<serde_json::Value>,
+    timeout_secs: Option<u64>,
+) -> Result<Response, AppError> {
+    if !path.starts_with('/') {
+        return Err(AppError::EngineRequest {
+            status: 400,
+            message: format!("{:?} is not an engine path", path),
+        });
+    }
+    let method = Method::from_bytes(method.to_ascii_uppercase().as_bytes()).map_err(|_| AppError::EngineRequest {
+        status: 405,
+        message: format!("unsupported method {:?}", method),
+    })?;
+    if !app_handle.state::<EngineManager>().is_ready() {
+        return Err(AppError::EngineNotRunning);
+    }
+
+    let client = app_handle.state::<EngineClient>();
+    let mut request = client.request(method, &path);
+    if let Some(body) = body {
+        request = request
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(&body).unwrap_or_default());
+    }
+    if let Some(timeout_secs) = timeout_secs {
+        request = request.timeout(Duration::from_secs(timeout_secs));
+    }
+
+    let response = client.send(request).await?;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    let bytes = response.bytes().await?;
+    Ok(if is_json {
+        Response::new(String::from_utf8_lossy(&bytes).into_owned())
+    } else {
+        Response::new(bytes.to_vec())
+    })
+}
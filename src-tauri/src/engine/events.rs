@@ -0,0 +1,105 @@
+//! Relays the engine's `/events` WebSocket (job progress, intermediate
+//! results) to the frontend as `engine-message` events, so long analyses can
+//! stream partial results instead of being polled.
+
+use super::transport::{self, Endpoint};
+use super::{EngineClient, EngineManager, EngineState};
+use futures_util::StreamExt;
+use serde_json::Value;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_http::reqwest::header;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+use tracing::{debug, info};
+
+/// Emitted with every JSON message the engine publishes on its event stream.
+pub const ENGINE_MESSAGE_EVENT: &str = "engine-message";
+
+const EVENTS_PATH: &str = "/events";
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// Keeps a connection to the event stream of the process `generation` open
+/// for as long as it runs, reconnecting whenever it drops.
+pub async fn bridge(app_handle: AppHandle, generation: u64) {
+    loop {
+        tokio::time::sleep(RECONNECT_DELAY).await;
+
+        let manager = app_handle.state::<EngineManager>();
+        if !manager.is_live(generation) {
+            return;
+        }
+        // Nothing is listening before the engine is up, and a paused one cannot accept.
+        if !matches!(manager.state(), EngineState::Ready | EngineState::Degraded) {
+            continue;
+        }
+
+        match connect(&app_handle).await {
+            Ok(()) => debug!("bio-engine event stream closed"),
+            Err(e) => debug!("bio-engine event stream unavailable: {}", e),
+        }
+    }
+}
+
+async fn connect(app_handle: &AppHandle) -> Result<(), String> {
+    let client = app_handle.state::<EngineClient>();
+    let url = client.url(EVENTS_PATH).replacen("http", "ws", 1);
+    let mut request = url.into_client_request().map_err(|e| e.to_string())?;
+    request
+        .headers_mut()
+        .insert(header::AUTHORIZATION, client.authorization());
+
+    match client.endpoint() {
+        Endpoint::Tcp(port) => {
+            let stream = tokio::net::TcpStream::connect(("127.0.0.1", *port))
+                .await
+                .map_err(|e| e.to_string())?;
+            relay(app_handle, handshake(stream, request).await?).await
+        }
+        Endpoint::Socket(path) => {
+            let stream = transport::connect(path).await.map_err(|e| e.to_string())?;
+            relay(app_handle, handshake(stream, request).await?).await
+        }
+        // May need TLS, which `connect_async` sets up from the URL.
+        Endpoint::Remote(_) => {
+            let (socket, _) = tokio_tungstenite::connect_async(request)
+                .await
+                .map_err(|e| e.to_string())?;
+            relay(app_handle, socket).await
+        }
+    }
+}
+
+async fn handshake<S>(stream: S, request: Request) -> Result<WebSocketStream<S>, String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (socket, _) = tokio_tungstenite::client_async(request, stream)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(socket)
+}
+
+async fn relay<S>(app_handle: &AppHandle, mut socket: WebSocketStream<S>) -> Result<(), String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    info!("Connected to bio-engine event stream");
+
+    while let Some(message) = socket.next().await {
+        match message.map_err(|e| e.to_string())? {
+            Message::Text(text) => match serde_json::from_str::<Value>(&text) {
+                Ok(payload) => {
+                    let _ = app_handle.emit(ENGINE_MESSAGE_EVENT, payload);
+                }
+                Err(e) => debug!("Ignoring malformed bio-engine event: {}", e),
+            },
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,70 @@
+//! Checks that the bio-engine speaks the API this shell was built against, so
+//! a stale engine left behind by a partial update fails loudly instead of with
+//! confusing analysis errors.
+
+use super::{EngineClient, EngineManager};
+use crate::error::AppError;
+use serde::Deserialize;
+use tauri::{AppHandle, Manager};
+use std::time::Duration;
+use tauri_plugin_http::reqwest::Method;
+use tracing::{info, warn};
+
+/// Engine API revision this shell understands. Bump together with the engine
+/// whenever its HTTP API changes incompatibly.
+pub const EXPECTED_API_REVISION: u32 = 1;
+
+#[derive(Debug, Deserialize)]
+struct VersionResponse {
+    version: String,
+    /// Engines predating the handshake only report `version`; they speak revision 1.
+    #[serde(default = "legacy_api_revision")]
+    api_revision: u32,
+}
+
+fn legacy_api_revision() -> u32 {
+    1
+}
+
+/// Queries `/version` and compares it against what the shell expects. Only a
+/// confirmed mismatch is an error; an engine that cannot be asked is let through.
+pub async fn verify(app_handle: &AppHandle, client: &EngineClient, timeout: Duration) -> Result<(), AppError> {
+    let engine = match fetch_version(client, timeout).await {
+        Ok(engine) => engine,
+        Err(e) => {
+            warn!("Could not query bio-engine version, skipping compatibility check: {}", e);
+            return Ok(());
+        }
+    };
+
+    let app_version = app_handle.package_info().version.to_string();
+    app_handle
+        .state::<EngineManager>()
+        .set_engine_version(engine.version.clone());
+
+    if engine.api_revision != EXPECTED_API_REVISION {
+        return Err(AppError::EngineIncompatible {
+            engine_version: engine.version,
+            engine_api_revision: engine.api_revision,
+            expected_api_revision: EXPECTED_API_REVISION,
+            app_version,
+        });
+    }
+
+    if engine.version != app_version {
+        warn!(
+            "bio-engine version {} differs from app version {} (API revision {} matches)",
+            engine.version, app_version, engine.api_revision
+        );
+    } else {
+        info!("bio-engine {} speaks API revision {}", engine.version, engine.api_revision);
+    }
+    Ok(())
+}
+
+async fn fetch_version(client: &EngineClient, timeout: Duration) -> Result<VersionResponse, String> {
+    let request = client.request(Method::GET, "/version").timeout(timeout);
+    let response = client.execute(request).await.map_err(|e| e.to_string())?;
+    let body = response.bytes().await.map_err(|e| e.to_string())?;
+    serde_json::from_slice(&body).map_err(|e| e.to_string())
+}
@@ -0,0 +1,81 @@
+use super::readiness::{became_ready, probe};
+use super::{EngineClient, EngineManager, EngineState};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{error, info, warn};
+
+/// Emitted when a running engine stops answering health probes.
+pub const ENGINE_UNRESPONSIVE_EVENT: &str = "engine-unresponsive";
+
+const INTERVAL: Duration = Duration::from_secs(5);
+/// Generous, since the engine may be busy with a heavy alignment.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Missed probes before the engine is reported as degraded (~30s).
+const UNRESPONSIVE_AFTER: u32 = 3;
+/// Missed probes before the engine is restarted automatically (~2min).
+const AUTO_RESTART_AFTER: u32 = 12;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UnresponsivePayload {
+    pub port: u16,
+    pub missed_heartbeats: u32,
+    /// Seconds until the engine is restarted automatically if it stays silent.
+    pub auto_restart_in_secs: u64,
+}
+
+/// Pings the engine for as long as the process `generation` is alive, catching
+/// hangs that never show up as a process exit.
+pub async fn watch(app_handle: AppHandle, generation: u64) {
+    let port = app_handle.state::<EngineManager>().port();
+    let client = app_handle.state::<EngineClient>();
+
+    let mut missed = 0u32;
+    loop {
+        tokio::time::sleep(INTERVAL).await;
+
+        let manager = app_handle.state::<EngineManager>();
+        if !manager.is_live(generation) {
+            return;
+        }
+        // Startup is the readiness probe's job, and a paused engine cannot answer.
+        if matches!(manager.state(), EngineState::Starting | EngineState::Paused) {
+            missed = 0;
+            continue;
+        }
+
+        if probe(&client, REQUEST_TIMEOUT).await {
+            // Undoes the degraded state of missed heartbeats or a slow start,
+            // never that of a failed handshake.
+            if manager.state() == EngineState::Degraded && !manager.is_incompatible() {
+                info!("bio-engine is responding again");
+                became_ready(&app_handle);
+            }
+            missed = 0;
+            continue;
+        }
+
+        missed += 1;
+        if missed == UNRESPONSIVE_AFTER {
+            warn!("bio-engine missed {} heartbeats", missed);
+            super::set_state(&app_handle, EngineState::Degraded);
+            let _ = app_handle.emit(
+                ENGINE_UNRESPONSIVE_EVENT,
+                UnresponsivePayload {
+                    port,
+                    missed_heartbeats: missed,
+                    auto_restart_in_secs: INTERVAL.as_secs()
+                        * u64::from(AUTO_RESTART_AFTER - UNRESPONSIVE_AFTER),
+                },
+            );
+        }
+
+        if missed >= AUTO_RESTART_AFTER {
+            warn!("bio-engine unresponsive for {} heartbeats, restarting it", missed);
+            if let Err(e) = super::restart(&app_handle) {
+                error!("Failed to restart unresponsive bio-engine: {}", e);
+            }
+            return;
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! Checks bundled sidecars against the SHA-256 manifest baked in at build time
+//! (see `build.rs`) before they are launched.
+
+use crate::error::AppError;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use tracing::{debug, warn};
+
+include!(concat!(env!("OUT_DIR"), "/binary_manifest.rs"));
+
+/// Sidecar name as it appears in the manifest: the file name without the
+/// target triple some resource copies keep, or the `.exe` suffix.
+fn manifest_name(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_string_lossy().to_string();
+    let stem = file_name.strip_suffix(".exe").unwrap_or(&file_name);
+    let suffix = format!("-{}", super::target_triple());
+    Some(stem.strip_suffix(&suffix).unwrap_or(stem).to_string())
+}
+
+/// Fails if `path` is a bundled sidecar whose contents differ from the build.
+/// Binaries the manifest does not know about (system tools, dev setups
+/// without real sidecars) are let through. Debug builds only warn, since
+/// sidecars are swapped freely during development.
+pub fn verify(path: &Path) -> Result<(), AppError> {
+    let Some(name) = manifest_name(path) else {
+        return Ok(());
+    };
+    let Some((_, expected)) = BUNDLED_BINARIES.iter().find(|(bundled, _)| *bundled == name) else {
+        debug!("{:?} is not in the bundled binary manifest, not verifying it", path);
+        return Ok(());
+    };
+    // A missing binary fails to spawn with its own, clearer, error.
+    let Ok(contents) = std::fs::read(path) else {
+        return Ok(());
+    };
+
+    let actual = format!("{:x}", Sha256::digest(&contents));
+    if actual == *expected {
+        debug!("Verified {} ({})", name, actual);
+        return Ok(());
+    }
+
+    let error = AppError::BinaryIntegrity {
+        name,
+        path: path.to_path_buf(),
+        expected: expected.to_string(),
+        actual,
+    };
+    if cfg!(debug_assertions) {
+        warn!("{} (ignored in debug builds)", error);
+        return Ok(());
+    }
+    Err(error)
+}
@@ -0,0 +1,270 @@
+use super::Endpoint;
+use crate::settings::Settings;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{debug, info, warn};
+
+/// Target triple suffix Tauri appends to `externalBin` sidecars for this build.
+pub fn target_triple() -> &'static str {
+    if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        "x86_64-unknown-linux-gnu"
+    } else if cfg!(all(target_os = "linux", target_arch = "aarch64")) {
+        "aarch64-unknown-linux-gnu"
+    } else if cfg!(target_os = "windows") {
+        "x86_64-pc-windows-msvc"
+    } else if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+        "aarch64-apple-darwin"
+    } else if cfg!(all(target_os = "macos", target_arch = "x86_64")) {
+        "x86_64-apple-darwin"
+    } else {
+        "unknown"
+    }
+}
+
+/// Where the bundler puts the bio-engine sidecar: next to the app executable,
+/// without the target triple.
+pub fn bundled_engine_path() -> Option<std::path::PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let name = format!("{}{}", super::SIDECAR_NAME, std::env::consts::EXE_SUFFIX);
+    Some(exe.parent()?.join(name))
+}
+
+/// Whether `path` is one of the bundled copies `resolve` looks for, in the
+/// resource directory or next to the executable, rather than a binary from
+/// a sidecar update, the settings or the system.
+pub fn is_bundled(app_handle: &AppHandle, path: &std::path::Path) -> bool {
+    let Some(dir) = path.parent() else {
+        return false;
+    };
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|p| p.to_path_buf()));
+    [
+        app_handle.path().resource_dir().ok(),
+        app_handle.path().executable_dir().ok(),
+        exe_dir,
+    ]
+    .into_iter()
+    .flatten()
+    .any(|bundled| bundled == dir)
+}
+
+/// Environment variable overriding how long startup may take, in seconds.
+pub const STARTUP_TIMEOUT_ENV: &str = "PS_ANALYZER_ENGINE_STARTUP_TIMEOUT";
+
+/// Environment and arguments the bio-engine is launched with.
+/// Resolved once at startup so every restart reuses the same tool paths.
+#[derive(Clone, Debug)]
+pub struct EngineLaunchConfig {
+    pub env: Vec<(String, String)>,
+    pub args: Vec<String>,
+    /// How long to wait for the engine to answer before reporting a failed start.
+    pub startup_timeout: Duration,
+}
+
+impl EngineLaunchConfig {
+    pub fn resolve(app_handle: &AppHandle, endpoint: &Endpoint, settings: &Settings) -> Self {
+        let startup_timeout = std::env::var(STARTUP_TIMEOUT_ENV)
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(settings.startup_timeout_secs));
+
+        let mut config = Self {
+            env: Vec::new(),
+            args: Vec::new(),
+            startup_timeout,
+        };
+        if let Some((listen_var, listen_on)) = endpoint.env() {
+            config.env.push((listen_var.to_string(), listen_on));
+        }
+        config.env.push((super::rpc::STDIO_RPC_ENV.to_string(), "1".to_string()));
+
+        // Resolve sidecar paths to pass them to the bio-engine
+        let target_triple = target_triple();
+
+        if let Ok(path_resolver) = app_handle.path().resource_dir() {
+            let tools = [
+                ("tracy", "TRACY_PATH", "--tracy-path"),
+                ("bgzip", "BIO_BGZIP_PATH", "--bgzip-path"),
+                ("samtools", "BIO_SAMTOOLS_PATH", "--samtools-path"),
+            ];
+
+            for (name, env_var, arg) in tools {
+                let sidecar_id = format!("ps-analyzer-{}", name);
+                let mut final_path = None;
+
+                // List of potential paths to check, in order of priority
+                let mut paths_to_check = Vec::new();
+
+                // 1. Standard sidecar location (Resource dir / {id}-{triple})
+                paths_to_check.push(path_resolver.join(format!("{}-{}", sidecar_id, target_triple)));
+            
+                // 2. Flattened resource location (Resource dir / {id})
+                paths_to_check.push(path_resolver.join(&sidecar_id));
+            
+                // Windows-specific: Add .exe variants
+                if cfg!(target_os = "windows") {
+                    paths_to_check.push(path_resolver.join(format!("{}-{}.exe", sidecar_id, target_triple)));
+                    paths_to_check.push(path_resolver.join(format!("{}.exe", sidecar_id)));
+                }
+
+                // 3. Executable directory (common for Linux packages)
+                if let Ok(exe_dir) = app_handle.path().executable_dir() {
+                    // Check with and without triple in exe_dir
+                    paths_to_check.push(exe_dir.join(format!("{}-{}", sidecar_id, target_triple)));
+                    paths_to_check.push(exe_dir.join(&sidecar_id));
+                    if cfg!(target_os = "windows") {
+                        paths_to_check.push(exe_dir.join(format!("{}-{}.exe", sidecar_id, target_triple)));
+                        paths_to_check.push(exe_dir.join(format!("{}.exe", sidecar_id)));
+                    }
+                }
+
+                // macOS: sidecars are bundled next to the main binary in
+                // `PS Analyzer.app/Contents/MacOS`, without the triple suffix.
+                if cfg!(target_os = "macos") {
+                    if let Some(macos_dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(|p| p.to_path_buf())) {
+                        paths_to_check.push(macos_dir.join(&sidecar_id));
+                        paths_to_check.push(macos_dir.join(format!("{}-{}", sidecar_id, target_triple)));
+                    }
+                }
+
+                // 4. Development fallback (Project root / src-tauri / binaries / {id}-{triple})
+                if let Ok(cwd) = std::env::current_dir() {
+                    paths_to_check.push(cwd.join(format!("src-tauri/binaries/{}-{}", sidecar_id, target_triple)));
+                    paths_to_check.push(cwd.join(format!("src-tauri/binaries/{}", sidecar_id)));
+                    if cfg!(target_os = "windows") {
+                        paths_to_check.push(cwd.join(format!("src-tauri/binaries/{}-{}.exe", sidecar_id, target_triple)));
+                        paths_to_check.push(cwd.join(format!("src-tauri/binaries/{}.exe", sidecar_id)));
+                    }
+                }
+
+                // 5. Explicit system paths (Final fallback for Linux and macOS)
+                if cfg!(any(target_os = "linux", target_os = "macos")) {
+                    paths_to_check.push(std::path::PathBuf::from(format!("/usr/bin/{}", sidecar_id)));
+                    paths_to_check.push(std::path::PathBuf::from(format!("/bin/{}", sidecar_id)));
+                    paths_to_check.push(std::path::PathBuf::from(format!("/usr/local/bin/{}", sidecar_id)));
+                    if cfg!(target_os = "macos") {
+                        // Homebrew prefix on Apple Silicon
+                        paths_to_check.push(std::path::PathBuf::from(format!("/opt/homebrew/bin/{}", sidecar_id)));
+                    }
+                
+                    // Conda fallbacks (for dev environment)
+                    if let Ok(home) = std::env::var("HOME") {
+                        paths_to_check.push(std::path::PathBuf::from(format!("{}/miniforge3/envs/bio-engine/bin/{}", home, name)));
+                        paths_to_check.push(std::path::PathBuf::from(format!("{}/anaconda3/envs/bio-engine/bin/{}", home, name)));
+                        paths_to_check.push(std::path::PathBuf::from(format!("{}/miniconda3/envs/bio-engine/bin/{}", home, name)));
+                    }
+                }
+
+                // Find the first path that exists
+                for path in &paths_to_check {
+                    debug!("Checking path for {}: {:?}", name, path);
+                    if path.exists() {
+                        final_path = Some(path.clone());
+                        debug!("Found {} at: {:?}", name, path);
+                        break;
+                    }
+                }
+
+                if let Some(path) = final_path {
+                    info!("Redirecting bio-engine to use {} at: {:?}", name, path);
+                    config.env.push((env_var.to_string(), path.to_string_lossy().to_string()));
+                    config.args.push(arg.to_string());
+                    config.args.push(path.to_string_lossy().to_string());
+                } else {
+                    // Final fallback: Don't pass the path, let bio-engine use system PATH
+                    warn!("Sidecar for {} not found. Bio-engine will attempt to use system '{}' from PATH.", name, name);
+                }
+            }
+
+            // Pass the resource directory itself (flat structure) for DLL discovery
+            let resource_path = path_resolver;
+            let resource_path_str = resource_path.to_string_lossy().to_string();
+            info!("Passing resource path to bio-engine: {}", resource_path_str);
+            config.args.push("--resource-path".to_string());
+            config.args.push(resource_path_str.clone());
+
+            // BEST PRACTICE: Add resource and binaries folders to the sidecar's PATH directly
+            // This helps Windows find DLLs even if the sidecar is launched from elsewhere
+            if let Ok(current_path) = std::env::var("PATH") {
+                let mut new_path = vec![resource_path_str.clone()];
+            
+                // Also add standard subfolders where DLLs might be
+                let binaries_sub = resource_path.join("binaries");
+                if binaries_sub.exists() {
+                    new_path.push(binaries_sub.to_string_lossy().to_string());
+                }
+            
+                let resources_sub = resource_path.join("resources/binaries");
+                if resources_sub.exists() {
+                    new_path.push(resources_sub.to_string_lossy().to_string());
+                }
+
+                new_path.push(current_path);
+                let final_path_env = new_path.join(if cfg!(target_os = "windows") { ";" } else { ":" });
+                config.env.push(("PATH".to_string(), final_path_env));
+            }
+        }
+
+        // Detect if we are running in a "portable" context
+        let mut data_dir_arg = None;
+        if let Ok(exe_dir) = app_handle.path().executable_dir() {
+            #[cfg_attr(not(target_os = "windows"), allow(unused_mut))]
+            let mut is_portable = exe_dir.join(".portable").exists() || exe_dir.join("portable").exists();
+        
+            // Windows: consider portable if not in Program Files and directory is writable
+            #[cfg(target_os = "windows")]
+            if !is_portable {
+                let exe_dir_str = exe_dir.to_string_lossy().to_lowercase();
+                let pf = std::env::var("ProgramFiles").unwrap_or_default().to_lowercase();
+                let pf86 = std::env::var("ProgramFiles(x86)").unwrap_or_default().to_lowercase();
+                if !exe_dir_str.starts_with(&pf) && !exe_dir_str.starts_with(&pf86) {
+                    is_portable = true;
+                }
+            }
+
+            if is_portable {
+                data_dir_arg = Some(exe_dir.join("data"));
+            }
+        }
+
+        // Linux: AppImage specific portable handling
+        if let Ok(appimage_path) = std::env::var("APPIMAGE") {
+            let path = std::path::Path::new(&appimage_path).parent();
+            if let Some(p) = path {
+                data_dir_arg = Some(p.join("ps-analyzer-data"));
+            }
+        }
+
+        if let Some(data_dir) = data_dir_arg {
+            let data_dir_str = data_dir.to_string_lossy().to_string();
+            info!("Portable mode detected. Using data directory: {}", data_dir_str);
+            config.args.push("--data-dir".to_string());
+            config.args.push(data_dir_str);
+        }
+
+        config
+    }
+
+    /// Sets an environment variable for the engine, replacing any earlier value.
+    pub fn set_env(&mut self, key: &str, value: String) {
+        self.env.retain(|(existing, _)| existing != key);
+        self.env.push((key.to_string(), value));
+    }
+
+    /// Points the engine at `path` for a tool, replacing whatever `resolve` found.
+    pub fn set_tool_path(&mut self, env_var: &str, arg: &str, path: &std::path::Path) {
+        let value = path.to_string_lossy().to_string();
+
+        self.set_env(env_var, value.clone());
+
+        match self.args.iter().position(|existing| existing == arg) {
+            Some(index) if index + 1 < self.args.len() => self.args[index + 1] = value,
+            _ => {
+                self.args.push(arg.to_string());
+                self.args.push(value);
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! CPU and memory use of the local engine together with everything it started
+//! (tracy, mostly), sampled every few seconds so the UI can show what the
+//! analysis costs and warn before the machine starts swapping.
+//!
+//! Past the `engine_memory_limit_gb` setting the engine is assumed to be
+//! leaking: it drains (running jobs finish, queued ones wait) and is then
+//! restarted, after which the queue carries on.
+
+use super::EngineManager;
+use crate::settings::SettingsStore;
+use crate::sidecar;
+use serde::Serialize;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{error, info, warn};
+
+/// Emitted with the [`EngineMetrics`] whenever the engine's memory use moves
+/// to another [`MemoryLevel`], including back to normal.
+pub const ENGINE_MEMORY_WARNING_EVENT: &str = "engine-memory-warning";
+
+/// Emitted with the [`EngineMetrics`] when the engine goes over its memory
+/// limit and starts draining for a restart.
+pub const ENGINE_MEMORY_LIMIT_EVENT: &str = "engine-memory-limit";
+
+const INTERVAL: Duration = Duration::from_secs(3);
+const GIGABYTE: u64 = 1024 * 1024 * 1024;
+/// Shares of the machine's memory, in percent, at which the levels start.
+const HIGH_MEMORY_PERCENT: u64 = 75;
+const CRITICAL_MEMORY_PERCENT: u64 = 90;
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryLevel {
+    Normal,
+    High,
+    Critical,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct EngineMetrics {
+    pub pid: u32,
+    /// The engine plus the processes it started.
+    pub process_count: usize,
+    /// Summed over the processes; 100 is one core fully busy.
+    pub cpu_percent: f32,
+    /// Resident memory summed over the processes.
+    pub memory_bytes: u64,
+    pub total_memory_bytes: u64,
+    pub memory_level: MemoryLevel,
+    /// Unix timestamp in seconds.
+    pub sampled_at: u64,
+}
+
+fn sample(system: &mut System, pid: u32) -> Option<EngineMetrics> {
+    system.refresh_memory();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_cpu().with_memory(),
+    );
+    let root = system.process(Pid::from_u32(pid))?;
+    let mut processes = vec![root];
+    processes.extend(
+        sidecar::descendants(system, pid)
+            .into_iter()
+            .filter_map(|pid| system.process(pid)),
+    );
+
+    let memory_bytes: u64 = processes.iter().map(|process| process.memory()).sum();
+    let total_memory_bytes = system.total_memory();
+    let percent = memory_bytes * 100 / total_memory_bytes.max(1);
+    let memory_level = if percent >= CRITICAL_MEMORY_PERCENT {
+        MemoryLevel::Critical
+    } else if percent >= HIGH_MEMORY_PERCENT {
+        MemoryLevel::High
+    } else {
+        MemoryLevel::Normal
+    };
+    Some(EngineMetrics {
+        pid,
+        process_count: processes.len(),
+        cpu_percent: processes.iter().map(|process| process.cpu_usage()).sum(),
+        memory_bytes,
+        total_memory_bytes,
+        memory_level,
+        sampled_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs()),
+    })
+}
+
+/// Samples the engine of `generation` for as long as it runs. A remote
+/// engine's processes are out of reach.
+pub async fn watch(app_handle: AppHandle, generation: u64) {
+    if app_handle.state::<EngineManager>().is_remote() {
+        return;
+    }
+    // CPU use is measured between refreshes of the same `System`.
+    let mut system = System::new();
+    let mut level = MemoryLevel::Normal;
+    loop {
+        tokio::time::sleep(INTERVAL).await;
+
+        let manager = app_handle.state::<EngineManager>();
+        if !manager.is_live(generation) {
+            *manager.metrics.lock().unwrap() = None;
+            return;
+        }
+        let Some(pid) = manager.status().pid else {
+            continue;
+        };
+        let Some(metrics) = sample(&mut system, pid) else {
+            continue;
+        };
+        *manager.metrics.lock().unwrap() = Some(metrics.clone());
+
+        let limit = app_handle
+            .state::<SettingsStore>()
+            .get()
+            .engine_memory_limit_gb
+            .map(|gb| u64::from(gb) * GIGABYTE);
+        if limit.is_some_and(|limit| metrics.memory_bytes > limit) && !manager.is_draining() {
+            warn!(
+                "bio-engine uses {} bytes, over its limit; restarting it once its jobs are done",
+                metrics.memory_bytes
+            );
+            manager.draining.store(true, Ordering::SeqCst);
+            let _ = app_handle.emit(ENGINE_MEMORY_LIMIT_EVENT, &metrics);
+        }
+        if manager.is_draining() && crate::jobs::running_count(&app_handle) == 0 {
+            recycle(&app_handle).await;
+            return;
+        }
+
+        if metrics.memory_level == level {
+            continue;
+        }
+        if metrics.memory_level > level {
+            warn!(
+                "bio-engine memory use is {:?}: {} of {} bytes",
+                metrics.memory_level, metrics.memory_bytes, metrics.total_memory_bytes
+            );
+        } else {
+            info!("bio-engine memory use is back to {:?}", metrics.memory_level);
+        }
+        level = metrics.memory_level;
+        let _ = app_handle.emit(ENGINE_MEMORY_WARNING_EVENT, &metrics);
+    }
+}
+
+/// Shuts the drained engine down, letting it exit cleanly, and starts a fresh
+/// one; the queue resumes once that is ready.
+async fn recycle(app_handle: &AppHandle) {
+    info!("Restarting the drained bio-engine to free its memory");
+    let handle = app_handle.clone();
+    let _ = tauri::async_runtime::spawn_blocking(move || super::shutdown(&handle)).await;
+    if let Err(e) = super::restart(app_handle) {
+        error!("Failed to restart bio-engine after draining it: {}", e);
+    }
+}
@@ -0,0 +1,638 @@
+mod auth;
+mod client;
+mod events;
+mod handshake;
+mod heartbeat;
+mod integrity;
+mod launch;
+mod metrics;
+mod orphan;
+mod readiness;
+mod recovery;
+mod rpc;
+mod state;
+mod transport;
+mod tunnel;
+
+pub use client::EngineClient;
+pub use launch::{bundled_engine_path, target_triple, EngineLaunchConfig};
+pub use metrics::EngineMetrics;
+pub use readiness::show_main_window;
+pub use state::{EngineState, EngineStatus};
+pub use transport::{Endpoint, EngineTransport};
+pub use tunnel::SshTunnel;
+
+use crate::engine_log::{self, EngineLogLine, LogStream};
+use crate::error::AppError;
+use crate::sidecar::{self, SidecarChild, SidecarEvent};
+use crate::settings::SettingsStore;
+use crate::sidecar_update;
+use crate::supervisor::ProcessSupervisor;
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::async_runtime::Receiver;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_shell::ShellExt;
+use tracing::{error, info, warn};
+
+/// Sidecar name as declared in `bundle.externalBin`.
+pub const SIDECAR_NAME: &str = "ps-analyzer-bio-engine";
+
+/// Name the engine is registered under with the [`ProcessSupervisor`].
+pub const TOOL_NAME: &str = "bio-engine";
+
+/// Environment variables pointing the engine at its helper binaries.
+pub const TOOL_PATH_VARS: [&str; 3] = ["TRACY_PATH", "BIO_BGZIP_PATH", "BIO_SAMTOOLS_PATH"];
+
+/// How long the engine gets to exit on its own when the app quits.
+const SHUTDOWN_GRACE_PERIOD: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Emitted with the engine port once a restart has respawned the sidecar.
+pub const ENGINE_RESTARTED_EVENT: &str = "engine-restarted";
+
+/// The running sidecar and the generation it was spawned as.
+struct Running {
+    generation: u64,
+    child: SidecarChild,
+}
+
+/// Managed state owning the running sidecar.
+pub struct EngineManager {
+    port: u16,
+    config: EngineLaunchConfig,
+    child: Mutex<Option<Running>>,
+    /// Bumped on every spawn so a monitor loop can tell whether the process it
+    /// watches is still the current one. Local spawns bump it while holding
+    /// `child`, so the two always agree under that lock.
+    generation: AtomicU64,
+    state: Mutex<EngineState>,
+    /// Consecutive crash-recovery attempts since the engine was last ready.
+    restart_attempts: AtomicU32,
+    /// Version reported by the running engine during the handshake.
+    engine_version: Mutex<Option<String>>,
+    /// Shared secret the engine requires on every request, see [`auth`].
+    token: String,
+    /// Calls made over the engine's stdin that are waiting for an answer.
+    rpc: rpc::Pending,
+    /// Whether the engine runs on another machine and is only monitored, never
+    /// spawned or killed.
+    remote: bool,
+    /// Latest resource sample of the running engine, see [`metrics`].
+    metrics: Mutex<Option<EngineMetrics>>,
+    /// The engine went over its memory limit: no further jobs start on it, and
+    /// it is restarted once the running ones are done.
+    draining: AtomicBool,
+    /// The handshake found an engine this build cannot talk to. Sticks until
+    /// the next spawn, so nothing takes the engine back to ready meanwhile.
+    incompatible: AtomicBool,
+}
+
+impl EngineManager {
+    pub fn new(port: u16, config: EngineLaunchConfig, remote: bool) -> Self {
+        Self {
+            port,
+            config,
+            child: Mutex::new(None),
+            generation: AtomicU64::new(0),
+            state: Mutex::new(EngineState::Stopped),
+            restart_attempts: AtomicU32::new(0),
+            engine_version: Mutex::new(None),
+            token: auth::generate_token(),
+            rpc: rpc::Pending::default(),
+            remote,
+            metrics: Mutex::new(None),
+            draining: AtomicBool::new(false),
+            incompatible: AtomicBool::new(false),
+        }
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    pub fn startup_timeout(&self) -> std::time::Duration {
+        self.config.startup_timeout
+    }
+
+    pub fn launch_config(&self) -> &EngineLaunchConfig {
+        &self.config
+    }
+
+    pub fn engine_version(&self) -> Option<String> {
+        self.engine_version.lock().unwrap().clone()
+    }
+
+    fn set_engine_version(&self, version: String) {
+        *self.engine_version.lock().unwrap() = Some(version);
+    }
+
+    pub fn state(&self) -> EngineState {
+        *self.state.lock().unwrap()
+    }
+
+    pub fn is_remote(&self) -> bool {
+        self.remote
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.state() == EngineState::Ready
+    }
+
+    /// Whether the handshake rejected the running engine.
+    pub fn is_incompatible(&self) -> bool {
+        self.incompatible.load(Ordering::SeqCst)
+    }
+
+    /// Whether the engine is waiting for its jobs to finish before a restart.
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    pub fn status(&self) -> EngineStatus {
+        EngineStatus {
+            state: self.state(),
+            port: self.port,
+            pid: self
+                .child
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|running| running.child.pid()),
+        }
+    }
+
+    /// Kills the processes the engine started (tracy, mostly) that run in
+    /// `dir` or were given a path inside it. Returns how many were killed;
+    /// always none for a remote engine.
+    pub fn kill_processes_in(&self, dir: &std::path::Path) -> usize {
+        let running = self.child.lock().unwrap();
+        let Some(Running { child, .. }) = running.as_ref() else {
+            return 0;
+        };
+        child.kill_descendants(|process| {
+            process.cwd().is_some_and(|cwd| cwd.starts_with(dir))
+                || process
+                    .cmd()
+                    .iter()
+                    .skip(1)
+                    .any(|arg| std::path::Path::new(arg).starts_with(dir))
+        })
+    }
+
+    fn is_current(&self, generation: u64) -> bool {
+        self.generation.load(Ordering::SeqCst) == generation
+    }
+
+    /// Whether the engine of `generation` is still in use and, if local, still running.
+    fn is_live(&self, generation: u64) -> bool {
+        self.is_current(generation) && (self.remote || self.child.lock().unwrap().is_some())
+    }
+}
+
+/// Moves the engine to `new_state`, notifying the frontend if it changed.
+pub fn set_state(app_handle: &AppHandle, new_state: EngineState) {
+    let manager = app_handle.state::<EngineManager>();
+    // An engine the handshake rejected never counts as ready, whatever
+    // answers it gives.
+    let new_state = if new_state == EngineState::Ready && manager.is_incompatible() {
+        EngineState::Degraded
+    } else {
+        new_state
+    };
+    let previous = std::mem::replace(&mut *manager.state.lock().unwrap(), new_state);
+    if previous != new_state {
+        info!("bio-engine state: {:?} -> {:?}", previous, new_state);
+        let _ = app_handle.emit(state::ENGINE_STATE_CHANGED_EVENT, manager.status());
+        crate::tray::update(app_handle);
+        if new_state == EngineState::Ready {
+            crate::file_intake::flush(app_handle);
+            crate::deep_link::flush(app_handle);
+            crate::jobs::dispatch(app_handle);
+        }
+    }
+}
+
+/// Returns `preferred` if it can be bound, otherwise asks the OS for a free
+/// ephemeral port by binding to port 0 and reading it back. The listener is
+/// dropped immediately so the sidecar can bind the port itself.
+pub fn get_available_port(preferred: Option<u16>) -> Result<u16, AppError> {
+    if let Some(port) = preferred {
+        match TcpListener::bind(("127.0.0.1", port)) {
+            Ok(_) => return Ok(port),
+            Err(e) => warn!("Configured engine port {} is unavailable ({}), picking another", port, e),
+        }
+    }
+    let listener = TcpListener::bind("127.0.0.1:0").map_err(AppError::NoPort)?;
+    let address = listener.local_addr().map_err(AppError::NoPort)?;
+    Ok(address.port())
+}
+
+/// Cleans up after a previous session that crashed without stopping its engine.
+pub fn reap_orphans(app_handle: &AppHandle) {
+    orphan::reap(app_handle);
+}
+
+/// Spawns the bio-engine with the managed launch configuration and starts
+/// monitoring its output. A remote engine is connected to instead.
+pub fn spawn(app_handle: &AppHandle) -> Result<(), AppError> {
+    let manager = app_handle.state::<EngineManager>();
+    if manager.is_remote() {
+        info!("Using remote bio-engine at {}", app_handle.state::<EngineClient>().url(""));
+        let generation = manager.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        manager.incompatible.store(false, Ordering::SeqCst);
+        set_state(app_handle, EngineState::Starting);
+        if app_handle.try_state::<SshTunnel>().is_some() {
+            tauri::async_runtime::spawn(tunnel::supervise(app_handle.clone(), generation));
+        }
+        watch(app_handle, generation);
+        return Ok(());
+    }
+
+    // Binaries installed by a sidecar update take precedence over the bundled
+    // ones, and a path set in the settings over both.
+    let settings = app_handle.state::<SettingsStore>().get();
+    let mut config = manager.config.clone();
+    if let Some(tracy) = sidecar_update::override_path(app_handle, "tracy") {
+        info!("Using updated tracy at {:?}", tracy);
+        config.set_tool_path("TRACY_PATH", "--tracy-path", &tracy);
+    }
+    if let Some(tracy) = &settings.tracy_path {
+        info!("Using tracy from settings at {:?}", tracy);
+        config.set_tool_path("TRACY_PATH", "--tracy-path", tracy);
+    }
+    let workers = settings.worker_count.unwrap_or_else(crate::system::recommended_workers);
+    config.set_env("BIO_WORKERS", workers.to_string());
+    for (key, value) in &settings.extra_env {
+        config.set_env(key, value.clone());
+    }
+    // Set on every spawn rather than in the resolved config, which ends up in
+    // support bundles.
+    config.set_env(auth::AUTH_TOKEN_ENV, manager.token().to_string());
+    let command = match sidecar_update::override_path(app_handle, "bio-engine") {
+        Some(path) => {
+            info!("Using updated bio-engine at {:?}", path);
+            app_handle.shell().command(path)
+        }
+        None => {
+            if let Some(path) = bundled_engine_path() {
+                integrity::verify(&path)?;
+            }
+            app_handle
+                .shell()
+                .sidecar(SIDECAR_NAME)
+                .map_err(AppError::SidecarMissing)?
+        }
+    };
+    // Updated binaries were verified when installed, and paths from the
+    // settings or the system are the user's own; only bundled copies are
+    // held to the manifest.
+    for (key, value) in &config.env {
+        let path = std::path::Path::new(value);
+        if TOOL_PATH_VARS.contains(&key.as_str()) && launch::is_bundled(app_handle, path) {
+            integrity::verify(path)?;
+        }
+    }
+    let sidecar_command = command.envs(config.env).args(config.args);
+
+    app_handle.state::<EngineClient>().endpoint().clear();
+    let (rx, child) =
+        sidecar::spawn(sidecar_command, settings.engine_priority).map_err(AppError::EngineSpawn)?;
+    manager.draining.store(false, Ordering::SeqCst);
+    manager.incompatible.store(false, Ordering::SeqCst);
+
+    let pid = child.pid();
+    app_handle.state::<ProcessSupervisor>().started(TOOL_NAME, pid, None);
+    orphan::record(app_handle, pid, manager.port());
+    let generation = {
+        let mut running = manager.child.lock().unwrap();
+        let generation = manager.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        *running = Some(Running { generation, child });
+        generation
+    };
+    set_state(app_handle, EngineState::Starting);
+
+    tauri::async_runtime::spawn(monitor(app_handle.clone(), rx, generation, pid));
+    watch(app_handle, generation);
+    Ok(())
+}
+
+/// Starts the readiness probe, heartbeat and event bridge for `generation`.
+fn watch(app_handle: &AppHandle, generation: u64) {
+    tauri::async_runtime::spawn(readiness::wait_until_ready(app_handle.clone(), generation));
+    tauri::async_runtime::spawn(heartbeat::watch(app_handle.clone(), generation));
+    tauri::async_runtime::spawn(metrics::watch(app_handle.clone(), generation));
+    tauri::async_runtime::spawn(events::bridge(app_handle.clone(), generation));
+}
+
+/// Stops monitoring a remote engine, which itself keeps running.
+fn disconnect(app_handle: &AppHandle) {
+    let manager = app_handle.state::<EngineManager>();
+    manager.generation.fetch_add(1, Ordering::SeqCst);
+    tunnel::close(app_handle);
+    set_state(app_handle, EngineState::Stopped);
+}
+
+/// Stops the engine on app exit or on request: asks it to shut down, gives it
+/// `SHUTDOWN_GRACE_PERIOD` to finish in-flight writes, then kills whatever is left.
+/// Blocks the calling thread.
+pub fn shutdown(app_handle: &AppHandle) {
+    let manager = app_handle.state::<EngineManager>();
+    if manager.is_remote() {
+        disconnect(app_handle);
+        return;
+    }
+    let running = manager.child.lock().unwrap().take();
+    let Some(Running { child, .. }) = running else {
+        return;
+    };
+    set_state(app_handle, EngineState::Stopped);
+
+    info!("Asking bio-engine (pid {}) to shut down", child.pid());
+    request_shutdown(&child, app_handle);
+
+    if child.wait_timeout(SHUTDOWN_GRACE_PERIOD) {
+        info!("bio-engine exited cleanly");
+    } else {
+        warn!(
+            "bio-engine did not exit within {:?}, forcing it",
+            SHUTDOWN_GRACE_PERIOD
+        );
+    }
+    // Also reaps any helper processes that outlived the engine itself.
+    if let Err(e) = child.kill_tree() {
+        if !is_already_gone(&e) {
+            error!("Failed to kill bio-engine process tree: {}", e);
+        }
+    }
+    orphan::clear(app_handle);
+    app_handle.state::<EngineClient>().endpoint().clear();
+}
+
+#[cfg(unix)]
+fn request_shutdown(child: &SidecarChild, _app_handle: &AppHandle) {
+    // A paused engine would only see the SIGTERM once resumed.
+    let _ = child.resume();
+    // uvicorn finishes in-flight requests on SIGTERM.
+    if let Err(e) = child.terminate() {
+        warn!("Failed to send SIGTERM to bio-engine: {}", e);
+    }
+}
+
+#[cfg(windows)]
+fn request_shutdown(_child: &SidecarChild, app_handle: &AppHandle) {
+    // No SIGTERM on Windows; ask over HTTP instead.
+    use tauri_plugin_http::reqwest::Method;
+    let client = app_handle.state::<EngineClient>();
+    let request = client
+        .request(Method::POST, "/shutdown")
+        .timeout(std::time::Duration::from_secs(2));
+    let result = tauri::async_runtime::block_on(client.execute(request));
+    if let Err(e) = result {
+        warn!("Shutdown request to bio-engine failed: {}", e);
+    }
+}
+
+/// `killpg` fails with ESRCH once the whole group is already gone.
+fn is_already_gone(error: &std::io::Error) -> bool {
+    #[cfg(unix)]
+    {
+        error.raw_os_error() == Some(libc::ESRCH)
+    }
+    #[cfg(windows)]
+    {
+        let _ = error;
+        false
+    }
+}
+
+/// Kills the running sidecar, if any, along with every process it started.
+pub fn kill(app_handle: &AppHandle) {
+    let manager = app_handle.state::<EngineManager>();
+    if manager.is_remote() {
+        disconnect(app_handle);
+        return;
+    }
+    let running = manager.child.lock().unwrap().take();
+    set_state(app_handle, EngineState::Stopped);
+    orphan::clear(app_handle);
+    if let Some(Running { child, .. }) = running {
+        let pid = child.pid();
+        match child.kill_tree() {
+            Ok(()) => info!("Killed bio-engine (pid {})", pid),
+            Err(e) => error!("Failed to kill bio-engine (pid {}): {}", pid, e),
+        }
+    }
+}
+
+async fn monitor(app_handle: AppHandle, mut rx: Receiver<SidecarEvent>, generation: u64, pid: u32) {
+    let port = app_handle.state::<EngineManager>().port();
+
+    while let Some(event) = rx.recv().await {
+        match event {
+            SidecarEvent::Stdout(line) => {
+                if rpc::dispatch(&app_handle, &line) || crate::progress::dispatch(&app_handle, &line) {
+                    continue;
+                }
+                let entry = EngineLogLine::new(LogStream::Stdout, &line);
+                engine_log::publish(&app_handle, entry);
+            }
+            SidecarEvent::Stderr(line) => {
+                let entry = EngineLogLine::new(LogStream::Stderr, &line);
+                if entry.line.contains("address already in use") {
+                    error!("CRITICAL: Port {} is occupied. Please ensure no other PS Analyzer instance is running.", port);
+                }
+                engine_log::publish(&app_handle, entry);
+            }
+            SidecarEvent::Terminated { code, signal } => {
+                app_handle.state::<ProcessSupervisor>().exited(pid, code, signal);
+                let manager = app_handle.state::<EngineManager>();
+                // Decided under the child lock, so a restart in between cannot
+                // hand this loop the child it just spawned.
+                let (current, exited) = {
+                    let mut running = manager.child.lock().unwrap();
+                    let ours = running
+                        .as_ref()
+                        .is_some_and(|running| running.generation == generation);
+                    let exited = if ours { running.take() } else { None };
+                    (manager.is_current(generation), exited)
+                };
+                if !current {
+                    break;
+                }
+                manager.rpc.fail_all();
+                // `kill` and `shutdown` take the child out of the slot and set
+                // the state themselves, so only a child still present here
+                // died on its own.
+                if exited.is_none() {
+                    break;
+                }
+                orphan::clear(&app_handle);
+                if code != Some(0) {
+                    set_state(&app_handle, EngineState::Crashed);
+                    tauri::async_runtime::spawn(recovery::recover(app_handle.clone(), generation));
+                } else {
+                    set_state(&app_handle, EngineState::Stopped);
+                }
+                break;
+            }
+        }
+    }
+}
+
+/// Proxies a frontend call to the engine. `path` is relative to the engine
+/// (e.g. `/jobs/42?full=true`); `body`, if any, is sent as JSON. JSON
+/// responses come back as JSON, anything else (reports, exports) as raw bytes.
+#[tauri::command]
+pub async fn engine_request(
+    app_handle: AppHandle,
+    method: String,
+    path: String,
+    body: Option<serde_json::Value>,
+    timeout_secs: Option<u64>,
+) -> Result<tauri::ipc::Response, AppError> {
+    client::proxy(&app_handle, method, path, body, timeout_secs).await
+}
+
+/// Calls `method` on the engine over stdin/stdout rather than HTTP. Also
+/// works while the engine is degraded because its HTTP server did not start.
+#[tauri::command]
+pub async fn engine_rpc(
+    app_handle: AppHandle,
+    method: String,
+    params: Option<serde_json::Value>,
+    timeout_secs: Option<u64>,
+) -> Result<serde_json::Value, AppError> {
+    let timeout = timeout_secs.map_or(rpc::DEFAULT_TIMEOUT, std::time::Duration::from_secs);
+    rpc::call(&app_handle, &method, params, timeout).await
+}
+
+/// Returns the port the bio-engine sidecar was told to listen on.
+#[tauri::command]
+pub fn get_engine_port(state: tauri::State<EngineManager>) -> u16 {
+    state.port()
+}
+
+/// Current lifecycle state of the engine, for windows that need it on load
+/// rather than waiting for the next `engine-state-changed` event.
+#[tauri::command]
+pub fn get_engine_status(state: tauri::State<EngineManager>) -> EngineStatus {
+    state.status()
+}
+
+/// CPU and memory use of the engine and its processes, as last sampled; `None`
+/// while no local engine runs.
+#[tauri::command]
+pub fn get_engine_metrics(state: tauri::State<EngineManager>) -> Option<EngineMetrics> {
+    state.metrics.lock().unwrap().clone()
+}
+
+/// Whether the engine has answered its readiness probe, for windows that
+/// missed the `engine-ready` event.
+#[tauri::command]
+pub fn is_engine_ready(state: tauri::State<EngineManager>) -> bool {
+    state.is_ready()
+}
+
+/// Kills the current bio-engine and starts a fresh one with the same configuration.
+pub fn restart(app_handle: &AppHandle) -> Result<(), AppError> {
+    info!("Restarting bio-engine...");
+    app_handle
+        .state::<EngineManager>()
+        .restart_attempts
+        .store(0, Ordering::SeqCst);
+    kill(app_handle);
+    spawn(app_handle)?;
+
+    let port = app_handle.state::<EngineManager>().port();
+    let _ = app_handle.emit(ENGINE_RESTARTED_EVENT, port);
+    Ok(())
+}
+
+/// Freezes the running engine so it stops using CPU until [`resume`]. Its
+/// memory is kept; stop the engine to free that as well.
+#[cfg(unix)]
+pub fn pause(app_handle: &AppHandle) -> Result<(), AppError> {
+    let manager = app_handle.state::<EngineManager>();
+    if manager.is_remote() {
+        return Err(AppError::RemoteUnsupported("pausing"));
+    }
+    if !matches!(manager.state(), EngineState::Ready | EngineState::Degraded) {
+        return Err(AppError::EngineNotRunning);
+    }
+    if let Some(Running { child, .. }) = manager.child.lock().unwrap().as_ref() {
+        child.suspend()?;
+    }
+    info!("bio-engine paused");
+    set_state(app_handle, EngineState::Paused);
+    Ok(())
+}
+
+#[cfg(windows)]
+pub fn pause(_app_handle: &AppHandle) -> Result<(), AppError> {
+    Err(AppError::PauseUnsupported)
+}
+
+/// Lets a paused engine run again. The heartbeat takes it back to degraded if
+/// it does not answer.
+pub fn resume(app_handle: &AppHandle) -> Result<(), AppError> {
+    let manager = app_handle.state::<EngineManager>();
+    if manager.state() != EngineState::Paused {
+        return Ok(());
+    }
+    #[cfg(unix)]
+    if let Some(Running { child, .. }) = manager.child.lock().unwrap().as_ref() {
+        child.resume()?;
+    }
+    info!("bio-engine resumed");
+    set_state(app_handle, EngineState::Ready);
+    Ok(())
+}
+
+/// Starts the engine after it was stopped. Does nothing if it is running.
+pub fn start(app_handle: &AppHandle) -> Result<(), AppError> {
+    let manager = app_handle.state::<EngineManager>();
+    let running = if manager.is_remote() {
+        manager.state() != EngineState::Stopped
+    } else {
+        manager.child.lock().unwrap().is_some()
+    };
+    if running {
+        return Ok(());
+    }
+    manager.restart_attempts.store(0, Ordering::SeqCst);
+    spawn(app_handle)
+}
+
+/// Stops the engine gracefully to free its memory and CPU until `start_engine`.
+#[tauri::command]
+pub async fn stop_engine(app_handle: AppHandle) -> Result<(), AppError> {
+    tauri::async_runtime::spawn_blocking(move || shutdown(&app_handle))
+        .await
+        .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))
+}
+
+#[tauri::command]
+pub async fn start_engine(app_handle: AppHandle) -> Result<(), AppError> {
+    start(&app_handle)
+}
+
+#[tauri::command]
+pub fn pause_engine(app_handle: AppHandle) -> Result<(), AppError> {
+    pause(&app_handle)
+}
+
+#[tauri::command]
+pub fn resume_engine(app_handle: AppHandle) -> Result<(), AppError> {
+    resume(&app_handle)
+}
+
+/// Kills the current bio-engine and starts a fresh one with the same configuration.
+#[tauri::command]
+pub async fn restart_engine(app_handle: AppHandle) -> Result<(), AppError> {
+    restart(&app_handle)
+}
@@ -0,0 +1,106 @@
+//! Pidfile bookkeeping so a bio-engine left behind by a crashed session can be
+//! found and terminated on the next launch.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+const PIDFILE_NAME: &str = "bio-engine.pid";
+
+/// Substring every bio-engine process carries in its name or executable,
+/// including the PyInstaller bootloader and its unpacked child.
+const PROCESS_MARKER: &str = "bio-engine";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PidFile {
+    pid: u32,
+    port: u16,
+}
+
+fn pidfile_path(app_handle: &AppHandle) -> Option<PathBuf> {
+    crate::portable::app_data_dir(app_handle)
+        .ok()
+        .map(|dir| dir.join(PIDFILE_NAME))
+}
+
+/// Remembers the freshly spawned engine so it can be reaped if we crash.
+pub fn record(app_handle: &AppHandle, pid: u32, port: u16) {
+    let Some(path) = pidfile_path(app_handle) else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let contents = serde_json::to_string(&PidFile { pid, port }).unwrap_or_default();
+    if let Err(e) = std::fs::write(&path, contents) {
+        warn!("Could not write engine pidfile {:?}: {}", path, e);
+    }
+}
+
+/// Forgets the engine after it stopped through our own hands.
+pub fn clear(app_handle: &AppHandle) {
+    if let Some(path) = pidfile_path(app_handle) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Terminates a bio-engine recorded by a previous session that is still alive.
+/// The pid is only trusted if the process still looks like a bio-engine, since
+/// the OS may have recycled it for something unrelated.
+pub fn reap(app_handle: &AppHandle) {
+    let Some(path) = pidfile_path(app_handle) else {
+        return;
+    };
+    let Some(previous) = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<PidFile>(&contents).ok())
+    else {
+        return;
+    };
+
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing()
+            .with_exe(UpdateKind::OnlyIfNotSet)
+            .with_cmd(UpdateKind::OnlyIfNotSet),
+    );
+
+    let root = Pid::from_u32(previous.pid);
+    let is_engine = |pid: Pid| {
+        system.process(pid).is_some_and(|process| {
+            process.name().to_string_lossy().contains(PROCESS_MARKER)
+                || process
+                    .exe()
+                    .is_some_and(|exe| exe.to_string_lossy().contains(PROCESS_MARKER))
+                || process
+                    .cmd()
+                    .first()
+                    .is_some_and(|arg| arg.to_string_lossy().contains(PROCESS_MARKER))
+        })
+    };
+
+    if is_engine(root) {
+        warn!(
+            "Found orphaned bio-engine from a previous session (pid {}, port {}), terminating it",
+            previous.pid, previous.port
+        );
+        // Children first: the PyInstaller bootloader does not always take its
+        // unpacked interpreter down with it.
+        for (pid, process) in system.processes() {
+            if process.parent() == Some(root) && is_engine(*pid) {
+                process.kill();
+            }
+        }
+        if let Some(process) = system.process(root) {
+            process.kill();
+        }
+    } else {
+        info!("Stale engine pidfile found, process {} is gone", previous.pid);
+    }
+
+    clear(app_handle);
+}
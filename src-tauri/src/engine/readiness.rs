@@ -0,0 +1,145 @@
+use super::{EngineClient, EngineManager, EngineState};
+use crate::engine_log::{EngineLogBuffer, LogStream};
+use serde::Serialize;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use tauri_plugin_http::reqwest::Method;
+use tracing::{info, warn};
+
+/// Emitted with the engine port once the HTTP server answers.
+pub const ENGINE_READY_EVENT: &str = "engine-ready";
+
+/// Emitted with a [`StartFailure`] when the engine misses its startup deadline.
+pub const ENGINE_START_FAILED_EVENT: &str = "engine-start-failed";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+/// Stderr lines included in the failure report.
+const STDERR_TAIL: usize = 40;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StartFailure {
+    pub timeout_secs: u64,
+    pub stderr: Vec<String>,
+}
+
+/// Whether the engine answers its root endpoint with a success status within `timeout`.
+pub async fn probe(client: &EngineClient, timeout: Duration) -> bool {
+    let request = client.request(Method::GET, "/").timeout(timeout);
+    match client.execute(request).await {
+        Ok(response) => response.status().is_success(),
+        Err(_) => false,
+    }
+}
+
+/// Polls the engine root endpoint until it answers, then marks the engine ready
+/// and swaps the splash screen for the main window.
+pub async fn wait_until_ready(app_handle: AppHandle, generation: u64) {
+    let timeout = app_handle.state::<EngineManager>().startup_timeout();
+
+    let client = app_handle.state::<EngineClient>();
+
+    let started = Instant::now();
+    loop {
+        if !app_handle.state::<EngineManager>().is_current(generation) {
+            // A restart superseded this process; its own probe takes over.
+            return;
+        }
+
+        if probe(&client, REQUEST_TIMEOUT).await {
+            break;
+        }
+
+        if started.elapsed() > timeout {
+            warn!("bio-engine did not become ready within {:?}", timeout);
+            super::set_state(&app_handle, EngineState::Degraded);
+            show_main_window(&app_handle);
+            // An engine whose HTTP server could not bind may still be usable over stdio.
+            if super::rpc::call(&app_handle, "ping", None, REQUEST_TIMEOUT).await.is_ok() {
+                warn!("bio-engine HTTP API is unreachable, only stdio RPC is available");
+            } else {
+                report_start_failure(&app_handle, timeout);
+            }
+            return;
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    if let Err(e) = super::handshake::verify(&app_handle, &client, REQUEST_TIMEOUT).await {
+        app_handle
+            .state::<EngineManager>()
+            .incompatible
+            .store(true, Ordering::SeqCst);
+        super::set_state(&app_handle, EngineState::Degraded);
+        show_main_window(&app_handle);
+        crate::error::report(&app_handle, &e);
+        return;
+    }
+
+    info!("bio-engine ready on {:?} after {:?}", client.endpoint(), started.elapsed());
+    became_ready(&app_handle);
+    show_main_window(&app_handle);
+}
+
+/// Marks the engine ready and tells the frontend, whether it just started or
+/// came back from being degraded.
+pub fn became_ready(app_handle: &AppHandle) {
+    super::set_state(app_handle, EngineState::Ready);
+    if !app_handle.state::<EngineManager>().is_ready() {
+        return;
+    }
+    super::recovery::mark_recovered(app_handle);
+    let port = app_handle.state::<EngineManager>().port();
+    let _ = app_handle.emit(ENGINE_READY_EVENT, port);
+}
+
+/// Tells the user the engine failed to start, with whatever it printed to
+/// stderr so far, both as an event and as a native dialog.
+fn report_start_failure(app_handle: &AppHandle, timeout: Duration) {
+    let stderr: Vec<String> = app_handle
+        .state::<EngineLogBuffer>()
+        .recent(None, None)
+        .into_iter()
+        .filter(|entry| entry.stream == LogStream::Stderr)
+        .map(|entry| entry.line)
+        .collect();
+    let stderr = stderr[stderr.len().saturating_sub(STDERR_TAIL)..].to_vec();
+
+    let failure = StartFailure {
+        timeout_secs: timeout.as_secs(),
+        stderr,
+    };
+    let _ = app_handle.emit(ENGINE_START_FAILED_EVENT, &failure);
+
+    let mut message = format!(
+        "The analysis engine did not start within {} seconds. Analyses will not be available until it does.",
+        failure.timeout_secs
+    );
+    if !failure.stderr.is_empty() {
+        message.push_str("\n\nEngine output:\n");
+        message.push_str(&failure.stderr.join("\n"));
+    }
+    app_handle
+        .dialog()
+        .message(message)
+        .title("PS Analyzer – engine failed to start")
+        .kind(MessageDialogKind::Error)
+        .show(|_| {});
+}
+
+/// Closes the splash screen (if still open) and reveals the main window.
+pub fn show_main_window(app_handle: &AppHandle) {
+    if let Some(splash) = app_handle.get_webview_window("splashscreen") {
+        let _ = splash.close();
+    }
+    if let Some(main) = app_handle.get_webview_window("main") {
+        let _ = main.show();
+        let _ = main.set_focus();
+    }
+    if let Some(main) = app_handle.get_window("main") {
+        crate::window_state::restore_maximized(&main);
+    }
+}
@@ -0,0 +1,78 @@
+use super::{EngineManager, TOOL_NAME};
+use crate::supervisor::ProcessSupervisor;
+use serde::Serialize;
+use std::sync::atomic::Ordering;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{error, info, warn};
+
+/// Emitted while the engine is being brought back after a crash.
+pub const ENGINE_RECOVERY_EVENT: &str = "engine-recovery";
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RecoveryStatus {
+    Restarting {
+        attempt: u32,
+        max_attempts: u32,
+        delay_ms: u64,
+    },
+    Recovered {
+        attempts: u32,
+    },
+    GaveUp {
+        attempts: u32,
+    },
+}
+
+fn emit(app_handle: &AppHandle, status: RecoveryStatus) {
+    let _ = app_handle.emit(ENGINE_RECOVERY_EVENT, status);
+}
+
+/// Respawns a crashed engine, backing off between attempts as its
+/// [`RestartPolicy`](crate::supervisor::RestartPolicy) says. A respawn only
+/// counts as successful once the readiness probe passes, which calls
+/// [`mark_recovered`]; until then every further crash consumes an attempt.
+pub async fn recover(app_handle: AppHandle, crashed_generation: u64) {
+    let policy = app_handle.state::<ProcessSupervisor>().restart_policy(TOOL_NAME);
+    let max_attempts = policy.max_attempts();
+    loop {
+        let manager = app_handle.state::<EngineManager>();
+        let attempt = manager.restart_attempts.fetch_add(1, Ordering::SeqCst) + 1;
+        let Some(delay) = policy.delay(attempt) else {
+            error!("bio-engine crashed {} times in a row, giving up", max_attempts);
+            emit(&app_handle, RecoveryStatus::GaveUp { attempts: max_attempts });
+            return;
+        };
+
+        warn!("Restarting bio-engine in {:?} (attempt {}/{})", delay, attempt, max_attempts);
+        emit(
+            &app_handle,
+            RecoveryStatus::Restarting {
+                attempt,
+                max_attempts,
+                delay_ms: delay.as_millis() as u64,
+            },
+        );
+        tokio::time::sleep(delay).await;
+
+        // Someone restarted the engine by hand while we were waiting.
+        if !app_handle.state::<EngineManager>().is_current(crashed_generation) {
+            return;
+        }
+
+        match super::spawn(&app_handle) {
+            Ok(()) => return,
+            Err(e) => error!("Failed to respawn bio-engine: {}", e),
+        }
+    }
+}
+
+/// Called once a (re)spawned engine passes its readiness probe.
+pub fn mark_recovered(app_handle: &AppHandle) {
+    let manager = app_handle.state::<EngineManager>();
+    let attempts = manager.restart_attempts.swap(0, Ordering::SeqCst);
+    if attempts > 0 {
+        info!("bio-engine recovered after {} attempt(s)", attempts);
+        emit(app_handle, RecoveryStatus::Recovered { attempts });
+    }
+}
@@ -0,0 +1,145 @@
+//! Line-delimited JSON-RPC 2.0 over the sidecar's stdin/stdout, for when the
+//! engine's HTTP server cannot bind and for small calls that do not need the
+//! HTTP stack.
+//!
+//! Requests are written to the engine's stdin one per line. The engine answers
+//! on stdout, where response lines are picked out of its regular output by
+//! [`dispatch`]; everything else still goes to the engine log.
+
+use super::EngineManager;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::oneshot;
+use tracing::debug;
+
+/// Environment variable telling the engine to serve JSON-RPC on stdin/stdout.
+pub const STDIO_RPC_ENV: &str = "BIO_STDIO_RPC";
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Serialize)]
+struct Request<'a> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    params: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    jsonrpc: String,
+    id: u64,
+    #[serde(default)]
+    result: Value,
+    error: Option<ErrorObject>,
+}
+
+#[derive(Deserialize)]
+struct ErrorObject {
+    code: i64,
+    message: String,
+}
+
+/// Calls waiting for their response line.
+#[derive(Default)]
+pub struct Pending {
+    next_id: AtomicU64,
+    waiting: Mutex<HashMap<u64, oneshot::Sender<Result<Value, AppError>>>>,
+}
+
+impl Pending {
+    fn register(&self) -> (u64, oneshot::Receiver<Result<Value, AppError>>) {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let (tx, rx) = oneshot::channel();
+        self.waiting.lock().unwrap().insert(id, tx);
+        (id, rx)
+    }
+
+    fn forget(&self, id: u64) {
+        self.waiting.lock().unwrap().remove(&id);
+    }
+
+    /// Fails every outstanding call, once the engine that was to answer is gone.
+    pub fn fail_all(&self) {
+        self.waiting.lock().unwrap().clear();
+    }
+}
+
+/// Hands `line` to the call it answers. Returns whether it was an RPC
+/// response, in which case it is not engine output to be logged.
+pub fn dispatch(app_handle: &AppHandle, line: &[u8]) -> bool {
+    if !line.trim_ascii_start().starts_with(b"{") {
+        return false;
+    }
+    let Ok(response) = serde_json::from_slice::<Response>(line) else {
+        return false;
+    };
+    if response.jsonrpc != "2.0" {
+        return false;
+    }
+    let result = match response.error {
+        Some(error) => Err(AppError::EngineRpc(format!("{} (code {})", error.message, error.code))),
+        None => Ok(response.result),
+    };
+    let manager = app_handle.state::<EngineManager>();
+    match manager.rpc.waiting.lock().unwrap().remove(&response.id) {
+        Some(tx) => {
+            let _ = tx.send(result);
+        }
+        None => debug!("Dropping engine RPC response {} nobody is waiting for", response.id),
+    }
+    true
+}
+
+/// Calls `method` on the running engine over its stdin.
+pub async fn call(
+    app_handle: &AppHandle,
+    method: &str,
+    params: Option<Value>,
+    timeout: Duration,
+) -> Result<Value, AppError> {
+    let manager = app_handle.state::<EngineManager>();
+    if manager.is_remote() {
+        return Err(AppError::RemoteUnsupported("stdio RPC"));
+    }
+    let (id, rx) = manager.rpc.register();
+    let request = Request {
+        jsonrpc: "2.0",
+        id,
+        method,
+        params,
+    };
+    let mut line = serde_json::to_vec(&request).map_err(|e| AppError::EngineRpc(e.to_string()))?;
+    line.push(b'\n');
+
+    let written = match manager.child.lock().unwrap().as_ref() {
+        Some(running) => running
+            .child
+            .write_stdin(&line)
+            .map_err(|e| AppError::EngineRpc(e.to_string())),
+        None => Err(AppError::EngineNotRunning),
+    };
+    if let Err(e) = written {
+        manager.rpc.forget(id);
+        return Err(e);
+    }
+
+    match tokio::time::timeout(timeout, rx).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err(AppError::EngineRpc(format!(
+            "the bio-engine exited before answering {}",
+            method
+        ))),
+        Err(_) => {
+            manager.rpc.forget(id);
+            Err(AppError::EngineRpc(format!("no answer to {} within {:?}", method, timeout)))
+        }
+    }
+}
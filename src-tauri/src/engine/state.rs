@@ -0,0 +1,36 @@
+use serde::Serialize;
+
+/// Emitted with an [`EngineStatus`] whenever the engine changes state.
+pub const ENGINE_STATE_CHANGED_EVENT: &str = "engine-state-changed";
+
+/// Lifecycle of the bio-engine sidecar as seen by the Rust shell.
+///
+/// ```text
+/// Stopped ──spawn──▶ Starting ──probe ok──▶ Ready
+///                       │  └──probe timeout / API mismatch──▶ Degraded
+///                       └──────exit≠0──────▶ Crashed ──recovery──▶ Starting
+/// Ready ──pause──▶ Paused ──resume──▶ Ready
+/// ```
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineState {
+    /// Process spawned, HTTP server not answering yet.
+    Starting,
+    /// Answering requests.
+    Ready,
+    /// Process alive but not answering as it should.
+    Degraded,
+    /// Process frozen on request; not using CPU until resumed.
+    Paused,
+    /// Process exited unexpectedly.
+    Crashed,
+    /// Not running, on purpose.
+    Stopped,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct EngineStatus {
+    pub state: EngineState,
+    pub port: u16,
+    pub pid: Option<u32>,
+}
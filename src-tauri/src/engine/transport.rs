@@ -0,0 +1,184 @@
+//! How the shell reaches the engine's HTTP API. By default that is TCP on a
+//! localhost port; with the `socket` transport the engine listens on a Unix
+//! domain socket (Linux/macOS) or named pipe (Windows) at a path picked here,
+//! which cannot clash with another program's port and is not reachable
+//! through the network stack at all.
+//!
+//! reqwest only speaks TCP, so requests over a socket are sent with hyper
+//! directly and handed back as ordinary `reqwest::Response`s.
+
+use crate::error::AppError;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri_plugin_http::reqwest::{self, header};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tracing::debug;
+
+/// Environment variable telling the engine which socket or pipe to listen on
+/// instead of `BIO_PORT`.
+pub const SOCKET_ENV: &str = "BIO_SOCKET";
+
+/// Which transport the engine is started with.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineTransport {
+    #[default]
+    Tcp,
+    Socket,
+}
+
+/// Where the engine listens.
+#[derive(Clone, Debug)]
+pub enum Endpoint {
+    Tcp(u16),
+    Socket(PathBuf),
+    /// Base URL of an engine on another machine, which the shell does not launch.
+    Remote(String),
+}
+
+impl Endpoint {
+    /// The endpoint for `transport`; `port` is only used for TCP.
+    pub fn new(transport: EngineTransport, port: u16) -> Self {
+        match transport {
+            EngineTransport::Tcp => Endpoint::Tcp(port),
+            EngineTransport::Socket => Endpoint::Socket(socket_path()),
+        }
+    }
+
+    /// Environment variable and value telling a local engine where to listen.
+    pub fn env(&self) -> Option<(&'static str, String)> {
+        match self {
+            Endpoint::Tcp(port) => Some(("BIO_PORT", port.to_string())),
+            Endpoint::Socket(path) => Some((SOCKET_ENV, path.to_string_lossy().to_string())),
+            Endpoint::Remote(_) => None,
+        }
+    }
+
+    /// What request URLs are built on. Over a socket only the path is sent.
+    pub fn base_url(&self) -> String {
+        match self {
+            Endpoint::Tcp(port) => format!("http://127.0.0.1:{}", port),
+            Endpoint::Socket(_) => "http://localhost".to_string(),
+            Endpoint::Remote(url) => url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Removes a socket file left behind by an engine that was killed, which
+    /// would otherwise keep the next one from binding. Pipes vanish on their own.
+    pub fn clear(&self) {
+        #[cfg(unix)]
+        if let Endpoint::Socket(path) = self {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// A socket or pipe path unique to this app instance. Kept short: Unix socket
+/// paths are limited to ~100 bytes.
+fn socket_path() -> PathBuf {
+    let name = format!("ps-analyzer-engine-{}", std::process::id());
+    #[cfg(unix)]
+    {
+        std::env::temp_dir().join(format!("{}.sock", name))
+    }
+    #[cfg(windows)]
+    {
+        PathBuf::from(format!(r"\\.\pipe\{}", name))
+    }
+}
+
+#[cfg(unix)]
+pub type SocketStream = tokio::net::UnixStream;
+#[cfg(windows)]
+pub type SocketStream = tokio::net::windows::named_pipe::NamedPipeClient;
+
+/// Opens a connection to the engine's socket or pipe at `path`.
+pub async fn connect(path: &Path) -> std::io::Result<SocketStream> {
+    #[cfg(unix)]
+    {
+        tokio::net::UnixStream::connect(path).await
+    }
+    #[cfg(windows)]
+    {
+        windows::connect(path).await
+    }
+}
+
+/// Sends `request` over the socket or pipe at `path`, on a connection of its own.
+pub async fn execute(path: &Path, request: reqwest::Request) -> Result<reqwest::Response, AppError> {
+    let request = to_hyper(&request)?;
+    let stream = connect(path).await.map_err(unreachable)?;
+    exchange(stream, request).await.map_err(unreachable)
+}
+
+fn unreachable(e: impl std::fmt::Display) -> AppError {
+    AppError::EngineUnreachable(e.to_string())
+}
+
+fn to_hyper(request: &reqwest::Request) -> Result<http::Request<Full<Bytes>>, AppError> {
+    let body = match request.body() {
+        None => Bytes::new(),
+        Some(body) => body
+            .as_bytes()
+            .map(Bytes::copy_from_slice)
+            .ok_or_else(|| unreachable("streaming bodies cannot be sent over the engine socket"))?,
+    };
+    let url = request.url();
+    let target = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    let mut builder = http::Request::builder()
+        .method(request.method().clone())
+        .uri(target)
+        .header(header::HOST, "localhost");
+    for (name, value) in request.headers() {
+        builder = builder.header(name, value);
+    }
+    builder.body(Full::new(body)).map_err(unreachable)
+}
+
+async fn exchange<S>(stream: S, request: http::Request<Full<Bytes>>) -> Result<reqwest::Response, hyper::Error>
+where
+    S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = connection.await {
+            debug!("Engine socket connection ended: {}", e);
+        }
+    });
+    let response = sender.send_request(request).await?;
+    let (parts, body) = response.into_parts();
+    let body = body.collect().await?.to_bytes();
+    Ok(http::Response::from_parts(parts, body).into())
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::path::Path;
+    use std::time::Duration;
+    use tokio::net::windows::named_pipe::{ClientOptions, NamedPipeClient};
+    use windows_sys::Win32::Foundation::ERROR_PIPE_BUSY;
+
+    const BUSY_ATTEMPTS: u32 = 20;
+    const BUSY_DELAY: Duration = Duration::from_millis(50);
+
+    /// Opens the pipe, waiting while every instance is taken; the server
+    /// creates a fresh one as soon as it accepts a connection.
+    pub async fn connect(path: &Path) -> std::io::Result<NamedPipeClient> {
+        let mut attempt = 1;
+        loop {
+            match ClientOptions::new().open(path) {
+                Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY as i32) && attempt < BUSY_ATTEMPTS => {
+                    attempt += 1;
+                    tokio::time::sleep(BUSY_DELAY).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! SSH port forwarding for remote engines behind institutional firewalls.
+//!
+//! Runs the system `ssh` client (`ssh -N -L …`), which already handles keys,
+//! agents, `~/.ssh/config` and known hosts, and restarts it whenever it drops
+//! for as long as the engine is in use.
+
+use super::EngineManager;
+use crate::error::AppError;
+use crate::settings::SshSettings;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Url};
+use tracing::{info, warn};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const BASE_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+/// A tunnel that stayed up this long resets the reconnect backoff.
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+/// Managed state for the forward to a remote engine, present only when one
+/// is configured.
+pub struct SshTunnel {
+    settings: SshSettings,
+    /// Engine host and port as seen from the SSH server.
+    target: (String, u16),
+    target_path: String,
+    local_port: u16,
+    child: Mutex<Option<Child>>,
+}
+
+impl SshTunnel {
+    /// A forward from `local_port` to the engine at `url`, which is resolved
+    /// on the SSH server (so `http://127.0.0.1:8000` is its own loopback).
+    pub fn new(settings: SshSettings, url: &str, local_port: u16) -> Result<Self, AppError> {
+        let url = Url::parse(url)
+            .map_err(|e| AppError::InvalidSettings(format!("remote_engine.url {:?}: {}", url, e)))?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| AppError::InvalidSettings(format!("remote_engine.url {} has no host", url)))?;
+        let port = url.port_or_known_default().unwrap_or(80);
+        Ok(Self {
+            settings,
+            target: (host.to_string(), port),
+            target_path: url.path().trim_end_matches('/').to_string(),
+            local_port,
+            child: Mutex::new(None),
+        })
+    }
+
+    /// The URL the engine is reachable at through the tunnel.
+    pub fn local_url(&self) -> String {
+        format!("http://127.0.0.1:{}{}", self.local_port, self.target_path)
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new("ssh");
+        command
+            .arg("-N")
+            .args(["-o", "BatchMode=yes"])
+            .args(["-o", "ExitOnForwardFailure=yes"])
+            .args(["-o", "ServerAliveInterval=15"])
+            .args(["-o", "ServerAliveCountMax=3"])
+            .args(["-o", "StrictHostKeyChecking=accept-new"])
+            .arg("-L")
+            .arg(format!("127.0.0.1:{}:{}:{}", self.local_port, self.target.0, self.target.1));
+        if let Some(port) = self.settings.port {
+            command.arg("-p").arg(port.to_string());
+        }
+        if let Some(identity) = &self.settings.identity_file {
+            command.arg("-i").arg(identity).args(["-o", "IdentitiesOnly=yes"]);
+        }
+        command
+            .arg(&self.settings.host)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+        command
+    }
+
+    /// Closes the tunnel, if open.
+    pub fn close(&self) {
+        if let Some(mut child) = self.child.lock().unwrap().take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+
+    /// Whether the current `ssh` is still running; reaps it if not.
+    fn is_open(&self) -> bool {
+        let mut child = self.child.lock().unwrap();
+        match child.as_mut().map(Child::try_wait) {
+            Some(Ok(None)) => true,
+            Some(Ok(Some(status))) => {
+                warn!("SSH tunnel to {} exited with {}", self.settings.host, status);
+                *child = None;
+                false
+            }
+            Some(Err(e)) => {
+                warn!("SSH tunnel to {} lost: {}", self.settings.host, e);
+                *child = None;
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+/// Keeps the tunnel open for as long as the engine connection `generation`
+/// is in use, reconnecting with backoff whenever it drops.
+pub async fn supervise(app_handle: AppHandle, generation: u64) {
+    let mut delay = BASE_DELAY;
+    loop {
+        // `disconnect` closes the tunnel; by then a newer connection may
+        // already have opened its own, which must be left alone.
+        let tunnel = app_handle.state::<SshTunnel>();
+        if !app_handle.state::<EngineManager>().is_live(generation) {
+            return;
+        }
+
+        let mut child = match tunnel.command().spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                let e = AppError::Io(std::io::Error::new(
+                    e.kind(),
+                    format!("could not start ssh for the remote engine tunnel: {}", e),
+                ));
+                crate::error::report(&app_handle, &e);
+                return;
+            }
+        };
+        info!(
+            "Opened SSH tunnel 127.0.0.1:{} -> {} via {}",
+            tunnel.local_port, tunnel.target.0, tunnel.settings.host
+        );
+        if let Some(stderr) = child.stderr.take() {
+            std::thread::spawn(move || {
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    warn!("ssh: {}", line);
+                }
+            });
+        }
+        *tunnel.child.lock().unwrap() = Some(child);
+
+        let opened = Instant::now();
+        while tunnel.is_open() {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            if !app_handle.state::<EngineManager>().is_live(generation) {
+                return;
+            }
+        }
+
+        if opened.elapsed() > STABLE_AFTER {
+            delay = BASE_DELAY;
+        }
+        warn!("Reconnecting SSH tunnel in {:?}", delay);
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_DELAY);
+    }
+}
+
+/// Closes the tunnel, if one is configured, so no `ssh` outlives the connection.
+pub fn close(app_handle: &AppHandle) {
+    if let Some(tunnel) = app_handle.try_state::<SshTunnel>() {
+        tunnel.close();
+    }
+}
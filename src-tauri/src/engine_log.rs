@@ -0,0 +1,154 @@
+use crate::logging::ENGINE_TARGET;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Event name the frontend listens on for live engine console output.
+pub const ENGINE_LOG_EVENT: &str = "engine-log";
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// Ordered by severity, so `level >= LogLevel::Warn` selects warnings and errors.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+/// A single line of sidecar output, as delivered to the frontend.
+#[derive(Clone, Debug, Serialize)]
+pub struct EngineLogLine {
+    pub level: LogLevel,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    pub stream: LogStream,
+    pub line: String,
+    /// Correlation ID of the job the engine wrote this line for, if any.
+    pub job: Option<String>,
+}
+
+impl EngineLogLine {
+    pub fn new(stream: LogStream, raw: &[u8]) -> Self {
+        let line = String::from_utf8_lossy(raw).trim_end().to_string();
+        let level = detect_level(stream, &line);
+        let job = crate::jobs::log_tag(&line);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        Self {
+            level,
+            timestamp,
+            stream,
+            line,
+            job,
+        }
+    }
+}
+
+/// Guesses the severity of a line from the Python/uvicorn log prefixes.
+/// Python logs everything to stderr, so the stream alone says little.
+fn detect_level(stream: LogStream, line: &str) -> LogLevel {
+    let upper = line.to_uppercase();
+    if upper.contains("CRITICAL") || upper.contains("ERROR") || upper.starts_with("TRACEBACK") {
+        LogLevel::Error
+    } else if upper.contains("WARNING") || upper.contains("WARN:") {
+        LogLevel::Warn
+    } else if upper.contains("DEBUG") {
+        LogLevel::Debug
+    } else if upper.contains("INFO") {
+        LogLevel::Info
+    } else if stream == LogStream::Stderr {
+        LogLevel::Warn
+    } else {
+        LogLevel::Info
+    }
+}
+
+/// Number of recent lines kept in memory for `get_engine_logs`.
+const BUFFER_CAPACITY: usize = 5000;
+
+/// Bounded ring buffer of the most recent sidecar output, held in managed state.
+pub struct EngineLogBuffer {
+    lines: Mutex<VecDeque<EngineLogLine>>,
+}
+
+impl EngineLogBuffer {
+    pub fn new() -> Self {
+        Self {
+            lines: Mutex::new(VecDeque::with_capacity(BUFFER_CAPACITY)),
+        }
+    }
+
+    fn push(&self, entry: EngineLogLine) {
+        let mut lines = self.lines.lock().unwrap();
+        if lines.len() == BUFFER_CAPACITY {
+            lines.pop_front();
+        }
+        lines.push_back(entry);
+    }
+
+    /// The newest `limit` lines at or above `min_level`, oldest first.
+    pub fn recent(&self, min_level: Option<LogLevel>, limit: Option<usize>) -> Vec<EngineLogLine> {
+        let lines = self.lines.lock().unwrap();
+        let limit = limit.unwrap_or(BUFFER_CAPACITY);
+        let mut selected: Vec<EngineLogLine> = lines
+            .iter()
+            .rev()
+            .filter(|entry| min_level.is_none_or(|level| entry.level >= level))
+            .take(limit)
+            .cloned()
+            .collect();
+        selected.reverse();
+        selected
+    }
+}
+
+/// Logs, buffers and forwards a line of sidecar output.
+pub fn publish(app_handle: &AppHandle, entry: EngineLogLine) {
+    record(&entry);
+    emit(app_handle, &entry);
+    if let Some(job) = &entry.job {
+        crate::jobs::append_log(app_handle, job, &entry);
+    }
+    app_handle.state::<EngineLogBuffer>().push(entry);
+}
+
+/// Recent engine output for the diagnostics panel, without tailing log files.
+/// `level` is the minimum severity; `limit` caps the number of lines returned.
+#[tauri::command]
+pub fn get_engine_logs(
+    buffer: tauri::State<EngineLogBuffer>,
+    level: Option<LogLevel>,
+    limit: Option<usize>,
+) -> Vec<EngineLogLine> {
+    buffer.recent(level, limit)
+}
+
+/// Writes a line to the app log under the `engine` target, at the level
+/// detected from its content.
+fn record(entry: &EngineLogLine) {
+    match entry.level {
+        LogLevel::Debug => tracing::debug!(target: ENGINE_TARGET, "{}", entry.line),
+        LogLevel::Info => tracing::info!(target: ENGINE_TARGET, "{}", entry.line),
+        LogLevel::Warn => tracing::warn!(target: ENGINE_TARGET, "{}", entry.line),
+        LogLevel::Error => tracing::error!(target: ENGINE_TARGET, "{}", entry.line),
+    }
+}
+
+/// Emits a line to every window. Failures are ignored: losing a console line
+/// must never take down the monitor loop.
+fn emit(app_handle: &AppHandle, entry: &EngineLogLine) {
+    let _ = app_handle.emit(ENGINE_LOG_EVENT, entry);
+}
@@ -0,0 +1,207 @@
+use serde::{Serialize, Serializer};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use tracing::error;
+
+/// Emitted with an [`AppErrorPayload`] whenever an error is reported to the user.
+pub const APP_ERROR_EVENT: &str = "app-error";
+
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    /// The bundled bio-engine binary could not be located, usually a packaging problem.
+    #[error("the bio-engine sidecar could not be found: {0}")]
+    SidecarMissing(#[source] tauri_plugin_shell::Error),
+    #[error("the bio-engine could not be started: {0}")]
+    EngineSpawn(#[source] std::io::Error),
+    /// The engine speaks a different API revision than this build expects,
+    /// e.g. a stale binary left behind by a partial update.
+    #[error(
+        "the bio-engine ({engine_version}, API revision {engine_api_revision}) is not compatible \
+         with PS Analyzer {app_version}, which expects API revision {expected_api_revision}; \
+         please reinstall the application"
+    )]
+    EngineIncompatible {
+        engine_version: String,
+        engine_api_revision: u32,
+        expected_api_revision: u32,
+        app_version: String,
+    },
+    /// A bundled sidecar does not match the hash recorded at build time.
+    #[error(
+        "{name} at {path:?} has been modified or corrupted (SHA-256 {actual}, expected {expected}); \
+         refusing to launch it, please reinstall the application"
+    )]
+    BinaryIntegrity {
+        name: String,
+        path: std::path::PathBuf,
+        expected: String,
+        actual: String,
+    },
+    #[error("the bio-engine is not running")]
+    EngineNotRunning,
+    #[cfg_attr(unix, allow(dead_code))]
+    #[error("pausing the bio-engine is not supported on this platform; stop it instead")]
+    PauseUnsupported,
+    #[error("{0} is not possible with a remote bio-engine")]
+    RemoteUnsupported(&'static str),
+    #[error("{0} is not available in portable mode")]
+    PortableUnsupported(&'static str),
+    #[error("invalid settings: {0}")]
+    InvalidSettings(String),
+    #[error("no local port is available for the bio-engine: {0}")]
+    NoPort(#[source] std::io::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("update failed: {0}")]
+    Update(#[from] tauri_plugin_updater::Error),
+    #[error("sidecar update failed: {0}")]
+    SidecarUpdate(String),
+    #[error("download failed: {0}")]
+    Http(#[from] tauri_plugin_http::reqwest::Error),
+    /// Nothing answered on the engine's socket or pipe.
+    #[error("the bio-engine could not be reached: {0}")]
+    EngineUnreachable(String),
+    /// A JSON-RPC call over the engine's stdin/stdout failed.
+    #[error("bio-engine call failed: {0}")]
+    EngineRpc(String),
+    /// The engine answered with an error status.
+    #[error("the bio-engine rejected the request ({status}): {message}")]
+    EngineRequest { status: u16, message: String },
+    #[error("no job with id {0}")]
+    JobNotFound(u64),
+    #[error("job failed: {0}")]
+    JobFailed(String),
+    #[error("no log was kept for job {0}")]
+    JobLogNotFound(String),
+    #[error(
+        "not enough disk space at {path:?}: about {} MB needed, {} MB free",
+        .needed / 1_000_000,
+        .available / 1_000_000
+    )]
+    InsufficientSpace {
+        path: std::path::PathBuf,
+        needed: u64,
+        available: u64,
+    },
+    #[error("upload failed: {0}")]
+    Upload(String),
+    #[error("there is no update to install; check for updates first")]
+    NoPendingUpdate,
+    /// The build has no signing key to check downloads against.
+    #[error("updates are not configured in this build")]
+    UpdatesNotConfigured,
+    #[error("could not encode the response: {0}")]
+    Encoding(String),
+    #[error("failed to write archive: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("cannot open project: {0}")]
+    InvalidProject(String),
+    #[error("cannot read trace {0}")]
+    InvalidTrace(String),
+    #[error("invalid sequence file: {0}")]
+    InvalidSequence(String),
+    #[error("cannot read reference {0}")]
+    InvalidReference(String),
+    #[error("cannot use the data folder: {0}")]
+    DataDir(String),
+    #[error("could not write the report: {0}")]
+    Report(String),
+    #[error("could not draw the image: {0}")]
+    Image(String),
+    #[error("NCBI request failed: {0}")]
+    Ncbi(String),
+    /// A bundled command-line tool (BLAST, Primer3, ...) failed or is missing.
+    #[error("{0}")]
+    Tool(String),
+    #[error("cannot align: {0}")]
+    Alignment(String),
+    #[error("unknown restriction enzyme {0}")]
+    UnknownEnzyme(String),
+    #[error("no window labelled {0}")]
+    WindowNotFound(String),
+    #[error("database error: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Tauri(#[from] tauri::Error),
+}
+
+impl AppError {
+    /// Stable identifier the frontend can switch on.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::SidecarMissing(_) => "sidecar_missing",
+            AppError::EngineSpawn(_) => "engine_spawn",
+            AppError::EngineIncompatible { .. } => "engine_incompatible",
+            AppError::BinaryIntegrity { .. } => "binary_integrity",
+            AppError::EngineNotRunning => "engine_not_running",
+            AppError::PauseUnsupported => "pause_unsupported",
+            AppError::RemoteUnsupported(_) => "remote_unsupported",
+            AppError::PortableUnsupported(_) => "portable_unsupported",
+            AppError::InvalidSettings(_) => "invalid_settings",
+            AppError::NoPort(_) => "no_port",
+            AppError::Io(_) => "io",
+            AppError::Update(_) => "update",
+            AppError::SidecarUpdate(_) => "sidecar_update",
+            AppError::Http(_) => "http",
+            AppError::EngineUnreachable(_) => "engine_unreachable",
+            AppError::EngineRpc(_) => "engine_rpc",
+            AppError::EngineRequest { .. } => "engine_request",
+            AppError::JobNotFound(_) => "job_not_found",
+            AppError::JobFailed(_) => "job_failed",
+            AppError::JobLogNotFound(_) => "job_log_not_found",
+            AppError::InsufficientSpace { .. } => "insufficient_space",
+            AppError::Upload(_) => "upload",
+            AppError::NoPendingUpdate => "no_pending_update",
+            AppError::UpdatesNotConfigured => "updates_not_configured",
+            AppError::Encoding(_) => "encoding",
+            AppError::Zip(_) => "zip",
+            AppError::InvalidProject(_) => "invalid_project",
+            AppError::InvalidTrace(_) => "invalid_trace",
+            AppError::InvalidSequence(_) => "invalid_sequence",
+            AppError::InvalidReference(_) => "invalid_reference",
+            AppError::DataDir(_) => "data_dir",
+            AppError::Report(_) => "report",
+            AppError::Image(_) => "image",
+            AppError::Ncbi(_) => "ncbi",
+            AppError::Tool(_) => "tool",
+            AppError::Alignment(_) => "alignment",
+            AppError::UnknownEnzyme(_) => "unknown_enzyme",
+            AppError::WindowNotFound(_) => "window_not_found",
+            AppError::Database(_) => "database",
+            AppError::Tauri(_) => "tauri",
+        }
+    }
+}
+
+// Commands return the message; the structured form goes out with `app-error`.
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AppErrorPayload {
+    pub kind: &'static str,
+    pub message: String,
+}
+
+/// Logs `err`, emits it as `app-error` and shows it in a native error dialog,
+/// so a broken install tells the user what is wrong instead of silently
+/// leaving the splash screen up.
+pub fn report(app_handle: &AppHandle, err: &AppError) {
+    error!("{}", err);
+
+    let payload = AppErrorPayload {
+        kind: err.kind(),
+        message: err.to_string(),
+    };
+    let _ = app_handle.emit(APP_ERROR_EVENT, &payload);
+
+    app_handle
+        .dialog()
+        .message(payload.message)
+        .title("PS Analyzer – error")
+        .kind(MessageDialogKind::Error)
+        .show(|_| {});
+}
@@ -0,0 +1,151 @@
+//! Files dropped onto the window. Directories are expanded, unsupported files
+//! reported back, and large files copied to local staging before they go
+//! through [`file_intake`] like any other opened file.
+
+use crate::file_intake;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, DragDropEvent, Emitter, Manager, Window, WindowEvent};
+use tracing::{info, warn};
+
+/// Emitted with a [`RejectedFilesPayload`] for dropped files the app cannot open.
+pub const FILES_REJECTED_EVENT: &str = "files-rejected";
+
+/// Emitted with a [`StagingPayload`] while large dropped files are copied.
+pub const FILES_STAGING_EVENT: &str = "files-staging";
+
+/// How deep dropped directories are searched.
+const MAX_DEPTH: usize = 4;
+/// Dropped files above this size are copied locally first, so a slow network
+/// share or a removable drive that goes away does not break the analysis.
+pub const STAGE_THRESHOLD_BYTES: u64 = 50 * 1024 * 1024;
+const STAGING_DIR: &str = "staging";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RejectedFilesPayload {
+    pub paths: Vec<PathBuf>,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StagingPayload {
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+/// Window event hook; only drops are of interest.
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    if let WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) = event {
+        let app_handle = window.app_handle().clone();
+        let paths = paths.clone();
+        // Walking directories and copying files must not stall the event loop.
+        std::thread::spawn(move || intake(&app_handle, paths));
+    }
+}
+
+fn intake(app_handle: &AppHandle, dropped: Vec<PathBuf>) {
+    let mut files = Vec::new();
+    for path in dropped {
+        collect(&path, 0, &mut files);
+    }
+
+    let (supported, rejected): (Vec<_>, Vec<_>) =
+        files.into_iter().partition(|path| file_intake::is_supported(path));
+    if !rejected.is_empty() {
+        info!("Rejected {} dropped file(s) of unsupported type", rejected.len());
+        let _ = app_handle.emit(
+            FILES_REJECTED_EVENT,
+            RejectedFilesPayload {
+                paths: rejected,
+                reason: format!(
+                    "unsupported file type, expected one of: {}",
+                    file_intake::SUPPORTED_EXTENSIONS.join(", ")
+                ),
+            },
+        );
+    }
+
+    let staged: Vec<PathBuf> = supported
+        .into_iter()
+        .map(|path| stage_if_large(app_handle, path))
+        .collect();
+    file_intake::intake(app_handle, staged);
+}
+
+/// Adds `path` to `files`, or everything below it if it is a directory.
+/// Hidden entries (`.DS_Store`, `.git`, ...) are skipped.
+fn collect(path: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    let hidden = path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+    if hidden && depth > 0 {
+        return;
+    }
+
+    if path.is_dir() {
+        if depth >= MAX_DEPTH {
+            warn!("Not descending into {:?}: nested too deeply", path);
+            return;
+        }
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(path)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .collect();
+        entries.sort();
+        for entry in entries {
+            collect(&entry, depth + 1, files);
+        }
+    } else if !hidden {
+        files.push(path.to_path_buf());
+    }
+}
+
+/// Copies files above [`STAGE_THRESHOLD_BYTES`] to the app cache; returns the
+/// original path if the file is small or the copy fails.
+fn stage_if_large(app_handle: &AppHandle, path: PathBuf) -> PathBuf {
+    let Ok(bytes) = std::fs::metadata(&path).map(|metadata| metadata.len()) else {
+        return path;
+    };
+    if bytes <= STAGE_THRESHOLD_BYTES {
+        return path;
+    }
+    let (Ok(cache_dir), Some(file_name)) = (crate::data_dir::cache(app_handle), path.file_name())
+    else {
+        return path;
+    };
+
+    // A directory per drop keeps same-named files from different folders apart.
+    let nonce = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default();
+    let destination = cache_dir
+        .join(STAGING_DIR)
+        .join(nonce.to_string())
+        .join(file_name);
+
+    let _ = app_handle.emit(
+        FILES_STAGING_EVENT,
+        StagingPayload {
+            path: path.clone(),
+            bytes,
+        },
+    );
+    let copied = destination
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::copy(&path, &destination));
+    match copied {
+        Ok(_) => {
+            info!("Staged {:?} ({} bytes) at {:?}", path, bytes, destination);
+            destination
+        }
+        Err(e) => {
+            warn!("Could not stage {:?}, using it in place: {}", path, e);
+            path
+        }
+    }
+}
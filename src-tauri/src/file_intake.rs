@@ -0,0 +1,169 @@
+//! Single entry point for files handed to the app from outside the UI (command
+//! line, OS "open with", a second launch), so they are validated the same way
+//! and held back until the engine can analyse them.
+
+use crate::engine::EngineManager;
+use crate::recent::{self, RecentKind};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+/// Emitted, once the engine is ready, with the files to open.
+pub const OPEN_FILES_EVENT: &str = "open-files";
+
+/// Extensions the app opens, lower case. Keep in sync with
+/// `bundle.fileAssociations` in `tauri.conf.json`.
+pub const SUPPORTED_EXTENSIONS: [&str; 8] = ["ab1", "fasta", "fa", "fastq", "fq", "gb", "gbk", "dna"];
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OpenFilesPayload {
+    pub paths: Vec<PathBuf>,
+}
+
+/// Whether `path` has one of the extensions the app opens.
+pub fn is_supported(path: &Path) -> bool {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+        .is_some_and(|extension| SUPPORTED_EXTENSIONS.contains(&extension.as_str()))
+}
+
+/// The canonical form of `path` if it is an existing file of a supported type.
+pub fn validate(path: &Path) -> Option<PathBuf> {
+    if !is_supported(path) {
+        warn!("Ignoring {:?}: unsupported file type", path);
+        return None;
+    }
+    match path.canonicalize() {
+        Ok(canonical) if canonical.is_file() => Some(canonical),
+        Ok(_) => {
+            warn!("Ignoring {:?}: not a file", path);
+            None
+        }
+        Err(e) => {
+            warn!("Ignoring {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Files received before the engine was ready, in arrival order.
+#[derive(Default)]
+pub struct PendingFiles(Mutex<Vec<PathBuf>>);
+
+/// Validates `paths` and emits the usable ones as `open-files`, or queues them
+/// until [`flush`] if the engine is not ready yet.
+pub fn intake(app_handle: &AppHandle, paths: impl IntoIterator<Item = PathBuf>) {
+    let paths: Vec<PathBuf> = paths.into_iter().filter_map(|path| validate(&path)).collect();
+    if paths.is_empty() {
+        return;
+    }
+
+    let pending = app_handle.state::<PendingFiles>();
+    let mut queue = pending.0.lock().unwrap();
+    for path in paths {
+        if !queue.contains(&path) {
+            queue.push(path);
+        }
+    }
+    drop(queue);
+
+    if app_handle.state::<EngineManager>().is_ready() {
+        flush(app_handle);
+    } else {
+        info!("Engine not ready, holding files until it is");
+    }
+}
+
+/// Emits every queued file. Called whenever the engine becomes ready.
+pub fn flush(app_handle: &AppHandle) {
+    let paths = std::mem::take(&mut *app_handle.state::<PendingFiles>().0.lock().unwrap());
+    if paths.is_empty() {
+        return;
+    }
+    info!("Opening {} file(s): {:?}", paths.len(), paths);
+    for path in &paths {
+        recent::record(app_handle, path, RecentKind::File);
+    }
+    let _ = app_handle.emit(OPEN_FILES_EVENT, OpenFilesPayload { paths });
+}
+
+/// Files passed on the command line of this launch, e.g. `ps-analyzer run1/*.ab1`.
+pub fn intake_args(app_handle: &AppHandle, args: impl IntoIterator<Item = String>, cwd: &Path) {
+    let paths = args
+        .into_iter()
+        // Flags, and deep links, which the deep-link plugin picks up itself.
+        .filter(|arg| !arg.starts_with('-') && !arg.contains("://"))
+        .flat_map(|arg| expand(&cwd.join(arg)));
+    intake(app_handle, paths);
+}
+
+/// Expands a `*`/`?` wildcard in the file name, since Windows shells leave
+/// globs to the program. Paths without one, or that exist as is, are returned
+/// unchanged.
+fn expand(path: &Path) -> Vec<PathBuf> {
+    let Some(pattern) = path.file_name().map(|name| name.to_string_lossy().to_string()) else {
+        return vec![path.to_path_buf()];
+    };
+    if path.exists() || !pattern.contains(['*', '?']) {
+        return vec![path.to_path_buf()];
+    }
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+
+    let mut matches: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| wildcard_match(&pattern, &entry.file_name().to_string_lossy()))
+        .map(|entry| entry.path())
+        .collect();
+    matches.sort();
+    matches
+}
+
+/// Matches a file name against a pattern where `*` is any run of characters
+/// and `?` any single one.
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name index it was tried at.
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, tried)) = backtrack {
+            p = star + 1;
+            n = tried + 1;
+            backtrack = Some((star, tried + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Hands over and clears the queued files, for a frontend that loaded after
+/// the `open-files` event was sent.
+#[tauri::command]
+pub fn take_pending_files(pending: tauri::State<PendingFiles>) -> Vec<PathBuf> {
+    std::mem::take(&mut *pending.0.lock().unwrap())
+}
+
+/// Files the OS asked us to open through a file association (macOS delivers
+/// these as URLs instead of arguments).
+#[cfg(target_os = "macos")]
+pub fn intake_urls(app_handle: &AppHandle, urls: Vec<tauri::Url>) {
+    intake(
+        app_handle,
+        urls.into_iter().filter_map(|url| url.to_file_path().ok()),
+    );
+}
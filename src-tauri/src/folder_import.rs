@@ -0,0 +1,292 @@
+//! Importing a whole folder tree, e.g. a sequencing run, in two steps:
+//! `import_directory` scans it and returns an [`ImportPlan`] for the user to
+//! review, then `commit_import` opens the files they kept like any others.
+
+use crate::disk;
+use crate::duplicates::{self, DuplicateGroup, ImportedFiles, SkippedDuplicate};
+use crate::error::AppError;
+use crate::file_intake::{self, wildcard_match};
+use crate::temp::TempStore;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tracing::{debug, info, warn};
+
+/// Scans stop here, so pointing at a home directory cannot hang the UI.
+const MAX_FILES: usize = 50_000;
+
+/// Trailing name tokens marking a forward or reverse read, lower case.
+const FORWARD_TOKENS: [&str; 4] = ["f", "fwd", "forward", "r1"];
+const REVERSE_TOKENS: [&str; 4] = ["r", "rev", "reverse", "r2"];
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ImportOptions {
+    /// Globs a file must match one of, e.g. `*.ab1` or `plate1/**`. A pattern
+    /// without `/` is matched against the file name only. Everything
+    /// supported is included when empty.
+    pub include: Vec<String>,
+    /// Globs of files and folders to leave out, matched the same way.
+    pub exclude: Vec<String>,
+    /// Descend into subfolders.
+    pub recursive: bool,
+    /// Group forward and reverse reads of the same sample.
+    pub pair_reads: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            recursive: true,
+            pair_reads: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PlannedFile {
+    pub path: PathBuf,
+    /// Relative to the scanned folder, with `/` separators.
+    pub relative: String,
+    /// Lower-case extension, e.g. `ab1`.
+    pub format: String,
+    pub size: u64,
+}
+
+/// A forward and a reverse read of one sample, both also listed in `files`.
+#[derive(Clone, Debug, Serialize)]
+pub struct ReadPair {
+    /// The shared part of the two names, e.g. `sample1` for `sample1_F.ab1`.
+    pub sample: String,
+    pub forward: PathBuf,
+    pub reverse: PathBuf,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ImportPlan {
+    pub root: PathBuf,
+    /// Files to import, sorted by path.
+    pub files: Vec<PlannedFile>,
+    pub pairs: Vec<ReadPair>,
+    /// Files skipped for not being a supported format.
+    pub unsupported: usize,
+    /// Supported files left out by the include/exclude globs.
+    pub excluded: usize,
+    /// The scan stopped at the file limit before seeing the whole tree.
+    pub truncated: bool,
+    /// Files in `files` with the same contents under different names;
+    /// `commit_import` keeps only the first of each unless told otherwise.
+    pub duplicates: Vec<DuplicateGroup>,
+}
+
+/// Scans `path` for files the app opens and plans their import; nothing is
+/// opened until `commit_import`.
+#[tauri::command]
+pub async fn import_directory(path: PathBuf, options: Option<ImportOptions>) -> Result<ImportPlan, AppError> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || plan(&path, &options))
+        .await
+        .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))?
+}
+
+/// Opens the files of a reviewed plan, waiting for the engine if needed.
+/// Refused if the temp volume cannot hold what analysing them takes. Files
+/// whose contents were already imported, in this plan or earlier this
+/// session, are skipped and returned unless `allow_duplicates` is set.
+#[tauri::command]
+pub fn commit_import(
+    app_handle: AppHandle,
+    paths: Vec<PathBuf>,
+    allow_duplicates: Option<bool>,
+) -> Result<Vec<SkippedDuplicate>, AppError> {
+    let input_bytes = paths
+        .iter()
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+    let temp = app_handle.state::<TempStore>();
+    disk::preflight(&app_handle, input_bytes, temp.root(), None)?;
+    let (paths, skipped) = app_handle
+        .state::<ImportedFiles>()
+        .admit(paths, allow_duplicates.unwrap_or(false));
+    for duplicate in &skipped {
+        warn!("Skipping {:?}: same contents as {:?}", duplicate.path, duplicate.duplicate_of);
+    }
+    info!("Importing {} file(s) from a folder", paths.len());
+    file_intake::intake(&app_handle, paths);
+    Ok(skipped)
+}
+
+/// The plan for importing `root`, as returned by `import_directory`.
+pub fn plan(root: &Path, options: &ImportOptions) -> Result<ImportPlan, AppError> {
+    let root = root.canonicalize()?;
+    if !root.is_dir() {
+        return Err(AppError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{:?} is not a folder", root),
+        )));
+    }
+
+    let mut plan = ImportPlan {
+        root: root.clone(),
+        files: Vec::new(),
+        pairs: Vec::new(),
+        unsupported: 0,
+        excluded: 0,
+        truncated: false,
+        duplicates: Vec::new(),
+    };
+    let mut folders = vec![root.clone()];
+    while let Some(folder) = folders.pop() {
+        let entries = match std::fs::read_dir(&folder) {
+            Ok(entries) => entries,
+            Err(e) => {
+                debug!("Skipping unreadable folder {:?}: {}", folder, e);
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            let relative = relative(&root, &path);
+            // Symlinks are not followed, so a link loop cannot trap the scan.
+            let Ok(kind) = entry.file_type() else {
+                continue;
+            };
+            if kind.is_dir() {
+                if options.recursive && !matches_any(&options.exclude, &relative, &name) {
+                    folders.push(path);
+                }
+                continue;
+            }
+            if !kind.is_file() {
+                continue;
+            }
+            if !file_intake::is_supported(&path) {
+                plan.unsupported += 1;
+                continue;
+            }
+            let included = options.include.is_empty() || matches_any(&options.include, &relative, &name);
+            if !included || matches_any(&options.exclude, &relative, &name) {
+                plan.excluded += 1;
+                continue;
+            }
+            if plan.files.len() == MAX_FILES {
+                plan.truncated = true;
+                break;
+            }
+            plan.files.push(PlannedFile {
+                format: extension(&path),
+                size: entry.metadata().map_or(0, |metadata| metadata.len()),
+                path,
+                relative,
+            });
+        }
+        if plan.truncated {
+            break;
+        }
+    }
+
+    plan.files.sort_by(|a, b| a.path.cmp(&b.path));
+    if options.pair_reads {
+        plan.pairs = pair(&plan.files);
+    }
+    plan.duplicates = duplicates::find(plan.files.iter().map(|file| file.path.as_path()));
+    Ok(plan)
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+/// Whether any glob matches: ones without `/` against the name, the others
+/// against the whole relative path, where `**` spans any number of folders.
+fn matches_any(patterns: &[String], relative: &str, name: &str) -> bool {
+    patterns.iter().any(|pattern| {
+        if pattern.contains('/') {
+            let pattern: Vec<&str> = pattern.trim_start_matches('/').split('/').collect();
+            let path: Vec<&str> = relative.split('/').collect();
+            glob_segments(&pattern, &path)
+        } else {
+            wildcard_match(pattern, name)
+        }
+    })
+}
+
+fn glob_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| glob_segments(rest, &path[skip..])),
+        Some((segment, rest)) => match path.split_first() {
+            Some((name, path)) => wildcard_match(segment, name) && glob_segments(rest, path),
+            None => false,
+        },
+    }
+}
+
+/// Pairs files in the same folder, of the same format, whose names differ
+/// only in a trailing forward/reverse token such as `_F`/`_R` or `_R1`/`_R2`.
+fn pair(files: &[PlannedFile]) -> Vec<ReadPair> {
+    type Key = (PathBuf, String, String);
+    let mut candidates: BTreeMap<Key, (Vec<&PlannedFile>, Vec<&PlannedFile>)> = BTreeMap::new();
+    for file in files {
+        let Some((sample, forward)) = direction(&file.path) else {
+            continue;
+        };
+        let folder = file.path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let entry = candidates.entry((folder, sample, file.format.clone())).or_default();
+        if forward {
+            entry.0.push(file);
+        } else {
+            entry.1.push(file);
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter_map(|((_, sample, _), (forward, reverse))| match (forward.as_slice(), reverse.as_slice()) {
+            // Ambiguous groups are left for the user to pair by hand.
+            ([forward], [reverse]) => Some(ReadPair {
+                sample,
+                forward: forward.path.clone(),
+                reverse: reverse.path.clone(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The sample name and whether the read is forward, for names ending in a
+/// direction token after `_`, `-` or `.`.
+fn direction(path: &Path) -> Option<(String, bool)> {
+    let stem = path.file_stem()?.to_string_lossy().to_string();
+    let split = stem.rfind(['_', '-', '.'])?;
+    let (sample, token) = (&stem[..split], stem[split + 1..].to_ascii_lowercase());
+    if sample.is_empty() {
+        return None;
+    }
+    if FORWARD_TOKENS.contains(&token.as_str()) {
+        Some((sample.to_string(), true))
+    } else if REVERSE_TOKENS.contains(&token.as_str()) {
+        Some((sample.to_string(), false))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,223 @@
+//! GenBank and EMBL flat files. Both share the INSDC feature table layout
+//! (key in columns 6-20, location and qualifiers from column 22), so EMBL
+//! `FT` lines are read with the GenBank column offsets.
+
+use super::{Feature, Location, Reference, ReferenceFormat, Topology};
+use std::path::Path;
+use tracing::warn;
+
+const KEY_COLUMNS: std::ops::Range<usize> = 5..21;
+const VALUE_COLUMN: usize = 21;
+/// Where GenBank header values start, after the keyword.
+const GENBANK_VALUE_COLUMN: usize = 12;
+const EMBL_VALUE_COLUMN: usize = 5;
+
+/// The reference, and whether more records follow it.
+pub fn parse_genbank(path: &Path, contents: &str) -> Result<(Reference, bool), String> {
+    let mut reference = empty(path, ReferenceFormat::Genbank);
+    let mut feature_lines = Vec::new();
+    let mut section = "";
+    let mut lines = contents.lines();
+
+    for line in lines.by_ref() {
+        if line.starts_with("//") {
+            break;
+        }
+        let keyword = line.split_whitespace().next().unwrap_or_default();
+        if !line.starts_with(' ') && !keyword.is_empty() {
+            section = keyword;
+        }
+        let value = line.get(GENBANK_VALUE_COLUMN..).unwrap_or_default().trim();
+        match section {
+            "LOCUS" => {
+                reference.name = value.split_whitespace().next().unwrap_or_default().to_string();
+                reference.topology = topology(line);
+            }
+            "DEFINITION" => append(&mut reference.description, value),
+            "ACCESSION" if reference.accession.is_none() => {
+                reference.accession = value.split_whitespace().next().map(str::to_string);
+            }
+            "FEATURES" if line.starts_with(' ') => feature_lines.push(line),
+            "ORIGIN" if line.starts_with(' ') => push_bases(&mut reference.sequence, line),
+            _ => {}
+        }
+    }
+
+    if reference.name.is_empty() {
+        return Err("missing LOCUS name".into());
+    }
+    reference.features = parse_features(feature_lines.into_iter());
+    Ok((reference, has_more(lines)))
+}
+
+pub fn parse_embl(path: &Path, contents: &str) -> Result<(Reference, bool), String> {
+    let mut reference = empty(path, ReferenceFormat::Embl);
+    let mut feature_lines = Vec::new();
+    let mut in_sequence = false;
+    let mut lines = contents.lines();
+
+    for line in lines.by_ref() {
+        if line.starts_with("//") {
+            break;
+        }
+        if in_sequence {
+            push_bases(&mut reference.sequence, line);
+            continue;
+        }
+        let value = line.get(EMBL_VALUE_COLUMN..).unwrap_or_default().trim();
+        match line.get(..2).unwrap_or_default() {
+            "ID" => {
+                reference.name = value
+                    .split(';')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string();
+                reference.topology = topology(line);
+            }
+            "AC" if reference.accession.is_none() => {
+                reference.accession = value
+                    .split(';')
+                    .next()
+                    .map(|accession| accession.trim().to_string())
+                    .filter(|accession| !accession.is_empty());
+            }
+            "DE" => append(&mut reference.description, value),
+            "FT" => feature_lines.push(line.get(2..).unwrap_or_default()),
+            "SQ" => in_sequence = true,
+            _ => {}
+        }
+    }
+
+    if reference.name.is_empty() {
+        return Err("missing ID name".into());
+    }
+    // With the `FT` prefix cut off, the columns are two short of GenBank's.
+    let padded: Vec<String> = feature_lines.iter().map(|line| format!("  {}", line)).collect();
+    reference.features = parse_features(padded.iter().map(String::as_str));
+    Ok((reference, has_more(lines)))
+}
+
+fn empty(path: &Path, format: ReferenceFormat) -> Reference {
+    Reference {
+        path: path.to_path_buf(),
+        format,
+        name: String::new(),
+        accession: None,
+        description: None,
+        topology: Topology::Linear,
+        sequence: String::new(),
+        features: Vec::new(),
+    }
+}
+
+fn topology(line: &str) -> Topology {
+    if line.to_ascii_lowercase().contains("circular") {
+        Topology::Circular
+    } else {
+        Topology::Linear
+    }
+}
+
+/// Joins a wrapped header value onto what was read so far.
+fn append(field: &mut Option<String>, value: &str) {
+    if value.is_empty() {
+        return;
+    }
+    match field {
+        Some(existing) => {
+            existing.push(' ');
+            existing.push_str(value);
+        }
+        None => *field = Some(value.to_string()),
+    }
+}
+
+/// Adds the bases on a sequence line, skipping position numbers and spaces.
+fn push_bases(sequence: &mut String, line: &str) {
+    sequence.extend(
+        line.chars()
+            .filter(char::is_ascii_alphabetic)
+            .map(|c| c.to_ascii_uppercase()),
+    );
+}
+
+fn has_more<'a>(mut lines: impl Iterator<Item = &'a str>) -> bool {
+    lines.any(|line| !line.trim().is_empty())
+}
+
+struct PendingFeature {
+    key: String,
+    location: String,
+    qualifiers: Vec<(String, String)>,
+}
+
+impl PendingFeature {
+    /// Whether the last qualifier is a quoted value still waiting for its closing quote.
+    fn in_open_quote(&self) -> bool {
+        self.qualifiers
+            .last()
+            .is_some_and(|(_, value)| value.starts_with('"') && value.matches('"').count() % 2 == 1)
+    }
+
+    fn finish(self) -> Option<Feature> {
+        let location = match Location::parse(&self.location) {
+            Ok(location) => location,
+            Err(e) => {
+                warn!("Skipping {} feature: {}", self.key, e);
+                return None;
+            }
+        };
+        let qualifiers = self
+            .qualifiers
+            .into_iter()
+            .map(|(name, value)| {
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|value| value.strip_suffix('"'))
+                    .unwrap_or(&value)
+                    .replace("\"\"", "\"");
+                (name, value)
+            })
+            .collect();
+        Some(Feature::new(self.key, location, qualifiers))
+    }
+}
+
+fn parse_features<'a>(lines: impl Iterator<Item = &'a str>) -> Vec<Feature> {
+    let mut features = Vec::new();
+    let mut pending: Option<PendingFeature> = None;
+
+    for line in lines {
+        let key = line.get(KEY_COLUMNS).unwrap_or_default().trim();
+        let value = line.get(VALUE_COLUMN..).unwrap_or_default().trim();
+
+        if !key.is_empty() {
+            features.extend(pending.take().and_then(PendingFeature::finish));
+            pending = Some(PendingFeature {
+                key: key.to_string(),
+                location: value.to_string(),
+                qualifiers: Vec::new(),
+            });
+            continue;
+        }
+        let Some(feature) = pending.as_mut() else {
+            continue;
+        };
+
+        if value.starts_with('/') && !feature.in_open_quote() {
+            let (name, value) = value[1..].split_once('=').unwrap_or((&value[1..], ""));
+            feature.qualifiers.push((name.to_string(), value.to_string()));
+        } else if let Some((name, existing)) = feature.qualifiers.last_mut() {
+            // Protein translations wrap mid-word; free text wraps at spaces.
+            if name != "translation" {
+                existing.push(' ');
+            }
+            existing.push_str(value);
+        } else {
+            feature.location.push_str(value);
+        }
+    }
+    features.extend(pending.and_then(PendingFeature::finish));
+    features
+}
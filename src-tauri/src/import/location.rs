@@ -0,0 +1,171 @@
+//! INSDC feature locations (`complement(join(12..80,<1..5))`), as used by
+//! both GenBank and EMBL.
+
+use serde::Serialize;
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct Span {
+    /// 0-based, half-open.
+    pub start: u64,
+    pub end: u64,
+    /// 1 for the forward strand, -1 for the reverse.
+    pub strand: i8,
+    /// `<` or `>`: the feature continues past this end.
+    pub partial_start: bool,
+    pub partial_end: bool,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct Location {
+    /// In biological order, so the first span holds the feature's start.
+    pub spans: Vec<Span>,
+    pub strand: i8,
+    /// The location as written, for anything the spans cannot express.
+    pub raw: String,
+}
+
+impl Location {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let compact: String = raw.chars().filter(|c| !c.is_whitespace()).collect();
+        let spans = parse_spans(&compact).ok_or_else(|| format!("unsupported location {:?}", raw))?;
+        let strand = spans.first().map_or(1, |span| span.strand);
+        Ok(Self {
+            spans,
+            strand,
+            raw: compact,
+        })
+    }
+}
+
+fn parse_spans(expr: &str) -> Option<Vec<Span>> {
+    if let Some(inner) = operator(expr, "complement") {
+        let mut spans = parse_spans(inner)?;
+        spans.reverse();
+        for span in &mut spans {
+            span.strand = -span.strand;
+            std::mem::swap(&mut span.partial_start, &mut span.partial_end);
+        }
+        return Some(spans);
+    }
+    if let Some(inner) = operator(expr, "join").or_else(|| operator(expr, "order")) {
+        let mut spans = Vec::new();
+        for part in split_top_level(inner) {
+            spans.extend(parse_spans(part)?);
+        }
+        return Some(spans);
+    }
+    // A span on another record (`J00194.1:100..202`) has no place in this sequence.
+    if expr.contains(':') {
+        return Some(Vec::new());
+    }
+    parse_range(expr).map(|span| vec![span])
+}
+
+fn operator<'a>(expr: &'a str, name: &str) -> Option<&'a str> {
+    expr.strip_prefix(name)?.strip_prefix('(')?.strip_suffix(')')
+}
+
+fn split_top_level(expr: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut from) = (0i32, 0);
+    for (index, c) in expr.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&expr[from..index]);
+                from = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&expr[from..]);
+    parts
+}
+
+/// `a..b`, `a`, `a.b` (somewhere in between) or `a^b` (between two bases), 1-based.
+fn parse_range(expr: &str) -> Option<Span> {
+    let position = |text: &str| -> Option<(u64, bool)> {
+        let partial = text.starts_with(['<', '>']);
+        Some((text.trim_start_matches(['<', '>']).parse().ok()?, partial))
+    };
+
+    let (start, end, partial_start, partial_end) = if let Some((a, b)) = expr.split_once("..") {
+        let ((start, partial_start), (end, partial_end)) = (position(a)?, position(b)?);
+        (start.checked_sub(1)?, end, partial_start, partial_end)
+    } else if let Some((a, _)) = expr.split_once('^') {
+        let (after, _) = position(a)?;
+        (after, after, false, false)
+    } else if let Some((a, b)) = expr.split_once('.') {
+        let ((start, _), (end, _)) = (position(a)?, position(b)?);
+        (start.checked_sub(1)?, end, false, false)
+    } else {
+        let (base, partial) = position(expr)?;
+        (base.checked_sub(1)?, base, partial, partial)
+    };
+    (start <= end).then_some(Span {
+        start,
+        end,
+        strand: 1,
+        partial_start,
+        partial_end,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(start: u64, end: u64, strand: i8, partial_start: bool, partial_end: bool) -> Span {
+        Span {
+            start,
+            end,
+            strand,
+            partial_start,
+            partial_end,
+        }
+    }
+
+    #[test]
+    fn complement_join_reverses_spans_and_partial_ends() {
+        let location = Location::parse("complement(join(<1..5, 12..>80))").unwrap();
+        assert_eq!(location.strand, -1);
+        assert_eq!(location.raw, "complement(join(<1..5,12..>80))");
+        assert_eq!(
+            location.spans,
+            [span(11, 80, -1, true, false), span(0, 5, -1, false, true)]
+        );
+    }
+
+    #[test]
+    fn join_of_complements_keeps_written_order() {
+        let location = Location::parse("join(complement(20..30),complement(1..10))").unwrap();
+        assert_eq!(location.strand, -1);
+        assert_eq!(
+            location.spans,
+            [span(19, 30, -1, false, false), span(0, 10, -1, false, false)]
+        );
+    }
+
+    #[test]
+    fn single_positions() {
+        assert_eq!(Location::parse("467").unwrap().spans, [span(466, 467, 1, false, false)]);
+        assert_eq!(Location::parse("100..>200").unwrap().spans, [span(99, 200, 1, false, true)]);
+        assert_eq!(Location::parse("102.110").unwrap().spans, [span(101, 110, 1, false, false)]);
+        assert_eq!(Location::parse("5^6").unwrap().spans, [span(5, 5, 1, false, false)]);
+    }
+
+    #[test]
+    fn remote_spans_are_dropped() {
+        let location = Location::parse("join(1..10,J00194.1:100..202)").unwrap();
+        assert_eq!(location.spans, [span(0, 10, 1, false, false)]);
+        assert!(Location::parse("J00194.1:100..202").unwrap().spans.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_locations() {
+        for raw in ["", "0..5", "10..5", "join(1..5", "bond(1..5)", "1..x"] {
+            assert!(Location::parse(raw).is_err(), "{:?} should not parse", raw);
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Readers for annotated references (GenBank, EMBL, SnapGene), so plasmid maps and
+//! alignment tracks do not depend on Biopython in the sidecar. Every format is
+//! normalised into a [`Reference`].
+
+mod flatfile;
+mod location;
+mod snapgene;
+
+pub use location::Location;
+
+use crate::error::AppError;
+use crate::ipc::{self, Encoding};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceFormat {
+    Genbank,
+    Embl,
+    Snapgene,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Topology {
+    #[default]
+    Linear,
+    Circular,
+}
+
+/// Feature keys the UI draws specially; everything else is `Other`, with the
+/// original key kept in [`Feature::key`].
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FeatureKind {
+    Cds,
+    Gene,
+    PrimerBind,
+    MiscFeature,
+    Other,
+}
+
+impl FeatureKind {
+    fn from_key(key: &str) -> Self {
+        match key {
+            "CDS" => FeatureKind::Cds,
+            "gene" => FeatureKind::Gene,
+            "primer_bind" => FeatureKind::PrimerBind,
+            "misc_feature" => FeatureKind::MiscFeature,
+            _ => FeatureKind::Other,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Feature {
+    pub kind: FeatureKind,
+    /// The key as written in the file, e.g. `CDS` or `promoter`.
+    pub key: String,
+    pub location: Location,
+    /// Display name, from the first of `/label`, `/gene`, `/locus_tag`, `/product` or `/note`.
+    pub label: Option<String>,
+    /// All qualifiers in file order; flag qualifiers such as `/pseudo` have an empty value.
+    pub qualifiers: Vec<(String, String)>,
+}
+
+impl Feature {
+    fn new(key: String, location: Location, qualifiers: Vec<(String, String)>) -> Self {
+        let label = ["label", "gene", "locus_tag", "product", "note"]
+            .iter()
+            .find_map(|wanted| {
+                qualifiers
+                    .iter()
+                    .find(|(name, value)| name == wanted && !value.is_empty())
+                    .map(|(_, value)| value.clone())
+            });
+        Self {
+            kind: FeatureKind::from_key(&key),
+            key,
+            location,
+            label,
+            qualifiers,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Reference {
+    pub path: PathBuf,
+    pub format: ReferenceFormat,
+    pub name: String,
+    pub accession: Option<String>,
+    pub description: Option<String>,
+    pub topology: Topology,
+    /// Upper case.
+    pub sequence: String,
+    pub features: Vec<Feature>,
+}
+
+/// Reads the SnapGene file, or first record of the GenBank or EMBL file, at
+/// `path`, detecting the format from its contents.
+pub fn read(path: &Path) -> Result<Reference, AppError> {
+    let data = std::fs::read(path)?;
+    let invalid = |detail: String| AppError::InvalidReference(format!("{:?}: {}", path, detail));
+
+    let (reference, more) = if snapgene::is_snapgene(&data) {
+        (snapgene::parse(path, &data).map_err(invalid)?, false)
+    } else {
+        let contents = String::from_utf8_lossy(&data);
+        let first_line = contents.lines().find(|line| !line.trim().is_empty()).unwrap_or_default();
+        if first_line.starts_with("LOCUS") {
+            flatfile::parse_genbank(path, &contents).map_err(invalid)?
+        } else if first_line.starts_with("ID   ") {
+            flatfile::parse_embl(path, &contents).map_err(invalid)?
+        } else {
+            return Err(invalid("not a GenBank, EMBL or SnapGene file".into()));
+        }
+    };
+    if more {
+        warn!("{:?} holds several records, only the first is read", path);
+    }
+    debug!(
+        "Parsed {:?}: {} bp, {} features",
+        path,
+        reference.sequence.len(),
+        reference.features.len()
+    );
+    Ok(reference)
+}
+
+/// A [`Reference`] from any supported format, e.g. a SnapGene `.dna` file,
+/// in `encoding` if given.
+#[tauri::command]
+pub async fn import_reference(path: PathBuf, encoding: Option<Encoding>) -> Result<tauri::ipc::Response, AppError> {
+    let reference = parse_genbank(path).await?;
+    ipc::encode(&reference, encoding)
+}
+
+#[tauri::command]
+pub async fn parse_genbank(path: PathBuf) -> Result<Reference, AppError> {
+    tauri::async_runtime::spawn_blocking(move || read(&path))
+        .await
+        .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))?
+}
@@ -0,0 +1,415 @@
+//! SnapGene `.dna` files: a run of `type (1) | length (4, big-endian) | data`
+//! packets. The sequence is raw bytes; features, primers and notes are XML.
+
+use super::{Feature, Location, Reference, ReferenceFormat, Topology};
+use crate::import::location::Span;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::path::Path;
+
+const COOKIE: &[u8] = b"SnapGene";
+
+const COOKIE_PACKET: u8 = 0x09;
+const SEQUENCE_PACKET: u8 = 0x00;
+const PRIMERS_PACKET: u8 = 0x05;
+const NOTES_PACKET: u8 = 0x06;
+const FEATURES_PACKET: u8 = 0x0a;
+
+/// Low bit of the flags byte in front of the sequence.
+const CIRCULAR_FLAG: u8 = 0x01;
+
+/// Whether `data` starts with the SnapGene cookie packet.
+pub fn is_snapgene(data: &[u8]) -> bool {
+    data.first() == Some(&COOKIE_PACKET) && data.get(5..5 + COOKIE.len()) == Some(COOKIE)
+}
+
+pub fn parse(path: &Path, data: &[u8]) -> Result<Reference, String> {
+    let mut reference = Reference {
+        path: path.to_path_buf(),
+        format: ReferenceFormat::Snapgene,
+        name: path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        accession: None,
+        description: None,
+        topology: Topology::Linear,
+        sequence: String::new(),
+        features: Vec::new(),
+    };
+    let mut primers = Vec::new();
+
+    let mut offset = 0;
+    while offset < data.len() {
+        let header = data.get(offset..offset + 5).ok_or("truncated packet header")?;
+        let length = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+        let body = data
+            .get(offset + 5..offset + 5 + length)
+            .ok_or_else(|| format!("packet {:#04x} is truncated", header[0]))?;
+        offset += 5 + length;
+
+        match header[0] {
+            SEQUENCE_PACKET => {
+                let (&flags, bases) = body.split_first().ok_or("empty sequence packet")?;
+                if flags & CIRCULAR_FLAG != 0 {
+                    reference.topology = Topology::Circular;
+                }
+                reference.sequence = String::from_utf8_lossy(bases).to_ascii_uppercase();
+            }
+            FEATURES_PACKET => reference.features = parse_features(&String::from_utf8_lossy(body))?,
+            PRIMERS_PACKET => primers = parse_primers(&String::from_utf8_lossy(body))?,
+            NOTES_PACKET => apply_notes(&mut reference, &String::from_utf8_lossy(body))?,
+            _ => {}
+        }
+    }
+
+    if reference.sequence.is_empty() {
+        return Err("no DNA sequence (protein or RNA files are not supported)".into());
+    }
+    // Ranges across the origin are split, which needs the sequence length.
+    let length = reference.sequence.len() as u64;
+    for feature in reference.features.iter_mut().chain(primers.iter_mut()) {
+        feature.location.spans = std::mem::take(&mut feature.location.spans)
+            .into_iter()
+            .flat_map(|span| wrap(span, length))
+            .collect();
+    }
+    reference.features.extend(primers);
+    Ok(reference)
+}
+
+fn attributes(element: &BytesStart) -> HashMap<String, String> {
+    element
+        .attributes()
+        .flatten()
+        .map(|attribute| {
+            let key = String::from_utf8_lossy(attribute.key.as_ref()).to_string();
+            let value = attribute
+                .unescape_value()
+                .map(|value| value.to_string())
+                .unwrap_or_else(|_| String::from_utf8_lossy(&attribute.value).to_string());
+            (key, value)
+        })
+        .collect()
+}
+
+/// A `start-end` range, 1-based inclusive. `end` may be below `start` for
+/// ranges across the origin; [`wrap`] splits those.
+fn span(range: &str, strand: i8) -> Option<Span> {
+    let (start, end) = range.split_once('-')?;
+    Some(Span {
+        start: start.trim().parse::<u64>().ok()?.checked_sub(1)?,
+        end: end.trim().parse().ok()?,
+        strand,
+        partial_start: false,
+        partial_end: false,
+    })
+}
+
+fn wrap(span: Span, length: u64) -> Vec<Span> {
+    if span.start < span.end {
+        return vec![span];
+    }
+    let head = Span {
+        end: length,
+        ..span.clone()
+    };
+    let tail = Span { start: 0, ..span };
+    let mut spans = vec![head, tail];
+    if spans[0].strand < 0 {
+        spans.reverse();
+    }
+    spans
+}
+
+fn location(spans: Vec<Span>, strand: i8, raw: String) -> Location {
+    Location { spans, strand, raw }
+}
+
+struct PendingFeature {
+    key: String,
+    strand: i8,
+    spans: Vec<Span>,
+    qualifiers: Vec<(String, String)>,
+    qualifier: Option<String>,
+}
+
+fn parse_features(xml: &str) -> Result<Vec<Feature>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut features = Vec::new();
+    let mut pending: Option<PendingFeature> = None;
+
+    loop {
+        let event = reader.read_event().map_err(|e| format!("invalid feature XML: {}", e))?;
+        let (element, is_end) = match &event {
+            Event::Start(element) | Event::Empty(element) => (Some(element), false),
+            Event::End(element) if element.name().as_ref() == b"Feature" => (None, true),
+            Event::Eof => break,
+            _ => (None, false),
+        };
+        if is_end {
+            if let Some(feature) = pending.take() {
+                let raw = feature
+                    .spans
+                    .iter()
+                    .map(|span| format!("{}-{}", span.start + 1, span.end))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                let strand = feature.strand;
+                let mut spans = feature.spans;
+                if strand < 0 {
+                    spans.reverse();
+                }
+                features.push(Feature::new(feature.key, location(spans, strand, raw), feature.qualifiers));
+            }
+            continue;
+        }
+        let Some(element) = element else {
+            continue;
+        };
+
+        let attributes = attributes(element);
+        match element.name().as_ref() {
+            b"Feature" => {
+                let strand = if attributes.get("directionality").map(String::as_str) == Some("2") {
+                    -1
+                } else {
+                    1
+                };
+                let mut qualifiers = Vec::new();
+                if let Some(name) = attributes.get("name") {
+                    qualifiers.push(("label".to_string(), name.clone()));
+                }
+                pending = Some(PendingFeature {
+                    key: attributes.get("type").cloned().unwrap_or_else(|| "misc_feature".into()),
+                    strand,
+                    spans: Vec::new(),
+                    qualifiers,
+                    qualifier: None,
+                });
+            }
+            b"Segment" => {
+                if let Some(feature) = pending.as_mut() {
+                    feature
+                        .spans
+                        .extend(attributes.get("range").and_then(|range| span(range, feature.strand)));
+                }
+            }
+            b"Q" => {
+                if let Some(feature) = pending.as_mut() {
+                    feature.qualifier = attributes.get("name").cloned();
+                }
+            }
+            b"V" => {
+                if let Some(feature) = pending.as_mut() {
+                    let value = ["text", "predef", "int"]
+                        .iter()
+                        .find_map(|kind| attributes.get(*kind).cloned());
+                    if let (Some(name), Some(value)) = (feature.qualifier.clone(), value) {
+                        feature.qualifiers.push((name, value));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(features)
+}
+
+/// Primers become `primer_bind` features, one per binding site.
+fn parse_primers(xml: &str) -> Result<Vec<Feature>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut primers = Vec::new();
+    let mut primer: Vec<(String, String)> = Vec::new();
+
+    loop {
+        let event = reader.read_event().map_err(|e| format!("invalid primer XML: {}", e))?;
+        let element = match &event {
+            Event::Start(element) | Event::Empty(element) => element,
+            Event::Eof => break,
+            _ => continue,
+        };
+        let attributes = attributes(element);
+        match element.name().as_ref() {
+            b"Primer" => {
+                primer = [("label", "name"), ("sequence", "sequence"), ("note", "description")]
+                    .iter()
+                    .filter_map(|(qualifier, attribute)| {
+                        let value = attributes.get(*attribute).filter(|value| !value.is_empty())?;
+                        Some((qualifier.to_string(), value.clone()))
+                    })
+                    .collect();
+            }
+            b"BindingSite" => {
+                let strand = if attributes.get("boundStrand").map(String::as_str) == Some("1") {
+                    -1
+                } else {
+                    1
+                };
+                let Some(site) = attributes.get("location").and_then(|range| span(range, strand)) else {
+                    continue;
+                };
+                let raw = attributes.get("location").cloned().unwrap_or_default();
+                primers.push(Feature::new("primer_bind".into(), location(vec![site], strand, raw), primer.clone()));
+            }
+            _ => {}
+        }
+    }
+    Ok(primers)
+}
+
+/// The notes packet holds the description and, for files saved from a
+/// database, the accession.
+fn apply_notes(reference: &mut Reference, xml: &str) -> Result<(), String> {
+    let mut reader = Reader::from_str(xml);
+    let mut current: Option<Vec<u8>> = None;
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event().map_err(|e| format!("invalid notes XML: {}", e))? {
+            Event::Start(element) => {
+                current = Some(element.name().as_ref().to_vec());
+                text.clear();
+            }
+            Event::Text(content) => text.push_str(&content.decode().unwrap_or_default()),
+            Event::CData(content) => text.push_str(&String::from_utf8_lossy(&content)),
+            Event::GeneralRef(entity) => text.push_str(match &*entity {
+                b"amp" => "&",
+                b"lt" => "<",
+                b"gt" => ">",
+                b"quot" => "\"",
+                b"apos" => "'",
+                _ => "",
+            }),
+            Event::End(_) => {
+                let value = strip_html(&text);
+                match current.take().as_deref() {
+                    Some(b"Description") if !value.is_empty() => reference.description = Some(value),
+                    Some(b"AccessionNumber") if !value.is_empty() => reference.accession = Some(value),
+                    Some(b"CustomMapLabel") if !value.is_empty() => reference.name = value,
+                    _ => {}
+                }
+                text.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// SnapGene stores rich-text notes as HTML.
+fn strip_html(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => plain.push(c),
+            _ => {}
+        }
+    }
+    plain.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(kind: u8, body: &[u8]) -> Vec<u8> {
+        let mut packet = vec![kind];
+        packet.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        packet.extend_from_slice(body);
+        packet
+    }
+
+    fn span(start: u64, end: u64, strand: i8) -> Span {
+        Span {
+            start,
+            end,
+            strand,
+            partial_start: false,
+            partial_end: false,
+        }
+    }
+
+    /// A circular 20 bp file with features and a primer across the origin.
+    fn fixture() -> Vec<u8> {
+        let mut data = packet(COOKIE_PACKET, b"SnapGene\x00\x01\x00\x0f\x00\x13");
+        data.extend(packet(SEQUENCE_PACKET, b"\x01acgtacgtacgtacgtacgt"));
+        data.extend(packet(
+            FEATURES_PACKET,
+            br#"<Features>
+                <Feature name="ori" type="rep_origin" directionality="1"><Segment range="18-3"/></Feature>
+                <Feature name="bla" type="CDS" directionality="2">
+                    <Segment range="17-20"/><Segment range="1-4"/>
+                    <Q name="product"><V text="beta-lactamase"/></Q>
+                </Feature>
+                <Feature name="tag" type="misc_feature"><Segment range="5-10"/></Feature>
+            </Features>"#,
+        ));
+        data.extend(packet(
+            PRIMERS_PACKET,
+            br#"<Primers><Primer name="fw" sequence="TACGTAC"><BindingSite location="19-5" boundStrand="1"/></Primer></Primers>"#,
+        ));
+        data.extend(packet(
+            NOTES_PACKET,
+            b"<Notes><Description>&lt;html&gt;&lt;b&gt;Test&lt;/b&gt;  plasmid&lt;/html&gt;</Description>\
+              <AccessionNumber>X00001</AccessionNumber><CustomMapLabel>pTest</CustomMapLabel></Notes>",
+        ));
+        data
+    }
+
+    #[test]
+    fn reads_sequence_and_notes() {
+        let data = fixture();
+        assert!(is_snapgene(&data));
+        let reference = parse(Path::new("p.dna"), &data).unwrap();
+        assert_eq!(reference.topology, Topology::Circular);
+        assert_eq!(reference.sequence, "ACGT".repeat(5));
+        assert_eq!(reference.name, "pTest");
+        assert_eq!(reference.accession.as_deref(), Some("X00001"));
+        assert_eq!(reference.description.as_deref(), Some("Test plasmid"));
+    }
+
+    #[test]
+    fn splits_features_across_the_origin() {
+        let reference = parse(Path::new("p.dna"), &fixture()).unwrap();
+        let features = &reference.features;
+        assert_eq!(features.len(), 4);
+
+        assert_eq!(features[0].label.as_deref(), Some("ori"));
+        assert_eq!(features[0].location.spans, [span(17, 20, 1), span(0, 3, 1)]);
+
+        assert_eq!(features[1].key, "CDS");
+        assert_eq!(features[1].location.strand, -1);
+        assert_eq!(features[1].location.spans, [span(0, 4, -1), span(16, 20, -1)]);
+        assert!(features[1]
+            .qualifiers
+            .contains(&("product".to_string(), "beta-lactamase".to_string())));
+
+        assert_eq!(features[2].location.spans, [span(4, 10, 1)]);
+    }
+
+    #[test]
+    fn primers_become_primer_bind_features() {
+        let reference = parse(Path::new("p.dna"), &fixture()).unwrap();
+        let primer = &reference.features[3];
+        assert_eq!(primer.key, "primer_bind");
+        assert_eq!(primer.label.as_deref(), Some("fw"));
+        assert_eq!(primer.location.spans, [span(0, 5, -1), span(18, 20, -1)]);
+    }
+
+    #[test]
+    fn rejects_truncated_and_sequenceless_files() {
+        let mut data = fixture();
+        data.truncate(data.len() - 3);
+        assert!(parse(Path::new("p.dna"), &data).is_err());
+
+        let cookie = packet(COOKIE_PACKET, b"SnapGene\x00\x01\x00\x0f\x00\x13");
+        assert!(parse(Path::new("p.dna"), &cookie).is_err());
+        assert!(!is_snapgene(b"LOCUS       pTest"));
+    }
+}
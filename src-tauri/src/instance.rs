@@ -0,0 +1,23 @@
+use crate::file_intake;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+use tracing::info;
+
+/// Called by the single-instance plugin in the *first* instance when the app is
+/// launched again. The second process exits right after, so it never spawns its
+/// own bio-engine on top of ours.
+pub fn on_second_instance(app_handle: &AppHandle, argv: Vec<String>, cwd: String) {
+    info!("Second instance launched with {:?}", argv);
+    focus_main_window(app_handle);
+
+    // The executable path is skipped; relative paths are the second instance's.
+    file_intake::intake_args(app_handle, argv.into_iter().skip(1), Path::new(&cwd));
+}
+
+pub fn focus_main_window(app_handle: &AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
@@ -0,0 +1,114 @@
+//! Command response encodings.
+//!
+//! Large numeric arrays (trace channels, alignment matrices) are returned as a
+//! raw `ArrayBuffer` instead of JSON numbers; the frontend reads them with
+//! typed array views at the documented offsets. Every buffer starts with a
+//! 4-byte magic and a `u32` layout version, is little-endian, and aligns each
+//! array to its element size so it can be viewed without copying.
+//!
+//! Large structured results can instead be returned as MessagePack when the
+//! frontend asks for it: it calls `get_ipc_encodings` once, then passes
+//! `encoding: "msgpack"` to commands that take one and decodes the returned
+//! `ArrayBuffer`. Without an `encoding` they answer with plain JSON.
+
+use crate::blob::{BlobHandle, BlobStore};
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use tauri::ipc::Response;
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    #[default]
+    Json,
+    /// Maps keep their field names, so decoded objects look like the JSON ones.
+    Msgpack,
+}
+
+/// Encodings this build can produce; MessagePack depends on the `msgpack` feature.
+pub fn supported_encodings() -> Vec<Encoding> {
+    let mut encodings = vec![Encoding::Json];
+    if cfg!(feature = "msgpack") {
+        encodings.push(Encoding::Msgpack);
+    }
+    encodings
+}
+
+/// `value` as a command response in `encoding`, JSON by default.
+pub fn encode<T: Serialize>(value: &T, encoding: Option<Encoding>) -> Result<Response, AppError> {
+    match encoding.unwrap_or_default() {
+        Encoding::Json => serde_json::to_string(value)
+            .map(Response::new)
+            .map_err(|e| AppError::Encoding(e.to_string())),
+        #[cfg(feature = "msgpack")]
+        Encoding::Msgpack => rmp_serde::to_vec_named(value)
+            .map(Response::new)
+            .map_err(|e| AppError::Encoding(e.to_string())),
+        #[cfg(not(feature = "msgpack"))]
+        Encoding::Msgpack => Err(AppError::Encoding(
+            "this build was made without MessagePack support".into(),
+        )),
+    }
+}
+
+#[tauri::command]
+pub fn get_ipc_encodings() -> Vec<Encoding> {
+    supported_encodings()
+}
+
+pub struct BinaryWriter {
+    buffer: Vec<u8>,
+}
+
+impl BinaryWriter {
+    pub fn new(magic: &[u8; 4], version: u32, capacity: usize) -> Self {
+        let mut buffer = Vec::with_capacity(capacity + 8);
+        buffer.extend_from_slice(magic);
+        buffer.extend_from_slice(&version.to_le_bytes());
+        Self { buffer }
+    }
+
+    pub fn u32(&mut self, value: u32) -> &mut Self {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub fn f64(&mut self, value: f64) -> &mut Self {
+        self.align(8);
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub fn u8s(&mut self, values: &[u8]) -> &mut Self {
+        self.buffer.extend_from_slice(values);
+        self
+    }
+
+    pub fn u16s(&mut self, values: &[u16]) -> &mut Self {
+        self.align(2);
+        self.buffer.extend(values.iter().flat_map(|value| value.to_le_bytes()));
+        self
+    }
+
+    pub fn u32s(&mut self, values: &[u32]) -> &mut Self {
+        self.align(4);
+        self.buffer.extend(values.iter().flat_map(|value| value.to_le_bytes()));
+        self
+    }
+
+    /// Pads with zeros up to a multiple of `alignment`.
+    pub fn align(&mut self, alignment: usize) -> &mut Self {
+        let padding = (alignment - self.buffer.len() % alignment) % alignment;
+        self.buffer.resize(self.buffer.len() + padding, 0);
+        self
+    }
+
+    pub fn finish(self) -> Response {
+        Response::new(self.buffer)
+    }
+
+    /// Writes the buffer into a blob instead, for arrays too large for the bridge.
+    pub fn finish_to_blob(self, store: &BlobStore) -> Result<BlobHandle, AppError> {
+        store.write_bytes(&self.buffer)
+    }
+}
@@ -0,0 +1,166 @@
+//! Checkpoints of batch jobs, so a plate interrupted by a crash or power loss
+//! resumes with the samples it had not finished instead of starting over.
+//!
+//! Every batch job has a file in `checkpoints/` under the app data dir from
+//! submission until it finishes, holding its request and the samples the
+//! engine has reported done (a `sample_completed` progress line naming the
+//! patient). Checkpoints still there at startup belong to jobs the last
+//! session never finished; they are queued again without those samples.
+
+use crate::progress::AnalysisProgress;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+/// Progress stage the engine reports when it has finished one sample.
+pub const SAMPLE_COMPLETED_STAGE: &str = "sample_completed";
+
+const CHECKPOINT_DIR: &str = "checkpoints";
+
+/// Serialises read-modify-write cycles on checkpoint files.
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    /// The job's full request, as submitted.
+    request: Value,
+    /// Ids of the patients the engine has finished.
+    completed: Vec<String>,
+    /// Outputs the engine reported for those patients.
+    output_paths: Vec<PathBuf>,
+}
+
+fn dir(app_handle: &AppHandle) -> Option<PathBuf> {
+    crate::data_dir::data(app_handle).ok().map(|dir| dir.join(CHECKPOINT_DIR))
+}
+
+fn path(app_handle: &AppHandle, correlation_id: &str) -> Option<PathBuf> {
+    dir(app_handle).map(|dir| dir.join(format!("{}.json", correlation_id)))
+}
+
+fn load(path: &Path) -> Option<Checkpoint> {
+    let contents = std::fs::read(path).ok()?;
+    serde_json::from_slice(&contents).ok()
+}
+
+/// Writes through a staging file, so a power loss mid-write leaves the
+/// previous checkpoint rather than a truncated one.
+fn save(path: &Path, checkpoint: &Checkpoint) {
+    let staging = path.with_extension("tmp");
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(&staging, serde_json::to_vec(checkpoint).unwrap_or_default()))
+        .and_then(|()| std::fs::rename(&staging, path));
+    if let Err(e) = written {
+        warn!("Could not save the checkpoint {:?}: {}", path, e);
+    }
+}
+
+/// The id a patient is referred to by in progress reports.
+fn patient_id(patient: &Value) -> Option<String> {
+    match patient.get("id")? {
+        Value::String(id) => Some(id.clone()),
+        Value::Null => None,
+        id => Some(id.to_string()),
+    }
+}
+
+/// Starts the checkpoint of a newly submitted batch job.
+pub fn create(app_handle: &AppHandle, correlation_id: &str, request: &Value) {
+    let Some(path) = path(app_handle, correlation_id) else {
+        return;
+    };
+    let _guard = WRITE_LOCK.lock().unwrap();
+    save(
+        &path,
+        &Checkpoint {
+            request: request.clone(),
+            completed: Vec::new(),
+            output_paths: Vec::new(),
+        },
+    );
+}
+
+/// Records a sample the engine has finished, if the job is checkpointed.
+pub fn sample_completed(app_handle: &AppHandle, correlation_id: &str, progress: &AnalysisProgress) {
+    let (Some(sample), Some(path)) = (&progress.sample, path(app_handle, correlation_id)) else {
+        return;
+    };
+    let _guard = WRITE_LOCK.lock().unwrap();
+    let Some(mut checkpoint) = load(&path) else {
+        return;
+    };
+    if !checkpoint.completed.contains(sample) {
+        checkpoint.completed.push(sample.clone());
+        checkpoint.output_paths.extend(progress.output_paths.iter().cloned());
+        save(&path, &checkpoint);
+    }
+}
+
+/// Outputs of the samples finished before the job was resumed.
+pub fn outputs(app_handle: &AppHandle, correlation_id: &str) -> Vec<PathBuf> {
+    path(app_handle, correlation_id)
+        .and_then(|path| load(&path))
+        .map(|checkpoint| checkpoint.output_paths)
+        .unwrap_or_default()
+}
+
+/// Drops the checkpoint of a job that has finished, whichever way.
+pub fn remove(app_handle: &AppHandle, correlation_id: &str) {
+    if let Some(path) = path(app_handle, correlation_id) {
+        let _guard = WRITE_LOCK.lock().unwrap();
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Queues again the batch jobs the last session left unfinished, each with
+/// only the patients it had not finished. Called once at startup.
+pub fn resume(app_handle: &AppHandle) {
+    let Some(dir) = dir(app_handle) else {
+        return;
+    };
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|extension| extension != "json") {
+            let _ = std::fs::remove_file(&path);
+            continue;
+        }
+        let Some(correlation_id) = path.file_stem().map(|stem| stem.to_string_lossy().to_string()) else {
+            continue;
+        };
+        let Some(checkpoint) = load(&path) else {
+            warn!("Dropping unreadable checkpoint {:?}", path);
+            let _ = std::fs::remove_file(&path);
+            continue;
+        };
+
+        let mut request = checkpoint.request.clone();
+        let Some(patients) = request.get_mut("patients").and_then(Value::as_array_mut) else {
+            let _ = std::fs::remove_file(&path);
+            continue;
+        };
+        let total = patients.len();
+        patients.retain(|patient| patient_id(patient).is_none_or(|id| !checkpoint.completed.contains(&id)));
+        if patients.is_empty() {
+            info!("Interrupted job {} had finished every sample; not resuming it", correlation_id);
+            let _ = std::fs::remove_file(&path);
+            continue;
+        }
+
+        let done = total - patients.len();
+        info!("Resuming interrupted job {} with {} of {} samples left", correlation_id, total - done, total);
+        super::logs::note(
+            app_handle,
+            &correlation_id,
+            &format!("Resuming after a restart; {} of {} samples were already done", done, total),
+        );
+        super::enqueue(app_handle, request, super::JobPriority::Batch, correlation_id, done);
+    }
+}
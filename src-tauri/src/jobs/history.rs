@@ -0,0 +1,264 @@
+//! Every finished job, kept in a SQLite database in the app data dir so past
+//! analyses can be looked up across restarts. Old entries, and the outputs
+//! they point to, are removed by a background task according to the
+//! `job_history_days` and `job_history_max_gb` settings, along with their
+//! [`logs`](super::logs).
+
+use super::{logs, JobInfo, JobState};
+use crate::disk;
+use crate::error::AppError;
+use crate::settings::SettingsStore;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{debug, info, warn};
+
+/// Emitted with no payload whenever the history changes.
+pub const JOB_HISTORY_CHANGED_EVENT: &str = "job-history-changed";
+
+const DATABASE_FILE: &str = "jobs.sqlite";
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const GIGABYTE: u64 = 1024 * 1024 * 1024;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS jobs (
+        id            INTEGER PRIMARY KEY AUTOINCREMENT,
+        engine_job_id TEXT,
+        correlation_id TEXT NOT NULL,
+        name          TEXT NOT NULL,
+        state         TEXT NOT NULL,
+        request       TEXT NOT NULL,
+        error         TEXT,
+        submitted_at  INTEGER NOT NULL,
+        started_at    INTEGER,
+        finished_at   INTEGER NOT NULL,
+        output_paths  TEXT NOT NULL,
+        output_bytes  INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS jobs_finished_at ON jobs (finished_at);
+";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JobRecord {
+    pub id: i64,
+    pub engine_job_id: Option<String>,
+    /// Identifies the job's log, see [`super::export_job_log`].
+    pub correlation_id: String,
+    pub name: String,
+    pub state: JobState,
+    /// The analysis request as submitted: inputs and parameters.
+    pub request: Value,
+    pub error: Option<String>,
+    /// Seconds since the Unix epoch.
+    pub submitted_at: u64,
+    pub started_at: Option<u64>,
+    pub finished_at: u64,
+    pub duration_secs: Option<u64>,
+    pub output_paths: Vec<PathBuf>,
+    /// Size of the outputs when the job finished.
+    pub output_bytes: u64,
+}
+
+pub struct JobHistory {
+    connection: Mutex<Connection>,
+}
+
+impl JobHistory {
+    /// Opens (or creates) the database. If it cannot be opened the history is
+    /// kept in memory for this session rather than failing startup.
+    pub fn open(app_handle: &AppHandle) -> Self {
+        let connection = crate::data_dir::data(app_handle)
+            .map_err(AppError::from)
+            .and_then(|dir| {
+                std::fs::create_dir_all(&dir)?;
+                Ok(Connection::open(dir.join(DATABASE_FILE))?)
+            })
+            .and_then(|connection| {
+                connection.execute_batch(SCHEMA)?;
+                Ok(connection)
+            })
+            .unwrap_or_else(|e| {
+                warn!("Job history will not be saved this session: {}", e);
+                let connection = Connection::open_in_memory().expect("in-memory SQLite is available");
+                connection
+                    .execute_batch(SCHEMA)
+                    .expect("job history schema is valid");
+                connection
+            });
+        Self {
+            connection: Mutex::new(connection),
+        }
+    }
+
+    pub fn record(&self, info: &JobInfo, request: &Value) -> Result<(), AppError> {
+        // Outputs of a remote engine are not on this machine and count as zero.
+        let output_bytes: u64 = info.output_paths.iter().map(|path| disk::usage(path)).sum();
+        let output_paths = serde_json::to_string(&info.output_paths).unwrap_or_else(|_| "[]".to_string());
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT INTO jobs (engine_job_id, correlation_id, name, state, request, error,
+                               submitted_at, started_at, finished_at, output_paths, output_bytes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                info.engine_job_id,
+                info.correlation_id,
+                info.name,
+                info.state.as_str(),
+                request.to_string(),
+                info.error,
+                info.submitted_at as i64,
+                info.started_at.map(|at| at as i64),
+                info.finished_at.unwrap_or(info.submitted_at) as i64,
+                output_paths,
+                output_bytes as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Most recently finished first.
+    pub fn list(&self, limit: Option<u32>, offset: Option<u32>) -> Result<Vec<JobRecord>, AppError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT id, engine_job_id, name, state, request, error, submitted_at, started_at,
+                    finished_at, output_paths, output_bytes, correlation_id
+             FROM jobs ORDER BY finished_at DESC, id DESC LIMIT ?1 OFFSET ?2",
+        )?;
+        let limit = limit.map_or(-1, i64::from);
+        let offset = offset.map_or(0, i64::from);
+        let records = statement
+            .query_map(params![limit, offset], |row| {
+                let started_at = row.get::<_, Option<i64>>(7)?.map(|at| at as u64);
+                let finished_at = row.get::<_, i64>(8)? as u64;
+                Ok(JobRecord {
+                    id: row.get(0)?,
+                    engine_job_id: row.get(1)?,
+                    correlation_id: row.get(11)?,
+                    name: row.get(2)?,
+                    state: JobState::parse(&row.get::<_, String>(3)?),
+                    request: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or(Value::Null),
+                    error: row.get(5)?,
+                    submitted_at: row.get::<_, i64>(6)? as u64,
+                    started_at,
+                    finished_at,
+                    duration_secs: started_at.map(|started| finished_at.saturating_sub(started)),
+                    output_paths: serde_json::from_str(&row.get::<_, String>(9)?).unwrap_or_default(),
+                    output_bytes: row.get::<_, i64>(10)? as u64,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(records)
+    }
+
+    pub fn clear(&self) -> Result<(), AppError> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute("DELETE FROM jobs", [])?;
+        Ok(())
+    }
+
+    /// Drops entries finished more than `keep_days` ago, then the oldest ones
+    /// until their outputs fit in `max_bytes`, deleting those outputs too.
+    /// Returns the correlation IDs of the removed entries.
+    pub fn enforce(&self, keep_days: Option<u32>, max_bytes: Option<u64>) -> Result<Vec<String>, AppError> {
+        let connection = self.connection.lock().unwrap();
+        let mut expired: Vec<Expired> = Vec::new();
+
+        if let Some(days) = keep_days {
+            let cutoff = now() - i64::from(days) * 24 * 60 * 60;
+            let mut statement =
+                connection.prepare("SELECT id, output_paths, correlation_id FROM jobs WHERE finished_at < ?1")?;
+            let rows = statement.query_map(params![cutoff], row_outputs)?;
+            expired.extend(rows.collect::<Result<Vec<_>, _>>()?);
+        }
+
+        if let Some(max_bytes) = max_bytes {
+            let mut statement = connection.prepare(
+                "SELECT id, output_paths, correlation_id, output_bytes
+                 FROM jobs ORDER BY finished_at DESC, id DESC",
+            )?;
+            let rows = statement.query_map([], |row| Ok((row_outputs(row)?, row.get::<_, i64>(3)? as u64)))?;
+            let mut total = 0u64;
+            for row in rows {
+                let (entry, bytes) = row?;
+                total += bytes;
+                if total > max_bytes && !expired.iter().any(|(id, _, _)| *id == entry.0) {
+                    expired.push(entry);
+                }
+            }
+        }
+
+        let mut removed = Vec::with_capacity(expired.len());
+        for (id, outputs, correlation_id) in expired {
+            for path in &outputs {
+                remove_output(path);
+            }
+            connection.execute("DELETE FROM jobs WHERE id = ?1", params![id])?;
+            removed.push(correlation_id);
+        }
+        Ok(removed)
+    }
+}
+
+/// Row id, output paths and correlation ID of an entry to remove.
+type Expired = (i64, Vec<PathBuf>, String);
+
+fn row_outputs(row: &rusqlite::Row) -> rusqlite::Result<Expired> {
+    let paths: String = row.get(1)?;
+    Ok((row.get(0)?, serde_json::from_str(&paths).unwrap_or_default(), row.get(2)?))
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+fn remove_output(path: &Path) {
+    let removed = match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => std::fs::remove_dir_all(path),
+        Ok(_) => std::fs::remove_file(path),
+        Err(_) => return,
+    };
+    match removed {
+        Ok(()) => debug!("Removed expired job output {:?}", path),
+        Err(e) => warn!("Could not remove expired job output {:?}: {}", path, e),
+    }
+}
+
+/// Records a finished job, logging rather than failing: the job itself is done.
+pub fn record(app_handle: &AppHandle, info: &JobInfo, request: &Value) {
+    match app_handle.state::<JobHistory>().record(info, request) {
+        Ok(()) => {
+            let _ = app_handle.emit(JOB_HISTORY_CHANGED_EVENT, ());
+        }
+        Err(e) => warn!("Could not record job {} in the history: {}", info.id, e),
+    }
+}
+
+/// Applies the retention settings now and then every hour.
+pub fn start_cleanup(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let settings = app_handle.state::<SettingsStore>().get();
+            let max_bytes = settings.job_history_max_gb.map(|gb| u64::from(gb) * GIGABYTE);
+            let history = app_handle.state::<JobHistory>();
+            match history.enforce(settings.job_history_days, max_bytes) {
+                Ok(removed) if removed.is_empty() => {}
+                Ok(removed) => {
+                    for correlation_id in &removed {
+                        logs::remove(&app_handle, correlation_id);
+                    }
+                    info!("Removed {} expired jobs from the history", removed.len());
+                    let _ = app_handle.emit(JOB_HISTORY_CHANGED_EVENT, ());
+                }
+                Err(e) => warn!("Job history cleanup failed: {}", e),
+            }
+            tokio::time::sleep(CLEANUP_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,100 @@
+//! One log file per job, in `jobs/` under the app log directory, holding the
+//! engine output tagged with that job's correlation ID plus the shell's own
+//! notes on it, so the log of a single failed analysis can be exported.
+//!
+//! The shell sends the ID in the [`CORRELATION_HEADER`] of the requests that
+//! create and start a job; the engine tags every log record it writes on the
+//! job's behalf with `[cid=<id>]`.
+
+use crate::engine_log::{EngineLogLine, LogStream};
+use crate::error::AppError;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tracing::warn;
+
+/// Header carrying a job's correlation ID to the engine.
+pub const CORRELATION_HEADER: &str = "X-Correlation-ID";
+
+const TAG: &str = "[cid=";
+const LOG_DIR: &str = "jobs";
+
+/// Serialises appends so lines from the stdout and stderr readers do not interleave.
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// A fresh correlation ID: 16 random hex digits.
+pub fn correlation_id() -> String {
+    let mut bytes = [0u8; 8];
+    getrandom::fill(&mut bytes).expect("the OS random number generator is unavailable");
+    format!("{:016x}", u64::from_le_bytes(bytes))
+}
+
+/// The correlation ID a line of engine output is tagged with, if any.
+pub fn tagged(line: &str) -> Option<String> {
+    let start = line.find(TAG)? + TAG.len();
+    let rest = &line[start..];
+    let id = &rest[..rest.find(']')?];
+    is_valid(id).then(|| id.to_string())
+}
+
+/// IDs end up in file names, so anything but the hex digits we hand out is refused.
+fn is_valid(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 64 && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+}
+
+/// Where the log of the job with `correlation_id` is kept.
+pub fn path(app_handle: &AppHandle, correlation_id: &str) -> Result<PathBuf, AppError> {
+    if !is_valid(correlation_id) {
+        return Err(AppError::JobLogNotFound(correlation_id.to_string()));
+    }
+    let dir = crate::portable::app_log_dir(app_handle)?.join(LOG_DIR);
+    Ok(dir.join(format!("{}.log", correlation_id)))
+}
+
+/// Appends a line of engine output to the log of the job it is tagged with.
+pub fn append(app_handle: &AppHandle, correlation_id: &str, entry: &EngineLogLine) {
+    let stream = match entry.stream {
+        LogStream::Stdout => "stdout",
+        LogStream::Stderr => "stderr",
+    };
+    write(
+        app_handle,
+        correlation_id,
+        &format!("{} {} {}", entry.timestamp, stream, entry.line),
+    );
+}
+
+/// Appends a note from the shell, such as the job starting or why it failed.
+pub fn note(app_handle: &AppHandle, correlation_id: &str, message: &str) {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    write(app_handle, correlation_id, &format!("{} shell {}", timestamp, message));
+}
+
+/// Writes failures to the app log instead: a missing job log must never
+/// interrupt the engine monitor or the job itself.
+fn write(app_handle: &AppHandle, correlation_id: &str, line: &str) {
+    let written = path(app_handle, correlation_id).and_then(|path| {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let _guard = WRITE_LOCK.lock().unwrap();
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    });
+    if let Err(e) = written {
+        warn!("Could not write to the log of job {}: {}", correlation_id, e);
+    }
+}
+
+/// Deletes the log of a job dropped from the history.
+pub fn remove(app_handle: &AppHandle, correlation_id: &str) {
+    if let Ok(path) = path(app_handle, correlation_id) {
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,691 @@
+//! Analysis jobs queued in the shell and handed to the engine a few at a time,
+//! so a batch of submissions cannot overload it and every window sees the same
+//! job list.
+//!
+//! Interactive jobs start before any queued batch job, and batch jobs never
+//! take the last free slot, so a quick check is not stuck behind an overnight
+//! batch.
+//!
+//! Each job goes through the engine's job API:
+//! - `POST /create-job` with the analysis request → the engine's job `{id}`
+//! - `POST /run-job/{id}` to start it
+//! - `GET /jobs/{id}` → `{status, progress, status_message, error}`, polled
+//!   (finer-grained updates arrive as [`progress`](crate::progress) lines)
+//! - `POST /jobs/{id}/cancel`, best effort, when the user cancels
+//!
+//! A local engine is given a temporary directory per job in the
+//! [`WORK_DIR_HEADER`], under the [`TempStore`](crate::temp::TempStore), and
+//! runs the job's tracy processes in it. Cancelling a
+//! job kills whatever of those is still running and deletes the directory.
+//!
+//! Results of completed jobs are kept in the [`ResultCache`], and a job whose
+//! result is already there completes without reaching the engine.
+//!
+//! Batch jobs are [`checkpoint`]ed as their samples finish, and resumed at the
+//! next startup if the app stopped before they did.
+//!
+//! Finished jobs are recorded in the [`history`]; what the engine logged about
+//! each one is kept in its own file by [`logs`].
+
+mod checkpoint;
+mod history;
+mod logs;
+
+pub use checkpoint::resume as resume_interrupted;
+pub use history::{start_cleanup, JobHistory, JobRecord, JOB_HISTORY_CHANGED_EVENT};
+pub use logs::{append as append_log, path as log_path, tagged as log_tag};
+
+use crate::cache::{self, ResultCache};
+use crate::disk;
+use crate::engine::{EngineClient, EngineManager};
+use crate::error::AppError;
+use crate::progress::AnalysisProgress;
+use crate::settings::SettingsStore;
+use crate::temp::TempStore;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_http::reqwest::{self, header, Method};
+use tracing::{debug, info, warn};
+
+/// Emitted with a [`JobInfo`] whenever a job changes state or makes progress.
+pub const JOB_PROGRESS_EVENT: &str = "job-progress";
+
+/// Emitted with the [`JobInfo`] of a cancelled job once its engine work has
+/// been stopped and its temporary files removed.
+pub const JOB_CANCELLED_EVENT: &str = "job-cancelled";
+
+/// Emitted with whether the queue is paused whenever that changes.
+pub const QUEUE_PAUSED_EVENT: &str = "queue-paused-changed";
+
+/// Header telling a local engine which directory to keep the job's temporary files in.
+pub const WORK_DIR_HEADER: &str = "X-Job-Work-Dir";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Whether someone is waiting on the job right now.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPriority {
+    #[default]
+    Interactive,
+    Batch,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    fn as_str(self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Completed => "completed",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        }
+    }
+
+    fn parse(state: &str) -> Self {
+        match state {
+            "queued" => JobState::Queued,
+            "running" => JobState::Running,
+            "completed" => JobState::Completed,
+            "cancelled" => JobState::Cancelled,
+            _ => JobState::Failed,
+        }
+    }
+
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobState::Completed | JobState::Failed | JobState::Cancelled)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JobInfo {
+    pub id: u64,
+    /// The engine's id for the job, once it has been created there.
+    pub engine_job_id: Option<String>,
+    /// Tags the engine output about this job and names its log file.
+    pub correlation_id: String,
+    pub name: String,
+    pub priority: JobPriority,
+    pub state: JobState,
+    /// Percent done, as last reported by the engine.
+    pub progress: Option<f64>,
+    /// Step the engine is on, from its progress reports.
+    pub stage: Option<String>,
+    pub message: Option<String>,
+    pub error: Option<String>,
+    /// Unix timestamps in seconds.
+    pub submitted_at: u64,
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+    /// Files and directories the engine reported as the job's results.
+    pub output_paths: Vec<PathBuf>,
+    /// Result cache key, once computed; see `get_job_result`.
+    pub result_key: Option<String>,
+    /// The result came from the cache instead of the engine.
+    pub cached: bool,
+    /// Samples finished before the app was interrupted, left out of this run.
+    pub resumed_samples: usize,
+}
+
+struct Job {
+    info: JobInfo,
+    /// Body for `POST /create-job`.
+    request: Value,
+}
+
+#[derive(Deserialize)]
+struct CreatedJob {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct EngineJob {
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    progress: Option<f64>,
+    #[serde(default)]
+    status_message: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    output_paths: Vec<PathBuf>,
+}
+
+/// Every job submitted this session, oldest first.
+#[derive(Default)]
+pub struct JobQueue {
+    jobs: Mutex<Vec<Job>>,
+    /// Queued jobs wait until this is cleared; running ones carry on.
+    paused: AtomicBool,
+}
+
+impl JobQueue {
+    /// Applies `change` to job `id` and tells the frontend; records the job in
+    /// the history once it has finished.
+    fn update(&self, app_handle: &AppHandle, id: u64, change: impl FnOnce(&mut JobInfo)) -> Option<JobInfo> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.iter_mut().find(|job| job.info.id == id)?;
+        let was_finished = job.info.state.is_finished();
+        change(&mut job.info);
+        let info = job.info.clone();
+        let request = (!was_finished && info.state.is_finished()).then(|| job.request.clone());
+        drop(jobs);
+        let _ = app_handle.emit(JOB_PROGRESS_EVENT, &info);
+        crate::taskbar::update(app_handle);
+        if let Some(request) = request {
+            checkpoint::remove(app_handle, &info.correlation_id);
+            history::record(app_handle, &info, &request);
+            crate::notify::job_finished(app_handle, &info);
+        }
+        Some(info)
+    }
+
+    pub fn get(&self, id: u64) -> Option<JobInfo> {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.iter().find(|job| job.info.id == id).map(|job| job.info.clone())
+    }
+
+    /// The analysis request job `id` was submitted with.
+    pub fn request(&self, id: u64) -> Option<Value> {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.iter().find(|job| job.info.id == id).map(|job| job.request.clone())
+    }
+
+    fn state(&self, id: u64) -> Option<JobState> {
+        self.get(id).map(|info| info.state)
+    }
+}
+
+async fn json<T: for<'de> Deserialize<'de>>(response: reqwest::Response) -> Result<T, AppError> {
+    parse(&response.bytes().await?)
+}
+
+fn parse<T: for<'de> Deserialize<'de>>(body: &[u8]) -> Result<T, AppError> {
+    serde_json::from_slice(body).map_err(|e| AppError::JobFailed(format!("unexpected engine response: {}", e)))
+}
+
+/// The result cache key of `request`, unless the engine version is not known
+/// yet or an input cannot be read.
+async fn result_key(app_handle: &AppHandle, request: &Value) -> Option<String> {
+    let version = app_handle.state::<EngineManager>().engine_version()?;
+    let request = request.clone();
+    match tauri::async_runtime::spawn_blocking(move || cache::key(&version, &request)).await {
+        Ok(Ok(key)) => Some(key),
+        Ok(Err(e)) => {
+            debug!("Not caching a job whose inputs cannot be read: {}", e);
+            None
+        }
+        Err(_) => None,
+    }
+}
+
+/// The outputs of the cached result for `key`, if there is one and they are
+/// all still on disk (the history cleanup may have removed them).
+fn cached_outputs(app_handle: &AppHandle, key: &str) -> Option<Vec<PathBuf>> {
+    let result = app_handle.state::<ResultCache>().get(key)?;
+    let job: EngineJob = parse(&result).ok()?;
+    job.output_paths
+        .iter()
+        .all(|path| path.exists())
+        .then_some(job.output_paths)
+}
+
+/// Checks there is room for the job's temporary files and results before it
+/// is queued or started. The volumes of a remote engine are its own business.
+fn check_space(app_handle: &AppHandle, request: &Value) -> Result<(), AppError> {
+    if app_handle.state::<EngineManager>().is_remote() {
+        return Ok(());
+    }
+    let output = request.get("output_dir").and_then(Value::as_str).map(Path::new);
+    disk::preflight(
+        app_handle,
+        disk::request_input_bytes(request),
+        app_handle.state::<TempStore>().root(),
+        output,
+    )
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Applies a progress report to the running job it is about, if any, and
+/// returns that job's id.
+pub fn apply_progress(app_handle: &AppHandle, progress: &AnalysisProgress) -> Option<u64> {
+    let queue = app_handle.state::<JobQueue>();
+    let (id, correlation_id) = queue.jobs.lock().unwrap().iter().find_map(|job| {
+        let info = &job.info;
+        let matches = info.engine_job_id.as_deref() == Some(progress.job_id.as_str())
+            || info.correlation_id == progress.job_id;
+        (matches && info.state == JobState::Running).then(|| (info.id, info.correlation_id.clone()))
+    })?;
+    if progress.stage == checkpoint::SAMPLE_COMPLETED_STAGE {
+        checkpoint::sample_completed(app_handle, &correlation_id, progress);
+    }
+    queue.update(app_handle, id, |info| {
+        info.stage = Some(progress.stage.clone());
+        if progress.percent.is_some() {
+            info.progress = progress.percent;
+        }
+        if progress.message.is_some() {
+            info.message = progress.message.clone();
+        }
+    });
+    Some(id)
+}
+
+/// How many jobs are running on the engine.
+pub fn running_count(app_handle: &AppHandle) -> usize {
+    let queue = app_handle.state::<JobQueue>();
+    let jobs = queue.jobs.lock().unwrap();
+    jobs.iter().filter(|job| job.info.state == JobState::Running).count()
+}
+
+/// How many jobs may run at once: `max_concurrent_jobs`, or what suits the
+/// machine.
+fn slots(app_handle: &AppHandle) -> usize {
+    let configured = app_handle.state::<SettingsStore>().get().max_concurrent_jobs;
+    configured.unwrap_or_else(crate::system::recommended_concurrent_jobs) as usize
+}
+
+/// Starts queued jobs, interactive ones first, while there are free slots.
+/// Batch jobs leave one slot free for interactive work unless there is only
+/// one. Nothing starts while the queue is paused or the engine drains for a
+/// restart. Called on submission, when a job finishes and when the engine
+/// becomes ready.
+pub fn dispatch(app_handle: &AppHandle) {
+    let busy = app_handle
+        .state::<JobQueue>()
+        .jobs
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|job| !job.info.state.is_finished());
+    crate::power::update(app_handle, busy);
+    crate::taskbar::update(app_handle);
+
+    let manager = app_handle.state::<EngineManager>();
+    let paused = app_handle.state::<JobQueue>().paused.load(Ordering::SeqCst);
+    if !manager.is_ready() || manager.is_draining() || paused {
+        return;
+    }
+    let limit = slots(app_handle);
+    let batch_limit = if limit > 1 { limit - 1 } else { limit };
+    let queue = app_handle.state::<JobQueue>();
+    let mut jobs = queue.jobs.lock().unwrap();
+    let running = |jobs: &[Job], priority: Option<JobPriority>| {
+        jobs.iter()
+            .filter(|job| job.info.state == JobState::Running)
+            .filter(|job| priority.is_none_or(|priority| job.info.priority == priority))
+            .count()
+    };
+    let mut running_all = running(&jobs, None);
+    let mut running_batch = running(&jobs, Some(JobPriority::Batch));
+
+    let mut started = Vec::new();
+    for priority in [JobPriority::Interactive, JobPriority::Batch] {
+        for job in jobs
+            .iter_mut()
+            .filter(|job| job.info.state == JobState::Queued && job.info.priority == priority)
+        {
+            if running_all >= limit || (priority == JobPriority::Batch && running_batch >= batch_limit) {
+                break;
+            }
+            job.info.state = JobState::Running;
+            job.info.started_at = Some(now());
+            running_all += 1;
+            if priority == JobPriority::Batch {
+                running_batch += 1;
+            }
+            started.push((job.info.clone(), job.request.clone()));
+        }
+    }
+    drop(jobs);
+
+    for (info, request) in started {
+        info!("Starting job {} ({})", info.id, info.name);
+        logs::note(app_handle, &info.correlation_id, &format!("Starting job {} ({})", info.id, info.name));
+        let _ = app_handle.emit(JOB_PROGRESS_EVENT, &info);
+        tauri::async_runtime::spawn(run(app_handle.clone(), info.id, info.correlation_id, request));
+    }
+}
+
+async fn run(app_handle: AppHandle, id: u64, correlation_id: String, request: Value) {
+    let result = execute(&app_handle, id, &correlation_id, request).await.map(|output_paths| {
+        let mut resumed = checkpoint::outputs(&app_handle, &correlation_id);
+        resumed.extend(output_paths);
+        resumed
+    });
+    app_handle.state::<TempStore>().release(&correlation_id);
+    let queue = app_handle.state::<JobQueue>();
+    // A cancelled job keeps its state, whatever the engine did since.
+    if queue.state(id) == Some(JobState::Running) {
+        match &result {
+            Ok(_) => logs::note(&app_handle, &correlation_id, "Job completed"),
+            Err(e) => logs::note(&app_handle, &correlation_id, &format!("Job failed: {}", e)),
+        }
+        queue.update(&app_handle, id, |info| {
+            info.finished_at = Some(now());
+            match result {
+                Ok(output_paths) => {
+                    info.state = JobState::Completed;
+                    info.progress = Some(100.0);
+                    info.output_paths = output_paths;
+                }
+                Err(e) => {
+                    warn!("Job {} failed: {}", id, e);
+                    info.state = JobState::Failed;
+                    info.error = Some(e.to_string());
+                }
+            }
+        });
+    }
+    dispatch(&app_handle);
+}
+
+/// Creates and starts the job on the engine, then follows it until it is done.
+/// Returns the outputs it reported.
+async fn execute(
+    app_handle: &AppHandle,
+    id: u64,
+    correlation_id: &str,
+    request: Value,
+) -> Result<Vec<PathBuf>, AppError> {
+    let client = app_handle.state::<EngineClient>();
+    let queue = app_handle.state::<JobQueue>();
+
+    let key = result_key(app_handle, &request).await;
+    if let Some(key) = &key {
+        if let Some(output_paths) = cached_outputs(app_handle, key) {
+            info!("Job {} answered from the result cache", id);
+            logs::note(app_handle, correlation_id, "Result taken from the cache");
+            queue.update(app_handle, id, |info| {
+                info.result_key = Some(key.clone());
+                info.cached = true;
+            });
+            return Ok(output_paths);
+        }
+        queue.update(app_handle, id, |info| info.result_key = Some(key.clone()));
+    }
+    check_space(app_handle, &request)?;
+
+    let mut create = client
+        .request(Method::POST, "/create-job")
+        .header(logs::CORRELATION_HEADER, correlation_id)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_vec(&request).unwrap_or_default());
+    if !app_handle.state::<EngineManager>().is_remote() {
+        let dir = app_handle.state::<TempStore>().create(app_handle, correlation_id)?;
+        create = create.header(WORK_DIR_HEADER, dir.to_string_lossy().as_ref());
+    }
+    let created: CreatedJob = json(client.send(create).await?).await?;
+    queue.update(app_handle, id, |info| info.engine_job_id = Some(created.id.clone()));
+    // Cancelled while being created: `cancel_job` did not know the engine's id yet.
+    if queue.state(id) != Some(JobState::Running) {
+        cancel_on_engine(app_handle, &created.id).await;
+        return Ok(Vec::new());
+    }
+
+    let start = client
+        .request(Method::POST, &format!("/run-job/{}", created.id))
+        .header(logs::CORRELATION_HEADER, correlation_id);
+    client.send(start).await?;
+
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        if queue.state(id) != Some(JobState::Running) {
+            return Ok(Vec::new());
+        }
+        let status = client.request(Method::GET, &format!("/jobs/{}", created.id));
+        let body = client.send(status).await?.bytes().await?;
+        let job: EngineJob = parse(&body)?;
+        match job.status.as_deref() {
+            Some("completed") => {
+                if let Some(key) = &key {
+                    let results = app_handle.state::<ResultCache>();
+                    if let Err(e) = results.put(key, &body, cache::max_bytes(app_handle)) {
+                        warn!("Could not cache the result of job {}: {}", id, e);
+                    }
+                }
+                return Ok(job.output_paths);
+            }
+            Some("failed") | Some("error") => {
+                return Err(AppError::JobFailed(
+                    job.error.unwrap_or_else(|| "the engine reported a failure".to_string()),
+                ))
+            }
+            _ => {
+                queue.update(app_handle, id, |info| {
+                    if job.progress.is_some() {
+                        info.progress = job.progress;
+                    }
+                    if job.status_message.is_some() {
+                        info.message = job.status_message;
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Queues an analysis request (the body `/create-job` takes) and returns its
+/// job, which starts as soon as a slot is free. Jobs are interactive unless
+/// `priority` says otherwise.
+#[tauri::command]
+pub fn submit_job(
+    app_handle: AppHandle,
+    request: Value,
+    priority: Option<JobPriority>,
+) -> Result<JobInfo, AppError> {
+    check_space(&app_handle, &request)?;
+    let priority = priority.unwrap_or_default();
+    let correlation_id = logs::correlation_id();
+    if priority == JobPriority::Batch {
+        checkpoint::create(&app_handle, &correlation_id, &request);
+    }
+    Ok(enqueue(&app_handle, request, priority, correlation_id, 0))
+}
+
+/// Adds a job to the queue and starts it if there is a free slot.
+fn enqueue(
+    app_handle: &AppHandle,
+    request: Value,
+    priority: JobPriority,
+    correlation_id: String,
+    resumed_samples: usize,
+) -> JobInfo {
+    let name = request
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or("Untitled analysis")
+        .to_string();
+    let queue = app_handle.state::<JobQueue>();
+    let info = {
+        let mut jobs = queue.jobs.lock().unwrap();
+        let id = jobs.last().map_or(1, |job| job.info.id + 1);
+        let info = JobInfo {
+            id,
+            engine_job_id: None,
+            correlation_id,
+            name,
+            priority,
+            state: JobState::Queued,
+            progress: None,
+            stage: None,
+            message: None,
+            error: None,
+            submitted_at: now(),
+            started_at: None,
+            finished_at: None,
+            output_paths: Vec::new(),
+            result_key: None,
+            cached: false,
+            resumed_samples,
+        };
+        jobs.push(Job {
+            info: info.clone(),
+            request,
+        });
+        info
+    };
+    info!("Queued job {} ({})", info.id, info.name);
+    let _ = app_handle.emit(JOB_PROGRESS_EVENT, &info);
+    dispatch(app_handle);
+    info
+}
+
+/// Cancels a queued or running job. Finished jobs are left as they are.
+#[tauri::command]
+pub fn cancel_job(app_handle: AppHandle, id: u64) -> Result<JobInfo, AppError> {
+    let queue = app_handle.state::<JobQueue>();
+    let current = queue.get(id).ok_or(AppError::JobNotFound(id))?;
+    if current.state.is_finished() {
+        return Ok(current);
+    }
+    let info = queue
+        .update(&app_handle, id, |info| {
+            info.state = JobState::Cancelled;
+            info.finished_at = Some(now());
+        })
+        .ok_or(AppError::JobNotFound(id))?;
+    info!("Cancelled job {}", id);
+    logs::note(&app_handle, &info.correlation_id, "Job cancelled");
+
+    let handle = app_handle.clone();
+    let cancelled = info.clone();
+    tauri::async_runtime::spawn(async move { tear_down(&handle, cancelled).await });
+    dispatch(&app_handle);
+    Ok(info)
+}
+
+/// Stops what a cancelled job left running: the engine's work on it, then any
+/// of its tracy processes still alive, then its temporary directory.
+async fn tear_down(app_handle: &AppHandle, info: JobInfo) {
+    if let Some(engine_job_id) = &info.engine_job_id {
+        cancel_on_engine(app_handle, engine_job_id).await;
+    }
+    let temp = app_handle.state::<TempStore>();
+    let killed = app_handle
+        .state::<EngineManager>()
+        .kill_processes_in(&temp.path(&info.correlation_id));
+    if killed > 0 {
+        info!("Killed {} leftover processes of job {}", killed, info.id);
+        logs::note(app_handle, &info.correlation_id, &format!("Killed {} leftover processes", killed));
+    }
+    temp.remove(&info.correlation_id);
+    let _ = app_handle.emit(JOB_CANCELLED_EVENT, &info);
+}
+
+/// Asks the engine to stop a job, best effort.
+async fn cancel_on_engine(app_handle: &AppHandle, engine_job_id: &str) {
+    let client = app_handle.state::<EngineClient>();
+    let request = client.request(Method::POST, &format!("/jobs/{}/cancel", engine_job_id));
+    if let Err(e) = client.send(request).await {
+        warn!("Could not cancel job {} on the engine: {}", engine_job_id, e);
+    }
+}
+
+/// Stops queued jobs from starting, or lets them start again. Jobs already
+/// running are not affected.
+#[tauri::command]
+pub fn set_queue_paused(app_handle: AppHandle, paused: bool) {
+    let queue = app_handle.state::<JobQueue>();
+    if queue.paused.swap(paused, Ordering::SeqCst) == paused {
+        return;
+    }
+    info!("Job queue {}", if paused { "paused" } else { "resumed" });
+    let _ = app_handle.emit(QUEUE_PAUSED_EVENT, paused);
+    crate::tray::update(&app_handle);
+    dispatch(&app_handle);
+}
+
+#[tauri::command]
+pub fn is_queue_paused(queue: tauri::State<JobQueue>) -> bool {
+    queue.paused.load(Ordering::SeqCst)
+}
+
+/// Every job submitted this session, oldest first.
+#[tauri::command]
+pub fn list_jobs(queue: tauri::State<JobQueue>) -> Vec<JobInfo> {
+    queue.jobs.lock().unwrap().iter().map(|job| job.info.clone()).collect()
+}
+
+/// Asks where to save the log of the job with `correlation_id` and copies it
+/// there. Returns `None` when the user cancels the dialog.
+#[tauri::command]
+pub async fn export_job_log(app_handle: AppHandle, correlation_id: String) -> Result<Option<PathBuf>, AppError> {
+    let source = logs::path(&app_handle, &correlation_id)?;
+    if !source.is_file() {
+        return Err(AppError::JobLogNotFound(correlation_id));
+    }
+
+    let Some(destination) = app_handle
+        .dialog()
+        .file()
+        .set_title("Export job log")
+        .set_file_name(format!("ps-analyzer-job-{}.log", correlation_id))
+        .add_filter("Log file", &["log", "txt"])
+        .blocking_save_file()
+        .and_then(|path| path.into_path().ok())
+    else {
+        return Ok(None);
+    };
+
+    std::fs::copy(&source, &destination)?;
+    info!("Exported the log of job {} to {:?}", correlation_id, destination);
+    Ok(Some(destination))
+}
+
+/// The engine's final report on job `id` (`GET /jobs/{id}` once completed),
+/// from the result cache.
+pub fn result(app_handle: &AppHandle, id: u64) -> Result<Vec<u8>, AppError> {
+    let info = app_handle.state::<JobQueue>().get(id).ok_or(AppError::JobNotFound(id))?;
+    info.result_key
+        .filter(|_| info.state == JobState::Completed)
+        .and_then(|key| app_handle.state::<ResultCache>().get(&key))
+        .ok_or_else(|| AppError::JobFailed(format!("no result is available for job {}", id)))
+}
+
+/// [`result`] as JSON text.
+#[tauri::command]
+pub fn get_job_result(app_handle: AppHandle, id: u64) -> Result<tauri::ipc::Response, AppError> {
+    let result = result(&app_handle, id)?;
+    Ok(tauri::ipc::Response::new(String::from_utf8_lossy(&result).into_owned()))
+}
+
+/// Finished jobs from this and earlier sessions, most recent first.
+#[tauri::command]
+pub fn get_job_history(
+    history: tauri::State<JobHistory>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<JobRecord>, AppError> {
+    history.list(limit, offset)
+}
+
+/// Forgets every recorded job; their outputs are left alone.
+#[tauri::command]
+pub fn clear_job_history(app_handle: AppHandle) -> Result<(), AppError> {
+    app_handle.state::<JobHistory>().clear()?;
+    let _ = app_handle.emit(JOB_HISTORY_CHANGED_EVENT, ());
+    Ok(())
+}
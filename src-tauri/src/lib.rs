@@ -1,234 +1,335 @@
-use tauri::Manager;
-use tauri_plugin_shell::ShellExt;
-use tauri_plugin_shell::process::CommandEvent;
-use std::net::TcpListener;
-
-struct AppState {
-    port: u16,
-}
+mod align;
+mod archive_import;
+mod blast;
+mod blob;
+mod cache;
+mod clipboard;
+mod data_dir;
+mod deep_link;
+mod disk;
+mod duplicates;
+mod engine;
+mod engine_log;
+mod error;
+mod file_drop;
+mod file_intake;
+mod folder_import;
+mod import;
+mod instance;
+mod ipc;
+mod jobs;
+mod logging;
+mod menu;
+mod ncbi;
+mod notify;
+mod portable;
+mod power;
+mod primers;
+mod progress;
+mod project;
+mod recent;
+mod references;
+mod report;
+mod restriction;
+mod schedule;
+mod sequence;
+mod session;
+mod settings;
+mod sidecar;
+mod shortcuts;
+mod sidecar_update;
+mod supervisor;
+mod support;
+mod system;
+mod table;
+mod taskbar;
+mod temp;
+mod tools;
+mod trace;
+mod tracy;
+mod tray;
+mod updater;
+mod upload;
+mod watch;
+mod window_state;
+mod windows;
 
-#[tauri::command]
-fn get_backend_port(state: tauri::State<AppState>) -> u16 {
-    state.port
-}
-
-fn get_available_port() -> u16 {
-    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind to random port");
-    listener.local_addr().expect("Failed to get local address").port()
-}
+use engine::{Endpoint, EngineLaunchConfig, EngineManager};
+use engine_log::EngineLogBuffer;
+use settings::SettingsStore;
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    // Before any webview is created.
+    portable::init();
+    let app = tauri::Builder::default()
+        // Must be registered first so a second launch exits before doing any work.
+        .plugin(tauri_plugin_single_instance::init(instance::on_second_instance))
+        // Right after single-instance, which forwards links from a second launch to it.
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_http::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(shortcuts::on_shortcut)
+                .build(),
+        )
+        .plugin(tauri_plugin_updater::Builder::new().build())
         .setup(|app| {
             let app_handle = app.handle().clone();
-            
-            tauri::async_runtime::spawn(async move {
-                let port = get_available_port();
-                app_handle.manage(AppState { port });
-
-                let mut sidecar_command = app_handle
-                    .shell()
-                    .sidecar("ps-analyzer-bio-engine")
-                    .expect("failed to create sidecar")
-                    .env("BIO_PORT", port.to_string());
-
-                // Resolve sidecar paths to pass them to the bio-engine
-                let target_triple = if cfg!(target_os = "linux") {
-                    "x86_64-unknown-linux-gnu"
-                } else if cfg!(target_os = "windows") {
-                    "x86_64-pc-windows-msvc"
-                } else {
-                    "unknown"
-                };
-
-                if let Ok(path_resolver) = app_handle.path().resource_dir() {
-                    let tools = [
-                        ("tracy", "TRACY_PATH", "--tracy-path"),
-                        ("bgzip", "BIO_BGZIP_PATH", "--bgzip-path"),
-                        ("samtools", "BIO_SAMTOOLS_PATH", "--samtools-path"),
-                    ];
-
-                    for (name, env_var, arg) in tools {
-                        let sidecar_id = format!("ps-analyzer-{}", name);
-                        let mut final_path = None;
-
-                        // List of potential paths to check, in order of priority
-                        let mut paths_to_check = Vec::new();
+            let (settings_store, settings_notes) = SettingsStore::load(&app_handle);
+            let settings = settings_store.get();
+            app.manage(settings_store);
 
-                        // 1. Standard sidecar location (Resource dir / {id}-{triple})
-                        paths_to_check.push(path_resolver.join(format!("{}-{}", sidecar_id, target_triple)));
-                        
-                        // 2. Flattened resource location (Resource dir / {id})
-                        paths_to_check.push(path_resolver.join(&sidecar_id));
-                        
-                        // Windows-specific: Add .exe variants
-                        if cfg!(target_os = "windows") {
-                            paths_to_check.push(path_resolver.join(format!("{}-{}.exe", sidecar_id, target_triple)));
-                            paths_to_check.push(path_resolver.join(format!("{}.exe", sidecar_id)));
-                        }
-
-                        // 3. Executable directory (common for Linux packages)
-                        if let Ok(exe_dir) = app_handle.path().executable_dir() {
-                            // Check with and without triple in exe_dir
-                            paths_to_check.push(exe_dir.join(format!("{}-{}", sidecar_id, target_triple)));
-                            paths_to_check.push(exe_dir.join(&sidecar_id));
-                            if cfg!(target_os = "windows") {
-                                paths_to_check.push(exe_dir.join(format!("{}-{}.exe", sidecar_id, target_triple)));
-                                paths_to_check.push(exe_dir.join(format!("{}.exe", sidecar_id)));
-                            }
-                        }
-
-                        // 4. Development fallback (Project root / src-tauri / binaries / {id}-{triple})
-                        if let Ok(cwd) = std::env::current_dir() {
-                            paths_to_check.push(cwd.join(format!("src-tauri/binaries/{}-{}", sidecar_id, target_triple)));
-                            paths_to_check.push(cwd.join(format!("src-tauri/binaries/{}", sidecar_id)));
-                            if cfg!(target_os = "windows") {
-                                paths_to_check.push(cwd.join(format!("src-tauri/binaries/{}-{}.exe", sidecar_id, target_triple)));
-                                paths_to_check.push(cwd.join(format!("src-tauri/binaries/{}.exe", sidecar_id)));
-                            }
-                        }
-
-                        // 5. Explicit system paths (Final fallback for Linux)
-                        if cfg!(target_os = "linux") {
-                            paths_to_check.push(std::path::PathBuf::from(format!("/usr/bin/{}", sidecar_id)));
-                            paths_to_check.push(std::path::PathBuf::from(format!("/bin/{}", sidecar_id)));
-                            paths_to_check.push(std::path::PathBuf::from(format!("/usr/local/bin/{}", sidecar_id)));
-                            
-                            // Conda fallbacks (for dev environment)
-                            if let Ok(home) = std::env::var("HOME") {
-                                paths_to_check.push(std::path::PathBuf::from(format!("{}/miniforge3/envs/bio-engine/bin/{}", home, name)));
-                                paths_to_check.push(std::path::PathBuf::from(format!("{}/anaconda3/envs/bio-engine/bin/{}", home, name)));
-                                paths_to_check.push(std::path::PathBuf::from(format!("{}/miniconda3/envs/bio-engine/bin/{}", home, name)));
-                            }
-                        }
-
-                        // Find the first path that exists
-                        for path in &paths_to_check {
-                            println!("Checking path for {}: {:?}", name, path);
-                            if path.exists() {
-                                final_path = Some(path.clone());
-                                println!("Found {} at: {:?}", name, path);
-                                break;
-                            }
-                        }
-
-                        if let Some(path) = final_path {
-                            println!("Redirecting bio-engine to use {} at: {:?}", name, path);
-                            sidecar_command = sidecar_command
-                                .env(env_var, path.to_string_lossy().to_string())
-                                .args([arg, &path.to_string_lossy()]);
-                        } else {
-                            // Final fallback: Don't pass the path, let bio-engine use system PATH
-                            println!("Sidecar for {} not found. Bio-engine will attempt to use system '{}' from PATH.", name, name);
-                        }
-                    }
+            logging::init(app, settings.log_level.as_deref());
+            for note in settings_notes {
+                tracing::warn!("{}", note);
+            }
+            if let Some(root) = portable::root() {
+                tracing::info!("Portable mode: keeping settings, data and logs in {:?}", root);
+            } else if let Some(problem) = portable::problem() {
+                tracing::warn!("Portable mode not available: {}", problem);
+            }
+            // Before anything opens a file under the data or cache folders.
+            app.manage(data_dir::DataDirs::resolve(&app_handle));
 
-                    // Pass the resource directory itself (flat structure) for DLL discovery
-                    let resource_path = path_resolver;
-                    let resource_path_str = resource_path.to_string_lossy().to_string();
-                    println!("Passing resource path to bio-engine: {}", resource_path_str);
-                    sidecar_command = sidecar_command.args(["--resource-path", &resource_path_str]);
+            // Pick the port before anything else so `get_engine_port` is answerable
+            // as soon as the frontend boots, even while the sidecar is still starting.
+            let port = engine::get_available_port(settings.engine_port)?;
+            tracing::info!("Allocated port {} for bio-engine", port);
 
-                    // BEST PRACTICE: Add resource and binaries folders to the sidecar's PATH directly
-                    // This helps Windows find DLLs even if the sidecar is launched from elsewhere
-                    if let Ok(current_path) = std::env::var("PATH") {
-                        let mut new_path = vec![resource_path_str.clone()];
-                        
-                        // Also add standard subfolders where DLLs might be
-                        let binaries_sub = resource_path.join("binaries");
-                        if binaries_sub.exists() {
-                            new_path.push(binaries_sub.to_string_lossy().to_string());
-                        }
-                        
-                        let resources_sub = resource_path.join("resources/binaries");
-                        if resources_sub.exists() {
-                            new_path.push(resources_sub.to_string_lossy().to_string());
+            let endpoint = Endpoint::new(settings.engine_transport, port);
+            let config = EngineLaunchConfig::resolve(&app_handle, &endpoint, &settings);
+            let manager = EngineManager::new(port, config, settings.remote_engine.is_some());
+            let client = match &settings.remote_engine {
+                Some(remote) => {
+                    let url = match &remote.ssh {
+                        Some(ssh) => {
+                            let local_port = engine::get_available_port(None)?;
+                            let tunnel = engine::SshTunnel::new(ssh.clone(), &remote.url, local_port)?;
+                            let url = tunnel.local_url();
+                            app.manage(tunnel);
+                            url
                         }
-
-                        new_path.push(current_path);
-                        let final_path_env = new_path.join(if cfg!(target_os = "windows") { ";" } else { ":" });
-                        sidecar_command = sidecar_command.env("PATH", final_path_env);
-                    }
+                        None => remote.url.clone(),
+                    };
+                    engine::EngineClient::new(Endpoint::Remote(url), &remote.token)?
                 }
+                None => engine::EngineClient::new(endpoint, manager.token())?,
+            };
+            app.manage(client);
+            app.manage(manager);
+            app.manage(EngineLogBuffer::new());
+            app.manage(supervisor::ProcessSupervisor::default());
+            app.manage(tools::ToolManager::default());
+            app.manage(updater::PendingUpdate::default());
+            app.manage(file_intake::PendingFiles::default());
+            app.manage(duplicates::ImportedFiles::default());
+            app.manage(ncbi::NcbiClient::default());
+            app.manage(deep_link::PendingAccessions::default());
+            app.manage(recent::RecentStore::open(&app_handle));
+            app.manage(sequence::SequenceIndexes::default());
+            app.manage(trace::TraceCache::default());
+            app.manage(upload::Uploads::default());
+            app.manage(temp::TempStore::open());
+            app.manage(cache::ResultCache::open(&app_handle));
+            app.manage(jobs::JobQueue::default());
+            app.manage(power::SleepGuard::default());
+            app.manage(notify::Notifier::default());
+            app.manage(shortcuts::Shortcuts::default());
+            app.manage(taskbar::TaskbarProgress::default());
+            app.manage(jobs::JobHistory::open(&app_handle));
+            app.manage(references::ReferenceStore::open(&app_handle));
+            app.manage(watch::FolderWatcher::default());
+            app.manage(schedule::Scheduler::open(&app_handle));
+            app.manage(blob::BlobStore::open(&app_handle));
+            app.manage(session::SessionManager::start(&app_handle));
+            app.manage(window_state::WindowStates::open(&app_handle));
+            app.manage(windows::Windows::default());
+            if let Some(main) = app.get_window("main") {
+                window_state::restore(&main);
+            }
 
-                // Detect if we are running in a "portable" context
-                let mut data_dir_arg = None;
-                if let Ok(exe_dir) = app_handle.path().executable_dir() {
-                    let mut is_portable = exe_dir.join(".portable").exists() || exe_dir.join("portable").exists();
-                    
-                    // Windows: consider portable if not in Program Files and directory is writable
-                    #[cfg(target_os = "windows")]
-                    if !is_portable {
-                        let exe_dir_str = exe_dir.to_string_lossy().to_lowercase();
-                        let pf = std::env::var("ProgramFiles").unwrap_or_default().to_lowercase();
-                        let pf86 = std::env::var("ProgramFiles(x86)").unwrap_or_default().to_lowercase();
-                        if !exe_dir_str.starts_with(&pf) && !exe_dir_str.starts_with(&pf86) {
-                            is_portable = true;
-                        }
-                    }
-
-                    if is_portable {
-                        data_dir_arg = Some(exe_dir.join("data"));
-                    }
-                }
+            // Not fatal either: some Linux desktops have no tray.
+            if let Err(e) = tray::init(&app_handle) {
+                tracing::warn!("Could not create the tray icon: {}", e);
+            }
 
-                // Linux: AppImage specific portable handling
-                if let Ok(appimage_path) = std::env::var("APPIMAGE") {
-                    let path = std::path::Path::new(&appimage_path).parent();
-                    if let Some(p) = path {
-                        data_dir_arg = Some(p.join("ps-analyzer-data"));
-                    }
-                }
+            if let Err(e) = menu::init(&app_handle) {
+                tracing::warn!("Could not create the application menu: {}", e);
+            }
+            shortcuts::register_all(&app_handle);
 
-                if let Some(data_dir) = data_dir_arg {
-                    let data_dir_str = data_dir.to_string_lossy().to_string();
-                    println!("Portable mode detected. Using data directory: {}", data_dir_str);
-                    sidecar_command = sidecar_command.args(["--data-dir", &data_dir_str]);
-                }
+            engine::reap_orphans(&app_handle);
+            // Not fatal: the UI stays up to explain the problem and offer a restart.
+            if let Err(e) = engine::spawn(&app_handle) {
+                error::report(&app_handle, &e);
+                engine::show_main_window(&app_handle);
+            }
 
-                let (mut rx, _child) = sidecar_command
-                    .spawn()
-                    .expect("failed to spawn sidecar");
+            updater::check_on_launch(&app_handle);
+            session::start_autosave(&app_handle);
+            jobs::start_cleanup(&app_handle);
+            jobs::resume_interrupted(&app_handle);
+            watch::start(&app_handle);
+            schedule::start(&app_handle);
 
-                // Monitor the sidecar output
-                while let Some(event) = rx.recv().await {
-                    match event {
-                        CommandEvent::Stdout(line) => {
-                            println!("Python: {}", String::from_utf8_lossy(&line));
-                        }
-                        CommandEvent::Stderr(line) => {
-                            let error_msg = String::from_utf8_lossy(&line);
-                            eprintln!("Python Error: {}", error_msg);
-                            if error_msg.contains("address already in use") {
-                                eprintln!("CRITICAL: Port 8000 is occupied. Please ensure no other PS Analyzer instance is running.");
-                            }
-                        }
-                        CommandEvent::Terminated(payload) => {
-                            println!("Python sidecar terminated with code: {:?}", payload.code);
-                            break;
-                        }
-                        _ => {}
-                    }
-                }
-            });
+            // Files on our own command line wait for the engine like any others.
+            if let Ok(cwd) = std::env::current_dir() {
+                file_intake::intake_args(&app_handle, std::env::args().skip(1), &cwd);
+            }
+            deep_link::init(&app_handle);
 
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![get_backend_port])
-        .build(tauri::generate_context!()) // Use .build() instead of .run() to get access to events
-        .expect("error while building tauri application")
-        .run(|_app_handle, event| {
-            // This captures the Global Exit event
-            if let tauri::RunEvent::Exit = event {
-                // Tauri v2 automatically attempts to kill child processes 
-                // spawned via the shell plugin on Exit, but this confirms it.
-                println!("Application exiting, cleaning up processes...");
-            }
-        });
-}
\ No newline at end of file
+        .on_menu_event(menu::on_menu_event)
+        .on_window_event(|window, event| {
+            file_drop::on_window_event(window, event);
+            notify::on_window_event(window, event);
+            tray::on_window_event(window, event);
+            window_state::on_window_event(window, event);
+            windows::on_window_event(window, event);
+        })
+        .invoke_handler(tauri::generate_handler![
+            blob::release_blob,
+            cache::get_result_cache_stats,
+            cache::clear_result_cache,
+            clipboard::copy_as_fasta,
+            clipboard::copy_reverse_complement,
+            clipboard::paste_sequence,
+            data_dir::move_data_dir,
+            data_dir::get_data_dir_status,
+            engine::engine_request,
+            engine::engine_rpc,
+            engine::get_engine_port,
+            engine::get_engine_status,
+            engine::get_engine_metrics,
+            engine::is_engine_ready,
+            engine::restart_engine,
+            engine::stop_engine,
+            engine::start_engine,
+            engine::pause_engine,
+            engine::resume_engine,
+            engine_log::get_engine_logs,
+            file_intake::take_pending_files,
+            folder_import::import_directory,
+            folder_import::commit_import,
+            archive_import::import_archive,
+            blast::build_blast_db,
+            blast::list_blast_dbs,
+            blast::delete_blast_db,
+            blast::blast_search,
+            import::parse_genbank,
+            import::import_reference,
+            ipc::get_ipc_encodings,
+            jobs::submit_job,
+            jobs::cancel_job,
+            jobs::list_jobs,
+            jobs::set_queue_paused,
+            jobs::is_queue_paused,
+            jobs::get_job_result,
+            jobs::export_job_log,
+            jobs::get_job_history,
+            jobs::clear_job_history,
+            ncbi::fetch_accession,
+            primers::design_primers,
+            project::save_project,
+            project::open_project,
+            recent::get_recent,
+            recent::pin_recent,
+            recent::clear_recent,
+            references::list_references,
+            references::add_reference,
+            references::remove_reference,
+            references::verify_reference,
+            report::export_analysis_bundle,
+            report::export_report_html,
+            report::export_report_pdf,
+            report::export_report_xlsx,
+            report::export_table,
+            schedule::run_scheduled_now,
+            sequence::list_contigs,
+            sequence::get_sequence_region,
+            sequence::read_sequences,
+            sequence::sequence_stats,
+            sequence::find_orfs,
+            restriction::list_enzymes,
+            restriction::find_cut_sites,
+            restriction::simulate_digest,
+            session::update_session,
+            session::get_recovered_session,
+            session::restore_session,
+            session::discard_recovered_session,
+            settings::get_settings,
+            settings::set_settings,
+            support::create_support_bundle,
+            shortcuts::get_shortcut_status,
+            system::get_system_info,
+            table::get_trace_table,
+            table::get_engine_table,
+            tools::list_tools,
+            tools::install_tool,
+            tools::remove_tool,
+            trace::parse_trace,
+            trace::get_trace_envelope,
+            trace::get_trace_binary,
+            trace::get_trace_blob,
+            trace::get_trace_envelope_binary,
+            trace::export_trace_image,
+            trace::trim_trace,
+            tracy::tracy_basecall,
+            tracy::tracy_align,
+            tracy::tracy_decompose,
+            align::align_sequences,
+            align::align_batch,
+            sidecar_update::check_sidecar_updates,
+            sidecar_update::install_sidecar_updates,
+            supervisor::list_sidecars,
+            updater::check_for_updates,
+            updater::install_update,
+            updater::get_update_channel,
+            updater::set_update_channel,
+            upload::upload_file,
+            upload::cancel_upload,
+            windows::open_window,
+            windows::take_window_state,
+            windows::send_to_window,
+            windows::list_windows
+        ])
+        .build(tauri::generate_context!()); // Use .build() instead of .run() to get access to events
+
+    let app = match app {
+        Ok(app) => app,
+        Err(e) => {
+            // Setup failed before any window could report it.
+            tracing::error!("Failed to start PS Analyzer: {}", e);
+            eprintln!("Failed to start PS Analyzer: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    app.run(|app_handle, event| match event {
+        // This captures the Global Exit event
+        tauri::RunEvent::Exit => {
+            // The engine runs in its own process group / Job Object, so this
+            // also takes down any tracy processes it still has running.
+            tracing::info!("Application exiting, cleaning up processes...");
+            engine::shutdown(app_handle);
+            app_handle.state::<supervisor::ProcessSupervisor>().shutdown();
+            app_handle.state::<window_state::WindowStates>().save();
+            app_handle.state::<session::SessionManager>().end();
+            app_handle.state::<blob::BlobStore>().clear();
+            app_handle.state::<temp::TempStore>().clear();
+        }
+        // Files opened through a file association; other platforms pass them as arguments.
+        #[cfg(target_os = "macos")]
+        tauri::RunEvent::Opened { urls } => file_intake::intake_urls(app_handle, urls),
+        _ => {}
+    });
+}
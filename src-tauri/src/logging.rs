@@ -0,0 +1,72 @@
+use crate::error::AppError;
+use tauri::{App, AppHandle, Manager};
+use tauri_plugin_opener::OpenerExt;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter};
+
+/// `tracing` target used for lines coming from the bio-engine sidecar, so they
+/// can be told apart from (and filtered separately to) the app's own output.
+pub const ENGINE_TARGET: &str = "engine";
+
+/// Environment variable overriding the log filter, in `EnvFilter` syntax
+/// (e.g. `debug` or `info,engine=warn`).
+pub const LOG_FILTER_ENV: &str = "PS_ANALYZER_LOG";
+
+const DEFAULT_FILTER: &str = "info";
+const LOG_FILE_PREFIX: &str = "ps-analyzer";
+const MAX_LOG_FILES: usize = 7;
+
+/// Keeps the non-blocking file writer alive; dropping it flushes and stops it.
+pub struct LogGuard(#[allow(dead_code)] WorkerGuard);
+
+/// Installs the global subscriber: human-readable output on stdout plus a
+/// daily-rotated file in the app log directory. `PS_ANALYZER_LOG` takes
+/// precedence over the `configured` filter from the settings.
+pub fn init(app: &App, configured: Option<&str>) {
+    let filter = EnvFilter::try_from_env(LOG_FILTER_ENV)
+        .or_else(|_| EnvFilter::try_new(configured.unwrap_or(DEFAULT_FILTER)))
+        .unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+
+    let file_writer = crate::portable::app_log_dir(app.handle())
+        .map_err(|e| e.to_string())
+        .and_then(|dir| {
+            std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+            RollingFileAppender::builder()
+                .rotation(Rotation::DAILY)
+                .filename_prefix(LOG_FILE_PREFIX)
+                .filename_suffix("log")
+                .max_log_files(MAX_LOG_FILES)
+                .build(&dir)
+                .map_err(|e| e.to_string())
+        });
+
+    let registry = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_target(true));
+
+    match file_writer {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            registry
+                .with(fmt::layer().with_ansi(false).with_target(true).with_writer(writer))
+                .init();
+            app.manage(LogGuard(guard));
+        }
+        Err(e) => {
+            registry.init();
+            tracing::warn!("File logging disabled, could not open log directory: {}", e);
+        }
+    }
+}
+
+/// Opens the app log directory in the system file manager.
+pub fn open_log_dir(app_handle: &AppHandle) -> Result<(), AppError> {
+    let dir = crate::portable::app_log_dir(app_handle)?;
+    app_handle
+        .opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))
+}
@@ -0,0 +1,285 @@
+//! The native application menu:
+//! - File: Open Trace, Import Folder, Open Recent, Quit
+//! - Edit: the standard clipboard items, which macOS needs for the webview
+//! - Analysis: Run (handed to the frontend, which holds the form), Cancel
+//! - Engine: Restart, Open Logs, Create Support Bundle
+//!
+//! Menu ids are prefixed with `app_menu.` since menu event handlers, the
+//! tray's included, see the events of every menu.
+
+use crate::engine;
+use crate::file_intake::{self, SUPPORTED_EXTENSIONS};
+use crate::jobs::{self, JobQueue};
+use crate::logging;
+use crate::recent::{RecentStore, RECENT_CHANGED_EVENT};
+use crate::support;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::{AppHandle, Emitter, Listener, Manager, Wry};
+use tauri_plugin_dialog::DialogExt;
+use tracing::{error, info, warn};
+
+/// Emitted when Analysis ▸ Run is chosen; the frontend starts the analysis
+/// it has set up.
+pub const MENU_RUN_ANALYSIS_EVENT: &str = "menu-run-analysis";
+
+/// Emitted with the folder chosen through File ▸ Import Folder; the frontend
+/// plans the import with its options (see `import_directory`).
+pub const IMPORT_FOLDER_EVENT: &str = "import-folder-requested";
+
+const PREFIX: &str = "app_menu.";
+const RECENT_PREFIX: &str = "app_menu.recent.";
+const RECENT_ITEMS: u32 = 10;
+
+/// Managed state: the Open Recent submenu and the paths behind its items.
+pub struct AppMenu {
+    recent: Submenu<Wry>,
+    recent_paths: Mutex<Vec<PathBuf>>,
+}
+
+fn item(
+    app_handle: &AppHandle,
+    id: &str,
+    text: &str,
+    accelerator: Option<&str>,
+) -> tauri::Result<MenuItem<Wry>> {
+    MenuItem::with_id(
+        app_handle,
+        format!("{}{}", PREFIX, id),
+        text,
+        true,
+        accelerator,
+    )
+}
+
+/// Builds the menu and sets it for every window. Called once at startup.
+pub fn init(app_handle: &AppHandle) -> tauri::Result<()> {
+    let separator = || PredefinedMenuItem::separator(app_handle);
+    let recent = Submenu::with_id(app_handle, format!("{}recent", PREFIX), "Open Recent", true)?;
+
+    let file = Submenu::with_items(
+        app_handle,
+        "File",
+        true,
+        &[
+            &item(app_handle, "open_trace", "Open Trace…", Some("CmdOrCtrl+O"))?,
+            &item(
+                app_handle,
+                "import_folder",
+                "Import Folder…",
+                Some("CmdOrCtrl+Shift+O"),
+            )?,
+            &recent,
+            &separator()?,
+            &PredefinedMenuItem::quit(app_handle, None)?,
+        ],
+    )?;
+    let edit = Submenu::with_items(
+        app_handle,
+        "Edit",
+        true,
+        &[
+            &PredefinedMenuItem::undo(app_handle, None)?,
+            &PredefinedMenuItem::redo(app_handle, None)?,
+            &separator()?,
+            &PredefinedMenuItem::cut(app_handle, None)?,
+            &PredefinedMenuItem::copy(app_handle, None)?,
+            &PredefinedMenuItem::paste(app_handle, None)?,
+            &PredefinedMenuItem::select_all(app_handle, None)?,
+        ],
+    )?;
+    let analysis = Submenu::with_items(
+        app_handle,
+        "Analysis",
+        true,
+        &[
+            &item(
+                app_handle,
+                "run_analysis",
+                "Run Analysis",
+                Some("CmdOrCtrl+R"),
+            )?,
+            &item(
+                app_handle,
+                "cancel_analysis",
+                "Cancel Analysis",
+                Some("CmdOrCtrl+."),
+            )?,
+        ],
+    )?;
+    let engine = Submenu::with_items(
+        app_handle,
+        "Engine",
+        true,
+        &[
+            &item(app_handle, "restart_engine", "Restart Engine", None)?,
+            &item(app_handle, "open_logs", "Open Logs", None)?,
+            &item(app_handle, "support_bundle", "Create Support Bundle…", None)?,
+        ],
+    )?;
+
+    let menu = Menu::with_items(app_handle, &[&file, &edit, &analysis, &engine])?;
+    #[cfg(target_os = "macos")]
+    {
+        let name = app_handle.package_info().name.clone();
+        let app_menu = Submenu::with_items(
+            app_handle,
+            &name,
+            true,
+            &[
+                &PredefinedMenuItem::about(app_handle, None, None)?,
+                &separator()?,
+                &PredefinedMenuItem::hide(app_handle, None)?,
+                &PredefinedMenuItem::hide_others(app_handle, None)?,
+                &separator()?,
+                &PredefinedMenuItem::quit(app_handle, None)?,
+            ],
+        )?;
+        menu.prepend(&app_menu)?;
+    }
+    app_handle.set_menu(menu)?;
+
+    app_handle.manage(AppMenu {
+        recent,
+        recent_paths: Mutex::new(Vec::new()),
+    });
+    refresh_recent(app_handle);
+    let handle = app_handle.clone();
+    app_handle.listen(RECENT_CHANGED_EVENT, move |_| refresh_recent(&handle));
+    Ok(())
+}
+
+/// Rebuilds Open Recent from the recent store.
+fn refresh_recent(app_handle: &AppHandle) {
+    let Some(menu) = app_handle.try_state::<AppMenu>() else {
+        return;
+    };
+    let entries = match app_handle.state::<RecentStore>().list(Some(RECENT_ITEMS)) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("Could not list recent files for the menu: {}", e);
+            return;
+        }
+    };
+
+    let rebuilt = (|| -> tauri::Result<()> {
+        for existing in menu.recent.items()? {
+            menu.recent.remove(&existing)?;
+        }
+        for (index, entry) in entries.iter().enumerate() {
+            let id = format!("{}{}", RECENT_PREFIX, index);
+            menu.recent.append(&MenuItem::with_id(
+                app_handle,
+                id,
+                &entry.name,
+                entry.exists,
+                None::<&str>,
+            )?)?;
+        }
+        if entries.is_empty() {
+            menu.recent.append(&MenuItem::with_id(
+                app_handle,
+                "app_menu.recent_none",
+                "No Recent Files",
+                false,
+                None::<&str>,
+            )?)?;
+        }
+        Ok(())
+    })();
+    if let Err(e) = rebuilt {
+        warn!("Could not rebuild the Open Recent menu: {}", e);
+    }
+    *menu.recent_paths.lock().unwrap() = entries.into_iter().map(|entry| entry.path).collect();
+}
+
+/// Menu event handler of the app.
+pub fn on_menu_event(app_handle: &AppHandle, event: MenuEvent) {
+    let Some(id) = event.id().as_ref().strip_prefix(PREFIX) else {
+        return;
+    };
+    match id {
+        "open_trace" => {
+            let handle = app_handle.clone();
+            app_handle
+                .dialog()
+                .file()
+                .set_title("Open trace")
+                .add_filter("Sequences and traces", &SUPPORTED_EXTENSIONS)
+                .pick_files(move |paths| {
+                    let paths = paths
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|path| path.into_path().ok());
+                    file_intake::intake(&handle, paths);
+                });
+        }
+        "import_folder" => {
+            let handle = app_handle.clone();
+            app_handle
+                .dialog()
+                .file()
+                .set_title("Import folder")
+                .pick_folder(move |folder| {
+                    if let Some(folder) = folder.and_then(|folder| folder.into_path().ok()) {
+                        let _ = handle.emit(IMPORT_FOLDER_EVENT, folder);
+                    }
+                });
+        }
+        "run_analysis" => {
+            let _ = app_handle.emit(MENU_RUN_ANALYSIS_EVENT, ());
+        }
+        "cancel_analysis" => cancel_latest(app_handle),
+        "restart_engine" => {
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = engine::restart(&app_handle) {
+                    error!("Failed to restart bio-engine from the menu: {}", e);
+                }
+            });
+        }
+        "open_logs" => {
+            if let Err(e) = logging::open_log_dir(app_handle) {
+                warn!("Could not open the log folder: {}", e);
+            }
+        }
+        "support_bundle" => {
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = support::create_support_bundle(app_handle).await {
+                    error!("Could not create a support bundle: {}", e);
+                }
+            });
+        }
+        recent => {
+            let Some(index) = recent
+                .strip_prefix("recent.")
+                .and_then(|index| index.parse::<usize>().ok())
+            else {
+                return;
+            };
+            let path = app_handle
+                .try_state::<AppMenu>()
+                .and_then(|menu| menu.recent_paths.lock().unwrap().get(index).cloned());
+            if let Some(path) = path {
+                file_intake::intake(app_handle, [path]);
+            }
+        }
+    }
+}
+
+/// Cancels the most recently submitted job that has not finished.
+fn cancel_latest(app_handle: &AppHandle) {
+    let latest = jobs::list_jobs(app_handle.state::<JobQueue>())
+        .into_iter()
+        .rev()
+        .find(|job| !job.state.is_finished());
+    let Some(job) = latest else {
+        info!("Nothing to cancel");
+        return;
+    };
+    if let Err(e) = jobs::cancel_job(app_handle.clone(), job.id) {
+        warn!("Could not cancel job {}: {}", job.id, e);
+    }
+}
@@ -0,0 +1,200 @@
+//! Fetching reference sequences from NCBI by accession, so a user can type
+//! `NM_000546.6` instead of downloading the file themselves. Records come
+//! from Entrez `efetch` and are kept in the cache folder; a cached record is
+//! returned without asking NCBI again.
+//!
+//! NCBI allows three requests a second, ten with an API key (the
+//! `ncbi_api_key` setting); requests are spaced out to stay under that.
+
+use crate::error::AppError;
+use crate::settings::SettingsStore;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_http::reqwest::{self, StatusCode};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tracing::info;
+
+const EFETCH_URL: &str = "https://eutils.ncbi.nlm.nih.gov/entrez/eutils/efetch.fcgi";
+/// Identifies the app to NCBI, as their usage guidelines ask.
+const TOOL: &str = "ps-analyzer";
+const ACCESSIONS_DIR: &str = "accessions";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const INTERVAL: Duration = Duration::from_millis(340);
+const INTERVAL_WITH_KEY: Duration = Duration::from_millis(110);
+const MAX_ACCESSION_LEN: usize = 32;
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessionFormat {
+    Fasta,
+    #[default]
+    Genbank,
+}
+
+impl AccessionFormat {
+    fn rettype(self) -> &'static str {
+        match self {
+            AccessionFormat::Fasta => "fasta",
+            AccessionFormat::Genbank => "gb",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            AccessionFormat::Fasta => "fasta",
+            AccessionFormat::Genbank => "gb",
+        }
+    }
+
+    /// Whether `body` starts like a record of this format rather than an
+    /// error message.
+    fn is_record(self, body: &str) -> bool {
+        let body = body.trim_start();
+        match self {
+            AccessionFormat::Fasta => body.starts_with('>'),
+            AccessionFormat::Genbank => body.starts_with("LOCUS"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FetchedAccession {
+    pub accession: String,
+    pub format: AccessionFormat,
+    /// The record on disk, usable as a reference path.
+    pub path: PathBuf,
+    /// Taken from the cache rather than fetched.
+    pub cached: bool,
+}
+
+/// Spaces out requests to NCBI.
+pub struct NcbiClient {
+    http: reqwest::Client,
+    next_request: Mutex<Instant>,
+}
+
+impl Default for NcbiClient {
+    fn default() -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            next_request: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+impl NcbiClient {
+    /// Waits for this request's turn.
+    async fn wait_turn(&self, has_key: bool) {
+        let mut next_request = self.next_request.lock().await;
+        tokio::time::sleep_until(*next_request).await;
+        let interval = if has_key { INTERVAL_WITH_KEY } else { INTERVAL };
+        *next_request = Instant::now() + interval;
+    }
+
+    async fn efetch(
+        &self,
+        accession: &str,
+        format: AccessionFormat,
+        api_key: Option<&str>,
+    ) -> Result<String, AppError> {
+        let mut query = vec![
+            ("db", "nuccore"),
+            ("id", accession),
+            ("rettype", format.rettype()),
+            ("retmode", "text"),
+            ("tool", TOOL),
+        ];
+        if let Some(api_key) = api_key {
+            query.push(("api_key", api_key));
+        }
+        self.wait_turn(api_key.is_some()).await;
+        let response = self.http.get(EFETCH_URL).query(&query).send().await?;
+        match response.status() {
+            StatusCode::BAD_REQUEST | StatusCode::NOT_FOUND => {
+                return Err(AppError::Ncbi(format!("NCBI has no record {}", accession)))
+            }
+            StatusCode::TOO_MANY_REQUESTS => {
+                return Err(AppError::Ncbi(
+                    "NCBI is limiting requests; try again in a moment or set an API key".into(),
+                ))
+            }
+            _ => {}
+        }
+        let body = response.error_for_status()?.text().await?;
+        if !format.is_record(&body) {
+            let detail = body.lines().next().unwrap_or("an empty response").trim();
+            return Err(AppError::Ncbi(format!(
+                "no record {} was returned: {}",
+                accession, detail
+            )));
+        }
+        Ok(body)
+    }
+}
+
+/// `id` trimmed, if it looks like an accession (letters, digits, `_` and a
+/// version after `.`).
+fn accession(id: &str) -> Result<String, AppError> {
+    let id = id.trim();
+    let valid = !id.is_empty()
+        && id.len() <= MAX_ACCESSION_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+    if !valid {
+        return Err(AppError::Ncbi(format!("{:?} is not an accession", id)));
+    }
+    Ok(id.to_ascii_uppercase())
+}
+
+/// The record of accession `id` from NCBI nuccore, in `format` (GenBank
+/// unless asked otherwise), saved in the cache folder. Cached records are
+/// reused unless `refresh` is set.
+#[tauri::command]
+pub async fn fetch_accession(
+    app_handle: AppHandle,
+    id: String,
+    format: Option<AccessionFormat>,
+    refresh: Option<bool>,
+) -> Result<FetchedAccession, AppError> {
+    let accession = accession(&id)?;
+    let format = format.unwrap_or_default();
+    let dir = crate::data_dir::cache(&app_handle)?.join(ACCESSIONS_DIR);
+    let path = dir.join(format!("{}.{}", accession, format.extension()));
+    if path.is_file() && !refresh.unwrap_or(false) {
+        return Ok(FetchedAccession {
+            accession,
+            format,
+            path,
+            cached: true,
+        });
+    }
+
+    let api_key = app_handle
+        .state::<SettingsStore>()
+        .get()
+        .ncbi_api_key
+        .filter(|key| !key.trim().is_empty());
+    let record = app_handle
+        .state::<NcbiClient>()
+        .efetch(&accession, format, api_key.as_deref())
+        .await?;
+
+    std::fs::create_dir_all(&dir)?;
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, record)?;
+    std::fs::rename(&partial, &path)?;
+    info!("Fetched {} from NCBI to {:?}", accession, path);
+    Ok(FetchedAccession {
+        accession,
+        format,
+        path,
+        cached: false,
+    })
+}
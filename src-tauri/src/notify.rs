@@ -0,0 +1,85 @@
+//! OS notifications when analyses finish or fail while nobody is looking at
+//! the app, per the `notifications` setting.
+//!
+//! Desktop notifications give no click callback, but clicking one brings the
+//! app to the front; the first focus of the main window shortly after a
+//! notification is taken as that click and opens the job's result.
+
+use crate::jobs::{JobInfo, JobState};
+use crate::settings::SettingsStore;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, Window, WindowEvent};
+use tauri_plugin_notification::NotificationExt;
+use tracing::warn;
+
+/// Emitted with a job id when the user comes back through its notification;
+/// the frontend shows that job's result.
+pub const OPEN_JOB_RESULT_EVENT: &str = "open-job-result";
+
+/// A focus this long after the notification is no longer taken as a click on it.
+const CLICK_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// Managed state: the job of the last notification shown, and when.
+#[derive(Default)]
+pub struct Notifier {
+    last: Mutex<Option<(u64, Instant)>>,
+}
+
+fn app_in_focus(app_handle: &AppHandle) -> bool {
+    app_handle
+        .get_webview_window("main")
+        .is_some_and(|window| window.is_focused().unwrap_or(false))
+}
+
+/// Notifies about a job that has just finished, if the settings ask for it
+/// and the app is not in focus. Called by the job queue for every finished job.
+pub fn job_finished(app_handle: &AppHandle, info: &JobInfo) {
+    let settings = app_handle.state::<SettingsStore>().get().notifications;
+    let (title, body) = match info.state {
+        JobState::Completed if settings.completed => {
+            ("Analysis complete", format!("{} has finished", info.name))
+        }
+        JobState::Failed if settings.failed => (
+            "Analysis failed",
+            format!("{}: {}", info.name, info.error.as_deref().unwrap_or("unknown error")),
+        ),
+        _ => return,
+    };
+    if settings.only_when_unfocused && app_in_focus(app_handle) {
+        return;
+    }
+
+    let shown = app_handle
+        .notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show();
+    match shown {
+        Ok(()) => {
+            if let Some(notifier) = app_handle.try_state::<Notifier>() {
+                *notifier.last.lock().unwrap() = Some((info.id, Instant::now()));
+            }
+        }
+        Err(e) => warn!("Could not show a notification for job {}: {}", info.id, e),
+    }
+}
+
+/// Window event hook: opens the result of the last notified job when the
+/// main window is focused soon after.
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    if window.label() != "main" || !matches!(event, WindowEvent::Focused(true)) {
+        return;
+    }
+    let app_handle = window.app_handle();
+    let Some(notifier) = app_handle.try_state::<Notifier>() else {
+        return;
+    };
+    let last = notifier.last.lock().unwrap().take();
+    if let Some((id, shown_at)) = last {
+        if shown_at.elapsed() < CLICK_WINDOW {
+            let _ = app_handle.emit(OPEN_JOB_RESULT_EVENT, id);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Portable mode, for running from a USB stick on lab PCs where nothing may
+//! be installed or written to the user profile: with a `portable` file next
+//! to the executable, or when started with `--portable`, the settings, data,
+//! caches, logs and webview storage all live in `portable-data/` beside it.
+//!
+//! The folder must be writable; if it is not, the app runs as if installed
+//! and says so in the log. Updates are not installed in portable mode, since
+//! the installer would put the new version elsewhere.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::{AppHandle, Manager};
+
+const MARKER_FILE: &str = "portable";
+const FLAG: &str = "--portable";
+const DATA_DIR: &str = "portable-data";
+
+static ROOT: OnceLock<Result<Option<PathBuf>, String>> = OnceLock::new();
+
+fn detect() -> Result<Option<PathBuf>, String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let Some(exe_dir) = exe.parent() else {
+        return Ok(None);
+    };
+    let requested = exe_dir.join(MARKER_FILE).is_file() || std::env::args().any(|arg| arg == FLAG);
+    if !requested {
+        return Ok(None);
+    }
+    let root = exe_dir.join(DATA_DIR);
+    std::fs::create_dir_all(&root)
+        .and_then(|()| {
+            let probe = root.join(".write-test");
+            std::fs::write(&probe, b"ok")?;
+            std::fs::remove_file(&probe)
+        })
+        .map_err(|e| format!("portable data folder {:?} is not writable: {}", root, e))?;
+    Ok(Some(root))
+}
+
+/// The portable data folder, if running in portable mode.
+pub fn root() -> Option<&'static Path> {
+    ROOT.get_or_init(detect).as_ref().ok()?.as_deref()
+}
+
+/// Why portable mode was asked for but is not in use.
+pub fn problem() -> Option<&'static str> {
+    ROOT.get_or_init(detect).as_ref().err().map(String::as_str)
+}
+
+/// Points the webview's storage into the portable folder. Called before the
+/// app is built; only WebView2 (Windows) reads this.
+pub fn init() {
+    if let Some(root) = root() {
+        std::env::set_var("WEBVIEW2_USER_DATA_FOLDER", root.join("webview"));
+    }
+}
+
+fn dir(name: &str, platform: impl FnOnce() -> tauri::Result<PathBuf>) -> tauri::Result<PathBuf> {
+    match root() {
+        Some(root) => Ok(root.join(name)),
+        None => platform(),
+    }
+}
+
+/// The app config dir, beside the executable in portable mode.
+pub fn app_config_dir(app_handle: &AppHandle) -> tauri::Result<PathBuf> {
+    dir("config", || app_handle.path().app_config_dir())
+}
+
+/// The app data dir, beside the executable in portable mode.
+pub fn app_data_dir(app_handle: &AppHandle) -> tauri::Result<PathBuf> {
+    dir("data", || app_handle.path().app_data_dir())
+}
+
+/// The app cache dir, beside the executable in portable mode.
+pub fn app_cache_dir(app_handle: &AppHandle) -> tauri::Result<PathBuf> {
+    dir("cache", || app_handle.path().app_cache_dir())
+}
+
+/// The app log dir, beside the executable in portable mode.
+pub fn app_log_dir(app_handle: &AppHandle) -> tauri::Result<PathBuf> {
+    dir("logs", || app_handle.path().app_log_dir())
+}
@@ -0,0 +1,48 @@
+//! Keeps the machine from going to sleep while analyses are queued or running,
+//! so an overnight batch on a laptop is not suspended halfway through. The
+//! display may still turn off.
+
+use crate::settings::SettingsStore;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+/// Managed state holding the OS power assertion while there is work.
+#[derive(Default)]
+pub struct SleepGuard {
+    assertion: Mutex<Option<keepawake::KeepAwake>>,
+}
+
+/// Takes the power assertion while `busy` and the `prevent_sleep` setting
+/// allows it, and releases it otherwise. Called whenever the job queue changes.
+pub fn update(app_handle: &AppHandle, busy: bool) {
+    let Some(guard) = app_handle.try_state::<SleepGuard>() else {
+        return;
+    };
+    let wanted = busy && app_handle.state::<SettingsStore>().get().prevent_sleep;
+    let mut assertion = guard.assertion.lock().unwrap();
+    if wanted == assertion.is_some() {
+        return;
+    }
+    if !wanted {
+        // Dropping it releases the assertion.
+        *assertion = None;
+        info!("Analyses finished; the system may sleep again");
+        return;
+    }
+    let created = keepawake::Builder::default()
+        .display(false)
+        .idle(true)
+        .sleep(true)
+        .reason("Analyses are running")
+        .app_name(&app_handle.package_info().name)
+        .app_reverse_domain(&app_handle.config().identifier)
+        .create();
+    match created {
+        Ok(keep_awake) => {
+            info!("Preventing system sleep while analyses run");
+            *assertion = Some(keep_awake);
+        }
+        Err(e) => warn!("Could not prevent system sleep: {}", e),
+    }
+}
@@ -0,0 +1,37 @@
+//! Primer3's Boulder-IO format: one `TAG=value` per line, a record ended by
+//! a line holding only `=`.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+/// Builds one input record.
+#[derive(Default)]
+pub struct Record(String);
+
+impl Record {
+    pub fn tag(&mut self, tag: &str, value: impl std::fmt::Display) -> &mut Self {
+        let _ = writeln!(self.0, "{}={}", tag, value);
+        self
+    }
+
+    /// The record, terminated.
+    pub fn finish(mut self) -> String {
+        self.0.push_str("=\n");
+        self.0
+    }
+}
+
+/// The tags of the first record in `output`.
+pub fn parse(output: &str) -> HashMap<String, String> {
+    let mut tags = HashMap::new();
+    for line in output.lines() {
+        let line = line.trim_end_matches('\r');
+        if line == "=" {
+            break;
+        }
+        if let Some((tag, value)) = line.split_once('=') {
+            tags.insert(tag.to_string(), value.to_string());
+        }
+    }
+    tags
+}
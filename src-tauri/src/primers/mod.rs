@@ -0,0 +1,347 @@
+//! Primer design with Primer3, downloaded on first use. The constraints are
+//! written as a Boulder-IO record to a scratch file, `primer3_core` reads it
+//! and its output is parsed back into typed primers. Positions are 0-based
+//! on the template throughout, as in the rest of the app.
+//!
+//! The published build is 2.5 or later, which has the thermodynamic tables
+//! compiled in, so no config folder has to ship next to it.
+
+mod boulder;
+
+use crate::error::AppError;
+use crate::sequence;
+use crate::supervisor;
+use crate::temp::TempStore;
+use boulder::Record;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+const PRIMER3: &str = "primer3";
+const INPUT_FILE: &str = "input.boulder";
+/// Lines of a failed run's stderr put in the error.
+const STDERR_LINES: usize = 5;
+
+/// What Primer3 should pick.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PrimerTask {
+    /// Left and right primers that amplify the target together.
+    #[default]
+    Pcr,
+    /// Primers on both strands that read across the target, not paired.
+    Sequencing,
+}
+
+/// A stretch of the template, 0-based.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Region {
+    pub start: usize,
+    pub length: usize,
+}
+
+/// Minimum, optimum and maximum of a primer property.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Range<T> {
+    pub min: T,
+    pub opt: T,
+    pub max: T,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrimerConstraints {
+    pub task: PrimerTask,
+    /// What the primers must flank (PCR) or read across (sequencing).
+    pub target: Option<Region>,
+    /// Where primers must not be, e.g. known variants or repeats.
+    pub excluded_regions: Vec<Region>,
+    /// Where primers must be; the whole template when unset.
+    pub included_region: Option<Region>,
+    /// Product size range, in bases.
+    pub product_size: (usize, usize),
+    /// Primer length, in bases.
+    pub size: Range<usize>,
+    /// Melting temperature, in °C.
+    pub tm: Range<f64>,
+    /// GC content, in percent.
+    pub gc_min: f64,
+    pub gc_max: f64,
+    /// Number of G or C bases wanted at the 3' end.
+    pub gc_clamp: u32,
+    /// Longest run of one base allowed.
+    pub max_poly_x: u32,
+    /// Monovalent and divalent cation concentrations, in mM.
+    pub salt_monovalent: f64,
+    pub salt_divalent: f64,
+    /// dNTP concentration, in mM.
+    pub dntp: f64,
+    /// Primer concentration, in nM.
+    pub dna_concentration: f64,
+    /// How many primers or pairs to return, best first.
+    pub num_return: usize,
+}
+
+impl Default for PrimerConstraints {
+    fn default() -> Self {
+        // Primer3's own defaults, except for a wider product range.
+        Self {
+            task: PrimerTask::default(),
+            target: None,
+            excluded_regions: Vec::new(),
+            included_region: None,
+            product_size: (100, 1000),
+            size: Range {
+                min: 18,
+                opt: 20,
+                max: 27,
+            },
+            tm: Range {
+                min: 57.0,
+                opt: 60.0,
+                max: 63.0,
+            },
+            gc_min: 20.0,
+            gc_max: 80.0,
+            gc_clamp: 0,
+            max_poly_x: 5,
+            salt_monovalent: 50.0,
+            salt_divalent: 1.5,
+            dntp: 0.6,
+            dna_concentration: 50.0,
+            num_return: 5,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Primer {
+    /// 5' to 3', as ordered.
+    pub sequence: String,
+    /// Leftmost base on the template, 0-based, for either strand.
+    pub start: usize,
+    pub length: usize,
+    pub tm: f64,
+    pub gc_percent: f64,
+    /// Thermodynamic self-complementarity scores.
+    pub self_any: f64,
+    pub self_end: f64,
+    pub hairpin: f64,
+    /// ΔG of the last five 3' bases, in kcal/mol.
+    pub end_stability: f64,
+    pub penalty: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PrimerPair {
+    pub left: Primer,
+    pub right: Primer,
+    pub product_size: usize,
+    pub penalty: f64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct PrimerDesign {
+    /// Best first. Empty for sequencing primers, which are not paired.
+    pub pairs: Vec<PrimerPair>,
+    /// Unpaired primers, best first; only for sequencing primers.
+    pub left: Vec<Primer>,
+    pub right: Vec<Primer>,
+    /// Primer3's account of how many candidates each check rejected.
+    pub explain: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+fn region(region: &Region) -> String {
+    format!("{},{}", region.start, region.length)
+}
+
+fn input(name: &str, template: &str, constraints: &PrimerConstraints) -> String {
+    let mut record = Record::default();
+    record
+        .tag("SEQUENCE_ID", name)
+        .tag("SEQUENCE_TEMPLATE", template)
+        .tag("PRIMER_FIRST_BASE_INDEX", 0)
+        .tag("PRIMER_EXPLAIN_FLAG", 1)
+        .tag(
+            "PRIMER_TASK",
+            match constraints.task {
+                PrimerTask::Pcr => "generic",
+                PrimerTask::Sequencing => "pick_sequencing_primers",
+            },
+        )
+        .tag("PRIMER_PICK_LEFT_PRIMER", 1)
+        .tag("PRIMER_PICK_RIGHT_PRIMER", 1)
+        .tag("PRIMER_NUM_RETURN", constraints.num_return.max(1))
+        .tag(
+            "PRIMER_PRODUCT_SIZE_RANGE",
+            format!(
+                "{}-{}",
+                constraints.product_size.0, constraints.product_size.1
+            ),
+        )
+        .tag("PRIMER_MIN_SIZE", constraints.size.min)
+        .tag("PRIMER_OPT_SIZE", constraints.size.opt)
+        .tag("PRIMER_MAX_SIZE", constraints.size.max)
+        .tag("PRIMER_MIN_TM", constraints.tm.min)
+        .tag("PRIMER_OPT_TM", constraints.tm.opt)
+        .tag("PRIMER_MAX_TM", constraints.tm.max)
+        .tag("PRIMER_MIN_GC", constraints.gc_min)
+        .tag("PRIMER_MAX_GC", constraints.gc_max)
+        .tag("PRIMER_GC_CLAMP", constraints.gc_clamp)
+        .tag("PRIMER_MAX_POLY_X", constraints.max_poly_x)
+        .tag("PRIMER_SALT_MONOVALENT", constraints.salt_monovalent)
+        .tag("PRIMER_SALT_DIVALENT", constraints.salt_divalent)
+        .tag("PRIMER_DNTP_CONC", constraints.dntp)
+        .tag("PRIMER_DNA_CONC", constraints.dna_concentration);
+    if let Some(target) = &constraints.target {
+        record.tag("SEQUENCE_TARGET", region(target));
+    }
+    if let Some(included) = &constraints.included_region {
+        record.tag("SEQUENCE_INCLUDED_REGION", region(included));
+    }
+    if !constraints.excluded_regions.is_empty() {
+        let excluded: Vec<String> = constraints.excluded_regions.iter().map(region).collect();
+        record.tag("SEQUENCE_EXCLUDED_REGION", excluded.join(" "));
+    }
+    record.finish()
+}
+
+fn number<T: std::str::FromStr>(tags: &HashMap<String, String>, tag: &str) -> Option<T> {
+    tags.get(tag).and_then(|value| value.trim().parse().ok())
+}
+
+/// Primer `index` on `side` (`LEFT` or `RIGHT`).
+fn primer(tags: &HashMap<String, String>, side: &str, index: usize) -> Option<Primer> {
+    let prefix = format!("PRIMER_{}_{}", side, index);
+    let (position, length) = tags.get(&prefix)?.split_once(',')?;
+    let (position, length): (usize, usize) =
+        (position.trim().parse().ok()?, length.trim().parse().ok()?);
+    // Right primers are given by their 3' end, the rightmost base.
+    let start = if side == "RIGHT" {
+        (position + 1).checked_sub(length)?
+    } else {
+        position
+    };
+    let value = |name: &str| number(tags, &format!("{}_{}", prefix, name)).unwrap_or(f64::NAN);
+    Some(Primer {
+        sequence: tags.get(&format!("{}_SEQUENCE", prefix))?.clone(),
+        start,
+        length,
+        tm: value("TM"),
+        gc_percent: value("GC_PERCENT"),
+        self_any: value("SELF_ANY_TH"),
+        self_end: value("SELF_END_TH"),
+        hairpin: value("HAIRPIN_TH"),
+        end_stability: value("END_STABILITY"),
+        penalty: value("PENALTY"),
+    })
+}
+
+fn primers(tags: &HashMap<String, String>, side: &str) -> Vec<Primer> {
+    let returned = number(tags, &format!("PRIMER_{}_NUM_RETURNED", side)).unwrap_or(0);
+    (0..returned)
+        .filter_map(|index| primer(tags, side, index))
+        .collect()
+}
+
+fn design(tags: &HashMap<String, String>, task: PrimerTask) -> PrimerDesign {
+    let mut design = PrimerDesign {
+        explain: ["LEFT", "RIGHT", "PAIR"]
+            .iter()
+            .filter_map(|side| {
+                tags.get(&format!("PRIMER_{}_EXPLAIN", side))
+                    .map(|explain| format!("{}: {}", side.to_lowercase(), explain))
+            })
+            .collect(),
+        warnings: tags
+            .get("PRIMER_WARNING")
+            .map(|warnings| warnings.split(';').map(|w| w.trim().to_string()).collect())
+            .unwrap_or_default(),
+        ..PrimerDesign::default()
+    };
+    if task == PrimerTask::Sequencing {
+        design.left = primers(tags, "LEFT");
+        design.right = primers(tags, "RIGHT");
+        return design;
+    }
+    let returned = number(tags, "PRIMER_PAIR_NUM_RETURNED").unwrap_or(0);
+    design.pairs = (0..returned)
+        .filter_map(|index| {
+            Some(PrimerPair {
+                left: primer(tags, "LEFT", index)?,
+                right: primer(tags, "RIGHT", index)?,
+                product_size: number(tags, &format!("PRIMER_PAIR_{}_PRODUCT_SIZE", index))?,
+                penalty: number(tags, &format!("PRIMER_PAIR_{}_PENALTY", index))
+                    .unwrap_or(f64::NAN),
+            })
+        })
+        .collect();
+    design
+}
+
+/// Designs primers on `template` (bare bases, FASTA or GenBank text) under
+/// `constraints`. Finding no primers is not an error: the result is then
+/// empty and `explain` says which checks rejected the candidates.
+#[tauri::command]
+pub async fn design_primers(
+    app_handle: AppHandle,
+    template: String,
+    constraints: Option<PrimerConstraints>,
+) -> Result<PrimerDesign, AppError> {
+    let constraints = constraints.unwrap_or_default();
+    let template = sequence::sanitize(&template)?;
+    let bases: String = template
+        .sequence
+        .chars()
+        .filter(|&base| base != '-')
+        .collect();
+    if bases.is_empty() {
+        return Err(AppError::InvalidSequence(
+            "the template is empty".to_string(),
+        ));
+    }
+    if constraints.task == PrimerTask::Sequencing && constraints.target.is_none() {
+        return Err(AppError::Tool(
+            "sequencing primers need a target to read across".to_string(),
+        ));
+    }
+    let name = template.name.unwrap_or_else(|| "template".to_string());
+
+    let temp = app_handle.state::<TempStore>();
+    let scratch_name = format!(
+        "primer3-{}",
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos())
+    );
+    let scratch = temp.create(&app_handle, &scratch_name)?;
+    let input_path = scratch.join(INPUT_FILE);
+    let designed = async {
+        std::fs::write(&input_path, input(&name, &bases, &constraints))?;
+        let output = supervisor::run_unchecked(
+            &app_handle,
+            PRIMER3,
+            vec![input_path.to_string_lossy().into_owned()],
+        )
+        .await?;
+        let tags = boulder::parse(&String::from_utf8_lossy(&output.stdout));
+        // Problems with the input are reported in the record, not the exit code.
+        if let Some(error) = tags.get("PRIMER_ERROR") {
+            return Err(AppError::Tool(format!("Primer3: {}", error)));
+        }
+        if !output.success() {
+            return Err(AppError::Tool(format!(
+                "{} exited with {:?}: {}",
+                PRIMER3,
+                output.code,
+                output.stderr_tail(STDERR_LINES)
+            )));
+        }
+        Ok(design(&tags, constraints.task))
+    }
+    .await;
+    temp.remove(&scratch_name);
+    designed
+}
@@ -0,0 +1,76 @@
+//! Progress of long-running engine operations, reported by the engine as
+//! NDJSON on stdout and re-emitted to the frontend as typed events, so every
+//! operation gets the same progress bar.
+//!
+//! A progress line is a single JSON object:
+//! `{"event": "progress", "job_id": "…", "stage": "aligning", "percent": 42.5, "message": "…"}`
+//! where `job_id` is the engine's job id or the correlation ID the shell sent
+//! with the request, and `percent` and `message` are optional. Reports about
+//! one sample of a batch also carry its patient id as `sample`, and the
+//! `sample_completed` report its `output_paths`.
+//!
+//! Batches aligned in the shell ([`crate::align`]) report through the same
+//! event, with no `job`.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter};
+
+/// Emitted with an [`AnalysisProgress`] for every progress line of the engine.
+pub const ANALYSIS_PROGRESS_EVENT: &str = "analysis-progress";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AnalysisProgress {
+    /// Id of the operation as the engine knows it.
+    pub job_id: String,
+    /// Id of the shell job it belongs to, if it is one.
+    #[serde(default, skip_deserializing)]
+    pub job: Option<u64>,
+    /// Short machine-readable step name, e.g. `basecalling` or `aligning`.
+    pub stage: String,
+    /// 0–100; absent while the engine cannot tell.
+    #[serde(default)]
+    pub percent: Option<f64>,
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Patient id of the sample the report is about, in a batch.
+    #[serde(default)]
+    pub sample: Option<String>,
+    #[serde(default)]
+    pub output_paths: Vec<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct Line {
+    event: String,
+    #[serde(flatten)]
+    progress: AnalysisProgress,
+}
+
+/// Parses a line of engine stdout as a progress report.
+fn parse(line: &[u8]) -> Option<AnalysisProgress> {
+    if !line.trim_ascii_start().starts_with(b"{") {
+        return None;
+    }
+    let line: Line = serde_json::from_slice(line).ok()?;
+    if line.event != "progress" {
+        return None;
+    }
+    let mut progress = line.progress;
+    progress.percent = progress
+        .percent
+        .filter(|percent| percent.is_finite())
+        .map(|percent| percent.clamp(0.0, 100.0));
+    Some(progress)
+}
+
+/// Emits `line` if it is a progress report, updating the job it belongs to.
+/// Returns whether it was one, in which case it is not logged.
+pub fn dispatch(app_handle: &AppHandle, line: &[u8]) -> bool {
+    let Some(mut progress) = parse(line) else {
+        return false;
+    };
+    progress.job = crate::jobs::apply_progress(app_handle, &progress);
+    let _ = app_handle.emit(ANALYSIS_PROGRESS_EVENT, &progress);
+    true
+}
@@ -0,0 +1,316 @@
+//! `.psproj` project files: a zip holding a manifest, the analysis results the
+//! frontend hands over, and optionally copies of the traces themselves.
+//!
+//! Trace paths are stored relative to the project file, so a folder holding
+//! the project and its traces can be moved or shared as a whole. Embedded
+//! copies cover projects sent on their own.
+
+use crate::error::AppError;
+use crate::recent::{self, RecentKind};
+use crate::references::{PinnedReference, ReferenceStore};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+pub const PROJECT_EXTENSION: &str = "psproj";
+
+/// Version of the manifest written by this build. Older files are read as is;
+/// newer ones are refused rather than half understood.
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST_ENTRY: &str = "manifest.json";
+const RESULTS_ENTRY: &str = "results.json";
+/// Where embedded traces are unpacked when their originals cannot be found.
+const EXTRACT_DIR: &str = "projects";
+
+/// A project as the frontend sees it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Project {
+    pub name: String,
+    /// Absolute paths of the traces in the project.
+    pub traces: Vec<PathBuf>,
+    /// Analysis results, stored verbatim.
+    #[serde(default)]
+    pub results: Value,
+    /// Managed references the project was analysed with.
+    #[serde(default)]
+    pub references: Vec<PinnedReference>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    format_version: u32,
+    app_version: String,
+    name: String,
+    saved_at: u64,
+    traces: Vec<TraceRef>,
+    #[serde(default)]
+    references: Vec<PinnedReference>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TraceRef {
+    /// Relative to the project file with `/` separators, or absolute when the
+    /// trace is on another drive.
+    path: String,
+    /// Archive entry holding a copy of the trace, if it was embedded.
+    #[serde(default)]
+    embedded: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OpenedProject {
+    pub path: PathBuf,
+    pub project: Project,
+    /// Traces whose original was not found and that were unpacked from the project instead.
+    pub extracted: Vec<PathBuf>,
+    /// Traces found neither in place nor embedded, as stored in the manifest.
+    pub missing: Vec<String>,
+    /// Pinned references that are not in the reference store.
+    pub missing_references: Vec<PinnedReference>,
+}
+
+#[tauri::command]
+pub async fn save_project(
+    app_handle: AppHandle,
+    path: PathBuf,
+    project: Project,
+    embed_traces: Option<bool>,
+) -> Result<PathBuf, AppError> {
+    let path = path.with_extension(PROJECT_EXTENSION);
+    let app_version = app_handle.package_info().version.to_string();
+    let destination = path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        write(&destination, &project, &app_version, embed_traces.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))??;
+
+    info!("Saved project to {:?}", path);
+    recent::record(&app_handle, &path, RecentKind::Project);
+    Ok(path)
+}
+
+#[tauri::command]
+pub async fn open_project(app_handle: AppHandle, path: PathBuf) -> Result<OpenedProject, AppError> {
+    let extract_root = crate::data_dir::cache(&app_handle)?.join(EXTRACT_DIR);
+    let source = path.clone();
+    let mut opened = tauri::async_runtime::spawn_blocking(move || read(&source, &extract_root))
+        .await
+        .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))??;
+    opened.missing_references = app_handle
+        .state::<ReferenceStore>()
+        .missing(&opened.project.references);
+
+    info!(
+        "Opened project {:?} ({} traces, {} missing)",
+        path,
+        opened.project.traces.len(),
+        opened.missing.len()
+    );
+    recent::record(&app_handle, &path, RecentKind::Project);
+    Ok(opened)
+}
+
+fn write(path: &Path, project: &Project, app_version: &str, embed_traces: bool) -> Result<(), AppError> {
+    let project_dir = absolute_parent(path)?;
+
+    // Into a temporary file first so a failed save leaves the previous version intact.
+    let staging = path.with_extension(format!("{}.tmp", PROJECT_EXTENSION));
+    let mut zip = ZipWriter::new(File::create(&staging)?);
+    let options = SimpleFileOptions::default();
+
+    let mut traces = Vec::with_capacity(project.traces.len());
+    for (index, trace) in project.traces.iter().enumerate() {
+        let embedded = if embed_traces {
+            let file_name = trace
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| format!("trace-{}", index));
+            // Indexed, since traces from different folders may share a name.
+            let entry = format!("traces/{}/{}", index, file_name);
+            zip.start_file(entry.as_str(), options)?;
+            std::io::copy(&mut File::open(trace)?, &mut zip)?;
+            Some(entry)
+        } else {
+            None
+        };
+        traces.push(TraceRef {
+            path: relative_to(trace, &project_dir),
+            embedded,
+        });
+    }
+
+    let manifest = Manifest {
+        format_version: FORMAT_VERSION,
+        app_version: app_version.to_string(),
+        name: project.name.clone(),
+        saved_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default(),
+        traces,
+        references: project.references.clone(),
+    };
+    zip.start_file(MANIFEST_ENTRY, options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest).unwrap_or_default())?;
+    zip.start_file(RESULTS_ENTRY, options)?;
+    zip.write_all(&serde_json::to_vec(&project.results).unwrap_or_default())?;
+    zip.finish()?;
+
+    std::fs::rename(&staging, path)?;
+    Ok(())
+}
+
+fn read(path: &Path, extract_root: &Path) -> Result<OpenedProject, AppError> {
+    let invalid = |detail: String| AppError::InvalidProject(format!("{:?} {}", path, detail));
+
+    let mut zip = ZipArchive::new(File::open(path)?)
+        .map_err(|e| invalid(format!("is not a project file: {}", e)))?;
+    let manifest: Manifest = serde_json::from_slice(&read_entry(&mut zip, MANIFEST_ENTRY)?)
+        .map_err(|e| invalid(format!("has an unreadable manifest: {}", e)))?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(invalid(format!(
+            "was saved by PS Analyzer {} in a newer format; update to open it",
+            manifest.app_version
+        )));
+    }
+    let results = match read_entry(&mut zip, RESULTS_ENTRY) {
+        Ok(contents) => serde_json::from_slice(&contents)
+            .map_err(|e| invalid(format!("has unreadable results: {}", e)))?,
+        Err(_) => Value::Null,
+    };
+
+    let project_dir = absolute_parent(path)?;
+    let project_stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut traces = Vec::new();
+    let mut extracted = Vec::new();
+    let mut missing = Vec::new();
+    for trace in &manifest.traces {
+        let in_place = resolve(&trace.path, &project_dir);
+        if in_place.is_file() {
+            traces.push(in_place);
+            continue;
+        }
+        let Some(entry) = &trace.embedded else {
+            warn!("Trace {:?} of project {:?} not found", trace.path, path);
+            missing.push(trace.path.clone());
+            continue;
+        };
+
+        // The manifest comes with the file, which may come from anyone: only
+        // entries `save` could have written are unpacked, and only into the
+        // project's own folder.
+        let folder = extract_root.join(format!("{}-{}", project_stem, manifest.saved_at));
+        let Some(destination) = embedded_destination(&mut zip, entry, &folder) else {
+            warn!("Refusing unsafe trace entry {:?} in project {:?}", entry, path);
+            missing.push(trace.path.clone());
+            continue;
+        };
+        if !destination.is_file() {
+            let contents = read_entry(&mut zip, entry)?;
+            if let Some(parent) = destination.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&destination, contents)?;
+        }
+        traces.push(destination.clone());
+        extracted.push(destination);
+    }
+
+    Ok(OpenedProject {
+        path: path.to_path_buf(),
+        project: Project {
+            name: manifest.name,
+            traces,
+            results,
+            references: manifest.references,
+        },
+        extracted,
+        missing,
+        missing_references: Vec::new(),
+    })
+}
+
+/// Where the embedded trace `entry` is unpacked under `folder`, or `None`
+/// unless it is a `traces/<index>/<file>` entry of the archive.
+fn embedded_destination(
+    zip: &mut ZipArchive<File>,
+    entry: &str,
+    folder: &Path,
+) -> Option<PathBuf> {
+    let relative = zip.by_name(entry).ok()?.enclosed_name()?;
+    let components: Vec<&str> = relative
+        .components()
+        .map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    let ["traces", index, file_name] = components.as_slice() else {
+        return None;
+    };
+    if index.is_empty() || !index.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let destination = folder.join(index).join(file_name);
+    destination.starts_with(folder).then_some(destination)
+}
+
+fn read_entry(zip: &mut ZipArchive<File>, name: &str) -> Result<Vec<u8>, AppError> {
+    let mut entry = zip
+        .by_name(name)
+        .map_err(|e| AppError::InvalidProject(format!("missing {}: {}", name, e)))?;
+    let mut contents = Vec::new();
+    entry.read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+fn absolute_parent(path: &Path) -> Result<PathBuf, AppError> {
+    let parent = path.parent().unwrap_or(Path::new("."));
+    let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+    Ok(parent.canonicalize()?)
+}
+
+/// `path` relative to `base` with `/` separators, or `path` unchanged if the
+/// two share no root (different drives on Windows).
+fn relative_to(path: &Path, base: &Path) -> String {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let path_components: Vec<Component> = path.components().collect();
+    let base_components: Vec<Component> = base.components().collect();
+
+    let common = path_components
+        .iter()
+        .zip(&base_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let shares_root = matches!(path_components.first(), Some(first) if base_components.first() == Some(first));
+    if common == 0 || !shares_root {
+        return path.to_string_lossy().to_string();
+    }
+
+    let ups = std::iter::repeat_n("..".to_string(), base_components.len() - common);
+    let downs = path_components[common..]
+        .iter()
+        .map(|component| component.as_os_str().to_string_lossy().to_string());
+    ups.chain(downs).collect::<Vec<_>>().join("/")
+}
+
+/// Inverse of [`relative_to`].
+fn resolve(stored: &str, base: &Path) -> PathBuf {
+    let stored_path = Path::new(stored);
+    if stored_path.is_absolute() {
+        return stored_path.to_path_buf();
+    }
+    stored.split('/').fold(base.to_path_buf(), |path, part| path.join(part))
+}
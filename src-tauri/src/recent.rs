@@ -0,0 +1,216 @@
+//! Recently opened files and projects, kept in a small SQLite database in the
+//! app data dir so the start screen can list them across restarts.
+
+use crate::error::AppError;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{debug, warn};
+
+/// Emitted with no payload whenever the recent list changes.
+pub const RECENT_CHANGED_EVENT: &str = "recent-changed";
+
+const DATABASE_FILE: &str = "recent.sqlite";
+/// Unpinned entries beyond this many are dropped, oldest first.
+const MAX_UNPINNED: u32 = 50;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS recent (
+        path       TEXT PRIMARY KEY NOT NULL,
+        kind       TEXT NOT NULL,
+        name       TEXT NOT NULL,
+        size_bytes INTEGER,
+        opened_at  INTEGER NOT NULL,
+        pinned     INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX IF NOT EXISTS recent_opened_at ON recent (opened_at);
+";
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecentKind {
+    File,
+    Project,
+}
+
+impl RecentKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            RecentKind::File => "file",
+            RecentKind::Project => "project",
+        }
+    }
+
+    fn parse(kind: &str) -> Self {
+        match kind {
+            "project" => RecentKind::Project,
+            _ => RecentKind::File,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RecentEntry {
+    pub path: PathBuf,
+    pub kind: RecentKind,
+    /// File name, so the list renders without touching the file system.
+    pub name: String,
+    pub size_bytes: Option<u64>,
+    /// Seconds since the Unix epoch.
+    pub opened_at: u64,
+    pub pinned: bool,
+    /// Whether the path still exists; moved or deleted entries stay listed
+    /// until cleared.
+    pub exists: bool,
+}
+
+pub struct RecentStore {
+    connection: Mutex<Connection>,
+}
+
+impl RecentStore {
+    /// Opens (or creates) the database. If it cannot be opened the list is
+    /// kept in memory for this session rather than failing startup.
+    pub fn open(app_handle: &AppHandle) -> Self {
+        let connection = crate::portable::app_data_dir(app_handle)
+            .map_err(AppError::from)
+            .and_then(|dir| {
+                std::fs::create_dir_all(&dir)?;
+                Ok(Connection::open(dir.join(DATABASE_FILE))?)
+            })
+            .and_then(|connection| {
+                connection.execute_batch(SCHEMA)?;
+                Ok(connection)
+            })
+            .unwrap_or_else(|e| {
+                warn!("Recent files will not be saved this session: {}", e);
+                let connection = Connection::open_in_memory().expect("in-memory SQLite is available");
+                connection
+                    .execute_batch(SCHEMA)
+                    .expect("recent files schema is valid");
+                connection
+            });
+        Self {
+            connection: Mutex::new(connection),
+        }
+    }
+
+    /// Records that `path` was opened, moving it to the top of the list.
+    pub fn record(&self, path: &Path, kind: RecentKind) -> Result<(), AppError> {
+        // Canonical paths, so `./run1/A01.ab1` and the absolute form are one entry.
+        let path = path.canonicalize()?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let size_bytes = std::fs::metadata(&path).ok().map(|metadata| metadata.len() as i64);
+
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT INTO recent (path, kind, name, size_bytes, opened_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (path) DO UPDATE SET
+                 kind = excluded.kind,
+                 name = excluded.name,
+                 size_bytes = excluded.size_bytes,
+                 opened_at = excluded.opened_at",
+            params![path.to_string_lossy(), kind.as_str(), name, size_bytes, now()],
+        )?;
+        connection.execute(
+            "DELETE FROM recent WHERE pinned = 0 AND path NOT IN (
+                 SELECT path FROM recent WHERE pinned = 0 ORDER BY opened_at DESC LIMIT ?1
+             )",
+            params![MAX_UNPINNED],
+        )?;
+        debug!("Recorded recent {:?} {:?}", kind, path);
+        Ok(())
+    }
+
+    /// Pinned entries first, then most recently opened.
+    pub fn list(&self, limit: Option<u32>) -> Result<Vec<RecentEntry>, AppError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT path, kind, name, size_bytes, opened_at, pinned FROM recent
+             ORDER BY pinned DESC, opened_at DESC LIMIT ?1",
+        )?;
+        let limit = limit.map_or(-1, i64::from);
+        let entries = statement
+            .query_map(params![limit], |row| {
+                let path = PathBuf::from(row.get::<_, String>(0)?);
+                Ok(RecentEntry {
+                    exists: path.exists(),
+                    path,
+                    kind: RecentKind::parse(&row.get::<_, String>(1)?),
+                    name: row.get(2)?,
+                    size_bytes: row.get::<_, Option<i64>>(3)?.map(|size| size as u64),
+                    opened_at: row.get::<_, i64>(4)? as u64,
+                    pinned: row.get(5)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(entries)
+    }
+
+    /// Returns whether `path` was in the list.
+    pub fn pin(&self, path: &Path, pinned: bool) -> Result<bool, AppError> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let connection = self.connection.lock().unwrap();
+        let updated = connection.execute(
+            "UPDATE recent SET pinned = ?2 WHERE path = ?1",
+            params![path.to_string_lossy(), pinned],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Removes unpinned entries, or everything if `include_pinned`.
+    pub fn clear(&self, include_pinned: bool) -> Result<(), AppError> {
+        let connection = self.connection.lock().unwrap();
+        if include_pinned {
+            connection.execute("DELETE FROM recent", [])?;
+        } else {
+            connection.execute("DELETE FROM recent WHERE pinned = 0", [])?;
+        }
+        Ok(())
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+/// Records `path` and tells the frontend, logging rather than failing: a
+/// missed recent entry should never stop a file from opening.
+pub fn record(app_handle: &AppHandle, path: &Path, kind: RecentKind) {
+    match app_handle.state::<RecentStore>().record(path, kind) {
+        Ok(()) => {
+            let _ = app_handle.emit(RECENT_CHANGED_EVENT, ());
+        }
+        Err(e) => warn!("Could not record {:?} as recent: {}", path, e),
+    }
+}
+
+#[tauri::command]
+pub fn get_recent(store: tauri::State<RecentStore>, limit: Option<u32>) -> Result<Vec<RecentEntry>, AppError> {
+    store.list(limit)
+}
+
+#[tauri::command]
+pub fn pin_recent(app_handle: AppHandle, path: PathBuf, pinned: bool) -> Result<bool, AppError> {
+    let found = app_handle.state::<RecentStore>().pin(&path, pinned)?;
+    let _ = app_handle.emit(RECENT_CHANGED_EVENT, ());
+    Ok(found)
+}
+
+#[tauri::command]
+pub fn clear_recent(app_handle: AppHandle, include_pinned: Option<bool>) -> Result<(), AppError> {
+    app_handle
+        .state::<RecentStore>()
+        .clear(include_pinned.unwrap_or(false))?;
+    let _ = app_handle.emit(RECENT_CHANGED_EVENT, ());
+    Ok(())
+}
@@ -0,0 +1,380 @@
+//! Managed references: plasmid backbones, amplicon references and small
+//! genomes kept under `references/` in the data folder, so analyses do not
+//! depend on files scattered over the disk. A reference is added from a
+//! file, a URL or an NCBI accession; it is checksummed (and checked against
+//! the checksum given with a URL), stored, and indexed so it can be used at
+//! once. Its id is the SHA-256 of its contents, which projects pin to keep
+//! using exactly the reference they were analysed with.
+
+use crate::error::AppError;
+use crate::import;
+use crate::ncbi::{self, AccessionFormat};
+use crate::sequence::SequenceIndexes;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_http::reqwest;
+use tracing::{info, warn};
+
+const REFERENCES_DIR: &str = "references";
+const CATALOG_FILE: &str = "catalog.json";
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Where a reference came from.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReferenceSource {
+    File {
+        path: PathBuf,
+    },
+    Url {
+        url: String,
+        /// Expected SHA-256 in hex; the download is refused if it differs.
+        #[serde(default)]
+        sha256: Option<String>,
+    },
+    Accession {
+        id: String,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceKind {
+    /// FASTA, indexed with a `.fai` next to it.
+    Fasta,
+    /// GenBank, EMBL or SnapGene.
+    Annotated,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ManagedReference {
+    /// SHA-256 of the file, in hex.
+    pub id: String,
+    pub name: String,
+    pub kind: ReferenceKind,
+    /// The stored copy.
+    pub path: PathBuf,
+    pub size: u64,
+    pub source: ReferenceSource,
+    pub sequences: usize,
+    /// Bases over all sequences.
+    pub length: u64,
+    /// Seconds since the Unix epoch.
+    pub added_at: u64,
+}
+
+/// A managed reference as a project records it.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PinnedReference {
+    pub id: String,
+    /// For telling the user what is missing when the id is not in the store.
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ReferenceCheck {
+    pub id: String,
+    /// The stored file still has the checksum it was added with.
+    pub intact: bool,
+    pub actual: Option<String>,
+}
+
+/// The catalog of managed references, saved next to them.
+pub struct ReferenceStore {
+    dir: Option<PathBuf>,
+    catalog: Mutex<Vec<ManagedReference>>,
+}
+
+impl ReferenceStore {
+    pub fn open(app_handle: &AppHandle) -> Self {
+        let dir = crate::data_dir::data(app_handle)
+            .ok()
+            .map(|dir| dir.join(REFERENCES_DIR));
+        let catalog = dir
+            .as_ref()
+            .and_then(|dir| std::fs::read(dir.join(CATALOG_FILE)).ok())
+            .and_then(|contents| serde_json::from_slice(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            dir,
+            catalog: Mutex::new(catalog),
+        }
+    }
+
+    fn dir(&self) -> Result<&Path, AppError> {
+        self.dir.as_deref().ok_or_else(|| {
+            AppError::DataDir("there is no data folder to keep references in".into())
+        })
+    }
+
+    fn save(&self, catalog: &[ManagedReference]) -> Result<(), AppError> {
+        let dir = self.dir()?;
+        std::fs::create_dir_all(dir)?;
+        let staging = dir.join(format!("{}.tmp", CATALOG_FILE));
+        std::fs::write(
+            &staging,
+            serde_json::to_vec_pretty(catalog).unwrap_or_default(),
+        )?;
+        std::fs::rename(&staging, dir.join(CATALOG_FILE))?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<ManagedReference> {
+        self.catalog.lock().unwrap().clone()
+    }
+
+    pub fn get(&self, id: &str) -> Option<ManagedReference> {
+        self.catalog
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|reference| reference.id == id)
+            .cloned()
+    }
+
+    /// Which of `pins` are not in the store.
+    pub fn missing(&self, pins: &[PinnedReference]) -> Vec<PinnedReference> {
+        let catalog = self.catalog.lock().unwrap();
+        pins.iter()
+            .filter(|pin| !catalog.iter().any(|reference| reference.id == pin.id))
+            .cloned()
+            .collect()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn join_error(e: impl std::fmt::Display) -> AppError {
+    AppError::Io(std::io::Error::other(e.to_string()))
+}
+
+fn sha256_file(path: &Path) -> Result<String, AppError> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Downloads `url` to `target`, returning the SHA-256 of what was written.
+async fn download(url: &str, target: &Path) -> Result<String, AppError> {
+    let client = reqwest::Client::builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .build()?;
+    let mut response = client.get(url).send().await?.error_for_status()?;
+    let mut file = File::create(target)?;
+    let mut hasher = Sha256::new();
+    while let Some(chunk) = response.chunk().await? {
+        hasher.update(&chunk);
+        file.write_all(&chunk)?;
+    }
+    file.flush()?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// The kind of reference at `path` with its sequence count and length,
+/// indexing FASTA files on the way.
+fn inspect(app_handle: &AppHandle, path: &Path) -> Result<(ReferenceKind, usize, u64), AppError> {
+    let mut first = [0u8; 1];
+    let is_fasta = File::open(path)?.read(&mut first)? == 1 && first[0] == b'>';
+    if is_fasta {
+        let index = app_handle
+            .state::<SequenceIndexes>()
+            .get(app_handle, path)?;
+        let contigs = index.contigs();
+        let length = contigs.iter().map(|contig| contig.length).sum();
+        return Ok((ReferenceKind::Fasta, contigs.len(), length));
+    }
+    let reference = import::read(path)?;
+    Ok((ReferenceKind::Annotated, 1, reference.sequence.len() as u64))
+}
+
+/// File name for a reference fetched from `url`.
+fn url_file_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    path.rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or("reference.fasta")
+        .to_string()
+}
+
+/// Copies, downloads or fetches the reference into `staging`; returns its
+/// file name and checksum.
+async fn acquire(
+    app_handle: &AppHandle,
+    source: &ReferenceSource,
+    staging: &Path,
+) -> Result<(String, String), AppError> {
+    match source {
+        ReferenceSource::File { path } => {
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .ok_or_else(|| AppError::InvalidReference(format!("{:?} is not a file", path)))?;
+            std::fs::copy(path, staging.join(&file_name))?;
+            let checksum = sha256_file(&staging.join(&file_name))?;
+            Ok((file_name, checksum))
+        }
+        ReferenceSource::Url { url, sha256 } => {
+            let file_name = url_file_name(url);
+            let checksum = download(url, &staging.join(&file_name)).await?;
+            if let Some(expected) = sha256 {
+                if !expected.trim().eq_ignore_ascii_case(&checksum) {
+                    return Err(AppError::InvalidReference(format!(
+                        "{}: SHA-256 is {}, expected {}",
+                        url, checksum, expected
+                    )));
+                }
+            }
+            Ok((file_name, checksum))
+        }
+        ReferenceSource::Accession { id } => {
+            let fetched = ncbi::fetch_accession(
+                app_handle.clone(),
+                id.clone(),
+                Some(AccessionFormat::Genbank),
+                None,
+            )
+            .await?;
+            let file_name = fetched
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| format!("{}.gb", fetched.accession));
+            std::fs::copy(&fetched.path, staging.join(&file_name))?;
+            let checksum = sha256_file(&staging.join(&file_name))?;
+            Ok((file_name, checksum))
+        }
+    }
+}
+
+/// Every managed reference.
+#[tauri::command]
+pub fn list_references(store: tauri::State<ReferenceStore>) -> Vec<ManagedReference> {
+    store.list()
+}
+
+/// Adds a reference from `source` under `name` (its file name when not
+/// given). Adding the same contents again returns the existing reference.
+#[tauri::command]
+pub async fn add_reference(
+    app_handle: AppHandle,
+    source: ReferenceSource,
+    name: Option<String>,
+) -> Result<ManagedReference, AppError> {
+    let store = app_handle.state::<ReferenceStore>();
+    let root = store.dir()?.to_path_buf();
+    let staging = root.join(format!("adding-{}", now()));
+    std::fs::create_dir_all(&staging)?;
+
+    let acquired = acquire(&app_handle, &source, &staging).await;
+    let (file_name, id) = match acquired {
+        Ok(acquired) => acquired,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+    };
+    if let Some(existing) = store.get(&id) {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Ok(existing);
+    }
+
+    let dir = root.join(&id);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::rename(&staging, &dir)?;
+    let path = dir.join(&file_name);
+    let handle = app_handle.clone();
+    let indexed = path.clone();
+    let inspected = tauri::async_runtime::spawn_blocking(move || inspect(&handle, &indexed))
+        .await
+        .unwrap_or_else(|e| Err(join_error(e)));
+    let (kind, sequences, length) = match inspected {
+        Ok(inspected) => inspected,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&dir);
+            return Err(e);
+        }
+    };
+
+    let reference = ManagedReference {
+        name: name
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| file_name.clone()),
+        id,
+        kind,
+        size: std::fs::metadata(&path)?.len(),
+        path,
+        source,
+        sequences,
+        length,
+        added_at: now(),
+    };
+    let mut catalog = store.catalog.lock().unwrap();
+    // Added meanwhile by another call with the same contents.
+    if let Some(existing) = catalog.iter().find(|existing| existing.id == reference.id) {
+        return Ok(existing.clone());
+    }
+    catalog.push(reference.clone());
+    store.save(&catalog)?;
+    info!(
+        "Added reference {} ({} sequences, {} bp)",
+        reference.name, sequences, length
+    );
+    Ok(reference)
+}
+
+/// Deletes reference `id` and its files. Projects pinning it will report it
+/// missing until it is added again.
+#[tauri::command]
+pub fn remove_reference(store: tauri::State<ReferenceStore>, id: String) -> Result<(), AppError> {
+    let mut catalog = store.catalog.lock().unwrap();
+    let Some(index) = catalog.iter().position(|reference| reference.id == id) else {
+        return Err(AppError::InvalidReference(format!(
+            "no managed reference {}",
+            id
+        )));
+    };
+    let removed = catalog.remove(index);
+    store.save(&catalog)?;
+    if let Some(dir) = removed.path.parent() {
+        if let Err(e) = std::fs::remove_dir_all(dir) {
+            warn!(
+                "Could not delete the files of reference {}: {}",
+                removed.name, e
+            );
+        }
+    }
+    info!("Removed reference {}", removed.name);
+    Ok(())
+}
+
+/// Recomputes the checksum of reference `id` to catch files changed or
+/// damaged on disk.
+#[tauri::command]
+pub async fn verify_reference(
+    app_handle: AppHandle,
+    id: String,
+) -> Result<ReferenceCheck, AppError> {
+    let reference = app_handle
+        .state::<ReferenceStore>()
+        .get(&id)
+        .ok_or_else(|| AppError::InvalidReference(format!("no managed reference {}", id)))?;
+    let actual = tauri::async_runtime::spawn_blocking(move || sha256_file(&reference.path).ok())
+        .await
+        .map_err(join_error)?;
+    Ok(ReferenceCheck {
+        intact: actual.as_deref() == Some(id.as_str()),
+        id,
+        actual,
+    })
+}
@@ -0,0 +1,254 @@
+//! A zip holding the complete record of an analysis, to archive or share:
+//! the input files, the request with its parameters, the job log, every
+//! report format, and the engine's result as JSON and TSV. `manifest.json`
+//! lists each entry with its size and SHA-256, and maps the inputs back to
+//! the paths they were read from.
+
+use super::table::{self, DecimalSeparator, TableFormat, TableOptions};
+use super::{html, pdf, xlsx, Report};
+use crate::error::AppError;
+use crate::jobs::{self, JobInfo, JobQueue};
+use crate::temp::TempStore;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tracing::warn;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+/// Bumped when the layout of the bundle changes.
+const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Entry {
+    /// Path inside the zip.
+    name: String,
+    size: u64,
+    sha256: String,
+    /// Where the file was taken from, for inputs and engine outputs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct Manifest {
+    bundle_version: u32,
+    app_version: String,
+    /// Seconds since the Unix epoch.
+    created_at: u64,
+    job: JobInfo,
+    entries: Vec<Entry>,
+}
+
+struct Bundle {
+    zip: ZipWriter<File>,
+    entries: Vec<Entry>,
+    names: HashSet<String>,
+}
+
+impl Bundle {
+    /// Adds `contents` as `name`, hashing it on the way in.
+    fn add(
+        &mut self,
+        name: String,
+        mut contents: impl Read,
+        source: Option<PathBuf>,
+    ) -> Result<(), AppError> {
+        self.zip
+            .start_file(name.as_str(), SimpleFileOptions::default())?;
+        let mut hasher = Sha256::new();
+        let mut size = 0;
+        let mut buffer = vec![0; 64 * 1024];
+        loop {
+            let read = contents.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            self.zip.write_all(&buffer[..read])?;
+            size += read as u64;
+        }
+        self.names.insert(name.clone());
+        self.entries.push(Entry {
+            name,
+            size,
+            sha256: format!("{:x}", hasher.finalize()),
+            source,
+        });
+        Ok(())
+    }
+
+    fn add_file(&mut self, dir: &str, path: &Path) -> Result<(), AppError> {
+        let file_name = path.file_name().map_or_else(
+            || "file".to_string(),
+            |name| name.to_string_lossy().to_string(),
+        );
+        let name = self.unique(format!("{}/{}", dir, file_name));
+        self.add(name, File::open(path)?, Some(path.to_path_buf()))
+    }
+
+    /// `name`, or `name` with a number added if another entry has it.
+    fn unique(&self, name: String) -> String {
+        if !self.names.contains(&name) {
+            return name;
+        }
+        let (dir, file) = name.rsplit_once('/').unwrap_or(("", name.as_str()));
+        (2..)
+            .map(|n| format!("{}/{}-{}", dir, n, file))
+            .find(|candidate| !self.names.contains(candidate))
+            .expect("some number is free")
+    }
+}
+
+/// Every existing file named by an absolute path in the request.
+fn input_files(request: &Value, files: &mut Vec<PathBuf>) {
+    match request {
+        Value::String(text) => {
+            let path = Path::new(text);
+            if path.is_absolute() && path.is_file() && !files.iter().any(|known| known == path) {
+                files.push(path.to_path_buf());
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| input_files(item, files)),
+        Value::Object(fields) => fields.values().for_each(|value| input_files(value, files)),
+        _ => {}
+    }
+}
+
+/// The files in `path`, which may be a file or a directory.
+fn output_files(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_file() {
+        files.push(path.to_path_buf());
+    } else if let Ok(entries) = std::fs::read_dir(path) {
+        for entry in entries.flatten() {
+            output_files(&entry.path(), files);
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Writes the bundle of completed job `job_id` to `path`, with the reports
+/// drawn in `scratch`.
+pub fn write(
+    app_handle: &AppHandle,
+    job_id: u64,
+    path: &Path,
+    scratch: &Path,
+) -> Result<(), AppError> {
+    let queue = app_handle.state::<JobQueue>();
+    let info = queue.get(job_id).ok_or(AppError::JobNotFound(job_id))?;
+    let request = queue.request(job_id).unwrap_or(Value::Null);
+    let result = jobs::result(app_handle, job_id)?;
+    let job: Value = serde_json::from_slice(&result).map_err(|e| {
+        AppError::Report(format!(
+            "the result of job {} is not readable: {}",
+            job_id, e
+        ))
+    })?;
+    let report = Report::from_job(&job);
+
+    let mut bundle = Bundle {
+        zip: ZipWriter::new(File::create(path)?),
+        entries: Vec::new(),
+        names: HashSet::new(),
+    };
+    bundle.add(
+        "parameters.json".to_string(),
+        serde_json::to_vec_pretty(&request)
+            .unwrap_or_default()
+            .as_slice(),
+        None,
+    )?;
+    bundle.add("results/result.json".to_string(), result.as_slice(), None)?;
+
+    let variants = scratch.join("variants.tsv");
+    table::write(
+        &report,
+        &variants,
+        &TableOptions {
+            format: TableFormat::Tsv,
+            columns: Vec::new(),
+            delimiter: None,
+            decimal: DecimalSeparator::Point,
+        },
+    )?;
+    bundle.add(
+        "results/variants.tsv".to_string(),
+        File::open(&variants)?,
+        None,
+    )?;
+
+    let formats: [(&str, fn(&Report, &Path) -> Result<(), AppError>); 3] = [
+        ("pdf", pdf::write),
+        ("html", html::write),
+        ("xlsx", xlsx::write),
+    ];
+    for (extension, write) in formats {
+        let name = format!("report.{}", extension);
+        let drawn = scratch.join(&name);
+        write(&report, &drawn)?;
+        bundle.add(format!("reports/{}", name), File::open(&drawn)?, None)?;
+    }
+
+    match jobs::log_path(app_handle, &info.correlation_id) {
+        Ok(log) if log.is_file() => {
+            bundle.add("logs/job.log".to_string(), File::open(&log)?, None)?
+        }
+        Ok(_) => {}
+        Err(e) => warn!("Leaving the log of job {} out of its bundle: {}", job_id, e),
+    }
+
+    let mut inputs = Vec::new();
+    input_files(&request, &mut inputs);
+    for input in &inputs {
+        bundle.add_file("inputs", input)?;
+    }
+    let mut outputs = Vec::new();
+    for output in &info.output_paths {
+        output_files(output, &mut outputs);
+    }
+    for output in outputs.iter().filter(|output| !inputs.contains(output)) {
+        bundle.add_file("outputs", output)?;
+    }
+
+    let manifest = Manifest {
+        bundle_version: BUNDLE_VERSION,
+        app_version: app_handle.package_info().version.to_string(),
+        created_at: now(),
+        job: info,
+        entries: std::mem::take(&mut bundle.entries),
+    };
+    bundle
+        .zip
+        .start_file("manifest.json", SimpleFileOptions::default())?;
+    bundle
+        .zip
+        .write_all(&serde_json::to_vec_pretty(&manifest).unwrap_or_default())?;
+    bundle.zip.finish()?;
+    Ok(())
+}
+
+/// Writes the bundle of completed job `job_id` to `path`, working in a
+/// temporary directory of its own.
+pub fn export(app_handle: &AppHandle, job_id: u64, path: &Path) -> Result<(), AppError> {
+    let temp = app_handle.state::<TempStore>();
+    let name = format!("bundle-{}", job_id);
+    let scratch = temp.create(app_handle, &name)?;
+    let written = write(app_handle, job_id, path, &scratch);
+    temp.remove(&name);
+    if written.is_err() {
+        let _ = std::fs::remove_file(path);
+    }
+    written
+}
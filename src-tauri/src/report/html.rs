@@ -0,0 +1,301 @@
+//! The report as one self-contained HTML file: styles, script and the
+//! chromatograms (as inline SVG) are all inside it, so it opens offline in
+//! any browser and survives being emailed. Samples are switched with tabs,
+//! variant tables can be sorted and filtered, and chromatograms zoomed with
+//! the wheel and panned by dragging.
+
+use super::{Chromatogram, Report, SampleReport};
+use crate::error::AppError;
+use std::fmt::Write as _;
+use std::path::Path;
+
+const SVG_WIDTH: f32 = 960.0;
+const SVG_HEIGHT: f32 = 160.0;
+
+const STYLE: &str = r#"
+body { font-family: system-ui, sans-serif; margin: 0; color: #1d1d1f; background: #f5f5f7; }
+header { background: #fff; padding: 16px 24px; border-bottom: 1px solid #ddd; }
+header h1 { margin: 0 0 4px; font-size: 22px; }
+header p { margin: 2px 0; color: #555; font-size: 13px; }
+nav { display: flex; flex-wrap: wrap; gap: 6px; padding: 12px 24px; }
+nav button { border: 1px solid #ccc; background: #fff; border-radius: 14px; padding: 4px 12px; cursor: pointer; }
+nav button.active { background: #1d1d1f; color: #fff; border-color: #1d1d1f; }
+section { display: none; background: #fff; margin: 0 24px 24px; padding: 16px 20px; border-radius: 8px; }
+section.active { display: block; }
+h2 { margin: 0 0 8px; font-size: 17px; }
+.qc { display: flex; flex-wrap: wrap; gap: 18px; font-size: 13px; margin-bottom: 12px; }
+.qc b { display: block; font-size: 16px; }
+pre { background: #fafafa; padding: 8px; overflow-x: auto; font-size: 12px; }
+.chromatogram svg { width: 100%; height: auto; border: 1px solid #eee; cursor: grab; }
+input.filter { margin: 8px 0; padding: 4px 8px; width: 240px; }
+table { border-collapse: collapse; width: 100%; font-size: 12px; }
+th, td { border-bottom: 1px solid #eee; padding: 4px 6px; text-align: left; }
+th { cursor: pointer; user-select: none; background: #fafafa; }
+th.sorted-asc::after { content: " ▲"; } th.sorted-desc::after { content: " ▼"; }
+.error { color: #b3261e; }
+"#;
+
+const SCRIPT: &str = r#"
+document.querySelectorAll('nav button').forEach(function (button) {
+  button.addEventListener('click', function () {
+    document.querySelectorAll('nav button, section').forEach(function (el) { el.classList.remove('active'); });
+    button.classList.add('active');
+    document.getElementById(button.dataset.target).classList.add('active');
+  });
+});
+document.querySelectorAll('table.variants').forEach(function (table) {
+  var body = table.tBodies[0];
+  table.querySelectorAll('th').forEach(function (th, column) {
+    th.addEventListener('click', function () {
+      var descending = th.classList.contains('sorted-asc');
+      table.querySelectorAll('th').forEach(function (other) { other.classList.remove('sorted-asc', 'sorted-desc'); });
+      th.classList.add(descending ? 'sorted-desc' : 'sorted-asc');
+      var rows = Array.prototype.slice.call(body.rows);
+      rows.sort(function (a, b) {
+        var x = a.cells[column].textContent, y = b.cells[column].textContent;
+        var nx = parseFloat(x), ny = parseFloat(y);
+        var order = (!isNaN(nx) && !isNaN(ny)) ? nx - ny : x.localeCompare(y);
+        return descending ? -order : order;
+      });
+      rows.forEach(function (row) { body.appendChild(row); });
+    });
+  });
+  var filter = table.parentNode.querySelector('input.filter');
+  if (filter) {
+    filter.addEventListener('input', function () {
+      var needle = filter.value.toLowerCase();
+      Array.prototype.forEach.call(body.rows, function (row) {
+        row.style.display = row.textContent.toLowerCase().indexOf(needle) >= 0 ? '' : 'none';
+      });
+    });
+  }
+});
+document.querySelectorAll('.chromatogram svg').forEach(function (svg) {
+  var box = svg.viewBox.baseVal, full = box.width, drag = null;
+  function show(x, width) {
+    box.width = Math.min(full, Math.max(full / 20, width));
+    box.x = Math.min(full - box.width, Math.max(0, x));
+  }
+  svg.addEventListener('wheel', function (event) {
+    event.preventDefault();
+    var rect = svg.getBoundingClientRect();
+    var at = box.x + (event.clientX - rect.left) / rect.width * box.width;
+    var width = box.width * (event.deltaY < 0 ? 0.8 : 1.25);
+    show(at - (at - box.x) * width / box.width, width);
+  });
+  svg.addEventListener('mousedown', function (event) { drag = { x: event.clientX, from: box.x }; });
+  window.addEventListener('mouseup', function () { drag = null; });
+  window.addEventListener('mousemove', function (event) {
+    if (!drag) { return; }
+    var rect = svg.getBoundingClientRect();
+    show(drag.from - (event.clientX - drag.x) / rect.width * box.width, box.width);
+  });
+});
+"#;
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The chromatogram as an SVG element, one polyline per base.
+pub fn chromatogram_svg(chromatogram: &Chromatogram, width: f32, height: f32) -> String {
+    let samples = chromatogram.a.len().max(2);
+    let max = [
+        &chromatogram.a,
+        &chromatogram.c,
+        &chromatogram.g,
+        &chromatogram.t,
+    ]
+    .iter()
+    .flat_map(|channel| channel.iter().copied())
+    .fold(1.0f32, f32::max);
+    let x = |sample: usize| sample as f32 * width / (samples - 1) as f32;
+    let y = |value: f32| height - value.max(0.0) / max * (height - 4.0);
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="{w}" height="{h}">"#,
+        w = width,
+        h = height
+    );
+    for &peak in &chromatogram.peaks {
+        let _ = write!(
+            svg,
+            r##"<line x1="{x:.1}" y1="{h}" x2="{x:.1}" y2="{t}" stroke="#bbb" stroke-width="1"/>"##,
+            x = x(peak),
+            h = height,
+            t = height - 4.0
+        );
+    }
+    if let Some(marker) = chromatogram.marker {
+        let _ = write!(
+            svg,
+            r##"<line x1="{x:.1}" y1="0" x2="{x:.1}" y2="{h}" stroke="#f2a900" stroke-width="1.5"/>"##,
+            x = x(marker),
+            h = height
+        );
+    }
+    for (channel, color) in [
+        (&chromatogram.a, "#2ea633"),
+        (&chromatogram.c, "#2659d9"),
+        (&chromatogram.g, "#1a1a1a"),
+        (&chromatogram.t, "#d92626"),
+    ] {
+        let points: Vec<String> = channel
+            .iter()
+            .enumerate()
+            .map(|(sample, &value)| format!("{:.1},{:.1}", x(sample), y(value)))
+            .collect();
+        let _ = write!(
+            svg,
+            r#"<polyline fill="none" stroke="{}" stroke-width="1" points="{}"/>"#,
+            color,
+            points.join(" ")
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn sample_section(html: &mut String, index: usize, sample: &SampleReport, active: bool) {
+    let _ = write!(
+        html,
+        r#"<section id="sample-{}" class="{}"><h2>{} — {}</h2>"#,
+        index,
+        if active { "active" } else { "" },
+        escape(&sample.patient_name),
+        escape(&sample.read)
+    );
+    if let Some(error) = &sample.error {
+        let _ = write!(
+            html,
+            r#"<p class="error">Not analysed: {}</p></section>"#,
+            escape(error)
+        );
+        return;
+    }
+
+    let qc = &sample.qc;
+    let optional =
+        |value: Option<u64>| value.map_or_else(|| "–".to_string(), |value| value.to_string());
+    let _ = write!(
+        html,
+        r#"<div class="qc"><span><b>{}</b>bases</span><span><b>{} / {}</b>trimmed start / end</span><span><b>{}</b>variants</span><span><b>{}</b>passing</span><span><b>{}</b>mean quality</span></div>"#,
+        optional(qc.base_count),
+        optional(qc.trimmed_start),
+        optional(qc.trimmed_end),
+        qc.variant_count,
+        qc.passing_variants,
+        qc.mean_variant_quality
+            .map_or_else(|| "–".to_string(), |quality| format!("{:.1}", quality))
+    );
+
+    if let Some(snapshot) = &sample.alignment {
+        let markers: String = snapshot
+            .reference
+            .chars()
+            .zip(snapshot.consensus.chars())
+            .map(|(reference, consensus)| {
+                if reference.eq_ignore_ascii_case(&consensus) {
+                    ' '
+                } else {
+                    '*'
+                }
+            })
+            .collect();
+        let _ = write!(
+            html,
+            "<pre>Reference {:>8}  {}\nConsensus {:>8}  {}\n          {:>8}  {}</pre>",
+            snapshot.start,
+            escape(&snapshot.reference),
+            "",
+            escape(&snapshot.consensus),
+            "",
+            markers
+        );
+    }
+
+    if let Some(chromatogram) = &sample.chromatogram {
+        let _ = write!(
+            html,
+            r#"<div class="chromatogram">{}</div>"#,
+            chromatogram_svg(chromatogram, SVG_WIDTH, SVG_HEIGHT)
+        );
+    }
+
+    let table = &sample.variants;
+    if table.rows.is_empty() {
+        html.push_str("<p>No variants found.</p></section>");
+        return;
+    }
+    html.push_str(r#"<div><input class="filter" type="search" placeholder="Filter variants"><table class="variants"><thead><tr>"#);
+    for column in &table.columns {
+        let _ = write!(html, "<th>{}</th>", escape(column));
+    }
+    html.push_str("</tr></thead><tbody>");
+    for row in &table.rows {
+        html.push_str("<tr>");
+        for cell in row {
+            let _ = write!(html, "<td>{}</td>", escape(cell));
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</tbody></table></div></section>");
+}
+
+/// The report as an HTML document.
+pub fn render(report: &Report) -> String {
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        r#"<!DOCTYPE html><html lang="en"><head><meta charset="utf-8"><meta name="viewport" content="width=device-width, initial-scale=1"><title>{}</title><style>{}</style></head><body><header><h1>{}</h1>"#,
+        escape(&report.job_name),
+        STYLE,
+        escape(&report.job_name)
+    );
+    for (label, value) in [
+        ("Reference", &report.reference),
+        ("Analysed", &report.created_at),
+        ("Engine", &report.version),
+    ] {
+        if let Some(value) = value {
+            let _ = write!(html, "<p>{}: {}</p>", label, escape(value));
+        }
+    }
+    let _ = write!(
+        html,
+        "<p>Samples: {}</p></header><nav>",
+        report.samples.len()
+    );
+    for (index, sample) in report.samples.iter().enumerate() {
+        let _ = write!(
+            html,
+            r#"<button data-target="sample-{}" class="{}">{}</button>"#,
+            index,
+            if index == 0 { "active" } else { "" },
+            escape(&sample.patient_name)
+        );
+    }
+    html.push_str("</nav>");
+    for (index, sample) in report.samples.iter().enumerate() {
+        sample_section(&mut html, index, sample, index == 0);
+    }
+    let _ = write!(html, "<script>{}</script></body></html>", SCRIPT);
+    html
+}
+
+/// Writes `report` to `path`.
+pub fn write(report: &Report, path: &Path) -> Result<(), AppError> {
+    std::fs::write(path, render(report))?;
+    Ok(())
+}
@@ -0,0 +1,419 @@
+//! Analysis reports for people without the app: per sample, the sample
+//! details, a QC summary, a snapshot of the alignment around the first
+//! variant with the chromatogram beneath it, and the variant table.
+//!
+//! [`Report`] is read from a completed job's result (the engine's
+//! `GET /jobs/{id}` document, kept in the result cache); each format writes
+//! it out in its own module.
+
+mod bundle;
+mod html;
+mod pdf;
+mod table;
+mod xlsx;
+
+pub use table::{DecimalSeparator, TableFormat, TableOptions};
+
+use crate::error::AppError;
+use crate::jobs;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tracing::info;
+
+/// Alignment columns shown in the snapshot.
+const SNAPSHOT_COLUMNS: usize = 80;
+/// Trace samples either side of the snapshot's centre in the chromatogram.
+const CHROMATOGRAM_HALF_WIDTH: usize = 120;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    pub job_name: String,
+    pub created_at: Option<String>,
+    /// Version of the engine that ran the analysis.
+    pub version: Option<String>,
+    /// Reference file or accession.
+    pub reference: Option<String>,
+    pub samples: Vec<SampleReport>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SampleReport {
+    pub patient_id: String,
+    pub patient_name: String,
+    /// File name of the read.
+    pub read: String,
+    /// Why the read could not be analysed.
+    pub error: Option<String>,
+    pub qc: QcSummary,
+    pub alignment: Option<AlignmentSnapshot>,
+    pub chromatogram: Option<Chromatogram>,
+    pub variants: VariantTable,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct QcSummary {
+    pub base_count: Option<u64>,
+    pub trimmed_start: Option<u64>,
+    pub trimmed_end: Option<u64>,
+    pub variant_count: usize,
+    /// Variants whose filter is `PASS`.
+    pub passing_variants: usize,
+    pub mean_variant_quality: Option<f64>,
+}
+
+/// Reference and consensus over [`SNAPSHOT_COLUMNS`] alignment columns.
+#[derive(Clone, Debug, Serialize)]
+pub struct AlignmentSnapshot {
+    /// Reference position (1-based) of the first column.
+    pub start: u64,
+    pub reference: String,
+    pub consensus: String,
+}
+
+/// A stretch of the trace, each channel as recorded.
+#[derive(Clone, Debug, Serialize)]
+pub struct Chromatogram {
+    pub a: Vec<f32>,
+    pub c: Vec<f32>,
+    pub g: Vec<f32>,
+    pub t: Vec<f32>,
+    /// Base call positions within the stretch.
+    pub peaks: Vec<usize>,
+    /// Position of the variant the stretch is centred on, if any.
+    pub marker: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct VariantTable {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl VariantTable {
+    fn column(&self, names: &[&str]) -> Option<usize> {
+        self.columns
+            .iter()
+            .position(|column| names.iter().any(|name| column.eq_ignore_ascii_case(name)))
+    }
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// The cell as a number, unless it only looks like one (`007`, `1e5` ids).
+fn number(cell: &str) -> Option<f64> {
+    let digits = cell.trim_start_matches('-');
+    if digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.") {
+        return None;
+    }
+    if cell.contains(['e', 'E']) {
+        return None;
+    }
+    cell.parse::<f64>().ok().filter(|value| value.is_finite())
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path).file_name().map_or_else(
+        || path.to_string(),
+        |name| name.to_string_lossy().to_string(),
+    )
+}
+
+fn variants(result: &Value) -> VariantTable {
+    let columns = result["variants"]["columns"]
+        .as_array()
+        .map(|columns| columns.iter().map(text).collect())
+        .unwrap_or_default();
+    let rows = result["variants"]["rows"]
+        .as_array()
+        .map(|rows| {
+            rows.iter()
+                .filter_map(Value::as_array)
+                .map(|row| row.iter().map(text).collect())
+                .collect()
+        })
+        .unwrap_or_default();
+    VariantTable { columns, rows }
+}
+
+fn qc(result: &Value, variants: &VariantTable) -> QcSummary {
+    let filter = variants.column(&["filter"]);
+    let quality = variants.column(&["qual", "quality"]);
+    let qualities: Vec<f64> = quality
+        .map(|column| {
+            variants
+                .rows
+                .iter()
+                .filter_map(|row| row.get(column)?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    QcSummary {
+        base_count: result["baseCount"].as_u64(),
+        trimmed_start: result["alignment"]["intro_trimmed"].as_u64(),
+        trimmed_end: result["alignment"]["outro_trimmed"].as_u64(),
+        variant_count: variants.rows.len(),
+        passing_variants: filter.map_or(0, |column| {
+            variants
+                .rows
+                .iter()
+                .filter(|row| row.get(column).is_some_and(|value| value == "PASS"))
+                .count()
+        }),
+        mean_variant_quality: (!qualities.is_empty())
+            .then(|| qualities.iter().sum::<f64>() / qualities.len() as f64),
+    }
+}
+
+/// An alignment row, given as an array of bases.
+fn bases(row: &Value) -> Vec<String> {
+    row.as_array()
+        .map(|bases| bases.iter().map(text).collect())
+        .unwrap_or_default()
+}
+
+/// The alignment around the first column where consensus and reference differ.
+fn snapshot(result: &Value) -> Option<AlignmentSnapshot> {
+    let reference = bases(&result["readSeqRef"]);
+    let consensus = bases(&result["readSeqConsensus"]);
+    let len = reference.len().min(consensus.len());
+    if len == 0 {
+        return None;
+    }
+    let first_difference = (0..len).find(|&i| !reference[i].eq_ignore_ascii_case(&consensus[i]));
+    let from = first_difference
+        .map_or(0, |i| i.saturating_sub(SNAPSHOT_COLUMNS / 2))
+        .min(len.saturating_sub(SNAPSHOT_COLUMNS));
+    let to = (from + SNAPSHOT_COLUMNS).min(len);
+    let skipped_bases = reference[..from].iter().filter(|base| *base != "-").count() as u64;
+    Some(AlignmentSnapshot {
+        start: result["alignment"]["refStart"].as_u64().unwrap_or(1) + skipped_bases,
+        reference: reference[from..to].concat(),
+        consensus: consensus[from..to].concat(),
+    })
+}
+
+fn channel(trace: &Value, name: &str, from: usize, to: usize) -> Vec<f32> {
+    trace[name]
+        .as_array()
+        .map(|values| {
+            values
+                .iter()
+                .skip(from)
+                .take(to - from)
+                .map(|value| value.as_f64().unwrap_or(0.0) as f32)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The trace around the first variant, or the middle of the trace.
+fn chromatogram(result: &Value, variants: &VariantTable) -> Option<Chromatogram> {
+    let trace = &result["trace"];
+    let len = trace["traceA"].as_array()?.len();
+    if len == 0 {
+        return None;
+    }
+    let variant_position = variants
+        .column(&["pos", "position"])
+        .and_then(|column| variants.rows.first()?.get(column)?.parse::<u64>().ok());
+    let variant_sample = variant_position.and_then(|position| {
+        result["consensusAlign"]
+            .as_object()?
+            .values()
+            .find(|item| item["refPos"].as_u64() == Some(position))?["sangerPos1"]
+            .as_array()?
+            .first()?
+            .as_u64()
+    });
+    let centre = variant_sample
+        .map_or(len / 2, |sample| sample as usize)
+        .min(len - 1);
+    let from = centre.saturating_sub(CHROMATOGRAM_HALF_WIDTH);
+    let to = (centre + CHROMATOGRAM_HALF_WIDTH).min(len);
+    let peaks = trace["peakLocations"]
+        .as_array()
+        .map(|peaks| {
+            peaks
+                .iter()
+                .filter_map(Value::as_u64)
+                .map(|peak| peak as usize)
+                .filter(|peak| (from..to).contains(peak))
+                .map(|peak| peak - from)
+                .collect()
+        })
+        .unwrap_or_default();
+    Some(Chromatogram {
+        a: channel(trace, "traceA", from, to),
+        c: channel(trace, "traceC", from, to),
+        g: channel(trace, "traceG", from, to),
+        t: channel(trace, "traceT", from, to),
+        peaks,
+        marker: variant_sample
+            .map(|sample| sample as usize)
+            .filter(|sample| (from..to).contains(sample))
+            .map(|sample| sample - from),
+    })
+}
+
+impl Report {
+    /// Reads the report out of an engine job document.
+    pub fn from_job(job: &Value) -> Self {
+        let names: HashMap<String, String> = job["patients"]
+            .as_array()
+            .map(|patients| {
+                patients
+                    .iter()
+                    .map(|patient| (text(&patient["id"]), text(&patient["name"])))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let samples = job["results"]
+            .as_array()
+            .map(|results| {
+                results
+                    .iter()
+                    .map(|entry| {
+                        let patient_id = text(&entry["patientId"]);
+                        let result = &entry["alignment"];
+                        let variants = variants(result);
+                        SampleReport {
+                            patient_name: names
+                                .get(&patient_id)
+                                .cloned()
+                                .unwrap_or_else(|| patient_id.clone()),
+                            patient_id,
+                            read: file_name(&text(&entry["readPath"])),
+                            error: entry["error"].as_str().map(str::to_string),
+                            qc: qc(result, &variants),
+                            alignment: snapshot(result),
+                            chromatogram: chromatogram(result, &variants),
+                            variants,
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            job_name: text(&job["name"]),
+            created_at: job["created_at"].as_str().map(str::to_string),
+            version: job["version"].as_str().map(str::to_string),
+            reference: job["reference"]["value"].as_str().map(str::to_string),
+            samples,
+        }
+    }
+
+    /// The report of completed job `id`.
+    pub fn for_job(app_handle: &AppHandle, id: u64) -> Result<Self, AppError> {
+        let result = jobs::result(app_handle, id)?;
+        let job: Value = serde_json::from_slice(&result).map_err(|e| {
+            AppError::Report(format!("the result of job {} is not readable: {}", id, e))
+        })?;
+        Ok(Self::from_job(&job))
+    }
+}
+
+fn join_error(e: impl std::fmt::Display) -> AppError {
+    AppError::Io(std::io::Error::other(e.to_string()))
+}
+
+/// Writes the report of completed job `job_id` to `path` with `write`, off
+/// the async runtime.
+async fn export(
+    app_handle: AppHandle,
+    job_id: u64,
+    path: PathBuf,
+    write: fn(&Report, &Path) -> Result<(), AppError>,
+) -> Result<PathBuf, AppError> {
+    let report = Report::for_job(&app_handle, job_id)?;
+    let target = path.clone();
+    tauri::async_runtime::spawn_blocking(move || write(&report, &target))
+        .await
+        .map_err(join_error)??;
+    info!("Exported the report of job {} to {:?}", job_id, path);
+    Ok(path)
+}
+
+/// Writes the report of completed job `job_id` to `path` as a PDF.
+#[tauri::command]
+pub async fn export_report_pdf(
+    app_handle: AppHandle,
+    job_id: u64,
+    path: PathBuf,
+) -> Result<PathBuf, AppError> {
+    export(app_handle, job_id, path, pdf::write).await
+}
+
+/// Writes the report of completed job `job_id` to `path` as a single HTML
+/// file that opens offline, for collaborators without the app.
+#[tauri::command]
+pub async fn export_report_html(
+    app_handle: AppHandle,
+    job_id: u64,
+    path: PathBuf,
+) -> Result<PathBuf, AppError> {
+    export(app_handle, job_id, path, html::write).await
+}
+
+/// Writes the report of completed job `job_id` to `path` as an Excel
+/// workbook, one sheet per sample.
+#[tauri::command]
+pub async fn export_report_xlsx(
+    app_handle: AppHandle,
+    job_id: u64,
+    path: PathBuf,
+) -> Result<PathBuf, AppError> {
+    export(app_handle, job_id, path, xlsx::write).await
+}
+
+/// Writes the variant tables of completed job `job_id` to `path` as CSV or
+/// TSV with the given `columns` (all when omitted), `delimiter` (the
+/// format's own when omitted) and `decimal` separator.
+#[tauri::command]
+pub async fn export_table(
+    app_handle: AppHandle,
+    job_id: u64,
+    path: PathBuf,
+    format: TableFormat,
+    columns: Option<Vec<String>>,
+    delimiter: Option<char>,
+    decimal: Option<DecimalSeparator>,
+) -> Result<PathBuf, AppError> {
+    let report = Report::for_job(&app_handle, job_id)?;
+    let options = TableOptions {
+        format,
+        columns: columns.unwrap_or_default(),
+        delimiter,
+        decimal: decimal.unwrap_or_default(),
+    };
+    let target = path.clone();
+    tauri::async_runtime::spawn_blocking(move || table::write(&report, &target, &options))
+        .await
+        .map_err(join_error)??;
+    info!("Exported the variant tables of job {} to {:?}", job_id, path);
+    Ok(path)
+}
+
+/// Writes everything about completed job `job_id` to `path` as a zip: its
+/// inputs, parameters, log, reports and results, listed in a manifest.
+#[tauri::command]
+pub async fn export_analysis_bundle(
+    app_handle: AppHandle,
+    job_id: u64,
+    path: PathBuf,
+) -> Result<PathBuf, AppError> {
+    let target = path.clone();
+    tauri::async_runtime::spawn_blocking(move || bundle::export(&app_handle, job_id, &target))
+        .await
+        .map_err(join_error)??;
+    info!("Exported the analysis bundle of job {} to {:?}", job_id, path);
+    Ok(path)
+}
@@ -0,0 +1,339 @@
+//! The report as an A4 PDF. Text is set in DejaVu, embedded so the report
+//! prints the same everywhere; the chromatogram is drawn as vector paths so
+//! it stays sharp when zoomed.
+
+use super::{AlignmentSnapshot, Chromatogram, QcSummary, Report, SampleReport, VariantTable};
+use crate::error::AppError;
+use printpdf::{
+    Color, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Point,
+    Rgb,
+};
+use std::fs::File;
+use std::io::{BufWriter, Cursor};
+use std::path::Path;
+
+const SANS: &[u8] = include_bytes!("../../fonts/DejaVuSans.ttf");
+const SANS_BOLD: &[u8] = include_bytes!("../../fonts/DejaVuSans-Bold.ttf");
+const MONO: &[u8] = include_bytes!("../../fonts/DejaVuSansMono.ttf");
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 15.0;
+const CONTENT_WIDTH: f32 = PAGE_WIDTH - 2.0 * MARGIN;
+const CHROMATOGRAM_HEIGHT: f32 = 35.0;
+
+/// Millimetres per point.
+const PT: f32 = 0.3528;
+/// Columns of the variant table that fit across the page.
+const MAX_TABLE_COLUMNS: usize = 8;
+
+/// Base colours as in the app's chromatogram view.
+const A_COLOR: (f32, f32, f32) = (0.18, 0.65, 0.2);
+const C_COLOR: (f32, f32, f32) = (0.15, 0.35, 0.85);
+const G_COLOR: (f32, f32, f32) = (0.1, 0.1, 0.1);
+const T_COLOR: (f32, f32, f32) = (0.85, 0.15, 0.15);
+const GREY: (f32, f32, f32) = (0.6, 0.6, 0.6);
+
+fn pdf_error(e: printpdf::Error) -> AppError {
+    AppError::Report(e.to_string())
+}
+
+fn color((r, g, b): (f32, f32, f32)) -> Color {
+    Color::Rgb(Rgb::new(r, g, b, None))
+}
+
+/// Writes top to bottom, starting a new page when the next block does not fit.
+struct Writer {
+    doc: PdfDocumentReference,
+    layer: PdfLayerReference,
+    /// Distance of the next line from the bottom of the page.
+    y: f32,
+    sans: IndirectFontRef,
+    bold: IndirectFontRef,
+    mono: IndirectFontRef,
+}
+
+impl Writer {
+    fn new(title: &str) -> Result<Self, AppError> {
+        let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Report");
+        let sans = doc
+            .add_external_font(Cursor::new(SANS))
+            .map_err(pdf_error)?;
+        let bold = doc
+            .add_external_font(Cursor::new(SANS_BOLD))
+            .map_err(pdf_error)?;
+        let mono = doc
+            .add_external_font(Cursor::new(MONO))
+            .map_err(pdf_error)?;
+        let layer = doc.get_page(page).get_layer(layer);
+        Ok(Self {
+            doc,
+            layer,
+            y: PAGE_HEIGHT - MARGIN,
+            sans,
+            bold,
+            mono,
+        })
+    }
+
+    /// Starts a new page unless `height` millimetres still fit on this one.
+    fn reserve(&mut self, height: f32) {
+        if self.y - height >= MARGIN {
+            return;
+        }
+        let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Report");
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    fn text_at(&self, text: &str, size: f32, x: f32, font: &IndirectFontRef) {
+        self.layer.set_fill_color(color((0.0, 0.0, 0.0)));
+        self.layer.use_text(text, size, Mm(x), Mm(self.y), font);
+    }
+
+    /// One line of text in `size` points, moving down past it.
+    fn line(&mut self, text: &str, size: f32, font: &IndirectFontRef) {
+        let height = size * PT * 1.4;
+        self.reserve(height);
+        self.y -= size * PT;
+        self.text_at(text, size, MARGIN, font);
+        self.y -= size * PT * 0.4;
+    }
+
+    fn gap(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    fn stroke(&self, points: &[(f32, f32)], rgb: (f32, f32, f32), thickness: f32) {
+        if points.len() < 2 {
+            return;
+        }
+        self.layer.set_outline_color(color(rgb));
+        self.layer.set_outline_thickness(thickness);
+        self.layer.add_line(Line {
+            points: points
+                .iter()
+                .map(|&(x, y)| (Point::new(Mm(x), Mm(y)), false))
+                .collect(),
+            is_closed: false,
+        });
+    }
+
+    fn rule(&self) {
+        self.stroke(
+            &[(MARGIN, self.y), (PAGE_WIDTH - MARGIN, self.y)],
+            GREY,
+            0.5,
+        );
+    }
+}
+
+/// `text` cut to about `width` millimetres at `size` points.
+fn fit(text: &str, width: f32, size: f32) -> String {
+    let max_chars = (width / (size * PT * 0.55)).max(1.0) as usize;
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
+
+fn header(writer: &mut Writer, report: &Report) {
+    let (bold, sans) = (writer.bold.clone(), writer.sans.clone());
+    writer.line(&report.job_name, 18.0, &bold);
+    let mut details = Vec::new();
+    if let Some(reference) = &report.reference {
+        details.push(format!("Reference: {}", reference));
+    }
+    if let Some(created_at) = &report.created_at {
+        details.push(format!("Analysed: {}", created_at));
+    }
+    if let Some(version) = &report.version {
+        details.push(format!("Engine: {}", version));
+    }
+    details.push(format!("Samples: {}", report.samples.len()));
+    for detail in details {
+        writer.line(&detail, 9.0, &sans);
+    }
+    writer.gap(2.0);
+    writer.rule();
+    writer.gap(4.0);
+}
+
+fn qc_lines(qc: &QcSummary) -> Vec<String> {
+    let optional =
+        |value: Option<u64>| value.map_or_else(|| "–".to_string(), |value| value.to_string());
+    vec![
+        format!(
+            "Bases: {}    Trimmed: {} at the start, {} at the end",
+            optional(qc.base_count),
+            optional(qc.trimmed_start),
+            optional(qc.trimmed_end)
+        ),
+        format!(
+            "Variants: {} ({} passing)    Mean quality: {}",
+            qc.variant_count,
+            qc.passing_variants,
+            qc.mean_variant_quality
+                .map_or_else(|| "–".to_string(), |quality| format!("{:.1}", quality))
+        ),
+    ]
+}
+
+fn alignment(writer: &mut Writer, snapshot: &AlignmentSnapshot) {
+    let mono = writer.mono.clone();
+    let size = 7.0;
+    let markers: String = snapshot
+        .reference
+        .chars()
+        .zip(snapshot.consensus.chars())
+        .map(|(reference, consensus)| {
+            if reference.eq_ignore_ascii_case(&consensus) {
+                ' '
+            } else {
+                '*'
+            }
+        })
+        .collect();
+    writer.line(
+        &format!("Reference {:>8}  {}", snapshot.start, snapshot.reference),
+        size,
+        &mono,
+    );
+    writer.line(
+        &format!("Consensus {:>8}  {}", "", snapshot.consensus),
+        size,
+        &mono,
+    );
+    writer.line(&format!("          {:>8}  {}", "", markers), size, &mono);
+}
+
+fn chromatogram(writer: &mut Writer, chromatogram: &Chromatogram) {
+    writer.reserve(CHROMATOGRAM_HEIGHT + 2.0);
+    let bottom = writer.y - CHROMATOGRAM_HEIGHT;
+    let samples = chromatogram.a.len().max(2);
+    let max = [
+        &chromatogram.a,
+        &chromatogram.c,
+        &chromatogram.g,
+        &chromatogram.t,
+    ]
+    .iter()
+    .flat_map(|channel| channel.iter().copied())
+    .fold(1.0f32, f32::max);
+    let x = |sample: usize| MARGIN + sample as f32 * CONTENT_WIDTH / (samples - 1) as f32;
+    let y = |value: f32| bottom + value.max(0.0) / max * (CHROMATOGRAM_HEIGHT - 2.0);
+
+    writer.stroke(
+        &[(MARGIN, bottom), (PAGE_WIDTH - MARGIN, bottom)],
+        GREY,
+        0.3,
+    );
+    for &peak in &chromatogram.peaks {
+        writer.stroke(&[(x(peak), bottom), (x(peak), bottom - 1.0)], GREY, 0.3);
+    }
+    if let Some(marker) = chromatogram.marker {
+        writer.stroke(
+            &[
+                (x(marker), bottom),
+                (x(marker), bottom + CHROMATOGRAM_HEIGHT),
+            ],
+            (0.95, 0.66, 0.0),
+            0.6,
+        );
+    }
+    for (channel, rgb) in [
+        (&chromatogram.a, A_COLOR),
+        (&chromatogram.c, C_COLOR),
+        (&chromatogram.g, G_COLOR),
+        (&chromatogram.t, T_COLOR),
+    ] {
+        let points: Vec<(f32, f32)> = channel
+            .iter()
+            .enumerate()
+            .map(|(sample, &value)| (x(sample), y(value)))
+            .collect();
+        writer.stroke(&points, rgb, 0.4);
+    }
+    writer.y = bottom - 3.0;
+}
+
+fn variant_table(writer: &mut Writer, table: &VariantTable) {
+    let (sans, bold) = (writer.sans.clone(), writer.bold.clone());
+    if table.rows.is_empty() {
+        writer.line("No variants found.", 9.0, &sans);
+        return;
+    }
+    let size = 7.5;
+    let row_height = size * PT * 1.6;
+    let columns = table.columns.len().min(MAX_TABLE_COLUMNS);
+    let width = CONTENT_WIDTH / columns.max(1) as f32;
+    let draw_row = |writer: &mut Writer, cells: &[String], font: &IndirectFontRef| {
+        writer.reserve(row_height);
+        writer.y -= size * PT;
+        for (index, cell) in cells.iter().take(columns).enumerate() {
+            let x = MARGIN + index as f32 * width;
+            writer.text_at(&fit(cell, width - 1.0, size), size, x, font);
+        }
+        writer.y -= size * PT * 0.6;
+    };
+
+    draw_row(writer, &table.columns, &bold);
+    writer.rule();
+    for row in &table.rows {
+        draw_row(writer, row, &sans);
+    }
+    if table.columns.len() > columns {
+        writer.line(
+            &format!(
+                "{} more columns are in the app's export.",
+                table.columns.len() - columns
+            ),
+            7.0,
+            &sans,
+        );
+    }
+}
+
+fn sample(writer: &mut Writer, sample: &SampleReport) {
+    let (bold, sans) = (writer.bold.clone(), writer.sans.clone());
+    // Keep the heading with at least the QC summary.
+    writer.reserve(25.0);
+    writer.line(
+        &format!("{} — {}", sample.patient_name, sample.read),
+        12.0,
+        &bold,
+    );
+    if let Some(error) = &sample.error {
+        writer.line(&format!("Not analysed: {}", error), 9.0, &sans);
+        writer.gap(4.0);
+        return;
+    }
+    for line in qc_lines(&sample.qc) {
+        writer.line(&line, 9.0, &sans);
+    }
+    writer.gap(2.0);
+    if let Some(snapshot) = &sample.alignment {
+        alignment(writer, snapshot);
+    }
+    if let Some(trace) = &sample.chromatogram {
+        writer.gap(1.0);
+        chromatogram(writer, trace);
+    }
+    variant_table(writer, &sample.variants);
+    writer.gap(4.0);
+    writer.rule();
+    writer.gap(4.0);
+}
+
+/// Writes `report` to `path`.
+pub fn write(report: &Report, path: &Path) -> Result<(), AppError> {
+    let mut writer = Writer::new(&report.job_name)?;
+    header(&mut writer, report);
+    for entry in &report.samples {
+        sample(&mut writer, entry);
+    }
+    let mut file = BufWriter::new(File::create(path)?);
+    writer.doc.save(&mut file).map_err(pdf_error)
+}
@@ -0,0 +1,154 @@
+//! Variant tables of all samples as one delimited text file, for pipelines
+//! downstream of the app. Columns are picked and ordered by the caller, and
+//! decimals can be written with a comma for spreadsheets set to such locales.
+
+use super::{number, Report};
+use crate::error::AppError;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Columns added in front of each sample's variant columns.
+const PATIENT_COLUMN: &str = "patient";
+const READ_COLUMN: &str = "read";
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TableFormat {
+    Csv,
+    Tsv,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DecimalSeparator {
+    #[default]
+    Point,
+    Comma,
+}
+
+#[derive(Clone, Debug)]
+pub struct TableOptions {
+    pub format: TableFormat,
+    /// Column names in output order, matched case-insensitively; all
+    /// columns when empty.
+    pub columns: Vec<String>,
+    pub delimiter: Option<char>,
+    pub decimal: DecimalSeparator,
+}
+
+impl TableOptions {
+    /// The delimiter asked for, or the format's: a tab for TSV, a comma for
+    /// CSV unless decimals take it, then a semicolon as Excel does.
+    fn delimiter(&self) -> Result<char, AppError> {
+        let delimiter = self.delimiter.unwrap_or(match (self.format, self.decimal) {
+            (TableFormat::Tsv, _) => '\t',
+            (TableFormat::Csv, DecimalSeparator::Point) => ',',
+            (TableFormat::Csv, DecimalSeparator::Comma) => ';',
+        });
+        if matches!(delimiter, '"' | '\r' | '\n') {
+            return Err(AppError::Report(format!(
+                "{:?} cannot be used as a delimiter",
+                delimiter
+            )));
+        }
+        if delimiter == ',' && matches!(self.decimal, DecimalSeparator::Comma) {
+            return Err(AppError::Report(
+                "a comma cannot be both the delimiter and the decimal separator".to_string(),
+            ));
+        }
+        Ok(delimiter)
+    }
+}
+
+/// Every column of the report: patient and read, then each variant column
+/// in the order it first appears.
+fn all_columns(report: &Report) -> Vec<String> {
+    let mut columns = vec![PATIENT_COLUMN.to_string(), READ_COLUMN.to_string()];
+    for sample in &report.samples {
+        for column in &sample.variants.columns {
+            if !columns
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(column))
+            {
+                columns.push(column.clone());
+            }
+        }
+    }
+    columns
+}
+
+fn field(cell: &str, delimiter: char, decimal: DecimalSeparator) -> String {
+    let cell = match decimal {
+        DecimalSeparator::Comma if number(cell).is_some() => cell.replace('.', ","),
+        _ => cell.to_string(),
+    };
+    if cell.contains([delimiter, '"', '\r', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell
+    }
+}
+
+/// Writes the variant rows of `report` to `path`, one line per variant.
+pub fn write(report: &Report, path: &Path, options: &TableOptions) -> Result<(), AppError> {
+    let delimiter = options.delimiter()?;
+    let available = all_columns(report);
+    let columns = if options.columns.is_empty() {
+        available
+    } else {
+        for column in &options.columns {
+            if !available
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(column))
+            {
+                return Err(AppError::Report(format!(
+                    "the results have no column {:?}",
+                    column
+                )));
+            }
+        }
+        options.columns.clone()
+    };
+
+    let mut out = BufWriter::new(File::create(path)?);
+    let line = |out: &mut BufWriter<File>, cells: Vec<String>| -> std::io::Result<()> {
+        writeln!(out, "{}", cells.join(&delimiter.to_string()))
+    };
+    line(
+        &mut out,
+        columns
+            .iter()
+            .map(|column| field(column, delimiter, DecimalSeparator::Point))
+            .collect(),
+    )?;
+    for sample in report
+        .samples
+        .iter()
+        .filter(|sample| sample.error.is_none())
+    {
+        let table = &sample.variants;
+        for row in &table.rows {
+            let cells = columns
+                .iter()
+                .map(|column| {
+                    let cell = if column.eq_ignore_ascii_case(PATIENT_COLUMN) {
+                        sample.patient_name.as_str()
+                    } else if column.eq_ignore_ascii_case(READ_COLUMN) {
+                        sample.read.as_str()
+                    } else {
+                        table
+                            .column(&[column.as_str()])
+                            .and_then(|index| row.get(index))
+                            .map_or("", String::as_str)
+                    };
+                    field(cell, delimiter, options.decimal)
+                })
+                .collect();
+            line(&mut out, cells)?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
@@ -0,0 +1,135 @@
+//! The report as an Excel workbook: a summary sheet with a row per sample,
+//! then a sheet per sample with its variant table. Numeric cells are
+//! written as numbers so they sort and sum in Excel, and header rows stay
+//! frozen while scrolling.
+
+use super::{number, Report, SampleReport};
+use crate::error::AppError;
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Excel's limit on sheet name length.
+const MAX_SHEET_NAME: usize = 31;
+
+const SUMMARY_COLUMNS: &[&str] = &[
+    "Patient",
+    "Read",
+    "Bases",
+    "Trimmed start",
+    "Trimmed end",
+    "Variants",
+    "Passing",
+    "Mean quality",
+    "Error",
+];
+
+fn xlsx_error(e: XlsxError) -> AppError {
+    AppError::Report(e.to_string())
+}
+
+/// `name` made into a sheet name Excel accepts and no other sheet has.
+fn sheet_name(name: &str, taken: &mut HashSet<String>) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '[' | ']' | ':' | '*' | '?' | '/' | '\\' => '_',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim_matches('\'').trim();
+    let base: String = if cleaned.is_empty() {
+        "Sample".to_string()
+    } else {
+        cleaned.chars().take(MAX_SHEET_NAME).collect()
+    };
+    let mut candidate = base.clone();
+    let mut n = 2;
+    while !taken.insert(candidate.to_lowercase()) {
+        let suffix = format!(" ({})", n);
+        let kept: String = base.chars().take(MAX_SHEET_NAME - suffix.len()).collect();
+        candidate = format!("{}{}", kept, suffix);
+        n += 1;
+    }
+    candidate
+}
+
+fn write_cell(sheet: &mut Worksheet, row: u32, column: u16, cell: &str) -> Result<(), AppError> {
+    match number(cell) {
+        Some(value) => sheet.write_number(row, column, value),
+        None => sheet.write_string(row, column, cell),
+    }
+    .map_err(xlsx_error)?;
+    Ok(())
+}
+
+fn write_header(sheet: &mut Worksheet, columns: &[impl AsRef<str>]) -> Result<(), AppError> {
+    let bold = Format::new().set_bold();
+    for (column, name) in columns.iter().enumerate() {
+        sheet
+            .write_string_with_format(0, column as u16, name.as_ref(), &bold)
+            .map_err(xlsx_error)?;
+    }
+    sheet.set_freeze_panes(1, 0).map_err(xlsx_error)?;
+    Ok(())
+}
+
+fn summary(sheet: &mut Worksheet, report: &Report) -> Result<(), AppError> {
+    sheet.set_name("Summary").map_err(xlsx_error)?;
+    write_header(sheet, SUMMARY_COLUMNS)?;
+    for (index, sample) in report.samples.iter().enumerate() {
+        let row = index as u32 + 1;
+        let qc = &sample.qc;
+        let numbers = [
+            qc.base_count.map(|value| value as f64),
+            qc.trimmed_start.map(|value| value as f64),
+            qc.trimmed_end.map(|value| value as f64),
+            Some(qc.variant_count as f64),
+            Some(qc.passing_variants as f64),
+            qc.mean_variant_quality,
+        ];
+        sheet
+            .write_string(row, 0, &sample.patient_name)
+            .map_err(xlsx_error)?;
+        sheet
+            .write_string(row, 1, &sample.read)
+            .map_err(xlsx_error)?;
+        for (offset, value) in numbers.into_iter().enumerate() {
+            if let Some(value) = value {
+                sheet
+                    .write_number(row, 2 + offset as u16, value)
+                    .map_err(xlsx_error)?;
+            }
+        }
+        if let Some(error) = &sample.error {
+            sheet.write_string(row, 8, error).map_err(xlsx_error)?;
+        }
+    }
+    sheet.autofit();
+    Ok(())
+}
+
+fn sample_sheet(sheet: &mut Worksheet, name: &str, sample: &SampleReport) -> Result<(), AppError> {
+    sheet.set_name(name).map_err(xlsx_error)?;
+    let table = &sample.variants;
+    write_header(sheet, &table.columns)?;
+    for (index, row) in table.rows.iter().enumerate() {
+        for (column, cell) in row.iter().enumerate() {
+            write_cell(sheet, index as u32 + 1, column as u16, cell)?;
+        }
+    }
+    sheet.autofit();
+    Ok(())
+}
+
+/// Writes `report` to `path`.
+pub fn write(report: &Report, path: &Path) -> Result<(), AppError> {
+    let mut workbook = Workbook::new();
+    summary(workbook.add_worksheet(), report)?;
+    let mut taken = HashSet::from(["summary".to_string()]);
+    for sample in &report.samples {
+        let name = sheet_name(&sample.patient_name, &mut taken);
+        sample_sheet(workbook.add_worksheet(), &name, sample)?;
+    }
+    workbook.save(path).map_err(xlsx_error)
+}
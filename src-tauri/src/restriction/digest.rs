@@ -0,0 +1,197 @@
+//! Finding where enzymes cut a sequence, and the fragments a digest leaves.
+//! Coordinates are on the top strand, 0-based; a cut at `n` falls between
+//! bases `n - 1` and `n`.
+
+use super::enzymes::Enzyme;
+use serde::Serialize;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CutSite {
+    pub enzyme: String,
+    /// First base of the recognition site; the site may run past the end of
+    /// a circular sequence.
+    pub site_start: usize,
+    /// Whether the site reads 5'->3' on the top strand.
+    pub forward: bool,
+    /// Cuts on the top and bottom strands.
+    pub position: usize,
+    pub complement_position: usize,
+    /// As [`Enzyme::overhang`].
+    pub overhang: isize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Fragment {
+    /// From one cut to the next; `end` is below `start` for the fragment
+    /// running through the origin of a circular sequence.
+    pub start: usize,
+    pub end: usize,
+    pub length: usize,
+    /// Enzymes whose cuts bound the fragment; `None` at the ends of a linear
+    /// sequence.
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Digest {
+    pub cuts: Vec<CutSite>,
+    /// In order along the sequence.
+    pub fragments: Vec<Fragment>,
+    /// Enzymes of the digest that do not cut.
+    pub uncut: Vec<String>,
+}
+
+/// Bases a nucleotide code stands for, one bit each for A, C, G and T.
+fn bases(code: u8) -> u8 {
+    match code.to_ascii_uppercase() {
+        b'A' => 0b0001,
+        b'C' => 0b0010,
+        b'G' => 0b0100,
+        b'T' | b'U' => 0b1000,
+        b'R' => 0b0101,
+        b'Y' => 0b1010,
+        b'S' => 0b0110,
+        b'W' => 0b1001,
+        b'K' => 0b1100,
+        b'M' => 0b0011,
+        b'B' => 0b1110,
+        b'D' => 0b1101,
+        b'H' => 0b1011,
+        b'V' => 0b0111,
+        b'N' => 0b1111,
+        _ => 0,
+    }
+}
+
+/// Whether `site` matches `sequence` from `start`, wrapping past its end.
+/// Ambiguous bases in the sequence never match.
+fn matches(sequence: &[u8], site: &[u8], start: usize) -> bool {
+    site.iter().enumerate().all(|(offset, &code)| {
+        let base = bases(sequence[(start + offset) % sequence.len()]);
+        base.count_ones() == 1 && base & bases(code) != 0
+    })
+}
+
+/// Where `enzyme` cuts `sequence` (bare bases), by position.
+pub fn cut_sites(sequence: &[u8], enzyme: &Enzyme, circular: bool) -> Vec<CutSite> {
+    let n = sequence.len();
+    let site = enzyme.site.as_bytes();
+    let reverse_site = crate::sequence::reverse_complement(&enzyme.site);
+    let length = site.len();
+    if n < length || length == 0 {
+        return Vec::new();
+    }
+    let starts = if circular { n } else { n - length + 1 };
+
+    let mut cuts = Vec::new();
+    for start in 0..starts {
+        let mut strands = Vec::with_capacity(2);
+        if matches(sequence, site, start) {
+            strands.push((true, enzyme.cut as isize, enzyme.complement_cut as isize));
+        }
+        if !enzyme.palindromic && matches(sequence, reverse_site.as_bytes(), start) {
+            strands.push((
+                false,
+                length as isize - enzyme.complement_cut as isize,
+                length as isize - enzyme.cut as isize,
+            ));
+        }
+        for (forward, cut, complement_cut) in strands {
+            let (position, complement_position) =
+                (start as isize + cut, start as isize + complement_cut);
+            let (position, complement_position) = if circular {
+                (
+                    position.rem_euclid(n as isize) as usize,
+                    complement_position.rem_euclid(n as isize) as usize,
+                )
+            } else if (1..n as isize).contains(&position)
+                && (1..n as isize).contains(&complement_position)
+            {
+                (position as usize, complement_position as usize)
+            } else {
+                // The enzyme would cut past the end of the sequence.
+                continue;
+            };
+            cuts.push(CutSite {
+                enzyme: enzyme.name.clone(),
+                site_start: start,
+                forward,
+                position,
+                complement_position,
+                overhang: enzyme.overhang(),
+            });
+        }
+    }
+    cuts.sort_by_key(|cut| cut.position);
+    cuts
+}
+
+/// Digests `sequence` with all of `enzymes` at once.
+pub fn digest(sequence: &[u8], enzymes: &[&Enzyme], circular: bool) -> Digest {
+    let n = sequence.len();
+    let mut cuts = Vec::new();
+    let mut uncut = Vec::new();
+    for enzyme in enzymes {
+        let sites = cut_sites(sequence, enzyme, circular);
+        if sites.is_empty() {
+            uncut.push(enzyme.name.clone());
+        }
+        cuts.extend(sites);
+    }
+    cuts.sort_by_key(|cut| cut.position);
+
+    // Two enzymes cutting at the same place make one cut.
+    let mut boundaries: Vec<(usize, &str)> = Vec::new();
+    for cut in &cuts {
+        if !boundaries
+            .last()
+            .is_some_and(|&(position, _)| position == cut.position)
+        {
+            boundaries.push((cut.position, cut.enzyme.as_str()));
+        }
+    }
+
+    let fragment = |start: usize, end: usize, left: Option<&str>, right: Option<&str>| Fragment {
+        start,
+        end,
+        length: if end > start {
+            end - start
+        } else {
+            n - start + end
+        },
+        left: left.map(str::to_string),
+        right: right.map(str::to_string),
+    };
+    let mut fragments = Vec::new();
+    if circular {
+        match boundaries.as_slice() {
+            [] => fragments.push(fragment(0, n, None, None)),
+            [(position, enzyme)] => {
+                fragments.push(fragment(*position, *position, Some(*enzyme), Some(*enzyme)))
+            }
+            _ => {
+                for pair in boundaries.windows(2) {
+                    let ((start, left), (end, right)) = (pair[0], pair[1]);
+                    fragments.push(fragment(start, end, Some(left), Some(right)));
+                }
+                let ((start, left), (end, right)) =
+                    (boundaries[boundaries.len() - 1], boundaries[0]);
+                fragments.push(fragment(start, end, Some(left), Some(right)));
+            }
+        }
+    } else {
+        let mut start = (0, None);
+        for &(position, enzyme) in &boundaries {
+            fragments.push(fragment(start.0, position, start.1, Some(enzyme)));
+            start = (position, Some(enzyme));
+        }
+        fragments.push(fragment(start.0, n, start.1, None));
+    }
+
+    Digest {
+        cuts,
+        fragments,
+        uncut,
+    }
+}
@@ -0,0 +1,66 @@
+//! The bundled enzyme table, `data/enzymes.tsv`, parsed on first use.
+
+use crate::error::AppError;
+use serde::Serialize;
+use std::sync::OnceLock;
+
+const TABLE: &str = include_str!("../../data/enzymes.tsv");
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Enzyme {
+    pub name: String,
+    /// Recognition site, IUPAC, 5'->3'.
+    pub site: String,
+    /// Bases of the top strand from the start of the site to the cut on the
+    /// top and on the bottom strand.
+    pub cut: usize,
+    pub complement_cut: usize,
+    /// Whether the site reads the same on both strands.
+    pub palindromic: bool,
+}
+
+impl Enzyme {
+    /// Single-stranded overhang the cut leaves: positive for a 5' overhang,
+    /// negative for a 3' one, 0 for blunt ends.
+    pub fn overhang(&self) -> isize {
+        self.complement_cut as isize - self.cut as isize
+    }
+}
+
+fn parse(line: &str) -> Option<Enzyme> {
+    let mut fields = line.split('\t');
+    let name = fields.next()?.to_string();
+    let site = fields.next()?.to_ascii_uppercase();
+    let cut = fields.next()?.parse().ok()?;
+    let complement_cut = fields.next()?.parse().ok()?;
+    let palindromic = crate::sequence::reverse_complement(&site) == site;
+    Some(Enzyme {
+        name,
+        site,
+        cut,
+        complement_cut,
+        palindromic,
+    })
+}
+
+/// Every enzyme, by name.
+pub fn all() -> &'static [Enzyme] {
+    static ENZYMES: OnceLock<Vec<Enzyme>> = OnceLock::new();
+    ENZYMES.get_or_init(|| {
+        let mut enzymes: Vec<Enzyme> = TABLE
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(|line| parse(line).expect("the bundled enzyme table is well formed"))
+            .collect();
+        enzymes.sort_by_key(|enzyme| enzyme.name.to_ascii_lowercase());
+        enzymes
+    })
+}
+
+/// The enzyme called `name`, in any case.
+pub fn get(name: &str) -> Result<&'static Enzyme, AppError> {
+    all()
+        .iter()
+        .find(|enzyme| enzyme.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| AppError::UnknownEnzyme(name.to_string()))
+}
@@ -0,0 +1,91 @@
+//! Restriction analysis of references and consensus sequences: where the
+//! enzymes of a bundled, REBASE-derived table cut, and the fragments a
+//! single or multiple digest gives, to check a clone against its gel.
+
+mod digest;
+mod enzymes;
+
+pub use digest::{CutSite, Digest};
+pub use enzymes::Enzyme;
+
+use crate::error::AppError;
+use crate::sequence;
+
+fn join_error(e: impl std::fmt::Display) -> AppError {
+    AppError::Io(std::io::Error::other(e.to_string()))
+}
+
+/// Bases of `text` (plain, FASTA or GenBank) without gaps.
+fn bases(text: &str) -> Result<String, AppError> {
+    let bases: String = sequence::sanitize(text)?
+        .sequence
+        .chars()
+        .filter(|&base| base != '-')
+        .collect();
+    if bases.is_empty() {
+        return Err(AppError::InvalidSequence(
+            "the sequence is empty".to_string(),
+        ));
+    }
+    Ok(bases)
+}
+
+/// Every enzyme of the bundled table.
+#[tauri::command]
+pub fn list_enzymes() -> Vec<Enzyme> {
+    enzymes::all().to_vec()
+}
+
+/// Where `enzymes` (all of the table if unset) cut `sequence`, by position.
+/// Gaps are removed first, so positions are in ungapped bases.
+#[tauri::command]
+pub async fn find_cut_sites(
+    sequence: String,
+    enzymes: Option<Vec<String>>,
+    circular: Option<bool>,
+) -> Result<Vec<CutSite>, AppError> {
+    let enzymes = match enzymes {
+        Some(names) => names
+            .iter()
+            .map(|name| enzymes::get(name))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => enzymes::all().iter().collect(),
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let sequence = bases(&sequence)?;
+        let mut sites: Vec<CutSite> = enzymes
+            .iter()
+            .flat_map(|enzyme| {
+                digest::cut_sites(sequence.as_bytes(), enzyme, circular.unwrap_or(false))
+            })
+            .collect();
+        sites.sort_by_key(|site| site.position);
+        Ok(sites)
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// The fragments of `sequence` cut with all of `enzymes` together, e.g.
+/// one for a single digest and two for a double digest.
+#[tauri::command]
+pub async fn simulate_digest(
+    sequence: String,
+    enzymes: Vec<String>,
+    circular: Option<bool>,
+) -> Result<Digest, AppError> {
+    let enzymes = enzymes
+        .iter()
+        .map(|name| enzymes::get(name))
+        .collect::<Result<Vec<_>, _>>()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let sequence = bases(&sequence)?;
+        Ok(digest::digest(
+            sequence.as_bytes(),
+            &enzymes,
+            circular.unwrap_or(false),
+        ))
+    })
+    .await
+    .map_err(join_error)?
+}
@@ -0,0 +1,200 @@
+//! Recurring batch analyses from the `scheduled_runs` setting, e.g. re-analysing
+//! a run folder every night against an updated reference.
+//!
+//! The scheduler runs in the shell, not in a window, so runs go ahead while
+//! every window is closed. Runs due while the app was not running are skipped
+//! rather than all started at the next launch. When each run last fired is
+//! kept in the app data dir, so a restart does not repeat the day's run.
+
+use crate::error::AppError;
+use crate::folder_import::{self, ImportOptions, ImportPlan};
+use crate::jobs::{self, JobPriority};
+use crate::settings::{ScheduledRun, SettingsStore};
+use chrono::{DateTime, Datelike, Local, NaiveTime, Weekday};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+const STATE_FILE: &str = "schedule.json";
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// A run is still started this long after its time, e.g. after the machine woke up.
+const GRACE_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+/// When each scheduled run last fired, by name.
+pub struct Scheduler {
+    path: Option<PathBuf>,
+    last_runs: Mutex<BTreeMap<String, DateTime<Local>>>,
+}
+
+impl Scheduler {
+    pub fn open(app_handle: &AppHandle) -> Self {
+        let path = crate::portable::app_data_dir(app_handle).ok().map(|dir| dir.join(STATE_FILE));
+        let last_runs = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            last_runs: Mutex::new(last_runs),
+        }
+    }
+
+    fn last_run(&self, name: &str) -> Option<DateTime<Local>> {
+        self.last_runs.lock().unwrap().get(name).copied()
+    }
+
+    fn mark(&self, name: &str, at: DateTime<Local>) {
+        let mut last_runs = self.last_runs.lock().unwrap();
+        last_runs.insert(name.to_string(), at);
+        let Some(path) = &self.path else {
+            return;
+        };
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(path, serde_json::to_vec_pretty(&*last_runs).unwrap_or_default()));
+        if let Err(e) = written {
+            warn!("Could not save the schedule state to {:?}: {}", path, e);
+        }
+    }
+}
+
+/// The local time of day a run is set for, from its `HH:MM`.
+pub fn parse_time(at: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(at, "%H:%M").ok()
+}
+
+/// The days a run is set for, from names such as `mon` or `Monday`.
+pub fn parse_days(days: &[String]) -> Option<HashSet<Weekday>> {
+    days.iter().map(|day| day.parse::<Weekday>().ok()).collect()
+}
+
+/// The start of the latest occurrence of `run` at or before `now`, if it is
+/// still within the grace period.
+fn due(run: &ScheduledRun, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let time = parse_time(&run.at)?;
+    let days = parse_days(&run.days)?;
+    if !days.is_empty() && !days.contains(&now.weekday()) {
+        return None;
+    }
+    let scheduled = now.date_naive().and_time(time).and_local_timezone(Local).earliest()?;
+    // Negative (not yet due) differences do not convert.
+    let late = (now - scheduled).to_std().ok()?;
+    (late < GRACE_PERIOD).then_some(scheduled)
+}
+
+/// Checks the schedule every half minute for as long as the app runs.
+pub fn start(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let now = Local::now();
+            for run in app_handle.state::<SettingsStore>().get().scheduled_runs {
+                let Some(scheduled) = due(&run, now) else {
+                    continue;
+                };
+                let scheduler = app_handle.state::<Scheduler>();
+                if scheduler.last_run(&run.name).is_some_and(|last| last >= scheduled) {
+                    continue;
+                }
+                scheduler.mark(&run.name, now);
+                fire(&app_handle, run).await;
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Scans the run's folder and queues one batch job with every trace found.
+async fn fire(app_handle: &AppHandle, run: ScheduledRun) -> Option<u64> {
+    let options = ImportOptions {
+        include: run.extensions.iter().map(|extension| format!("*.{}", extension)).collect(),
+        recursive: run.recursive,
+        ..ImportOptions::default()
+    };
+    let folder = run.folder.clone();
+    let scanned = tauri::async_runtime::spawn_blocking(move || folder_import::plan(&folder, &options))
+        .await
+        .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))
+        .and_then(|plan| plan);
+    let plan = match scanned {
+        Ok(plan) => plan,
+        Err(e) => {
+            warn!("Scheduled run {:?} could not scan {:?}: {}", run.name, run.folder, e);
+            return None;
+        }
+    };
+    if plan.files.is_empty() {
+        info!("Scheduled run {:?} found nothing to analyse in {:?}", run.name, run.folder);
+        return None;
+    }
+
+    let request = request(&run, &plan);
+    match jobs::submit_job(app_handle.clone(), request, Some(JobPriority::Batch)) {
+        Ok(job) => {
+            info!(
+                "Scheduled run {:?} queued job {} with {} files",
+                run.name,
+                job.id,
+                plan.files.len()
+            );
+            Some(job.id)
+        }
+        Err(e) => {
+            warn!("Scheduled run {:?} could not queue its job: {}", run.name, e);
+            None
+        }
+    }
+}
+
+/// The run's preset with one patient per forward/reverse pair or lone file.
+fn request(run: &ScheduledRun, plan: &ImportPlan) -> Value {
+    let read = |path: &PathBuf| {
+        let id = path.file_stem().map(|stem| stem.to_string_lossy().to_string());
+        json!({ "id": id, "file": path })
+    };
+    let mut paired = HashSet::new();
+    let mut patients = Vec::new();
+    for pair in &plan.pairs {
+        paired.insert(&pair.forward);
+        paired.insert(&pair.reverse);
+        patients.push(json!({
+            "id": pair.sample,
+            "name": pair.sample,
+            "reads": [read(&pair.forward), read(&pair.reverse)],
+        }));
+    }
+    for file in plan.files.iter().filter(|file| !paired.contains(&file.path)) {
+        let sample = file.path.file_stem().map(|stem| stem.to_string_lossy().to_string());
+        patients.push(json!({ "id": sample, "name": sample, "reads": [read(&file.path)] }));
+    }
+
+    let mut request = run.preset.clone();
+    if let Value::Object(object) = &mut request {
+        object.insert(
+            "name".to_string(),
+            Value::String(format!("{} ({})", run.name, Local::now().format("%Y-%m-%d %H:%M"))),
+        );
+        object.insert("patients".to_string(), Value::Array(patients));
+    }
+    request
+}
+
+/// Starts the scheduled run `name` now, outside its schedule. Returns the id
+/// of the queued job, or `None` if the folder had nothing to analyse.
+#[tauri::command]
+pub async fn run_scheduled_now(app_handle: AppHandle, name: String) -> Result<Option<u64>, AppError> {
+    let run = app_handle
+        .state::<SettingsStore>()
+        .get()
+        .scheduled_runs
+        .into_iter()
+        .find(|run| run.name == name)
+        .ok_or_else(|| AppError::InvalidSettings(format!("there is no scheduled run named {:?}", name)))?;
+    Ok(fire(&app_handle, run).await)
+}
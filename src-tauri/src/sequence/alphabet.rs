@@ -0,0 +1,133 @@
+//! Nucleotide text as it arrives from other tools: FASTA with a header,
+//! GenBank `ORIGIN` blocks with position numbers, or bases broken into
+//! groups and lines. [`sanitize`] reduces any of these to the bare sequence
+//! and refuses anything outside the IUPAC alphabet.
+
+use crate::error::AppError;
+use serde::Serialize;
+
+/// IUPAC nucleotide codes plus `-` for gaps, in either case.
+const ALPHABET: &[u8] = b"ACGTURYSWKMBDHVN-";
+
+/// Invalid characters listed in the error, at most.
+const MAX_REPORTED: usize = 5;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Sanitized {
+    /// The FASTA header or GenBank `LOCUS` name, if there was one.
+    pub name: Option<String>,
+    pub sequence: String,
+}
+
+pub fn is_nucleotide(base: char) -> bool {
+    base.is_ascii() && ALPHABET.contains(&(base as u8).to_ascii_uppercase())
+}
+
+/// The sequence in `text` without headers, numbering or whitespace, case
+/// kept. Fails on characters outside the alphabet, naming them and where
+/// they are, and on text holding more than one FASTA record.
+pub fn sanitize(text: &str) -> Result<Sanitized, AppError> {
+    let mut name = None;
+    let mut sequence = String::new();
+    let mut invalid = Vec::new();
+    // GenBank: only what is between ORIGIN and `//` is sequence.
+    let mut in_genbank_header = text.trim_start().starts_with("LOCUS");
+
+    for (line_number, line) in text.lines().enumerate() {
+        let trimmed = line.trim();
+        if in_genbank_header {
+            if let Some(locus) = trimmed.strip_prefix("LOCUS") {
+                name = locus.split_whitespace().next().map(str::to_string);
+            }
+            in_genbank_header = !trimmed.starts_with("ORIGIN");
+            continue;
+        }
+        if trimmed == "//" {
+            break;
+        }
+        if let Some(header) = trimmed.strip_prefix('>') {
+            if name.is_some() || !sequence.is_empty() {
+                return Err(AppError::InvalidSequence(
+                    "the text holds more than one sequence".to_string(),
+                ));
+            }
+            name = Some(header.trim().to_string()).filter(|header| !header.is_empty());
+            continue;
+        }
+        for (column, c) in line.chars().enumerate() {
+            if c.is_whitespace() || c.is_ascii_digit() {
+                continue;
+            }
+            if is_nucleotide(c) {
+                sequence.push(c);
+            } else if invalid.len() < MAX_REPORTED {
+                invalid.push(format!(
+                    "{:?} at line {} column {}",
+                    c,
+                    line_number + 1,
+                    column + 1
+                ));
+            } else {
+                break;
+            }
+        }
+    }
+
+    if !invalid.is_empty() {
+        return Err(AppError::InvalidSequence(format!(
+            "not a nucleotide sequence: {}",
+            invalid.join(", ")
+        )));
+    }
+    if sequence.is_empty() {
+        return Err(AppError::InvalidSequence("no sequence found".to_string()));
+    }
+    Ok(Sanitized { name, sequence })
+}
+
+fn complement(base: char, rna: bool) -> char {
+    let upper = match base.to_ascii_uppercase() {
+        'A' if rna => 'U',
+        'A' => 'T',
+        'T' | 'U' => 'A',
+        'C' => 'G',
+        'G' => 'C',
+        'R' => 'Y',
+        'Y' => 'R',
+        'K' => 'M',
+        'M' => 'K',
+        'B' => 'V',
+        'V' => 'B',
+        'D' => 'H',
+        'H' => 'D',
+        other => other,
+    };
+    if base.is_ascii_lowercase() {
+        upper.to_ascii_lowercase()
+    } else {
+        upper
+    }
+}
+
+/// Reverse complement of a sanitized sequence, case and ambiguity codes kept.
+/// Sequences with `U` and no `T` are taken as RNA and complemented with `U`.
+pub fn reverse_complement(sequence: &str) -> String {
+    let rna = sequence.contains(['U', 'u']) && !sequence.contains(['T', 't']);
+    sequence
+        .chars()
+        .rev()
+        .map(|base| complement(base, rna))
+        .collect()
+}
+
+/// FASTA text of one record, wrapped at `width` bases per line.
+pub fn to_fasta(name: &str, sequence: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut fasta = format!(">{}\n", name);
+    for chunk in sequence.as_bytes().chunks(width) {
+        // Sanitized sequences are ASCII.
+        fasta.push_str(&String::from_utf8_lossy(chunk));
+        fasta.push('\n');
+    }
+    fasta
+}
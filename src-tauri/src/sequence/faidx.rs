@@ -0,0 +1,351 @@
+//! samtools-compatible `.fai` indexes. An index next to the file is used when
+//! it is up to date; otherwise one is built in a single streaming pass and
+//! saved there, or in the app cache if the directory is read-only.
+
+use super::fastx::split_header;
+use super::SequenceRegion;
+use crate::error::AppError;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+const CACHE_DIR: &str = "fai";
+
+#[derive(Clone, Debug)]
+struct Entry {
+    name: String,
+    length: u64,
+    /// Byte offset of the first base.
+    offset: u64,
+    /// Bases per full line, and bytes per full line including the terminator.
+    line_bases: u64,
+    line_width: u64,
+    /// FASTQ only: byte offset of the first quality character.
+    qual_offset: Option<u64>,
+}
+
+impl Entry {
+    /// Byte offset of base `position`, counted from `start`.
+    fn offset_of(&self, start: u64, position: u64) -> u64 {
+        if self.line_bases == 0 {
+            return start;
+        }
+        start + (position / self.line_bases) * self.line_width + position % self.line_bases
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ContigInfo {
+    pub name: String,
+    pub length: u64,
+}
+
+#[derive(Debug)]
+pub struct FaiIndex {
+    entries: Vec<Entry>,
+    by_name: HashMap<String, usize>,
+}
+
+impl FaiIndex {
+    fn new(entries: Vec<Entry>) -> Self {
+        let by_name = entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| (entry.name.clone(), index))
+            .collect();
+        Self { entries, by_name }
+    }
+
+    pub fn contigs(&self) -> Vec<ContigInfo> {
+        self.entries
+            .iter()
+            .map(|entry| ContigInfo {
+                name: entry.name.clone(),
+                length: entry.length,
+            })
+            .collect()
+    }
+
+    pub fn fetch(&self, path: &Path, contig: &str, start: u64, end: u64) -> Result<SequenceRegion, AppError> {
+        let entry = self
+            .by_name
+            .get(contig)
+            .map(|&index| &self.entries[index])
+            .ok_or_else(|| AppError::InvalidSequence(format!("no contig {:?} in {:?}", contig, path)))?;
+        let end = end.min(entry.length);
+        if start >= end {
+            return Err(AppError::InvalidSequence(format!(
+                "{}..{} is outside {:?}, which has {} bases",
+                start, end, contig, entry.length
+            )));
+        }
+
+        let mut file = File::open(path)?;
+        let sequence = read_span(&mut file, entry, entry.offset, start, end)?;
+        let quality = match entry.qual_offset {
+            Some(qual_offset) => Some(read_span(&mut file, entry, qual_offset, start, end)?),
+            None => None,
+        };
+        Ok(SequenceRegion {
+            contig: contig.to_string(),
+            start,
+            end,
+            sequence,
+            quality,
+        })
+    }
+
+    fn parse(contents: &str) -> Option<Self> {
+        let entries = contents
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let fields: Vec<&str> = line.split('\t').collect();
+                let number = |index: usize| fields.get(index)?.parse::<u64>().ok();
+                Some(Entry {
+                    name: fields.first()?.to_string(),
+                    length: number(1)?,
+                    offset: number(2)?,
+                    line_bases: number(3)?,
+                    line_width: number(4)?,
+                    qual_offset: number(5),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self::new(entries))
+    }
+
+    fn serialize(&self) -> String {
+        let mut contents = String::new();
+        for entry in &self.entries {
+            contents.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}",
+                entry.name, entry.length, entry.offset, entry.line_bases, entry.line_width
+            ));
+            if let Some(qual_offset) = entry.qual_offset {
+                contents.push_str(&format!("\t{}", qual_offset));
+            }
+            contents.push('\n');
+        }
+        contents
+    }
+}
+
+fn read_span(file: &mut File, entry: &Entry, base_offset: u64, start: u64, end: u64) -> Result<String, AppError> {
+    let from = entry.offset_of(base_offset, start);
+    let to = entry.offset_of(base_offset, end);
+    file.seek(SeekFrom::Start(from))?;
+    let mut raw = Vec::with_capacity((to - from) as usize);
+    file.take(to - from).read_to_end(&mut raw)?;
+    raw.retain(|&byte| byte != b'\n' && byte != b'\r');
+    Ok(String::from_utf8_lossy(&raw).to_string())
+}
+
+/// Tracks line geometry while a record's lines are read: every line but the
+/// last must be full, or offsets cannot be computed.
+struct LineCheck<'a> {
+    name: &'a str,
+    short_seen: bool,
+}
+
+impl LineCheck<'_> {
+    fn add(&mut self, entry_line: (&mut u64, &mut u64), bases: u64, width: u64) -> Result<(), AppError> {
+        let (line_bases, line_width) = entry_line;
+        if bases == 0 {
+            self.short_seen = true;
+            return Ok(());
+        }
+        if self.short_seen || (*line_bases != 0 && bases > *line_bases) {
+            return Err(AppError::InvalidSequence(format!(
+                "{:?} has lines of different lengths, so it cannot be indexed",
+                self.name
+            )));
+        }
+        if *line_bases == 0 {
+            *line_bases = bases;
+            *line_width = width;
+        } else if bases < *line_bases || width != *line_width {
+            self.short_seen = true;
+        }
+        Ok(())
+    }
+}
+
+/// Builds the index for a FASTA or FASTQ file in one pass.
+fn build(path: &Path) -> Result<FaiIndex, AppError> {
+    let mut reader = BufReader::with_capacity(1 << 20, File::open(path)?);
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut raw = Vec::new();
+    let mut position = 0u64;
+
+    let mut next_line = |raw: &mut Vec<u8>, position: &mut u64| -> Result<Option<(u64, u64)>, AppError> {
+        raw.clear();
+        let width = reader.read_until(b'\n', raw)? as u64;
+        if width == 0 {
+            return Ok(None);
+        }
+        *position += width;
+        while matches!(raw.last(), Some(b'\n' | b'\r')) {
+            raw.pop();
+        }
+        Ok(Some((raw.len() as u64, width)))
+    };
+
+    let mut line = next_line(&mut raw, &mut position)?;
+    while let Some((bases, _)) = line {
+        if bases == 0 {
+            line = next_line(&mut raw, &mut position)?;
+            continue;
+        }
+        let marker = raw[0];
+        if marker != b'>' && marker != b'@' {
+            return Err(AppError::InvalidSequence(format!(
+                "{:?} is not FASTA or FASTQ: expected a '>' or '@' header at byte {}",
+                path,
+                position - bases
+            )));
+        }
+        let (name, _) = split_header(&String::from_utf8_lossy(&raw[1..]));
+        let mut entry = Entry {
+            name: name.clone(),
+            length: 0,
+            offset: position,
+            line_bases: 0,
+            line_width: 0,
+            qual_offset: None,
+        };
+        let mut check = LineCheck {
+            name: &name,
+            short_seen: false,
+        };
+
+        // Sequence lines, up to the next header (FASTA) or the `+` line (FASTQ).
+        line = next_line(&mut raw, &mut position)?;
+        while let Some((bases, width)) = line {
+            let starts_record = bases > 0 && (raw[0] == b'>' || (marker == b'@' && raw[0] == b'+'));
+            if starts_record {
+                break;
+            }
+            check.add((&mut entry.line_bases, &mut entry.line_width), bases, width)?;
+            entry.length += bases;
+            line = next_line(&mut raw, &mut position)?;
+        }
+
+        if marker == b'@' {
+            if line.is_none() {
+                return Err(AppError::InvalidSequence(format!("{:?}: record {:?} has no quality", path, name)));
+            }
+            entry.qual_offset = Some(position);
+            let mut quality = 0;
+            while quality < entry.length {
+                match next_line(&mut raw, &mut position)? {
+                    Some((bases, _)) => quality += bases,
+                    None => break,
+                }
+            }
+            if quality != entry.length {
+                return Err(AppError::InvalidSequence(format!(
+                    "{:?}: quality of {:?} does not match its sequence length",
+                    path, name
+                )));
+            }
+            line = next_line(&mut raw, &mut position)?;
+        }
+        entries.push(entry);
+    }
+    Ok(FaiIndex::new(entries))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// An index at `index_path` that is at least as new as the data.
+fn load_if_fresh(index_path: &Path, data_modified: Option<SystemTime>) -> Option<FaiIndex> {
+    let index_modified = modified(index_path)?;
+    if data_modified.is_some_and(|data| data > index_modified) {
+        return None;
+    }
+    FaiIndex::parse(&std::fs::read_to_string(index_path).ok()?)
+}
+
+/// Where the index goes when the data's own directory is not writable.
+fn cache_path(app_handle: &AppHandle, path: &Path) -> Option<PathBuf> {
+    let digest = format!("{:x}", Sha256::digest(path.to_string_lossy().as_bytes()));
+    let dir = crate::data_dir::cache(app_handle).ok()?.join(CACHE_DIR);
+    Some(dir.join(format!("{}.fai", &digest[..16])))
+}
+
+struct LoadedIndex {
+    /// Modification time of the data when the index was loaded.
+    data_modified: Option<SystemTime>,
+    index: Arc<FaiIndex>,
+}
+
+/// Indexes loaded this session, by canonical path.
+#[derive(Default)]
+pub struct SequenceIndexes(Mutex<HashMap<PathBuf, LoadedIndex>>);
+
+impl SequenceIndexes {
+    pub fn get(&self, app_handle: &AppHandle, path: &Path) -> Result<Arc<FaiIndex>, AppError> {
+        let path = path.canonicalize()?;
+        let data_modified = modified(&path);
+        if let Some(loaded) = self.0.lock().unwrap().get(&path) {
+            if loaded.data_modified == data_modified {
+                return Ok(loaded.index.clone());
+            }
+        }
+
+        // Built without holding the lock: indexing a genome takes a while.
+        let sibling = PathBuf::from(format!("{}.fai", path.display()));
+        let cached = cache_path(app_handle, &path);
+        let index = match load_if_fresh(&sibling, data_modified)
+            .or_else(|| cached.as_deref().and_then(|cached| load_if_fresh(cached, data_modified)))
+        {
+            Some(index) => index,
+            None => {
+                info!("Indexing {:?}", path);
+                let index = build(&path)?;
+                save(&index, &sibling, cached.as_deref());
+                index
+            }
+        };
+
+        let index = Arc::new(index);
+        self.0
+            .lock()
+            .unwrap()
+            .insert(
+                path,
+                LoadedIndex {
+                    data_modified,
+                    index: index.clone(),
+                },
+            );
+        Ok(index)
+    }
+}
+
+fn save(index: &FaiIndex, sibling: &Path, cached: Option<&Path>) {
+    let contents = index.serialize();
+    if std::fs::write(sibling, &contents).is_ok() {
+        return;
+    }
+    let Some(cached) = cached else {
+        return;
+    };
+    let written = cached
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(cached, &contents));
+    if let Err(e) = written {
+        warn!("Could not save index for {:?}, it will be rebuilt next time: {}", sibling, e);
+    }
+}
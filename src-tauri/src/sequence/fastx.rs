@@ -0,0 +1,142 @@
+//! Streaming FASTA/FASTQ reader, one record at a time. The format is taken
+//! from the first record's marker (`>` or `@`).
+
+use crate::error::AppError;
+use serde::Serialize;
+use std::io::BufRead;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Record {
+    pub name: String,
+    /// The rest of the header line after the name.
+    pub description: Option<String>,
+    pub sequence: String,
+    /// Only for FASTQ.
+    pub quality: Option<String>,
+}
+
+pub struct Records<R> {
+    reader: R,
+    /// A header line read ahead while finishing the previous FASTA record.
+    next_header: Option<String>,
+    line: String,
+    line_number: u64,
+    done: bool,
+}
+
+impl<R: BufRead> Records<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            next_header: None,
+            line: String::new(),
+            line_number: 0,
+            done: false,
+        }
+    }
+
+    /// The next line without its terminator, or `None` at end of file.
+    fn read_line(&mut self) -> Result<Option<String>, AppError> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line)? == 0 {
+            return Ok(None);
+        }
+        self.line_number += 1;
+        Ok(Some(self.line.trim_end_matches(['\n', '\r']).to_string()))
+    }
+
+    fn invalid(&self, detail: &str) -> AppError {
+        AppError::InvalidSequence(format!("line {}: {}", self.line_number, detail))
+    }
+
+    fn next_record(&mut self) -> Result<Option<Record>, AppError> {
+        let header = match self.next_header.take() {
+            Some(header) => header,
+            None => loop {
+                match self.read_line()? {
+                    None => return Ok(None),
+                    Some(line) if line.trim().is_empty() => continue,
+                    Some(line) => break line,
+                }
+            },
+        };
+
+        // Files saved by some Windows editors start with a byte order mark.
+        let header = header.strip_prefix('\u{feff}').unwrap_or(&header);
+        let marker = match header.as_bytes().first() {
+            Some(&marker @ (b'>' | b'@')) => marker,
+            _ => return Err(self.invalid("expected a '>' or '@' header")),
+        };
+        let (name, description) = split_header(header.strip_prefix(['>', '@']).unwrap_or(header));
+        let mut sequence = String::new();
+        match marker {
+            b'>' => {
+                while let Some(line) = self.read_line()? {
+                    if line.starts_with('>') {
+                        self.next_header = Some(line);
+                        break;
+                    }
+                    sequence.push_str(line.trim());
+                }
+                Ok(Some(Record {
+                    name,
+                    description,
+                    sequence,
+                    quality: None,
+                }))
+            }
+            // '@', the only other marker let through above.
+            _ => {
+                loop {
+                    match self.read_line()? {
+                        None => return Err(self.invalid("FASTQ record ends before its quality line")),
+                        Some(line) if line.starts_with('+') => break,
+                        Some(line) => sequence.push_str(line.trim()),
+                    }
+                }
+                // Quality may wrap like the sequence, and may itself start with `@`,
+                // so it is read by length rather than by marker.
+                let mut quality = String::with_capacity(sequence.len());
+                while quality.len() < sequence.len() {
+                    match self.read_line()? {
+                        None => return Err(self.invalid("FASTQ quality is shorter than the sequence")),
+                        Some(line) => quality.push_str(line.trim()),
+                    }
+                }
+                if quality.len() != sequence.len() {
+                    return Err(self.invalid("FASTQ quality is longer than the sequence"));
+                }
+                Ok(Some(Record {
+                    name,
+                    description,
+                    sequence,
+                    quality: Some(quality),
+                }))
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Records<R> {
+    type Item = Result<Record, AppError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let record = self.next_record().transpose();
+        // Stop after an error; the position in the file is no longer meaningful.
+        if !matches!(record, Some(Ok(_))) {
+            self.done = true;
+        }
+        record
+    }
+}
+
+/// Splits `name description...` after the header marker.
+pub fn split_header(header: &str) -> (String, Option<String>) {
+    match header.trim().split_once(char::is_whitespace) {
+        Some((name, description)) => (name.to_string(), Some(description.trim().to_string())),
+        None => (header.trim().to_string(), None),
+    }
+}
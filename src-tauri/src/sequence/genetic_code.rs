@@ -0,0 +1,229 @@
+//! NCBI genetic codes, in NCBI's own compact form: amino acids and start
+//! codons for the 64 codons, ordered with T, C, A, G for the first, second
+//! and third base in turn. Every table NCBI defines is here (1-6, 9-16 and
+//! 21-33; 7, 8 and 17-20 were never assigned or were merged into others).
+//! Codons that are a stop only at the end of a gene in some nuclear codes
+//! (27, 28 and 31) translate as their amino acid, so ORFs in those run on.
+
+use crate::error::AppError;
+
+pub struct GeneticCode {
+    id: u8,
+    amino_acids: &'static [u8; 64],
+    starts: &'static [u8; 64],
+}
+
+const CODES: &[GeneticCode] = &[
+    // Standard
+    GeneticCode {
+        id: 1,
+        amino_acids: b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"---M------**--*----M---------------M----------------------------",
+    },
+    // Vertebrate Mitochondrial
+    GeneticCode {
+        id: 2,
+        amino_acids: b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSS**VVVVAAAADDEEGGGG",
+        starts: b"----------**--------------------MMMM----------**---M------------",
+    },
+    // Yeast Mitochondrial
+    GeneticCode {
+        id: 3,
+        amino_acids: b"FFLLSSSSYY**CCWWTTTTPPPPHHQQRRRRIIMMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"----------**----------------------MM---------------M------------",
+    },
+    // Mold, Protozoan and Coelenterate Mitochondrial; Mycoplasma
+    GeneticCode {
+        id: 4,
+        amino_acids: b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"--MM------**-------M------------MMMM---------------M------------",
+    },
+    // Invertebrate Mitochondrial
+    GeneticCode {
+        id: 5,
+        amino_acids: b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSSSVVVVAAAADDEEGGGG",
+        starts: b"---M------**--------------------MMMM---------------M------------",
+    },
+    // Ciliate, Dasycladacean and Hexamita Nuclear
+    GeneticCode {
+        id: 6,
+        amino_acids: b"FFLLSSSSYYQQCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"--------------*--------------------M----------------------------",
+    },
+    // Echinoderm and Flatworm Mitochondrial
+    GeneticCode {
+        id: 9,
+        amino_acids: b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        starts: b"-----------------------------------M---------------M------------",
+    },
+    // Euplotid Nuclear
+    GeneticCode {
+        id: 10,
+        amino_acids: b"FFLLSSSSYY**CCCWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"-----------------------------------M----------------------------",
+    },
+    // Bacterial, Archaeal and Plant Plastid
+    GeneticCode {
+        id: 11,
+        amino_acids: b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"---M------**--*----M------------MMMM---------------M------------",
+    },
+    // Alternative Yeast Nuclear
+    GeneticCode {
+        id: 12,
+        amino_acids: b"FFLLSSSSYY**CC*WLLLSPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"-------------------M---------------M----------------------------",
+    },
+    // Ascidian Mitochondrial
+    GeneticCode {
+        id: 13,
+        amino_acids: b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSGGVVVVAAAADDEEGGGG",
+        starts: b"---M------------------------------MM---------------M------------",
+    },
+    // Alternative Flatworm Mitochondrial
+    GeneticCode {
+        id: 14,
+        amino_acids: b"FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        starts: b"-----------------------------------M----------------------------",
+    },
+    // Blepharisma Macronuclear
+    GeneticCode {
+        id: 15,
+        amino_acids: b"FFLLSSSSYY*QCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"-----------------------------------M----------------------------",
+    },
+    // Chlorophycean Mitochondrial
+    GeneticCode {
+        id: 16,
+        amino_acids: b"FFLLSSSSYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"-----------------------------------M----------------------------",
+    },
+    // Trematode Mitochondrial
+    GeneticCode {
+        id: 21,
+        amino_acids: b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        starts: b"-----------------------------------M---------------M------------",
+    },
+    // Scenedesmus obliquus Mitochondrial
+    GeneticCode {
+        id: 22,
+        amino_acids: b"FFLLSS*SYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"-----------------------------------M----------------------------",
+    },
+    // Thraustochytrium Mitochondrial
+    GeneticCode {
+        id: 23,
+        amino_acids: b"FF*LSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"--------------------------------M--M---------------M------------",
+    },
+    // Rhabdopleuridae Mitochondrial
+    GeneticCode {
+        id: 24,
+        amino_acids: b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+        starts: b"---M---------------M---------------M---------------M------------",
+    },
+    // Candidate Division SR1 and Gracilibacteria
+    GeneticCode {
+        id: 25,
+        amino_acids: b"FFLLSSSSYY**CCGWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"---M-------------------------------M---------------M------------",
+    },
+    // Pachysolen tannophilus Nuclear
+    GeneticCode {
+        id: 26,
+        amino_acids: b"FFLLSSSSYY**CC*WLLLAPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"-------------------M---------------M----------------------------",
+    },
+    // Karyorelict Nuclear
+    GeneticCode {
+        id: 27,
+        amino_acids: b"FFLLSSSSYYQQCCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"--------------*--------------------M----------------------------",
+    },
+    // Condylostoma Nuclear
+    GeneticCode {
+        id: 28,
+        amino_acids: b"FFLLSSSSYYQQCCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"----------**--*--------------------M----------------------------",
+    },
+    // Mesodinium Nuclear
+    GeneticCode {
+        id: 29,
+        amino_acids: b"FFLLSSSSYYYYCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"--------------*--------------------M----------------------------",
+    },
+    // Peritrich Nuclear
+    GeneticCode {
+        id: 30,
+        amino_acids: b"FFLLSSSSYYEECC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"--------------*--------------------M----------------------------",
+    },
+    // Blastocrithidia Nuclear
+    GeneticCode {
+        id: 31,
+        amino_acids: b"FFLLSSSSYYEECCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"----------**-----------------------M----------------------------",
+    },
+    // Balanophoraceae Plastid
+    GeneticCode {
+        id: 32,
+        amino_acids: b"FFLLSSSSYY*WCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        starts: b"---M---------------M------------MMMM---------------M------------",
+    },
+    // Cephalodiscidae Mitochondrial
+    GeneticCode {
+        id: 33,
+        amino_acids: b"FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+        starts: b"---M-------------------------------M---------------M------------",
+    },
+];
+
+/// Index of ATG in the tables.
+const ATG: usize = 2 * 16 + 3;
+
+/// Index of `codon` in the tables, `None` if it holds anything but A, C,
+/// G, T or U.
+fn index(codon: &[u8]) -> Option<usize> {
+    codon.iter().try_fold(0, |index, base| {
+        let code = match base.to_ascii_uppercase() {
+            b'T' | b'U' => 0,
+            b'C' => 1,
+            b'A' => 2,
+            b'G' => 3,
+            _ => return None,
+        };
+        Some(index * 4 + code)
+    })
+}
+
+impl GeneticCode {
+    /// Genetic code `id` by NCBI's numbering.
+    pub fn get(id: u8) -> Result<&'static Self, AppError> {
+        CODES.iter().find(|code| code.id == id).ok_or_else(|| {
+            let known: Vec<String> = CODES.iter().map(|code| code.id.to_string()).collect();
+            AppError::InvalidSequence(format!(
+                "unknown genetic code {}; NCBI tables {} are supported",
+                id,
+                known.join(", ")
+            ))
+        })
+    }
+
+    /// One-letter amino acid, `*` for a stop and `X` for an ambiguous codon.
+    pub fn translate(&self, codon: &[u8]) -> char {
+        index(codon).map_or('X', |index| self.amino_acids[index] as char)
+    }
+
+    pub fn is_stop(&self, codon: &[u8]) -> bool {
+        self.translate(codon) == '*'
+    }
+
+    /// Whether `codon` can start translation; only ATG unless `alternative`.
+    pub fn is_start(&self, codon: &[u8], alternative: bool) -> bool {
+        match index(codon) {
+            Some(index) if alternative => self.starts[index] == b'M',
+            Some(index) => index == ATG,
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! FASTA/FASTQ access for references and reads too large to load whole:
+//! streaming record parsing, and faidx-style indexes for random access.
+//! Also the cleanup of sequences pasted from other tools, and the figures
+//! of the info panes.
+
+mod alphabet;
+mod faidx;
+mod fastx;
+mod genetic_code;
+mod orf;
+mod stats;
+
+pub use alphabet::{reverse_complement, sanitize, to_fasta, Sanitized};
+pub use faidx::{ContigInfo, SequenceIndexes};
+pub use fastx::Records;
+pub use orf::Orf;
+pub use stats::SequenceStats;
+
+use crate::error::AppError;
+use crate::ipc::{self, Encoding};
+use serde::Serialize;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// Longest region `get_sequence_region` returns, to keep IPC payloads sane.
+pub const MAX_REGION_LEN: u64 = 10_000_000;
+/// NCBI's standard genetic code, used unless another is asked for.
+const STANDARD_CODE: u8 = 1;
+/// Shortest run of one base `sequence_stats` lists by default.
+const MIN_HOMOPOLYMER: usize = 5;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SequenceRegion {
+    pub contig: String,
+    /// 0-based, half-open; `end` is clamped to the contig length.
+    pub start: u64,
+    pub end: u64,
+    pub sequence: String,
+    /// Only for FASTQ files.
+    pub quality: Option<String>,
+}
+
+fn join_error(e: impl std::fmt::Display) -> AppError {
+    AppError::Io(std::io::Error::other(e.to_string()))
+}
+
+/// Contig names and lengths, building the index if needed.
+#[tauri::command]
+pub async fn list_contigs(app_handle: AppHandle, file: PathBuf) -> Result<Vec<ContigInfo>, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let index = app_handle.state::<SequenceIndexes>().get(&app_handle, &file)?;
+        Ok(index.contigs())
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// `contig[start..end)` from an indexed FASTA or FASTQ file.
+#[tauri::command]
+pub async fn get_sequence_region(
+    app_handle: AppHandle,
+    file: PathBuf,
+    contig: String,
+    start: u64,
+    end: u64,
+) -> Result<SequenceRegion, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if end <= start {
+            return Err(AppError::InvalidSequence(format!("empty region {}..{}", start, end)));
+        }
+        if end - start > MAX_REGION_LEN {
+            return Err(AppError::InvalidSequence(format!(
+                "region of {} bases is larger than the {} base limit",
+                end - start,
+                MAX_REGION_LEN
+            )));
+        }
+        let index = app_handle.state::<SequenceIndexes>().get(&app_handle, &file)?;
+        index.fetch(&file, &contig, start, end)
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// The first `max_records` records of a FASTA or FASTQ file, for files
+/// of reads rather than references, in `encoding` if given.
+#[tauri::command]
+pub async fn read_sequences(
+    file: PathBuf,
+    max_records: Option<usize>,
+    encoding: Option<Encoding>,
+) -> Result<tauri::ipc::Response, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let records = Records::new(BufReader::new(File::open(&file)?))
+            .take(max_records.unwrap_or(usize::MAX))
+            .collect::<Result<Vec<_>, _>>()?;
+        ipc::encode(&records, encoding)
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// GC content, base and ambiguity counts, homopolymer runs and, given
+/// `quality` (one Phred value per base), its distribution, over
+/// `sequence[start..end)` or the whole sequence. Positions count gaps.
+#[tauri::command]
+pub async fn sequence_stats(
+    sequence: String,
+    quality: Option<Vec<u8>>,
+    start: Option<usize>,
+    end: Option<usize>,
+    min_homopolymer: Option<usize>,
+) -> Result<SequenceStats, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let sequence = sanitize(&sequence)?.sequence;
+        if let Some(quality) = &quality {
+            if quality.len() != sequence.len() {
+                return Err(AppError::InvalidSequence(format!(
+                    "{} quality values for {} bases",
+                    quality.len(),
+                    sequence.len()
+                )));
+            }
+        }
+        let start = start.unwrap_or(0);
+        let end = end.unwrap_or(sequence.len()).min(sequence.len());
+        if start > end {
+            return Err(AppError::InvalidSequence(format!(
+                "empty selection {}..{}",
+                start, end
+            )));
+        }
+        Ok(stats::stats(
+            &sequence[start..end],
+            quality.as_deref().map(|quality| &quality[start..end]),
+            start,
+            min_homopolymer.unwrap_or(MIN_HOMOPOLYMER),
+        ))
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// ORFs of at least `min_len` bases, stop codon included, on all six frames
+/// of `sequence`, translated with NCBI genetic code `genetic_code`
+/// (standard if unset). ORFs start at ATG, or at any start codon of the
+/// code with `alternative_starts`. Gaps are removed first, so coordinates
+/// are in ungapped bases.
+#[tauri::command]
+pub async fn find_orfs(
+    sequence: String,
+    min_len: usize,
+    genetic_code: Option<u8>,
+    alternative_starts: Option<bool>,
+) -> Result<Vec<Orf>, AppError> {
+    let code = genetic_code::GeneticCode::get(genetic_code.unwrap_or(STANDARD_CODE))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let sequence: String = sanitize(&sequence)?
+            .sequence
+            .chars()
+            .filter(|&base| base != '-')
+            .collect();
+        Ok(orf::find(
+            &sequence,
+            code,
+            alternative_starts.unwrap_or(false),
+            min_len.max(3),
+        ))
+    })
+    .await
+    .map_err(join_error)?
+}
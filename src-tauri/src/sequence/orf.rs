@@ -0,0 +1,105 @@
+//! Open reading frames on all six frames, for annotating consensus
+//! sequences and references.
+
+use super::genetic_code::GeneticCode;
+use super::reverse_complement;
+use serde::Serialize;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Orf {
+    /// 1 to 3 on the forward strand, -1 to -3 on the reverse one.
+    pub frame: i8,
+    /// On the forward strand, 0-based, end exclusive, stop codon included.
+    pub start: usize,
+    pub end: usize,
+    /// Bases from the start codon to the stop codon, both included.
+    pub length: usize,
+    /// `false` if the sequence ends before a stop codon.
+    pub complete: bool,
+    /// Without the stop, the start codon read as `M`.
+    pub protein: String,
+}
+
+fn translate(code: &GeneticCode, bases: &[u8]) -> String {
+    let mut protein: String = bases
+        .chunks_exact(3)
+        .map(|codon| code.translate(codon))
+        .filter(|&amino_acid| amino_acid != '*')
+        .collect();
+    if !protein.is_empty() {
+        // Alternative start codons still initiate with methionine.
+        protein.replace_range(0..1, "M");
+    }
+    protein
+}
+
+/// The ORFs of one strand, in its own coordinates: from each start codon
+/// following a stop (or the beginning) to the next stop in frame.
+fn strand_orfs(
+    bases: &[u8],
+    code: &GeneticCode,
+    alternative_starts: bool,
+    min_len: usize,
+) -> Vec<(i8, usize, usize, bool)> {
+    let mut orfs = Vec::new();
+    for frame in 0..3 {
+        let mut start = None;
+        let mut position = frame;
+        while position + 3 <= bases.len() {
+            let codon = &bases[position..position + 3];
+            if code.is_stop(codon) {
+                if let Some(start) = start.take() {
+                    orfs.push((frame as i8 + 1, start, position + 3, true));
+                }
+            } else if start.is_none() && code.is_start(codon, alternative_starts) {
+                start = Some(position);
+            }
+            position += 3;
+        }
+        if let Some(start) = start {
+            orfs.push((frame as i8 + 1, start, position, false));
+        }
+    }
+    orfs.retain(|&(_, start, end, _)| end - start >= min_len);
+    orfs
+}
+
+/// ORFs of at least `min_len` bases in `sequence` (bare bases, no gaps),
+/// sorted by position.
+pub fn find(
+    sequence: &str,
+    code: &GeneticCode,
+    alternative_starts: bool,
+    min_len: usize,
+) -> Vec<Orf> {
+    let forward = sequence.as_bytes();
+    let reverse = reverse_complement(sequence);
+    let reverse = reverse.as_bytes();
+    let n = forward.len();
+
+    let mut orfs: Vec<Orf> = strand_orfs(forward, code, alternative_starts, min_len)
+        .into_iter()
+        .map(|(frame, start, end, complete)| Orf {
+            frame,
+            start,
+            end,
+            length: end - start,
+            complete,
+            protein: translate(code, &forward[start..end]),
+        })
+        .chain(
+            strand_orfs(reverse, code, alternative_starts, min_len)
+                .into_iter()
+                .map(|(frame, start, end, complete)| Orf {
+                    frame: -frame,
+                    start: n - end,
+                    end: n - start,
+                    length: end - start,
+                    complete,
+                    protein: translate(code, &reverse[start..end]),
+                }),
+        )
+        .collect();
+    orfs.sort_by_key(|orf| (orf.start, orf.end, orf.frame));
+    orfs
+}
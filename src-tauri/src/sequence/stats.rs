@@ -0,0 +1,155 @@
+//! Composition and quality figures for the info panes, over a whole
+//! sequence or a selection of it.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Homopolymer runs listed, at most; `longest_homopolymer` covers the rest.
+const MAX_HOMOPOLYMERS: usize = 1000;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Homopolymer {
+    pub base: char,
+    /// 0-based, in the whole sequence.
+    pub start: usize,
+    pub length: usize,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct QualityStats {
+    pub min: u8,
+    pub max: u8,
+    pub mean: f64,
+    pub median: f64,
+    /// Bases at Q20 and above, and at Q30 and above.
+    pub q20: usize,
+    pub q30: usize,
+    /// Bases per Phred value, index = quality, up to `max`.
+    pub histogram: Vec<usize>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SequenceStats {
+    /// Bases, not counting gaps.
+    pub length: usize,
+    pub gaps: usize,
+    /// Per upper-case base, `U` counted as `T`; ambiguity codes included.
+    pub counts: BTreeMap<char, usize>,
+    /// G, C and S over bases whose strength is known (A, C, G, T, S, W).
+    pub gc_content: Option<f64>,
+    /// Bases other than A, C, G and T, `N` included.
+    pub ambiguous: usize,
+    pub homopolymers: Vec<Homopolymer>,
+    pub longest_homopolymer: Option<Homopolymer>,
+    /// `None` without qualities.
+    pub quality: Option<QualityStats>,
+}
+
+fn quality_stats(quality: &[u8]) -> Option<QualityStats> {
+    let (&min, &max) = (quality.iter().min()?, quality.iter().max()?);
+    let mut histogram = vec![0; max as usize + 1];
+    for &q in quality {
+        histogram[q as usize] += 1;
+    }
+    // Median from the histogram, so nothing is sorted.
+    let quantile = |rank: usize| {
+        let mut seen = 0;
+        histogram
+            .iter()
+            .position(|&count| {
+                seen += count;
+                seen > rank
+            })
+            .unwrap_or(0) as f64
+    };
+    let middle = quality.len() / 2;
+    let median = if quality.len() % 2 == 0 {
+        (quantile(middle - 1) + quantile(middle)) / 2.0
+    } else {
+        quantile(middle)
+    };
+    Some(QualityStats {
+        min,
+        max,
+        mean: quality.iter().map(|&q| q as f64).sum::<f64>() / quality.len() as f64,
+        median,
+        q20: quality.iter().filter(|&&q| q >= 20).count(),
+        q30: quality.iter().filter(|&&q| q >= 30).count(),
+        histogram,
+    })
+}
+
+/// Figures for `sequence[offset..]`, already cut to the selection, with its
+/// `quality` if any (same length). Runs of at least `min_homopolymer` of
+/// the same base (gaps skipped, ambiguity codes breaking them) are listed.
+pub fn stats(
+    sequence: &str,
+    quality: Option<&[u8]>,
+    offset: usize,
+    min_homopolymer: usize,
+) -> SequenceStats {
+    let mut stats = SequenceStats::default();
+    let mut run: Option<Homopolymer> = None;
+    let close = |run: Homopolymer, stats: &mut SequenceStats| {
+        if run.length < min_homopolymer.max(2) {
+            return;
+        }
+        if !stats
+            .longest_homopolymer
+            .as_ref()
+            .is_some_and(|longest| longest.length >= run.length)
+        {
+            stats.longest_homopolymer = Some(run.clone());
+        }
+        if stats.homopolymers.len() < MAX_HOMOPOLYMERS {
+            stats.homopolymers.push(run);
+        }
+    };
+
+    for (index, base) in sequence.chars().enumerate() {
+        if base == '-' {
+            stats.gaps += 1;
+            continue;
+        }
+        let base = match base.to_ascii_uppercase() {
+            'U' => 'T',
+            base => base,
+        };
+        stats.length += 1;
+        *stats.counts.entry(base).or_default() += 1;
+        if !matches!(base, 'A' | 'C' | 'G' | 'T') {
+            stats.ambiguous += 1;
+            // Runs of N are unread bases, not homopolymers.
+            if let Some(previous) = run.take() {
+                close(previous, &mut stats);
+            }
+            continue;
+        }
+        run = match run {
+            Some(mut current) if current.base == base => {
+                current.length += 1;
+                Some(current)
+            }
+            previous => {
+                if let Some(previous) = previous {
+                    close(previous, &mut stats);
+                }
+                Some(Homopolymer {
+                    base,
+                    start: offset + index,
+                    length: 1,
+                })
+            }
+        };
+    }
+    if let Some(run) = run {
+        close(run, &mut stats);
+    }
+
+    let count = |base: char| stats.counts.get(&base).copied().unwrap_or(0);
+    let strong = count('G') + count('C') + count('S');
+    let known = strong + count('A') + count('T') + count('W');
+    stats.gc_content = (known > 0).then(|| strong as f64 / known as f64);
+    stats.quality = quality.and_then(quality_stats);
+    stats
+}
@@ -0,0 +1,175 @@
+//! Autosave of the working session and recovery after a crash.
+//!
+//! The frontend pushes its state with `update_session`; it is written to
+//! `session/session.json` every [`AUTOSAVE_INTERVAL`] when it changed. A
+//! `running` marker is created at launch and removed only on a graceful exit,
+//! so finding it on the next launch means the app did not shut down cleanly
+//! and the last snapshot is offered for restore.
+
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+/// Emitted with the recovered [`SessionSnapshot`] once the frontend can offer a restore.
+pub const SESSION_RECOVERY_EVENT: &str = "session-recovery-available";
+
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+const SESSION_DIR: &str = "session";
+const SESSION_FILE: &str = "session.json";
+const RUNNING_MARKER: &str = "running";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionSnapshot {
+    pub open_files: Vec<PathBuf>,
+    pub project: Option<PathBuf>,
+    /// Jobs that were queued or running, as the frontend describes them.
+    pub jobs: Vec<Value>,
+    /// Opaque UI state (selected trace, zoom, panel layout, ...).
+    pub ui_state: Value,
+    /// Seconds since the Unix epoch; set when the snapshot is written.
+    pub saved_at: u64,
+}
+
+pub struct SessionManager {
+    dir: Option<PathBuf>,
+    current: Mutex<SessionSnapshot>,
+    changed: AtomicBool,
+    /// The snapshot left behind by an unclean exit, until restored or discarded.
+    recovered: Mutex<Option<SessionSnapshot>>,
+}
+
+impl SessionManager {
+    /// Checks for an unclean previous exit and marks this session as running.
+    pub fn start(app_handle: &AppHandle) -> Self {
+        let dir = crate::data_dir::data(app_handle).ok().map(|dir| dir.join(SESSION_DIR));
+
+        let mut recovered = None;
+        if let Some(dir) = &dir {
+            if dir.join(RUNNING_MARKER).exists() {
+                match read(&dir.join(SESSION_FILE)) {
+                    Ok(snapshot) => {
+                        warn!("PS Analyzer did not exit cleanly; session from {} can be restored", snapshot.saved_at);
+                        recovered = Some(snapshot);
+                    }
+                    Err(e) => warn!("PS Analyzer did not exit cleanly, but no session was saved: {}", e),
+                }
+            }
+            let marked = std::fs::create_dir_all(dir)
+                .and_then(|_| std::fs::write(dir.join(RUNNING_MARKER), std::process::id().to_string()));
+            if let Err(e) = marked {
+                warn!("Could not mark the session as running, crash recovery is disabled: {}", e);
+            }
+        }
+
+        Self {
+            dir,
+            current: Mutex::new(SessionSnapshot::default()),
+            changed: AtomicBool::new(false),
+            recovered: Mutex::new(recovered),
+        }
+    }
+
+    fn update(&self, snapshot: SessionSnapshot) {
+        *self.current.lock().unwrap() = snapshot;
+        self.changed.store(true, Ordering::SeqCst);
+    }
+
+    /// Writes the current snapshot if it changed since the last save.
+    fn save_if_changed(&self) -> Result<(), AppError> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        if !self.changed.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let mut snapshot = self.current.lock().unwrap().clone();
+        snapshot.saved_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        let path = dir.join(SESSION_FILE);
+        let staging = path.with_extension("json.tmp");
+        let written = std::fs::write(&staging, serde_json::to_vec(&snapshot).unwrap_or_default())
+            .and_then(|_| std::fs::rename(&staging, &path));
+        if let Err(e) = written {
+            // Try again on the next tick.
+            self.changed.store(true, Ordering::SeqCst);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Saves a last time and removes the running marker. Only called on a
+    /// graceful exit.
+    pub fn end(&self) {
+        if let Err(e) = self.save_if_changed() {
+            warn!("Could not save the session on exit: {}", e);
+        }
+        if let Some(dir) = &self.dir {
+            let _ = std::fs::remove_file(dir.join(RUNNING_MARKER));
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<SessionSnapshot, AppError> {
+    let contents = std::fs::read(path)?;
+    serde_json::from_slice(&contents)
+        .map_err(|e| AppError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))
+}
+
+/// Saves the session every [`AUTOSAVE_INTERVAL`] for the life of the app, and
+/// announces a recoverable session if there is one.
+pub fn start_autosave(app_handle: &AppHandle) {
+    if let Some(snapshot) = app_handle.state::<SessionManager>().recovered.lock().unwrap().clone() {
+        let _ = app_handle.emit(SESSION_RECOVERY_EVENT, snapshot);
+    }
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(AUTOSAVE_INTERVAL).await;
+            if let Err(e) = app_handle.state::<SessionManager>().save_if_changed() {
+                warn!("Session autosave failed: {}", e);
+            }
+        }
+    });
+}
+
+/// Called by the frontend whenever its state changes; cheap, the disk write
+/// happens on the next autosave tick.
+#[tauri::command]
+pub fn update_session(session: tauri::State<SessionManager>, snapshot: SessionSnapshot) {
+    session.update(snapshot);
+}
+
+/// The session left behind by an unclean exit, for a frontend that loaded
+/// after the recovery event was sent.
+#[tauri::command]
+pub fn get_recovered_session(session: tauri::State<SessionManager>) -> Option<SessionSnapshot> {
+    session.recovered.lock().unwrap().clone()
+}
+
+/// Hands over the recovered session and forgets it.
+#[tauri::command]
+pub fn restore_session(session: tauri::State<SessionManager>) -> Option<SessionSnapshot> {
+    let snapshot = session.recovered.lock().unwrap().take();
+    if snapshot.is_some() {
+        info!("Restoring the previous session");
+    }
+    snapshot
+}
+
+#[tauri::command]
+pub fn discard_recovered_session(session: tauri::State<SessionManager>) {
+    if session.recovered.lock().unwrap().take().is_some() {
+        info!("Discarded the previous session");
+    }
+}
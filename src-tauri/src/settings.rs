@@ -0,0 +1,552 @@
+//! User settings for the desktop shell, stored as JSON in the app config dir.
+
+use crate::engine::EngineTransport;
+use crate::error::AppError;
+use crate::sidecar::ProcessPriority;
+use crate::updater::UpdateChannel;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+
+/// Emitted with the new [`Settings`] after `set_settings` saved them.
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+const SETTINGS_FILE: &str = "settings.json";
+/// Written by the updater before settings existed.
+const LEGACY_UPDATER_FILE: &str = "updater.json";
+
+/// Version of the on-disk format written by this build, stored in the file's
+/// `schema_version` key. Files without one predate versioning and are version 1.
+const SCHEMA_VERSION: u32 = 1;
+
+/// Upgrades a settings object by one version, renaming or restructuring keys.
+type Migration = fn(&mut Map<String, Value>);
+
+/// `MIGRATIONS[n]` upgrades version `n + 1` to `n + 2`. To change the format,
+/// bump `SCHEMA_VERSION` and append the migration from the previous version.
+const MIGRATIONS: &[Migration] = &[];
+
+/// What each setting affects and when it takes effect:
+/// - `engine_port`, `engine_transport`, `remote_engine`, `startup_timeout_secs`,
+///   `log_level`, `data_dir`: next app launch.
+/// - `worker_count`, `engine_priority`, `tracy_path`, `extra_env`: next engine
+///   (re)start; `tracy_path` also the next tracy run started by the app itself.
+/// - `engine_memory_limit_gb`: next sample of the engine's memory use (every
+///   few seconds).
+/// - `max_concurrent_jobs`: next time a queued job could start.
+/// - `prevent_sleep`: next time a job is queued, starts or finishes.
+/// - `job_history_days`, `job_history_max_gb`: next history cleanup (hourly).
+/// - `temp_max_gb`: next time a job creates its temporary directory.
+/// - `result_cache_max_gb`: next time a result is cached.
+/// - `watch_folders`: next poll of the folders (every few seconds).
+/// - `scheduled_runs`: next check of the schedule (every half minute).
+/// - `notifications`: next time a job finishes.
+/// - `close_to_tray`: next time the main window is closed.
+/// - `shortcuts`: as soon as the settings are saved.
+/// - `update_channel`: next update check.
+/// - `ncbi_api_key`: next accession fetched from NCBI.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    /// Fixed port for the engine; a free one is picked when unset or taken.
+    pub engine_port: Option<u16>,
+    /// `socket` runs the engine on a Unix domain socket (named pipe on
+    /// Windows) instead of a TCP port.
+    pub engine_transport: EngineTransport,
+    /// A bio-engine on another machine to use instead of starting the bundled one.
+    pub remote_engine: Option<RemoteEngine>,
+    /// Worker processes the engine runs; sized to the machine's cores and
+    /// memory when unset.
+    pub worker_count: Option<u32>,
+    /// CPU priority of the engine and the tracy processes it starts.
+    pub engine_priority: ProcessPriority,
+    /// Tracy binary to use instead of the bundled or updated one.
+    pub tracy_path: Option<PathBuf>,
+    /// Additional environment variables for the engine process.
+    pub extra_env: BTreeMap<String, String>,
+    /// Once the engine and its processes use more memory than this, it is
+    /// restarted as soon as its running jobs are done. No limit when unset.
+    pub engine_memory_limit_gb: Option<u32>,
+    /// Jobs the shell runs on the engine at once; the rest wait in the queue.
+    /// Sized to the machine's cores and memory when unset.
+    pub max_concurrent_jobs: Option<u32>,
+    /// Keep the system awake while jobs are queued or running.
+    pub prevent_sleep: bool,
+    /// Finished jobs older than this are dropped from the history, outputs included.
+    pub job_history_days: Option<u32>,
+    /// Oldest jobs are dropped once their outputs take up more than this.
+    pub job_history_max_gb: Option<u32>,
+    /// Oldest temporary directories of finished jobs are removed once all of
+    /// them take up more than this.
+    pub temp_max_gb: Option<u32>,
+    /// Least recently used results are evicted from the cache past this.
+    pub result_cache_max_gb: Option<u32>,
+    /// Folders whose new files are analysed automatically.
+    pub watch_folders: Vec<WatchFolder>,
+    /// Batch analyses of a folder repeated on a schedule.
+    pub scheduled_runs: Vec<ScheduledRun>,
+    /// Which finished jobs are announced with an OS notification.
+    pub notifications: NotificationSettings,
+    /// Closing the main window hides it in the tray instead of quitting, so
+    /// jobs, watch folders and schedules keep running.
+    pub close_to_tray: bool,
+    /// System-wide keyboard shortcuts.
+    pub shortcuts: ShortcutSettings,
+    /// Folder for the job history, checkpoints, autosaves and caches instead
+    /// of the platform's app data and cache folders. Change it with
+    /// `move_data_dir`, which moves the existing data along.
+    pub data_dir: Option<PathBuf>,
+    /// Log filter in `EnvFilter` syntax, e.g. `info,engine=warn`.
+    pub log_level: Option<String>,
+    pub startup_timeout_secs: u64,
+    pub update_channel: UpdateChannel,
+    /// NCBI E-utilities API key, which raises the request rate NCBI allows.
+    pub ncbi_api_key: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            engine_port: None,
+            engine_transport: EngineTransport::default(),
+            remote_engine: None,
+            worker_count: None,
+            engine_priority: ProcessPriority::default(),
+            tracy_path: None,
+            extra_env: BTreeMap::new(),
+            engine_memory_limit_gb: None,
+            max_concurrent_jobs: None,
+            prevent_sleep: true,
+            job_history_days: Some(90),
+            job_history_max_gb: None,
+            temp_max_gb: Some(20),
+            result_cache_max_gb: Some(5),
+            watch_folders: Vec::new(),
+            scheduled_runs: Vec::new(),
+            notifications: NotificationSettings::default(),
+            close_to_tray: false,
+            shortcuts: ShortcutSettings::default(),
+            data_dir: None,
+            log_level: None,
+            startup_timeout_secs: 120,
+            update_channel: UpdateChannel::default(),
+            ncbi_api_key: None,
+        }
+    }
+}
+
+/// A shared bio-engine, e.g. on a core facility's compute host.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RemoteEngine {
+    /// Base URL, e.g. `https://compute.example.org:8000`.
+    pub url: String,
+    /// Bearer token the server was started with.
+    pub token: String,
+    /// Reach the engine through an SSH port forward; `url` is then resolved
+    /// on the SSH server.
+    pub ssh: Option<SshSettings>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SshSettings {
+    /// SSH server as `host` or `user@host`; `~/.ssh/config` aliases work too.
+    pub host: String,
+    pub port: Option<u16>,
+    /// Private key to use; the SSH agent and default keys are tried when unset.
+    pub identity_file: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct WatchFolder {
+    pub path: PathBuf,
+    /// The `/create-job` body to analyse each new file with (reference,
+    /// config, ...); `patients` is filled in with the file.
+    pub preset: Value,
+    #[serde(default)]
+    pub recursive: bool,
+    /// Extensions of the files to pick up.
+    #[serde(default = "default_watch_extensions")]
+    pub extensions: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub completed: bool,
+    pub failed: bool,
+    /// Stay quiet while the main window has focus.
+    pub only_when_unfocused: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            completed: true,
+            failed: true,
+            only_when_unfocused: true,
+        }
+    }
+}
+
+/// Keys such as `CmdOrCtrl+Shift+P`; unset disables the shortcut.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ShortcutSettings {
+    /// Brings PS Analyzer to the front.
+    pub show_window: Option<String>,
+    /// Opens the sequence on the clipboard for analysis.
+    pub analyze_clipboard: Option<String>,
+}
+
+impl Default for ShortcutSettings {
+    fn default() -> Self {
+        Self {
+            show_window: Some("CmdOrCtrl+Shift+Alt+P".to_string()),
+            analyze_clipboard: Some("CmdOrCtrl+Shift+Alt+V".to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ScheduledRun {
+    /// Unique; also names the jobs it queues.
+    pub name: String,
+    pub folder: PathBuf,
+    /// The `/create-job` body to analyse the folder with; `patients` is
+    /// filled in with its files, forward and reverse reads paired.
+    pub preset: Value,
+    #[serde(default)]
+    pub recursive: bool,
+    #[serde(default = "default_watch_extensions")]
+    pub extensions: Vec<String>,
+    /// Local time of day, `HH:MM`.
+    pub at: String,
+    /// Weekdays to run on, e.g. `["mon", "thu"]`; every day when empty.
+    #[serde(default)]
+    pub days: Vec<String>,
+}
+
+fn default_watch_extensions() -> Vec<String> {
+    vec!["ab1".to_string()]
+}
+
+impl Settings {
+    /// Checks the settings about to replace `previous`. Folders are only
+    /// required to exist when they are new or changed, so a share that is
+    /// offline does not block saving anything else; the watcher reports it.
+    fn validate(&self, previous: &Settings) -> Result<(), AppError> {
+        if self.engine_port == Some(0) {
+            return Err(AppError::InvalidSettings("engine_port must not be 0".into()));
+        }
+        if self.worker_count == Some(0) {
+            return Err(AppError::InvalidSettings("worker_count must be at least 1".into()));
+        }
+        if self.engine_memory_limit_gb == Some(0) {
+            return Err(AppError::InvalidSettings(
+                "engine_memory_limit_gb must be at least 1; leave it unset for no limit".into(),
+            ));
+        }
+        if self.max_concurrent_jobs == Some(0) {
+            return Err(AppError::InvalidSettings(
+                "max_concurrent_jobs must be at least 1".into(),
+            ));
+        }
+        if self.temp_max_gb == Some(0) || self.result_cache_max_gb == Some(0) {
+            return Err(AppError::InvalidSettings(
+                "temp_max_gb and result_cache_max_gb must be at least 1; leave them unset for no limit".into(),
+            ));
+        }
+        if self.job_history_days == Some(0) || self.job_history_max_gb == Some(0) {
+            return Err(AppError::InvalidSettings(
+                "job history limits must be at least 1; leave them unset to keep everything".into(),
+            ));
+        }
+        let mut shortcuts = Vec::new();
+        for (action, keys) in crate::shortcuts::configured(&self.shortcuts) {
+            let shortcut = crate::shortcuts::parse(keys)
+                .map_err(|e| AppError::InvalidSettings(format!("shortcut {:?}: {}", keys, e)))?;
+            if let Some((other, _)) = shortcuts.iter().find(|(_, existing)| *existing == shortcut) {
+                return Err(AppError::InvalidSettings(format!(
+                    "shortcut {:?} is set for both {:?} and {:?}",
+                    keys, other, action
+                )));
+            }
+            shortcuts.push((action, shortcut));
+        }
+        if self.startup_timeout_secs == 0 {
+            return Err(AppError::InvalidSettings(
+                "startup_timeout_secs must be at least 1".into(),
+            ));
+        }
+        if let Some(level) = &self.log_level {
+            EnvFilter::try_new(level).map_err(|e| {
+                AppError::InvalidSettings(format!("invalid log_level {:?}: {}", level, e))
+            })?;
+        }
+        if let Some(remote) = &self.remote_engine {
+            if !remote.url.starts_with("http://") && !remote.url.starts_with("https://") {
+                return Err(AppError::InvalidSettings(format!(
+                    "remote_engine.url {:?} must be an http:// or https:// URL",
+                    remote.url
+                )));
+            }
+            if remote.token.is_empty() || !remote.token.bytes().all(|byte| byte.is_ascii_graphic()) {
+                return Err(AppError::InvalidSettings(
+                    "remote_engine.token must be non-empty printable ASCII".into(),
+                ));
+            }
+            if let Some(ssh) = &remote.ssh {
+                if ssh.host.is_empty() || ssh.host.starts_with('-') {
+                    return Err(AppError::InvalidSettings(format!(
+                        "remote_engine.ssh.host {:?} is not a host",
+                        ssh.host
+                    )));
+                }
+                // The certificate would not match the tunnel's local address.
+                if !remote.url.starts_with("http://") {
+                    return Err(AppError::InvalidSettings(
+                        "remote_engine.url must be http:// when tunnelling over SSH".into(),
+                    ));
+                }
+                if let Some(identity) = &ssh.identity_file {
+                    if !identity.is_file() {
+                        return Err(AppError::InvalidSettings(format!(
+                            "remote_engine.ssh.identity_file {:?} does not exist",
+                            identity
+                        )));
+                    }
+                }
+            }
+        }
+        for folder in &self.watch_folders {
+            let unchanged = previous
+                .watch_folders
+                .iter()
+                .any(|existing| existing.path == folder.path);
+            if !unchanged && !folder.path.is_dir() {
+                return Err(AppError::InvalidSettings(format!(
+                    "watch folder {:?} does not exist",
+                    folder.path
+                )));
+            }
+            if !folder.preset.is_object() {
+                return Err(AppError::InvalidSettings(format!(
+                    "the preset of watch folder {:?} must be a JSON object",
+                    folder.path
+                )));
+            }
+            if folder.extensions.is_empty() {
+                return Err(AppError::InvalidSettings(format!(
+                    "watch folder {:?} needs at least one extension",
+                    folder.path
+                )));
+            }
+        }
+        let mut names = std::collections::HashSet::new();
+        for run in &self.scheduled_runs {
+            if run.name.is_empty() || !names.insert(run.name.as_str()) {
+                return Err(AppError::InvalidSettings(format!(
+                    "scheduled run name {:?} must be non-empty and unique",
+                    run.name
+                )));
+            }
+            let unchanged = previous
+                .scheduled_runs
+                .iter()
+                .any(|existing| existing.name == run.name && existing.folder == run.folder);
+            if !unchanged && !run.folder.is_dir() {
+                return Err(AppError::InvalidSettings(format!(
+                    "the folder of scheduled run {:?} does not exist",
+                    run.name
+                )));
+            }
+            if !run.preset.is_object() || run.extensions.is_empty() {
+                return Err(AppError::InvalidSettings(format!(
+                    "scheduled run {:?} needs a JSON object preset and at least one extension",
+                    run.name
+                )));
+            }
+            if crate::schedule::parse_time(&run.at).is_none() {
+                return Err(AppError::InvalidSettings(format!(
+                    "scheduled run {:?}: {:?} is not a time of day (HH:MM)",
+                    run.name, run.at
+                )));
+            }
+            if crate::schedule::parse_days(&run.days).is_none() {
+                return Err(AppError::InvalidSettings(format!(
+                    "scheduled run {:?}: {:?} contains something other than weekdays",
+                    run.name, run.days
+                )));
+            }
+        }
+        if let Some(path) = &self.data_dir {
+            if !path.is_absolute() {
+                return Err(AppError::InvalidSettings(format!(
+                    "data_dir {:?} must be an absolute path",
+                    path
+                )));
+            }
+        }
+        if let Some(path) = &self.tracy_path {
+            if !path.is_file() {
+                return Err(AppError::InvalidSettings(format!(
+                    "tracy_path {:?} does not exist",
+                    path
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The loaded settings plus where they are saved.
+pub struct SettingsStore {
+    path: Option<PathBuf>,
+    settings: Mutex<Settings>,
+}
+
+impl SettingsStore {
+    /// Reads the settings file, falling back to defaults when there is none.
+    /// An unreadable file is reported but never overwritten until the user saves.
+    /// Runs before logging is set up (it decides the log level), so anything
+    /// worth logging is returned instead.
+    pub fn load(app_handle: &AppHandle) -> (Self, Vec<String>) {
+        let config_dir = crate::portable::app_config_dir(app_handle).ok();
+        let path = config_dir.as_ref().map(|dir| dir.join(SETTINGS_FILE));
+
+        let mut notes = Vec::new();
+        let settings = match &path {
+            Some(path) if path.exists() => match read(path, &mut notes) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    notes.push(format!("Using default settings: {}", e));
+                    Settings::default()
+                }
+            },
+            _ => legacy_defaults(config_dir.as_deref()),
+        };
+
+        let store = Self {
+            path,
+            settings: Mutex::new(settings),
+        };
+        (store, notes)
+    }
+
+    pub fn get(&self) -> Settings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    /// Validates and persists `settings`, replacing the current ones.
+    pub fn set(&self, settings: Settings) -> Result<(), AppError> {
+        settings.validate(&self.get())?;
+        if let Some(path) = &self.path {
+            write(path, &settings)?;
+        }
+        *self.settings.lock().unwrap() = settings;
+        Ok(())
+    }
+
+    /// Applies `change` to a copy of the current settings and saves the result.
+    pub fn update(&self, change: impl FnOnce(&mut Settings)) -> Result<(), AppError> {
+        let mut settings = self.get();
+        change(&mut settings);
+        self.set(settings)
+    }
+}
+
+fn read(path: &Path, notes: &mut Vec<String>) -> Result<Settings, AppError> {
+    let invalid = |e: serde_json::Error| {
+        AppError::InvalidSettings(format!("{:?} is not valid: {}", path, e))
+    };
+    let contents = std::fs::read_to_string(path)?;
+    let Value::Object(mut object) = serde_json::from_str(&contents).map_err(invalid)? else {
+        return Err(AppError::InvalidSettings(format!("{:?} is not a JSON object", path)));
+    };
+
+    let version = object
+        .remove("schema_version")
+        .and_then(|version| version.as_u64())
+        .map_or(1, |version| version as u32);
+
+    // Keep the original around: a migration may get something wrong, and saving
+    // a newer file from this build drops the keys it does not know.
+    let backup = path.with_extension(format!("v{}.json", version));
+    if version != SCHEMA_VERSION && !backup.exists() {
+        std::fs::copy(path, &backup)?;
+    }
+
+    if version > SCHEMA_VERSION {
+        notes.push(format!(
+            "Settings were written by a newer version (schema {}, this build knows {}); unknown keys are ignored, original kept at {:?}",
+            version, SCHEMA_VERSION, backup
+        ));
+    }
+
+    for migration in MIGRATIONS.iter().skip(version.saturating_sub(1) as usize) {
+        migration(&mut object);
+    }
+    let settings = serde_json::from_value(Value::Object(object)).map_err(invalid)?;
+
+    if version < SCHEMA_VERSION {
+        write(path, &settings)?;
+        notes.push(format!(
+            "Migrated settings from schema {} to {}, original kept at {:?}",
+            version, SCHEMA_VERSION, backup
+        ));
+    }
+    Ok(settings)
+}
+
+/// Writes to a temporary file first so a crash mid-write cannot truncate the settings.
+fn write(path: &Path, settings: &Settings) -> Result<(), AppError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut value = serde_json::to_value(settings).unwrap_or_default();
+    if let Value::Object(object) = &mut value {
+        object.insert("schema_version".to_string(), SCHEMA_VERSION.into());
+    }
+    let staging = path.with_extension("json.tmp");
+    let contents = serde_json::to_string_pretty(&value).unwrap_or_default();
+    std::fs::write(&staging, contents)?;
+    std::fs::rename(&staging, path)?;
+    Ok(())
+}
+
+/// Defaults for a first launch, keeping the update channel picked before
+/// settings existed.
+fn legacy_defaults(config_dir: Option<&Path>) -> Settings {
+    #[derive(Deserialize)]
+    struct LegacyUpdater {
+        channel: UpdateChannel,
+    }
+
+    let mut settings = Settings::default();
+    if let Some(legacy) = config_dir
+        .and_then(|dir| std::fs::read_to_string(dir.join(LEGACY_UPDATER_FILE)).ok())
+        .and_then(|contents| serde_json::from_str::<LegacyUpdater>(&contents).ok())
+    {
+        settings.update_channel = legacy.channel;
+    }
+    settings
+}
+
+#[tauri::command]
+pub fn get_settings(store: tauri::State<SettingsStore>) -> Settings {
+    store.get()
+}
+
+#[tauri::command]
+pub fn set_settings(app_handle: AppHandle, settings: Settings) -> Result<(), AppError> {
+    app_handle.state::<SettingsStore>().set(settings.clone())?;
+    info!("Settings saved");
+    crate::shortcuts::register_all(&app_handle);
+    let _ = app_handle.emit(SETTINGS_CHANGED_EVENT, settings);
+    Ok(())
+}
@@ -0,0 +1,141 @@
+//! System-wide keyboard shortcuts from the `shortcuts` setting, working while
+//! another application has focus:
+//! - `show_window` brings PS Analyzer to the front;
+//! - `analyze_clipboard` takes the sequence on the clipboard and opens it for
+//!   analysis.
+//!
+//! Two actions on the same keys are refused when the settings are saved; keys
+//! another application already holds are reported with
+//! [`SHORTCUT_CONFLICT_EVENT`] and left to that application.
+
+use crate::instance;
+use crate::settings::{SettingsStore, ShortcutSettings};
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+use tracing::{info, warn};
+
+/// Emitted with a [`ShortcutStatus`] for each shortcut that could not be
+/// registered.
+pub const SHORTCUT_CONFLICT_EVENT: &str = "shortcut-conflict";
+
+/// Emitted with the clipboard text when `analyze_clipboard` is pressed.
+pub const ANALYZE_SEQUENCE_EVENT: &str = "analyze-sequence";
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    ShowWindow,
+    AnalyzeClipboard,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ShortcutStatus {
+    pub action: ShortcutAction,
+    pub keys: String,
+    pub registered: bool,
+    /// Why it is not registered.
+    pub error: Option<String>,
+}
+
+/// Managed state: what is registered for which action.
+#[derive(Default)]
+pub struct Shortcuts {
+    registered: Mutex<Vec<(Shortcut, ShortcutAction)>>,
+    status: Mutex<Vec<ShortcutStatus>>,
+}
+
+/// The configured actions with their keys, disabled ones left out.
+pub fn configured(settings: &ShortcutSettings) -> Vec<(ShortcutAction, &str)> {
+    [
+        (ShortcutAction::ShowWindow, settings.show_window.as_deref()),
+        (ShortcutAction::AnalyzeClipboard, settings.analyze_clipboard.as_deref()),
+    ]
+    .into_iter()
+    .filter_map(|(action, keys)| keys.map(|keys| (action, keys)))
+    .collect()
+}
+
+/// Parses keys such as `CmdOrCtrl+Shift+P`.
+pub fn parse(keys: &str) -> Result<Shortcut, String> {
+    keys.parse::<Shortcut>().map_err(|e| e.to_string())
+}
+
+/// Registers the shortcuts in the settings, replacing whatever was registered
+/// before. Called at startup and whenever the settings are saved.
+pub fn register_all(app_handle: &AppHandle) {
+    let Some(shortcuts) = app_handle.try_state::<Shortcuts>() else {
+        return;
+    };
+    let global = app_handle.global_shortcut();
+    let mut registered = shortcuts.registered.lock().unwrap();
+    for (shortcut, _) in registered.drain(..) {
+        let _ = global.unregister(shortcut);
+    }
+
+    let settings = app_handle.state::<SettingsStore>().get().shortcuts;
+    let mut status = Vec::new();
+    for (action, keys) in configured(&settings) {
+        let result = parse(keys).and_then(|shortcut| {
+            global.register(shortcut).map_err(|e| e.to_string())?;
+            Ok(shortcut)
+        });
+        let entry = ShortcutStatus {
+            action,
+            keys: keys.to_string(),
+            registered: result.is_ok(),
+            error: result.as_ref().err().cloned(),
+        };
+        match result {
+            Ok(shortcut) => {
+                info!("Registered global shortcut {} for {:?}", keys, action);
+                registered.push((shortcut, action));
+            }
+            Err(e) => {
+                warn!("Could not register global shortcut {} for {:?}: {}", keys, action, e);
+                let _ = app_handle.emit(SHORTCUT_CONFLICT_EVENT, &entry);
+            }
+        }
+        status.push(entry);
+    }
+    *shortcuts.status.lock().unwrap() = status;
+}
+
+/// Handler of the global-shortcut plugin.
+pub fn on_shortcut(app_handle: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let Some(shortcuts) = app_handle.try_state::<Shortcuts>() else {
+        return;
+    };
+    let action = shortcuts
+        .registered
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(registered, _)| registered.id() == shortcut.id())
+        .map(|(_, action)| *action);
+    let Some(action) = action else {
+        return;
+    };
+    match action {
+        ShortcutAction::ShowWindow => instance::focus_main_window(app_handle),
+        ShortcutAction::AnalyzeClipboard => match app_handle.clipboard().read_text() {
+            Ok(text) if !text.trim().is_empty() => {
+                instance::focus_main_window(app_handle);
+                let _ = app_handle.emit(ANALYZE_SEQUENCE_EVENT, text);
+            }
+            Ok(_) => info!("Clipboard is empty; nothing to analyse"),
+            Err(e) => warn!("Could not read the clipboard: {}", e),
+        },
+    }
+}
+
+/// Each configured shortcut and whether it could be registered.
+#[tauri::command]
+pub fn get_shortcut_status(shortcuts: tauri::State<Shortcuts>) -> Vec<ShortcutStatus> {
+    shortcuts.status.lock().unwrap().clone()
+}
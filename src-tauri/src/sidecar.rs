@@ -0,0 +1,407 @@
+//! Spawns sidecar binaries in their own process group (Unix) or Job Object
+//! (Windows), so killing a sidecar also takes down everything it started
+//! (e.g. the tracy processes launched by the bio-engine).
+//!
+//! The shell plugin is still used to resolve the sidecar path and build the
+//! command, but its `spawn` gives no hook to set up the group, so spawning and
+//! pipe handling live here.
+//!
+//! A sidecar can be started at a lower CPU priority, which the processes it
+//! starts inherit, so background analyses leave the machine usable.
+
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::{ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+use tauri::async_runtime::{channel, Receiver, Sender};
+use tracing::warn;
+
+/// CPU priority a sidecar runs at.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessPriority {
+    #[default]
+    Normal,
+    /// `nice` 10, or the below-normal priority class on Windows.
+    BelowNormal,
+    /// `nice` 19, or the idle priority class on Windows: runs only when
+    /// nothing else wants the CPU.
+    Low,
+}
+
+/// Every process `pid` spawned, directly or not, as far as `system` has
+/// refreshed them.
+pub fn descendants(system: &System, pid: u32) -> Vec<Pid> {
+    let mut tree = vec![Pid::from_u32(pid)];
+    let mut index = 0;
+    while index < tree.len() {
+        let parent = tree[index];
+        index += 1;
+        for (pid, process) in system.processes() {
+            if process.parent() == Some(parent) {
+                tree.push(*pid);
+            }
+        }
+    }
+    tree.remove(0);
+    tree
+}
+
+/// Output and lifecycle events of a spawned sidecar.
+#[derive(Debug)]
+pub enum SidecarEvent {
+    Stdout(Vec<u8>),
+    Stderr(Vec<u8>),
+    Terminated {
+        code: Option<i32>,
+        signal: Option<i32>,
+    },
+}
+
+/// Handle to a running sidecar and its descendants.
+pub struct SidecarChild {
+    pid: u32,
+    // Held open so the sidecar never sees EOF on stdin while it runs.
+    stdin: Mutex<Option<ChildStdin>>,
+    exited: Arc<AtomicBool>,
+    #[cfg(windows)]
+    job: windows::Job,
+}
+
+impl SidecarChild {
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    pub fn has_exited(&self) -> bool {
+        self.exited.load(Ordering::SeqCst)
+    }
+
+    /// Writes `bytes` to the sidecar's stdin in one go.
+    pub fn write_stdin(&self, bytes: &[u8]) -> io::Result<()> {
+        let mut stdin = self.stdin.lock().unwrap();
+        let stdin = stdin
+            .as_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "stdin is closed"))?;
+        stdin.write_all(bytes)?;
+        stdin.flush()
+    }
+
+    /// Blocks until the sidecar exits or `timeout` elapses; returns whether it exited.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while !self.has_exited() {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        true
+    }
+
+    /// Asks the whole process group to terminate (SIGTERM), letting it clean up.
+    #[cfg(unix)]
+    pub fn terminate(&self) -> io::Result<()> {
+        self.signal_group(libc::SIGTERM)
+    }
+
+    /// Freezes the whole process group (SIGSTOP). Its memory stays allocated.
+    #[cfg(unix)]
+    pub fn suspend(&self) -> io::Result<()> {
+        self.signal_group(libc::SIGSTOP)
+    }
+
+    /// Lets a suspended process group run again (SIGCONT).
+    #[cfg(unix)]
+    pub fn resume(&self) -> io::Result<()> {
+        self.signal_group(libc::SIGCONT)
+    }
+
+    #[cfg(unix)]
+    fn signal_group(&self, signal: libc::c_int) -> io::Result<()> {
+        let result = unsafe { libc::killpg(self.pid as libc::pid_t, signal) };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Kills the processes the sidecar spawned, directly or not, for which
+    /// `select` holds, leaving the sidecar itself running. Returns how many
+    /// were killed.
+    pub fn kill_descendants(&self, select: impl Fn(&sysinfo::Process) -> bool) -> usize {
+        let mut system = System::new();
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing()
+                .with_cmd(UpdateKind::OnlyIfNotSet)
+                .with_cwd(UpdateKind::OnlyIfNotSet),
+        );
+
+        descendants(&system, self.pid)
+            .into_iter()
+            .filter_map(|pid| system.process(pid))
+            .filter(|process| select(process) && process.kill())
+            .count()
+    }
+
+    /// Kills the sidecar together with every process it spawned.
+    pub fn kill_tree(self) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            // The sidecar is the leader of its own group, so its pid is the pgid.
+            let result = unsafe { libc::killpg(self.pid as libc::pid_t, libc::SIGKILL) };
+            if result != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        #[cfg(windows)]
+        {
+            self.job.terminate()
+        }
+    }
+}
+
+/// Spawns `command` in a fresh process group / Job Object at `priority` and
+/// streams its output line by line.
+pub fn spawn(
+    command: tauri_plugin_shell::process::Command,
+    priority: ProcessPriority,
+) -> io::Result<(Receiver<SidecarEvent>, SidecarChild)> {
+    let mut command: Command = command.into();
+    command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    let mut child = command.spawn()?;
+
+    #[cfg(windows)]
+    let job = {
+        let job = windows::Job::new()?;
+        job.assign(&child)?;
+        job
+    };
+
+    // Not fatal: the sidecar still works, only at normal priority.
+    if let Err(e) = set_priority(&child, priority) {
+        warn!("Could not lower the priority of pid {}: {}", child.id(), e);
+    }
+
+    let (tx, rx) = channel(64);
+
+    if let Some(stdout) = child.stdout.take() {
+        spawn_pipe_reader(stdout, tx.clone(), SidecarEvent::Stdout);
+    }
+    if let Some(stderr) = child.stderr.take() {
+        spawn_pipe_reader(stderr, tx.clone(), SidecarEvent::Stderr);
+    }
+
+    let exited = Arc::new(AtomicBool::new(false));
+    let sidecar = SidecarChild {
+        pid: child.id(),
+        stdin: Mutex::new(child.stdin.take()),
+        exited: exited.clone(),
+        #[cfg(windows)]
+        job,
+    };
+
+    std::thread::spawn(move || {
+        // Sent as soon as the sidecar itself exits, without waiting for pipe EOF:
+        // a surviving grandchild may keep the pipes open indefinitely.
+        let status = child.wait();
+        exited.store(true, Ordering::SeqCst);
+        let event = match status {
+            Ok(status) => SidecarEvent::Terminated {
+                code: status.code(),
+                #[cfg(unix)]
+                signal: std::os::unix::process::ExitStatusExt::signal(&status),
+                #[cfg(windows)]
+                signal: None,
+            },
+            Err(_) => SidecarEvent::Terminated {
+                code: None,
+                signal: None,
+            },
+        };
+        let _ = tx.blocking_send(event);
+    });
+
+    Ok((rx, sidecar))
+}
+
+/// What a sidecar run to completion wrote, and how it exited.
+#[derive(Debug, Default)]
+pub struct SidecarOutput {
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+impl SidecarOutput {
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    /// The last lines of stderr, for error messages.
+    pub fn stderr_tail(&self, lines: usize) -> String {
+        let stderr = String::from_utf8_lossy(&self.stderr);
+        let mut tail: Vec<&str> = stderr.lines().rev().take(lines).collect();
+        tail.reverse();
+        tail.join("\n")
+    }
+}
+
+/// Waits until a sidecar from [`spawn`] has exited and closed its pipes,
+/// collecting everything it wrote. For short-lived tools that start nothing
+/// of their own.
+pub async fn collect(mut rx: Receiver<SidecarEvent>) -> SidecarOutput {
+    let mut output = SidecarOutput::default();
+    while let Some(event) = rx.recv().await {
+        match event {
+            SidecarEvent::Stdout(line) => output.stdout.extend(line),
+            SidecarEvent::Stderr(line) => output.stderr.extend(line),
+            SidecarEvent::Terminated { code, signal } => {
+                output.code = code;
+                output.signal = signal;
+            }
+        }
+    }
+    output
+}
+
+#[cfg(unix)]
+fn set_priority(child: &std::process::Child, priority: ProcessPriority) -> io::Result<()> {
+    let nice = match priority {
+        ProcessPriority::Normal => return Ok(()),
+        ProcessPriority::BelowNormal => 10,
+        ProcessPriority::Low => 19,
+    };
+    let result = unsafe { libc::setpriority(libc::PRIO_PROCESS, child.id() as libc::id_t, nice) };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn set_priority(child: &std::process::Child, priority: ProcessPriority) -> io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::System::Threading::{
+        SetPriorityClass, BELOW_NORMAL_PRIORITY_CLASS, IDLE_PRIORITY_CLASS,
+    };
+    let class = match priority {
+        ProcessPriority::Normal => return Ok(()),
+        ProcessPriority::BelowNormal => BELOW_NORMAL_PRIORITY_CLASS,
+        ProcessPriority::Low => IDLE_PRIORITY_CLASS,
+    };
+    let ok = unsafe { SetPriorityClass(child.as_raw_handle() as _, class) };
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn spawn_pipe_reader<R: Read + Send + 'static>(
+    pipe: R,
+    tx: Sender<SidecarEvent>,
+    wrap: fn(Vec<u8>) -> SidecarEvent,
+) {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if tx.blocking_send(wrap(line.clone())).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::io;
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, TerminateJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    /// Job Object that kills its processes when terminated or when the last
+    /// handle closes, which also covers the app itself crashing.
+    pub struct Job(HANDLE);
+
+    // The handle is only ever used through thread-safe Win32 calls.
+    unsafe impl Send for Job {}
+    unsafe impl Sync for Job {}
+
+    impl Job {
+        pub fn new() -> io::Result<Self> {
+            let handle = unsafe { CreateJobObjectW(std::ptr::null(), std::ptr::null()) };
+            if handle.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            let job = Job(handle);
+
+            let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { std::mem::zeroed() };
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            let ok = unsafe {
+                SetInformationJobObject(
+                    job.0,
+                    JobObjectExtendedLimitInformation,
+                    &info as *const _ as *const std::ffi::c_void,
+                    std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+                )
+            };
+            if ok == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(job)
+        }
+
+        pub fn assign(&self, child: &std::process::Child) -> io::Result<()> {
+            let ok = unsafe { AssignProcessToJobObject(self.0, child.as_raw_handle() as HANDLE) };
+            if ok == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        pub fn terminate(&self) -> io::Result<()> {
+            let ok = unsafe { TerminateJobObject(self.0, 1) };
+            if ok == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! Updates the bio-engine and tracy binaries on their own, without a new app
+//! build. Downloaded binaries live in the app data dir and take precedence over
+//! the bundled ones; they are only swapped in after their SHA-256 and minisign
+//! signature check out.
+
+use crate::engine::{self, target_triple};
+use crate::error::AppError;
+use base64::Engine as _;
+use minisign_verify::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_http::reqwest;
+use tracing::{info, warn};
+
+/// Emitted with the names of the binaries that were replaced.
+pub const SIDECARS_UPDATED_EVENT: &str = "sidecars-updated";
+
+/// Published with every bio-engine release.
+const MANIFEST_URL: &str =
+    "https://github.com/lagosproject/bio-engine/releases/latest/download/sidecars.json";
+
+/// Binaries that may be replaced, by manifest name.
+const UPDATABLE: [&str; 2] = ["bio-engine", "tracy"];
+
+const SIDECAR_DIR: &str = "sidecars";
+const INSTALLED_FILE: &str = "installed.json";
+
+/// Remote manifest: per target triple, the artifact of every updatable binary.
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    version: String,
+    platforms: BTreeMap<String, BTreeMap<String, Artifact>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Artifact {
+    pub url: String,
+    pub sha256: String,
+    /// Base64 minisign signature, as produced by `tauri signer sign`.
+    pub signature: String,
+}
+
+/// Versions of the binaries installed in a folder, by name: updates over the
+/// bundled sidecars, or tools downloaded on demand.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Installed(pub BTreeMap<String, String>);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SidecarUpdate {
+    pub name: String,
+    pub version: String,
+    pub installed_version: Option<String>,
+}
+
+fn sidecar_dir(app_handle: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(crate::portable::app_data_dir(app_handle)?.join(SIDECAR_DIR))
+}
+
+pub fn binary_file_name(name: &str) -> String {
+    format!("ps-analyzer-{}{}", name, std::env::consts::EXE_SUFFIX)
+}
+
+pub fn load_installed(dir: &Path) -> Installed {
+    std::fs::read_to_string(dir.join(INSTALLED_FILE))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Path of the downloaded `name` binary (e.g. `bio-engine`), if one is installed.
+pub fn override_path(app_handle: &AppHandle, name: &str) -> Option<PathBuf> {
+    let path = sidecar_dir(app_handle).ok()?.join(binary_file_name(name));
+    path.is_file().then_some(path)
+}
+
+async fn fetch_manifest() -> Result<Manifest, AppError> {
+    let body = reqwest::get(MANIFEST_URL).await?.error_for_status()?.bytes().await?;
+    serde_json::from_slice(&body)
+        .map_err(|e| AppError::SidecarUpdate(format!("invalid sidecar manifest: {}", e)))
+}
+
+/// Binaries in `manifest` that differ from what is installed here.
+fn pending(manifest: &Manifest, installed: &Installed) -> Vec<(String, Artifact)> {
+    let Some(artifacts) = manifest.platforms.get(target_triple()) else {
+        return Vec::new();
+    };
+    artifacts
+        .iter()
+        .filter(|(name, _)| UPDATABLE.contains(&name.as_str()))
+        .filter(|(name, _)| installed.0.get(*name) != Some(&manifest.version))
+        .map(|(name, artifact)| (name.clone(), artifact.clone()))
+        .collect()
+}
+
+/// Same key as the app updater, so releases need a single signing key.
+pub fn public_key(app_handle: &AppHandle) -> Result<PublicKey, AppError> {
+    let encoded = crate::updater::signing_key(app_handle)?;
+    let decoded = decode_base64(&encoded)?;
+    PublicKey::decode(&decoded)
+        .map_err(|e| AppError::SidecarUpdate(format!("invalid update signing key: {}", e)))
+}
+
+fn decode_base64(value: &str) -> Result<String, AppError> {
+    base64::engine::general_purpose::STANDARD
+        .decode(value)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or_else(|| AppError::SidecarUpdate("malformed base64 in signature data".into()))
+}
+
+pub fn verify(name: &str, bytes: &[u8], artifact: &Artifact, key: &PublicKey) -> Result<(), AppError> {
+    let digest = format!("{:x}", Sha256::digest(bytes));
+    if !digest.eq_ignore_ascii_case(&artifact.sha256) {
+        return Err(AppError::SidecarUpdate(format!(
+            "{} checksum mismatch: expected {}, got {}",
+            name, artifact.sha256, digest
+        )));
+    }
+    let signature = Signature::decode(&decode_base64(&artifact.signature)?)
+        .map_err(|e| AppError::SidecarUpdate(format!("invalid {} signature: {}", name, e)))?;
+    key.verify(bytes, &signature, false)
+        .map_err(|e| AppError::SidecarUpdate(format!("{} signature rejected: {}", name, e)))
+}
+
+/// Writes `bytes` next to the destination and renames it into place, so the
+/// engine never sees a half-written binary.
+pub fn replace_atomically(destination: &Path, bytes: &[u8]) -> Result<(), AppError> {
+    let staging = destination.with_extension("download");
+    {
+        let mut file = std::fs::File::create(&staging)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staging, std::fs::Permissions::from_mode(0o755))?;
+    }
+    std::fs::rename(&staging, destination)?;
+    Ok(())
+}
+
+pub fn save_installed(dir: &Path, installed: &Installed) -> Result<(), AppError> {
+    let contents = serde_json::to_string_pretty(installed).unwrap_or_default();
+    std::fs::write(dir.join(INSTALLED_FILE), contents)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn check_sidecar_updates(app_handle: AppHandle) -> Result<Vec<SidecarUpdate>, AppError> {
+    let manifest = fetch_manifest().await?;
+    let installed = load_installed(&sidecar_dir(&app_handle)?);
+    Ok(pending(&manifest, &installed)
+        .into_iter()
+        .map(|(name, _)| SidecarUpdate {
+            installed_version: installed.0.get(&name).cloned(),
+            name,
+            version: manifest.version.clone(),
+        })
+        .collect())
+}
+
+/// Downloads and verifies every pending binary, swaps them in and restarts
+/// the engine. Nothing is replaced unless all downloads verify.
+#[tauri::command]
+pub async fn install_sidecar_updates(app_handle: AppHandle) -> Result<Vec<String>, AppError> {
+    let key = public_key(&app_handle)?;
+    let manifest = fetch_manifest().await?;
+    let dir = sidecar_dir(&app_handle)?;
+    let mut installed = load_installed(&dir);
+
+    let mut verified = Vec::new();
+    for (name, artifact) in pending(&manifest, &installed) {
+        info!("Downloading {} {}", name, manifest.version);
+        let bytes = reqwest::get(&artifact.url)
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        verify(&name, &bytes, &artifact, &key)?;
+        verified.push((name, bytes));
+    }
+    if verified.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Windows refuses to replace a running executable.
+    let handle = app_handle.clone();
+    let _ = tauri::async_runtime::spawn_blocking(move || engine::kill(&handle)).await;
+
+    std::fs::create_dir_all(&dir)?;
+    let mut updated = Vec::new();
+    for (name, bytes) in verified {
+        replace_atomically(&dir.join(binary_file_name(&name)), &bytes)?;
+        installed.0.insert(name.clone(), manifest.version.clone());
+        updated.push(name);
+    }
+    save_installed(&dir, &installed)?;
+    info!("Installed sidecar updates {:?} ({})", updated, manifest.version);
+
+    if let Err(e) = engine::restart(&app_handle) {
+        warn!("Failed to restart bio-engine after sidecar update: {}", e);
+        return Err(e);
+    }
+    let _ = app_handle.emit(SIDECARS_UPDATED_EVENT, &updated);
+    Ok(updated)
+}
@@ -0,0 +1,365 @@
+//! One place that knows every sidecar the app ships and keeps track of the
+//! ones running. Each tool is registered with how it runs (a long-lived
+//! service or a task run to completion) and what to do when it crashes.
+//! Binaries are resolved the same way for all of them: an installed update
+//! first, then the bundled copy. Optional tools are not bundled but
+//! downloaded the first time they are run, see [`crate::tools`].
+//!
+//! The bio-engine keeps its own spawn and readiness logic in [`crate::engine`]
+//! but reports its process here and takes its restart policy from here; tasks
+//! such as BLAST and Primer3 are run entirely through [`run`]. Whatever is
+//! still running when the app quits is killed by [`ProcessSupervisor::shutdown`].
+
+use crate::error::AppError;
+use crate::settings::SettingsStore;
+use crate::sidecar::{self, ProcessPriority, SidecarChild, SidecarOutput};
+use crate::sidecar_update;
+use crate::tools;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_shell::process::Command;
+use tauri_plugin_shell::ShellExt;
+use tracing::{error, info, warn};
+
+/// Lines of a failed task's stderr put in the error.
+const STDERR_LINES: usize = 5;
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolKind {
+    /// Runs for the whole session, e.g. the bio-engine.
+    Service,
+    /// Started for one piece of work and run to completion.
+    Task,
+}
+
+/// Where a tool's binary comes from.
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolSource {
+    /// Shipped in `bundle.externalBin`.
+    Bundled,
+    /// Downloaded into the app data folder on first use.
+    Download,
+}
+
+/// What to do when a tool exits unexpectedly.
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RestartPolicy {
+    Never,
+    /// Restart after `base_delay`, doubling up to `max_delay`, at most
+    /// `max_attempts` times in a row.
+    OnCrash {
+        max_attempts: u32,
+        base_delay: Duration,
+        max_delay: Duration,
+    },
+}
+
+impl RestartPolicy {
+    /// How long to wait before restart `attempt` (from 1), or `None` once the
+    /// policy gives up.
+    pub fn delay(&self, attempt: u32) -> Option<Duration> {
+        match *self {
+            RestartPolicy::Never => None,
+            RestartPolicy::OnCrash {
+                max_attempts,
+                base_delay,
+                max_delay,
+            } => {
+                if attempt == 0 || attempt > max_attempts {
+                    return None;
+                }
+                let factor = 2u32.saturating_pow(attempt - 1);
+                Some(base_delay.saturating_mul(factor).min(max_delay))
+            }
+        }
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        match *self {
+            RestartPolicy::Never => 0,
+            RestartPolicy::OnCrash { max_attempts, .. } => max_attempts,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ToolSpec {
+    /// Short name, e.g. `blastn`; the binary is `ps-analyzer-<name>`.
+    pub name: &'static str,
+    pub kind: ToolKind,
+    pub source: ToolSource,
+    pub restart: RestartPolicy,
+    pub priority: ProcessPriority,
+}
+
+impl ToolSpec {
+    pub const fn task(name: &'static str) -> Self {
+        Self {
+            name,
+            kind: ToolKind::Task,
+            source: ToolSource::Bundled,
+            restart: RestartPolicy::Never,
+            priority: ProcessPriority::Normal,
+        }
+    }
+
+    /// A task whose binary is downloaded on first use.
+    pub const fn download(name: &'static str) -> Self {
+        Self {
+            source: ToolSource::Download,
+            ..Self::task(name)
+        }
+    }
+
+    /// Name of the binary as declared in `bundle.externalBin`.
+    pub fn sidecar_name(&self) -> String {
+        format!("ps-analyzer-{}", self.name)
+    }
+}
+
+/// Every sidecar in `bundle.externalBin`, and the tools downloaded on demand.
+fn builtin() -> Vec<ToolSpec> {
+    vec![
+        ToolSpec {
+            name: crate::engine::TOOL_NAME,
+            kind: ToolKind::Service,
+            source: ToolSource::Bundled,
+            restart: RestartPolicy::OnCrash {
+                max_attempts: 5,
+                base_delay: Duration::from_secs(1),
+                max_delay: Duration::from_secs(30),
+            },
+            priority: ProcessPriority::Normal,
+        },
+        // Normally started by the engine, which is passed their paths.
+        ToolSpec::task(crate::tracy::TOOL_NAME),
+        ToolSpec::task("samtools"),
+        ToolSpec::task("bgzip"),
+        ToolSpec::download("blastn"),
+        ToolSpec::download("makeblastdb"),
+        ToolSpec::download("primer3"),
+        ToolSpec::download("mafft"),
+    ]
+}
+
+struct Running {
+    tool: &'static str,
+    started: Instant,
+    /// `None` for services, whose owner keeps the handle.
+    child: Option<SidecarChild>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RunningProcess {
+    pub tool: &'static str,
+    pub pid: u32,
+    pub seconds: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ToolStatus {
+    #[serde(flatten)]
+    pub spec: ToolSpec,
+    pub running: Vec<RunningProcess>,
+}
+
+/// Managed state with the registered tools and their running processes.
+pub struct ProcessSupervisor {
+    tools: Mutex<HashMap<&'static str, ToolSpec>>,
+    /// By pid.
+    running: Mutex<HashMap<u32, Running>>,
+}
+
+impl Default for ProcessSupervisor {
+    fn default() -> Self {
+        let supervisor = Self {
+            tools: Mutex::new(HashMap::new()),
+            running: Mutex::new(HashMap::new()),
+        };
+        for spec in builtin() {
+            supervisor.register(spec);
+        }
+        supervisor
+    }
+}
+
+impl ProcessSupervisor {
+    /// Adds `spec`, replacing any tool of the same name.
+    pub fn register(&self, spec: ToolSpec) {
+        self.tools.lock().unwrap().insert(spec.name, spec);
+    }
+
+    pub fn spec(&self, name: &str) -> Option<ToolSpec> {
+        self.tools.lock().unwrap().get(name).cloned()
+    }
+
+    /// The restart policy of `name`; `Never` for unknown tools.
+    pub fn restart_policy(&self, name: &str) -> RestartPolicy {
+        self.spec(name)
+            .map_or(RestartPolicy::Never, |spec| spec.restart)
+    }
+
+    /// Records that `pid` of `tool` started. `child` is kept so the process
+    /// can be killed on shutdown; services pass `None` and stop themselves.
+    pub fn started(&self, tool: &'static str, pid: u32, child: Option<SidecarChild>) {
+        info!("Started {} (pid {})", tool, pid);
+        self.running.lock().unwrap().insert(
+            pid,
+            Running {
+                tool,
+                started: Instant::now(),
+                child,
+            },
+        );
+    }
+
+    /// Records that `pid` exited with `code`/`signal`.
+    pub fn exited(&self, pid: u32, code: Option<i32>, signal: Option<i32>) {
+        let Some(running) = self.running.lock().unwrap().remove(&pid) else {
+            return;
+        };
+        let elapsed = running.started.elapsed();
+        if code == Some(0) {
+            info!(
+                "{} (pid {}) finished after {:?}",
+                running.tool, pid, elapsed
+            );
+        } else {
+            warn!(
+                "{} (pid {}) exited with code {:?} (signal {:?}) after {:?}",
+                running.tool, pid, code, signal, elapsed
+            );
+        }
+    }
+
+    pub fn status(&self) -> Vec<ToolStatus> {
+        let running = self.running.lock().unwrap();
+        let mut tools: Vec<ToolStatus> = self
+            .tools
+            .lock()
+            .unwrap()
+            .values()
+            .map(|spec| ToolStatus {
+                spec: spec.clone(),
+                running: running
+                    .iter()
+                    .filter(|(_, process)| process.tool == spec.name)
+                    .map(|(&pid, process)| RunningProcess {
+                        tool: process.tool,
+                        pid,
+                        seconds: process.started.elapsed().as_secs(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        tools.sort_by_key(|tool| tool.spec.name);
+        tools
+    }
+
+    /// Kills every task still running. Services are stopped by their owners.
+    pub fn shutdown(&self) {
+        let tasks: Vec<(u32, Running)> = {
+            let mut running = self.running.lock().unwrap();
+            let pids: Vec<u32> = running
+                .iter()
+                .filter(|(_, process)| process.child.is_some())
+                .map(|(&pid, _)| pid)
+                .collect();
+            pids.into_iter()
+                .filter_map(|pid| running.remove(&pid).map(|process| (pid, process)))
+                .collect()
+        };
+        for (pid, process) in tasks {
+            let Some(child) = process.child else {
+                continue;
+            };
+            match child.kill_tree() {
+                Ok(()) => info!("Killed {} (pid {})", process.tool, pid),
+                Err(e) => error!("Failed to kill {} (pid {}): {}", process.tool, pid, e),
+            }
+        }
+    }
+}
+
+/// A command for the tool registered as `name`: an installed update if
+/// there is one, otherwise the bundled or downloaded binary. For tracy, a
+/// path set in the settings wins over both, as for the engine.
+pub fn command(app_handle: &AppHandle, name: &str) -> Result<Command, AppError> {
+    let supervisor = app_handle.state::<ProcessSupervisor>();
+    let spec = supervisor
+        .spec(name)
+        .ok_or_else(|| AppError::Tool(format!("{} is not a known tool", name)))?;
+    if spec.name == crate::tracy::TOOL_NAME {
+        if let Some(path) = app_handle.state::<SettingsStore>().get().tracy_path {
+            return Ok(app_handle.shell().command(path));
+        }
+    }
+    if let Some(path) = sidecar_update::override_path(app_handle, spec.name) {
+        return Ok(app_handle.shell().command(path));
+    }
+    if spec.source == ToolSource::Download {
+        let path = tools::installed_path(app_handle, spec.name)
+            .ok_or_else(|| AppError::Tool(format!("{} is not installed", spec.name)))?;
+        return Ok(app_handle.shell().command(path));
+    }
+    app_handle
+        .shell()
+        .sidecar(spec.sidecar_name())
+        .map_err(|e| AppError::Tool(format!("{} is not available: {}", spec.name, e)))
+}
+
+/// Runs the task `name` with `args` to completion, failing unless it exits
+/// cleanly. The error carries the end of its stderr.
+pub async fn run(
+    app_handle: &AppHandle,
+    name: &str,
+    args: Vec<String>,
+) -> Result<SidecarOutput, AppError> {
+    let output = run_unchecked(app_handle, name, args).await?;
+    if !output.success() {
+        return Err(AppError::Tool(format!(
+            "{} exited with {:?}: {}",
+            name,
+            output.code,
+            output.stderr_tail(STDERR_LINES)
+        )));
+    }
+    Ok(output)
+}
+
+/// Like [`run`], but leaves judging the exit status to the caller, for tools
+/// that report some failures in their output instead. A tool that is
+/// downloaded on demand is installed first if it is not yet.
+pub async fn run_unchecked(
+    app_handle: &AppHandle,
+    name: &str,
+    args: Vec<String>,
+) -> Result<SidecarOutput, AppError> {
+    let supervisor = app_handle.state::<ProcessSupervisor>();
+    let spec = supervisor
+        .spec(name)
+        .ok_or_else(|| AppError::Tool(format!("{} is not a known tool", name)))?;
+    if spec.source == ToolSource::Download {
+        tools::ensure(app_handle, spec.name).await?;
+    }
+    let command = command(app_handle, name)?.args(args);
+    let (rx, child) = sidecar::spawn(command, spec.priority)
+        .map_err(|e| AppError::Tool(format!("{} could not be started: {}", name, e)))?;
+    let pid = child.pid();
+    supervisor.started(spec.name, pid, Some(child));
+    let output = sidecar::collect(rx).await;
+    supervisor.exited(pid, output.code, output.signal);
+    Ok(output)
+}
+
+/// Every registered tool and what of it is running.
+#[tauri::command]
+pub fn list_sidecars(supervisor: tauri::State<ProcessSupervisor>) -> Vec<ToolStatus> {
+    supervisor.status()
+}
@@ -0,0 +1,208 @@
+//! Support bundle: a zip with everything needed to diagnose a bug report
+//! (logs, engine output, resolved binaries, OS details), written wherever the
+//! user chooses.
+
+use crate::engine::{self, EngineManager};
+use crate::engine_log::{EngineLogBuffer, LogStream};
+use crate::error::AppError;
+use crate::settings::{Settings, SettingsStore};
+use crate::sidecar_update;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use sysinfo::System;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+use tracing::{info, warn};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+/// Substrings marking an environment variable whose value must not leave the machine.
+const SECRET_MARKERS: [&str; 5] = ["KEY", "TOKEN", "SECRET", "PASSWORD", "CREDENTIAL"];
+
+#[derive(Serialize)]
+struct BinaryInfo {
+    name: String,
+    path: PathBuf,
+    size: Option<u64>,
+    sha256: Option<String>,
+}
+
+#[derive(Serialize)]
+struct OsInfo {
+    os: &'static str,
+    arch: &'static str,
+    name: Option<String>,
+    long_version: Option<String>,
+    kernel_version: Option<String>,
+    total_memory_bytes: u64,
+}
+
+#[derive(Serialize)]
+struct Environment {
+    app_version: String,
+    created_at: u64,
+    os: OsInfo,
+    engine: engine::EngineStatus,
+    engine_version: Option<String>,
+    tracy_path: Option<String>,
+    binaries: Vec<BinaryInfo>,
+    engine_env: Vec<(String, String)>,
+    engine_args: Vec<String>,
+    settings: Settings,
+}
+
+/// Asks where to save the bundle and writes it there. Returns `None` when the
+/// user cancels the dialog.
+#[tauri::command]
+pub async fn create_support_bundle(app_handle: AppHandle) -> Result<Option<PathBuf>, AppError> {
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+
+    let Some(destination) = app_handle
+        .dialog()
+        .file()
+        .set_title("Save support bundle")
+        .set_file_name(format!("ps-analyzer-support-{}.zip", created_at))
+        .add_filter("Zip archive", &["zip"])
+        .blocking_save_file()
+        .and_then(|path| path.into_path().ok())
+    else {
+        return Ok(None);
+    };
+
+    let handle = app_handle.clone();
+    let path = destination.clone();
+    tauri::async_runtime::spawn_blocking(move || write_bundle(&handle, &path, created_at))
+        .await
+        .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))??;
+
+    info!("Wrote support bundle to {:?}", destination);
+    Ok(Some(destination))
+}
+
+fn write_bundle(app_handle: &AppHandle, destination: &Path, created_at: u64) -> Result<(), AppError> {
+    let mut zip = ZipWriter::new(File::create(destination)?);
+    let options = SimpleFileOptions::default();
+
+    let environment = collect_environment(app_handle, created_at);
+    zip.start_file("environment.json", options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&environment).unwrap_or_default())?;
+
+    let stderr: Vec<String> = app_handle
+        .state::<EngineLogBuffer>()
+        .recent(None, None)
+        .into_iter()
+        .filter(|entry| entry.stream == LogStream::Stderr)
+        .map(|entry| entry.line)
+        .collect();
+    zip.start_file("engine-stderr.log", options)?;
+    zip.write_all(stderr.join("\n").as_bytes())?;
+
+    if let Ok(log_dir) = crate::portable::app_log_dir(app_handle) {
+        for entry in std::fs::read_dir(&log_dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if !path.is_file() {
+                continue;
+            }
+            match std::fs::read(&path) {
+                Ok(contents) => {
+                    zip.start_file(format!("logs/{}", entry.file_name().to_string_lossy()), options)?;
+                    zip.write_all(&contents)?;
+                }
+                Err(e) => warn!("Skipping log file {:?} in support bundle: {}", path, e),
+            }
+        }
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+fn collect_environment(app_handle: &AppHandle, created_at: u64) -> Environment {
+    let manager = app_handle.state::<EngineManager>();
+    let config = manager.launch_config();
+
+    let mut system = System::new();
+    system.refresh_memory();
+
+    let mut binaries = Vec::new();
+    if let Some(engine) = engine::bundled_engine_path() {
+        binaries.push(describe_binary(engine::SIDECAR_NAME, engine));
+    }
+    for (key, value) in &config.env {
+        if engine::TOOL_PATH_VARS.contains(&key.as_str()) {
+            binaries.push(describe_binary(key, PathBuf::from(value)));
+        }
+    }
+    for name in ["bio-engine", "tracy"] {
+        if let Some(path) = sidecar_update::override_path(app_handle, name) {
+            binaries.push(describe_binary(&format!("{} (updated)", name), path));
+        }
+    }
+
+    Environment {
+        app_version: app_handle.package_info().version.to_string(),
+        created_at,
+        os: OsInfo {
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            name: System::name(),
+            long_version: System::long_os_version(),
+            kernel_version: System::kernel_version(),
+            total_memory_bytes: system.total_memory(),
+        },
+        engine: manager.status(),
+        engine_version: manager.engine_version(),
+        tracy_path: config
+            .env
+            .iter()
+            .find(|(key, _)| key == "TRACY_PATH")
+            .map(|(_, value)| value.clone()),
+        binaries,
+        engine_env: config
+            .env
+            .iter()
+            .map(|(key, value)| (key.clone(), redact(key, value)))
+            .collect(),
+        engine_args: config.args.clone(),
+        settings: redacted_settings(app_handle.state::<SettingsStore>().get()),
+    }
+}
+
+fn describe_binary(name: &str, path: PathBuf) -> BinaryInfo {
+    let contents = std::fs::read(&path).ok();
+    BinaryInfo {
+        name: name.to_string(),
+        size: contents.as_ref().map(|bytes| bytes.len() as u64),
+        sha256: contents.map(|bytes| format!("{:x}", Sha256::digest(&bytes))),
+        path,
+    }
+}
+
+fn redacted_settings(mut settings: Settings) -> Settings {
+    for (key, value) in settings.extra_env.iter_mut() {
+        *value = redact(key, value);
+    }
+    if let Some(remote) = settings.remote_engine.as_mut() {
+        remote.token = "<redacted>".to_string();
+    }
+    if let Some(api_key) = settings.ncbi_api_key.as_mut() {
+        *api_key = "<redacted>".to_string();
+    }
+    settings
+}
+
+fn redact(key: &str, value: &str) -> String {
+    let key = key.to_ascii_uppercase();
+    if SECRET_MARKERS.iter().any(|marker| key.contains(marker)) {
+        "<redacted>".to_string()
+    } else {
+        value.to_string()
+    }
+}
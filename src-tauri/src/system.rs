@@ -0,0 +1,106 @@
+//! What the machine has to offer, and the engine worker count and job
+//! concurrency that suit it when the settings leave them unset.
+//!
+//! Python sizes its pools from the CPU count alone, which on a laptop with
+//! many cores and little memory ends with the OS swapping; the defaults here
+//! are bounded by memory as well.
+
+use crate::disk;
+use crate::temp::TempStore;
+use serde::Serialize;
+use std::sync::OnceLock;
+use sysinfo::System;
+use tauri::{AppHandle, Manager};
+
+const GIGABYTE: u64 = 1024 * 1024 * 1024;
+/// Left to the OS, the webview and everything else the user has open.
+const RESERVED_MEMORY: u64 = 2 * GIGABYTE;
+/// Peak memory of one engine worker with its tracy process.
+const WORKER_MEMORY: u64 = GIGABYTE;
+/// Peak memory of one running job on top of the workers.
+const JOB_MEMORY: u64 = 2 * GIGABYTE;
+
+/// Fixed for the life of the process, so detected once.
+struct Hardware {
+    logical_cores: usize,
+    physical_cores: Option<usize>,
+    total_memory: u64,
+}
+
+fn hardware() -> &'static Hardware {
+    static HARDWARE: OnceLock<Hardware> = OnceLock::new();
+    HARDWARE.get_or_init(|| {
+        let mut system = System::new();
+        system.refresh_memory();
+        Hardware {
+            logical_cores: std::thread::available_parallelism().map_or(1, |cores| cores.get()),
+            physical_cores: System::physical_core_count(),
+            total_memory: system.total_memory(),
+        }
+    })
+}
+
+fn usable_memory() -> u64 {
+    hardware().total_memory.saturating_sub(RESERVED_MEMORY)
+}
+
+/// Engine workers for this machine: one per physical core, as far as memory allows.
+pub fn recommended_workers() -> u32 {
+    let hardware = hardware();
+    let cores = hardware.physical_cores.unwrap_or(hardware.logical_cores);
+    let by_memory = usable_memory() / WORKER_MEMORY;
+    (cores as u64).min(by_memory).clamp(1, u64::from(u32::MAX)) as u32
+}
+
+/// Jobs to run at once on this machine: half the logical cores, since each
+/// job keeps engine workers and tracy busy, as far as memory allows.
+pub fn recommended_concurrent_jobs() -> u32 {
+    let by_cores = (hardware().logical_cores / 2) as u64;
+    let by_memory = usable_memory() / JOB_MEMORY;
+    by_cores.min(by_memory).clamp(1, u64::from(u32::MAX)) as u32
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SystemInfo {
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub os_name: Option<String>,
+    pub os_version: Option<String>,
+    pub logical_cores: usize,
+    pub physical_cores: Option<usize>,
+    pub total_memory_bytes: u64,
+    pub available_memory_bytes: u64,
+    /// Free space on the volume holding job temporary files, if it can be found.
+    pub free_disk_bytes: Option<u64>,
+    /// Defaults used for `worker_count` and `max_concurrent_jobs` when unset.
+    pub recommended_workers: u32,
+    pub recommended_concurrent_jobs: u32,
+}
+
+/// The machine's cores, memory, free disk and OS, with the engine settings
+/// that suit it.
+#[tauri::command]
+pub async fn get_system_info(app_handle: AppHandle) -> SystemInfo {
+    let hardware = hardware();
+    let mut system = System::new();
+    system.refresh_memory();
+    let temp = app_handle.state::<TempStore>().root().to_path_buf();
+    let free_disk_bytes = tauri::async_runtime::spawn_blocking(move || disk::available_space(&temp))
+        .await
+        .ok()
+        .flatten();
+
+    SystemInfo {
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        os_name: System::name(),
+        os_version: System::os_version(),
+        logical_cores: hardware.logical_cores,
+        physical_cores: hardware.physical_cores,
+        total_memory_bytes: hardware.total_memory,
+        available_memory_bytes: system.available_memory(),
+        free_disk_bytes,
+        recommended_workers: recommended_workers(),
+        recommended_concurrent_jobs: recommended_concurrent_jobs(),
+    }
+}
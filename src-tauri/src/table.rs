@@ -0,0 +1,145 @@
+//! Large result tables (per-base quality, variant lists, batch summaries) as
+//! Apache Arrow IPC streams, which the frontend opens with arrow-js for
+//! virtual scrolling and client-side filtering without parsing JSON rows.
+//! Needs the `arrow` feature; without it the commands return an error.
+
+use crate::engine::EngineClient;
+use crate::error::AppError;
+use crate::trace::TraceCache;
+use std::path::PathBuf;
+use tauri::ipc::Response;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_http::reqwest::Method;
+
+#[cfg(feature = "arrow")]
+mod arrow_ipc {
+    use crate::error::AppError;
+    use crate::trace::Trace;
+    use arrow::array::{ArrayRef, RecordBatch, StringArray, UInt32Array, UInt8Array};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::ipc::writer::StreamWriter;
+    use arrow::json::reader::{infer_json_schema_from_iterator, ReaderBuilder};
+    use serde_json::Value;
+    use std::sync::Arc;
+
+    /// Rows per record batch, so arrow-js can start on the first while the rest decode.
+    const BATCH_ROWS: usize = 16 * 1024;
+
+    fn arrow_error(e: impl std::fmt::Display) -> AppError {
+        AppError::Encoding(e.to_string())
+    }
+
+    fn write_stream(schema: &Schema, batches: &[RecordBatch]) -> Result<Vec<u8>, AppError> {
+        let mut buffer = Vec::new();
+        let mut writer = StreamWriter::try_new(&mut buffer, schema).map_err(arrow_error)?;
+        for batch in batches {
+            writer.write(batch).map_err(arrow_error)?;
+        }
+        writer.finish().map_err(arrow_error)?;
+        drop(writer);
+        Ok(buffer)
+    }
+
+    /// One row per base call: `position`, `base`, `quality`, `peak`.
+    pub fn trace_bases(trace: &Trace) -> Result<Vec<u8>, AppError> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("position", DataType::UInt32, false),
+            Field::new("base", DataType::Utf8, false),
+            Field::new("quality", DataType::UInt8, true),
+            Field::new("peak", DataType::UInt32, true),
+        ]));
+        let bases = trace.basecalls.len();
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt32Array::from_iter_values(0..bases as u32)),
+            Arc::new(StringArray::from_iter_values(
+                trace.basecalls.chars().map(|base| base.to_string()),
+            )),
+            Arc::new(UInt8Array::from_iter(
+                (0..bases).map(|index| trace.quality.get(index).copied()),
+            )),
+            Arc::new(UInt32Array::from_iter(
+                (0..bases).map(|index| trace.peak_locations.get(index).copied()),
+            )),
+        ];
+        let batch = RecordBatch::try_new(schema.clone(), columns).map_err(arrow_error)?;
+        write_stream(&schema, &[batch])
+    }
+
+    /// JSON objects as a table, with the schema inferred from the rows.
+    pub fn json_rows(rows: &[Value]) -> Result<Vec<u8>, AppError> {
+        let schema = Arc::new(
+            infer_json_schema_from_iterator(rows.iter().map(|row| Ok(row.clone()))).map_err(arrow_error)?,
+        );
+        let mut decoder = ReaderBuilder::new(schema.clone())
+            .with_batch_size(BATCH_ROWS)
+            .build_decoder()
+            .map_err(arrow_error)?;
+        let mut batches = Vec::new();
+        for chunk in rows.chunks(BATCH_ROWS) {
+            decoder.serialize(chunk).map_err(arrow_error)?;
+            batches.extend(decoder.flush().map_err(arrow_error)?);
+        }
+        write_stream(&schema, &batches)
+    }
+}
+
+#[cfg(not(feature = "arrow"))]
+mod arrow_ipc {
+    use crate::error::AppError;
+    use crate::trace::Trace;
+    use serde_json::Value;
+
+    fn unsupported() -> AppError {
+        AppError::Encoding("this build was made without Arrow support".into())
+    }
+
+    pub fn trace_bases(_trace: &Trace) -> Result<Vec<u8>, AppError> {
+        Err(unsupported())
+    }
+
+    pub fn json_rows(_rows: &[Value]) -> Result<Vec<u8>, AppError> {
+        Err(unsupported())
+    }
+}
+
+/// Per-base calls, quality and peak positions of a trace, as an Arrow stream.
+#[tauri::command]
+pub async fn get_trace_table(app_handle: AppHandle, path: PathBuf) -> Result<Response, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let trace = app_handle.state::<TraceCache>().get(&path)?;
+        arrow_ipc::trace_bases(&trace).map(Response::new)
+    })
+    .await
+    .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))?
+}
+
+/// Fetches `endpoint` from the engine (e.g. `/jobs/<id>`) and converts the
+/// array of row objects at `pointer` (a JSON pointer such as `/variants`, or
+/// the whole response when omitted) into an Arrow stream.
+#[tauri::command]
+pub async fn get_engine_table(
+    app_handle: AppHandle,
+    endpoint: String,
+    pointer: Option<String>,
+) -> Result<Response, AppError> {
+    if !endpoint.starts_with('/') {
+        return Err(AppError::Encoding(format!("{:?} is not an engine path", endpoint)));
+    }
+    let client = app_handle.state::<EngineClient>();
+    let response = client.send(client.request(Method::GET, &endpoint)).await?;
+    let body = response.bytes().await?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let document: serde_json::Value =
+            serde_json::from_slice(&body).map_err(|e| AppError::Encoding(e.to_string()))?;
+        let rows = match pointer.as_deref() {
+            Some(pointer) => document.pointer(pointer),
+            None => Some(&document),
+        }
+        .and_then(serde_json::Value::as_array)
+        .ok_or_else(|| AppError::Encoding(format!("{} has no array of rows there", endpoint)))?;
+        arrow_ipc::json_rows(rows).map(Response::new)
+    })
+    .await
+    .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))?
+}
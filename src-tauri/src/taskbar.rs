@@ -0,0 +1,72 @@
+//! Queue progress on the main window's taskbar button (Windows), dock icon
+//! (macOS) or launcher entry (Linux), so a batch can be followed while the
+//! window is minimised.
+//!
+//! Progress covers the jobs since the queue was last idle: finished ones
+//! count as done, queued ones as not started. The badge shows how many are
+//! still to go.
+
+use crate::jobs::{self, JobInfo, JobQueue, JobState};
+use std::sync::Mutex;
+use tauri::window::{ProgressBarState, ProgressBarStatus};
+use tauri::{AppHandle, Manager};
+
+/// Managed state: where the current stretch of work started.
+#[derive(Default)]
+pub struct TaskbarProgress {
+    /// Id of the first job since the queue was last idle.
+    first_job: Mutex<Option<u64>>,
+}
+
+/// Overall percent done of `jobs`.
+fn percent(jobs: &[&JobInfo]) -> u64 {
+    let done: f64 = jobs
+        .iter()
+        .map(|job| match job.state {
+            JobState::Queued => 0.0,
+            JobState::Running => job.progress.unwrap_or(0.0),
+            _ => 100.0,
+        })
+        .sum();
+    (done / jobs.len().max(1) as f64).round() as u64
+}
+
+/// Redraws the indicator from the job queue. Called whenever a job changes.
+pub fn update(app_handle: &AppHandle) {
+    let (Some(window), Some(state)) = (
+        app_handle.get_webview_window("main"),
+        app_handle.try_state::<TaskbarProgress>(),
+    ) else {
+        return;
+    };
+    let all = jobs::list_jobs(app_handle.state::<JobQueue>());
+    let remaining = all.iter().filter(|job| !job.state.is_finished()).count();
+
+    let mut first_job = state.first_job.lock().unwrap();
+    if remaining == 0 {
+        *first_job = None;
+        let _ = window.set_progress_bar(ProgressBarState {
+            status: Some(ProgressBarStatus::None),
+            progress: None,
+        });
+        let _ = window.set_badge_count(None);
+        return;
+    }
+
+    let start = *first_job.get_or_insert_with(|| {
+        all.iter()
+            .find(|job| !job.state.is_finished())
+            .map_or(0, |job| job.id)
+    });
+    let stretch: Vec<&JobInfo> = all.iter().filter(|job| job.id >= start).collect();
+    let status = if stretch.iter().any(|job| job.state == JobState::Failed) {
+        ProgressBarStatus::Error
+    } else {
+        ProgressBarStatus::Normal
+    };
+    let _ = window.set_progress_bar(ProgressBarState {
+        status: Some(status),
+        progress: Some(percent(&stretch)),
+    });
+    let _ = window.set_badge_count(Some(remaining as i64));
+}
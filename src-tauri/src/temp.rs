@@ -0,0 +1,121 @@
+//! The app's own temporary directory, holding one subdirectory per job for
+//! intermediate files. It is emptied when the app exits and, in case the last
+//! session crashed, again at startup. Once it grows past the `temp_max_gb`
+//! setting, the oldest subdirectories not in use are evicted.
+
+use crate::disk;
+use crate::error::AppError;
+use crate::settings::SettingsStore;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager};
+use tracing::{debug, info, warn};
+
+const TEMP_DIR: &str = "ps-analyzer";
+const GIGABYTE: u64 = 1024 * 1024 * 1024;
+
+pub struct TempStore {
+    root: PathBuf,
+    /// Subdirectories whose work is still running, which are never evicted.
+    in_use: Mutex<HashSet<String>>,
+}
+
+impl TempStore {
+    /// Clears whatever a previous session left behind.
+    pub fn open() -> Self {
+        let root = std::env::temp_dir().join(TEMP_DIR);
+        match std::fs::remove_dir_all(&root) {
+            Ok(()) => info!("Removed temporary files left by the previous session"),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Could not clear {:?}: {}", root, e),
+        }
+        Self {
+            root,
+            in_use: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Where the subdirectory `name` is, whether or not it exists.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+
+    /// Creates the subdirectory `name` and keeps it from eviction until
+    /// [`release`](Self::release), first making room under the size cap.
+    pub fn create(&self, app_handle: &AppHandle, name: &str) -> Result<PathBuf, AppError> {
+        self.in_use.lock().unwrap().insert(name.to_string());
+        let max_bytes = app_handle
+            .state::<SettingsStore>()
+            .get()
+            .temp_max_gb
+            .map(|gb| u64::from(gb) * GIGABYTE);
+        if let Some(max_bytes) = max_bytes {
+            self.evict(max_bytes);
+        }
+        let dir = self.path(name);
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Lets the subdirectory `name` be evicted; its files stay until then.
+    pub fn release(&self, name: &str) {
+        self.in_use.lock().unwrap().remove(name);
+    }
+
+    /// Deletes the subdirectory `name` now.
+    pub fn remove(&self, name: &str) {
+        self.release(name);
+        let dir = self.path(name);
+        match std::fs::remove_dir_all(&dir) {
+            Ok(()) => debug!("Removed temporary directory {:?}", dir),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Could not remove temporary directory {:?}: {}", dir, e),
+        }
+    }
+
+    /// Deletes the oldest subdirectories not in use until the rest fit in `max_bytes`.
+    fn evict(&self, max_bytes: u64) {
+        let mut entries: Vec<(String, SystemTime, u64)> = std::fs::read_dir(&self.root)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let modified = entry
+                    .metadata()
+                    .and_then(|metadata| metadata.modified())
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                (name, modified, disk::usage(&entry.path()))
+            })
+            .collect();
+        let mut total: u64 = entries.iter().map(|(_, _, bytes)| bytes).sum();
+        if total <= max_bytes {
+            return;
+        }
+
+        entries.sort_by_key(|(_, modified, _)| *modified);
+        let in_use = self.in_use.lock().unwrap().clone();
+        for (name, _, bytes) in entries {
+            if total <= max_bytes {
+                break;
+            }
+            if in_use.contains(&name) {
+                continue;
+            }
+            info!("Evicting temporary directory {} ({} bytes) to stay under the size cap", name, bytes);
+            self.remove(&name);
+            total = total.saturating_sub(bytes);
+        }
+    }
+
+    /// Empties the directory, on exit.
+    pub fn clear(&self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
@@ -0,0 +1,229 @@
+//! Optional tools (BLAST, Primer3, MAFFT) are not bundled with the app but
+//! downloaded the first time something runs them, so installs stay small
+//! for users who never need them. Releases publish a `tools.json` manifest
+//! with, per tool and target triple, a URL, SHA-256 and minisign signature;
+//! a binary is only installed once both check out, signed with the same key
+//! as app and sidecar updates.
+//!
+//! Tools live in `tools/` under the app data folder and are found from there
+//! by the [`supervisor`](crate::supervisor), which installs them on demand.
+
+use crate::engine::target_triple;
+use crate::error::AppError;
+use crate::sidecar_update::{self, Artifact};
+use crate::supervisor::{ProcessSupervisor, ToolSource};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_http::reqwest;
+use tracing::info;
+
+/// Emitted with a [`ToolDownloadProgress`] while a tool downloads.
+pub const TOOL_DOWNLOAD_EVENT: &str = "tool-download";
+
+/// Published with every bio-engine release, next to `sidecars.json`.
+const MANIFEST_URL: &str =
+    "https://github.com/lagosproject/bio-engine/releases/latest/download/tools.json";
+
+const TOOLS_DIR: &str = "tools";
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// Bytes downloaded between progress events.
+const PROGRESS_STEP: u64 = 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    tools: BTreeMap<String, ToolRelease>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolRelease {
+    version: String,
+    /// By target triple.
+    platforms: BTreeMap<String, Artifact>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ToolDownloadProgress {
+    pub name: String,
+    pub downloaded: u64,
+    /// `None` when the server does not say.
+    pub total: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OptionalTool {
+    pub name: String,
+    pub installed_version: Option<String>,
+}
+
+/// Managed state serializing installs, so a tool two analyses need at once
+/// is downloaded only once.
+#[derive(Default)]
+pub struct ToolManager {
+    installing: tokio::sync::Mutex<()>,
+}
+
+fn tools_dir(app_handle: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(crate::portable::app_data_dir(app_handle)?.join(TOOLS_DIR))
+}
+
+/// `name` if it is registered as a tool downloaded on demand.
+fn optional(app_handle: &AppHandle, name: &str) -> Result<&'static str, AppError> {
+    app_handle
+        .state::<ProcessSupervisor>()
+        .spec(name)
+        .filter(|spec| spec.source == ToolSource::Download)
+        .map(|spec| spec.name)
+        .ok_or_else(|| AppError::Tool(format!("{} is not a downloadable tool", name)))
+}
+
+/// Path of the downloaded `name` binary, if it is installed.
+pub fn installed_path(app_handle: &AppHandle, name: &str) -> Option<PathBuf> {
+    let path = tools_dir(app_handle)
+        .ok()?
+        .join(sidecar_update::binary_file_name(name));
+    path.is_file().then_some(path)
+}
+
+async fn fetch_manifest() -> Result<Manifest, AppError> {
+    let body = reqwest::get(MANIFEST_URL)
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    serde_json::from_slice(&body)
+        .map_err(|e| AppError::Tool(format!("invalid tool manifest: {}", e)))
+}
+
+async fn download(
+    app_handle: &AppHandle,
+    name: &str,
+    artifact: &Artifact,
+) -> Result<Vec<u8>, AppError> {
+    let client = reqwest::Client::builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .build()?;
+    let mut response = client.get(&artifact.url).send().await?.error_for_status()?;
+    let total = response.content_length();
+    let mut bytes = Vec::with_capacity(total.unwrap_or(0) as usize);
+    let mut reported = 0;
+    let progress = |downloaded: u64| {
+        let _ = app_handle.emit(
+            TOOL_DOWNLOAD_EVENT,
+            ToolDownloadProgress {
+                name: name.to_string(),
+                downloaded,
+                total,
+            },
+        );
+    };
+    progress(0);
+    while let Some(chunk) = response.chunk().await? {
+        bytes.extend_from_slice(&chunk);
+        let downloaded = bytes.len() as u64;
+        if downloaded - reported >= PROGRESS_STEP {
+            reported = downloaded;
+            progress(downloaded);
+        }
+    }
+    progress(bytes.len() as u64);
+    Ok(bytes)
+}
+
+/// Downloads, verifies and installs the latest published `name`.
+async fn install(app_handle: &AppHandle, name: &'static str) -> Result<OptionalTool, AppError> {
+    let key = sidecar_update::public_key(app_handle)?;
+    let manifest = fetch_manifest().await?;
+    let release = manifest
+        .tools
+        .get(name)
+        .ok_or_else(|| AppError::Tool(format!("no download of {} is published", name)))?;
+    let artifact = release.platforms.get(target_triple()).ok_or_else(|| {
+        AppError::Tool(format!("{} is not available for {}", name, target_triple()))
+    })?;
+
+    info!("Downloading {} {}", name, release.version);
+    let bytes = download(app_handle, name, artifact).await?;
+    sidecar_update::verify(name, &bytes, artifact, &key)?;
+
+    let dir = tools_dir(app_handle)?;
+    std::fs::create_dir_all(&dir)?;
+    sidecar_update::replace_atomically(&dir.join(sidecar_update::binary_file_name(name)), &bytes)?;
+    let mut installed = sidecar_update::load_installed(&dir);
+    installed
+        .0
+        .insert(name.to_string(), release.version.clone());
+    sidecar_update::save_installed(&dir, &installed)?;
+    info!("Installed {} {}", name, release.version);
+    Ok(OptionalTool {
+        name: name.to_string(),
+        installed_version: Some(release.version.clone()),
+    })
+}
+
+/// Installs `name` unless it already is, returning its path.
+pub async fn ensure(app_handle: &AppHandle, name: &str) -> Result<PathBuf, AppError> {
+    let name = optional(app_handle, name)?;
+    if let Some(path) = installed_path(app_handle, name) {
+        return Ok(path);
+    }
+    let manager = app_handle.state::<ToolManager>();
+    let _installing = manager.installing.lock().await;
+    // Installed while this call waited for another install to finish.
+    if let Some(path) = installed_path(app_handle, name) {
+        return Ok(path);
+    }
+    install(app_handle, name).await?;
+    installed_path(app_handle, name)
+        .ok_or_else(|| AppError::Tool(format!("{} was installed but cannot be found", name)))
+}
+
+/// Every downloadable tool and the version installed, if any.
+#[tauri::command]
+pub fn list_tools(app_handle: AppHandle) -> Result<Vec<OptionalTool>, AppError> {
+    let installed = sidecar_update::load_installed(&tools_dir(&app_handle)?);
+    Ok(app_handle
+        .state::<ProcessSupervisor>()
+        .status()
+        .into_iter()
+        .filter(|tool| tool.spec.source == ToolSource::Download)
+        .map(|tool| OptionalTool {
+            name: tool.spec.name.to_string(),
+            installed_version: installed
+                .0
+                .get(tool.spec.name)
+                .filter(|_| installed_path(&app_handle, tool.spec.name).is_some())
+                .cloned(),
+        })
+        .collect())
+}
+
+/// Installs `name` now rather than on first use, or updates it to the
+/// latest published version.
+#[tauri::command]
+pub async fn install_tool(app_handle: AppHandle, name: String) -> Result<OptionalTool, AppError> {
+    let name = optional(&app_handle, &name)?;
+    let manager = app_handle.state::<ToolManager>();
+    let _installing = manager.installing.lock().await;
+    install(&app_handle, name).await
+}
+
+/// Deletes the downloaded `name`; it is downloaded again when next needed.
+#[tauri::command]
+pub async fn remove_tool(app_handle: AppHandle, name: String) -> Result<(), AppError> {
+    let name = optional(&app_handle, &name)?;
+    let manager = app_handle.state::<ToolManager>();
+    let _installing = manager.installing.lock().await;
+    let dir = tools_dir(&app_handle)?;
+    if let Some(path) = installed_path(&app_handle, name) {
+        std::fs::remove_file(path)?;
+    }
+    let mut installed = sidecar_update::load_installed(&dir);
+    if installed.0.remove(name).is_some() {
+        sidecar_update::save_installed(&dir, &installed)?;
+    }
+    info!("Removed {}", name);
+    Ok(())
+}
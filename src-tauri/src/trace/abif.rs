@@ -0,0 +1,139 @@
+//! Applied Biosystems ABIF (`.ab1`) files: a big-endian directory of tagged
+//! entries, of which only the analysed data and base calls are read.
+
+use super::{Trace, TraceChannels, TraceFormat};
+use std::path::Path;
+
+pub const MAGIC: &[u8] = b"ABIF";
+
+const HEADER_LEN: usize = 34;
+const ENTRY_LEN: usize = 28;
+/// Entries holding at most this many bytes store them in the offset field.
+const INLINE_LEN: usize = 4;
+
+struct Entry {
+    name: [u8; 4],
+    number: i32,
+    data_size: usize,
+    /// Absolute offset of the data, already pointing into the entry for inline data.
+    data_offset: usize,
+}
+
+fn be_i32(data: &[u8], at: usize) -> Option<i32> {
+    Some(i32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn entry_at(data: &[u8], at: usize) -> Option<Entry> {
+    let data_size = usize::try_from(be_i32(data, at + 16)?).ok()?;
+    let data_offset = if data_size <= INLINE_LEN {
+        at + 20
+    } else {
+        usize::try_from(be_i32(data, at + 20)?).ok()?
+    };
+    Some(Entry {
+        name: data.get(at..at + 4)?.try_into().ok()?,
+        number: be_i32(data, at + 4)?,
+        data_size,
+        data_offset,
+    })
+}
+
+struct Directory<'a> {
+    data: &'a [u8],
+    entries: Vec<Entry>,
+}
+
+impl<'a> Directory<'a> {
+    fn read(data: &'a [u8]) -> Result<Self, String> {
+        if data.len() < HEADER_LEN {
+            return Err("file is too short for an ABIF header".into());
+        }
+        // The header holds a directory entry describing the directory itself.
+        let root = entry_at(data, 6).ok_or("malformed ABIF header")?;
+        let count = usize::try_from(be_i32(data, 6 + 12).unwrap_or(0)).unwrap_or(0);
+        let entries = (0..count)
+            .map(|index| entry_at(data, root.data_offset + index * ENTRY_LEN))
+            .collect::<Option<Vec<_>>>()
+            .ok_or("ABIF directory is truncated")?;
+        Ok(Self { data, entries })
+    }
+
+    fn get(&self, name: &[u8; 4], number: i32) -> Option<&'a [u8]> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| &entry.name == name && entry.number == number)?;
+        self.data
+            .get(entry.data_offset..entry.data_offset + entry.data_size)
+    }
+
+    /// `number` is tried first; instruments write edited calls there and the
+    /// original calls in `fallback`.
+    fn get_either(&self, name: &[u8; 4], number: i32, fallback: i32) -> Option<&'a [u8]> {
+        self.get(name, number).or_else(|| self.get(name, fallback))
+    }
+
+    fn shorts(&self, name: &[u8; 4], number: i32) -> Option<Vec<u16>> {
+        let bytes = self.get(name, number)?;
+        Some(
+            bytes
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect(),
+        )
+    }
+}
+
+pub fn parse(path: &Path, data: &[u8]) -> Result<Trace, String> {
+    let directory = Directory::read(data)?;
+
+    let base_order = directory.get(b"FWO_", 1).ok_or("missing base order (FWO_)")?;
+    let mut channels = TraceChannels::default();
+    // Analysed data is in DATA 9-12, in base order.
+    for (index, &base) in base_order.iter().take(4).enumerate() {
+        let signal = directory
+            .shorts(b"DATA", 9 + index as i32)
+            .ok_or_else(|| format!("missing analysed data for {}", base as char))?;
+        if let Some(channel) = channels.get_mut(base) {
+            *channel = signal;
+        }
+    }
+
+    let basecalls = directory
+        .get_either(b"PBAS", 2, 1)
+        .ok_or("missing base calls (PBAS)")?;
+    let quality = directory
+        .get_either(b"PCON", 2, 1)
+        .map(<[u8]>::to_vec)
+        .unwrap_or_default();
+    let peak_locations = directory
+        .get_either(b"PLOC", 2, 1)
+        .map(|bytes| {
+            bytes
+                .chunks_exact(2)
+                .map(|pair| u32::from(u16::from_be_bytes([pair[0], pair[1]])))
+                .collect()
+        })
+        .unwrap_or_default();
+    // A Pascal string: length byte first.
+    let sample_name = directory.get(b"SMPL", 1).and_then(|bytes| {
+        let (&len, rest) = bytes.split_first()?;
+        let name = String::from_utf8_lossy(rest.get(..len as usize)?).trim().to_string();
+        (!name.is_empty()).then_some(name)
+    });
+
+    Ok(Trace {
+        path: path.to_path_buf(),
+        format: TraceFormat::Abif,
+        sample_name,
+        // One char per byte, so base calls stay index-aligned with quality
+        // and peak locations.
+        basecalls: basecalls
+            .iter()
+            .map(|&base| if base.is_ascii() { base as char } else { 'N' })
+            .collect(),
+        quality,
+        peak_locations,
+        channels,
+    })
+}
@@ -0,0 +1,67 @@
+//! Min/max envelopes of the trace channels, one bucket per pixel, so a
+//! zoomed-out chromatogram is drawn from a few thousand points instead of
+//! every scan.
+
+use super::Trace;
+use serde::Serialize;
+
+/// Widest viewport served, in pixels (an 8K display).
+pub const MAX_WIDTH: usize = 8192;
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ChannelEnvelope {
+    pub min: Vec<u16>,
+    pub max: Vec<u16>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TraceEnvelope {
+    /// Scan range covered, clamped to the trace: `start..end`.
+    pub start: usize,
+    pub end: usize,
+    /// Scans per bucket; 1 means the values are the raw samples.
+    pub scans_per_bucket: f64,
+    pub a: ChannelEnvelope,
+    pub c: ChannelEnvelope,
+    pub g: ChannelEnvelope,
+    pub t: ChannelEnvelope,
+}
+
+pub fn envelope(trace: &Trace, start: usize, end: usize, width: usize) -> TraceEnvelope {
+    let channels = &trace.channels;
+    let scans = channels.a.len();
+    let end = end.min(scans);
+    let start = start.min(end);
+    let width = width.clamp(1, MAX_WIDTH);
+
+    let span = end - start;
+    // Zoomed in far enough that every scan gets a pixel: send them as they are.
+    let buckets = span.min(width);
+    let scans_per_bucket = if buckets == 0 { 1.0 } else { span as f64 / buckets as f64 };
+
+    let reduce = |signal: &[u16]| -> ChannelEnvelope {
+        let signal = &signal[start.min(signal.len())..end.min(signal.len())];
+        let mut envelope = ChannelEnvelope {
+            min: Vec::with_capacity(buckets),
+            max: Vec::with_capacity(buckets),
+        };
+        for bucket in 0..buckets {
+            let from = (bucket as f64 * scans_per_bucket) as usize;
+            let to = (((bucket + 1) as f64 * scans_per_bucket) as usize).max(from + 1);
+            let values = signal.get(from..to.min(signal.len())).unwrap_or_default();
+            envelope.min.push(values.iter().copied().min().unwrap_or(0));
+            envelope.max.push(values.iter().copied().max().unwrap_or(0));
+        }
+        envelope
+    };
+
+    TraceEnvelope {
+        start,
+        end,
+        scans_per_bucket,
+        a: reduce(&channels.a),
+        c: reduce(&channels.c),
+        g: reduce(&channels.g),
+        t: reduce(&channels.t),
+    }
+}
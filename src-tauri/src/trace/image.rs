@@ -0,0 +1,213 @@
+//! Figures of a trace region drawn without the WebView: the four channels,
+//! the base calls above their peaks and a quality bar per call. The figure
+//! is built as SVG; PNG is that SVG rasterized at a chosen scale, so both
+//! look the same.
+
+use super::Trace;
+use crate::error::AppError;
+use resvg::{tiny_skia, usvg};
+use serde::Deserialize;
+use std::fmt::Write as _;
+use std::path::Path;
+
+const SANS: &[u8] = include_bytes!("../../fonts/DejaVuSans.ttf");
+const FONT_FAMILY: &str = "DejaVu Sans";
+
+const DEFAULT_WIDTH: u32 = 1200;
+const DEFAULT_HEIGHT: u32 = 300;
+/// Pixels per SVG unit in PNGs, unless asked otherwise.
+const DEFAULT_SCALE: f32 = 3.0;
+const MAX_SCALE: f32 = 10.0;
+
+const QUALITY_BAND: f32 = 36.0;
+const BASECALL_BAND: f32 = 18.0;
+const AXIS_BAND: f32 = 16.0;
+/// Quality drawn at full bar height.
+const MAX_QUALITY: f32 = 60.0;
+/// Base calls between position labels.
+const LABEL_EVERY: usize = 10;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Svg,
+    Png,
+}
+
+/// What to draw and at which size; scans are 0-based, `end` exclusive.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TraceImageOptions {
+    pub start: Option<usize>,
+    pub end: Option<usize>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Pixels per SVG unit in PNGs.
+    pub scale: Option<f32>,
+}
+
+fn base_color(base: char) -> &'static str {
+    match base.to_ascii_uppercase() {
+        'A' => "#2ea633",
+        'C' => "#2659d9",
+        'G' => "#1a1a1a",
+        'T' => "#d92626",
+        _ => "#888888",
+    }
+}
+
+/// The region of `trace` as an SVG document.
+pub fn svg(trace: &Trace, options: &TraceImageOptions) -> Result<String, AppError> {
+    let channels = &trace.channels;
+    let scans = channels.a.len();
+    let start = options.start.unwrap_or(0);
+    let end = options.end.unwrap_or(scans).min(scans);
+    if start.saturating_add(1) >= end {
+        return Err(AppError::InvalidTrace(format!(
+            "scans {}..{} are not a region of a trace with {} scans",
+            start, end, scans
+        )));
+    }
+    let width = options.width.unwrap_or(DEFAULT_WIDTH).max(100) as f32;
+    let height = options.height.unwrap_or(DEFAULT_HEIGHT).max(120) as f32;
+    let plot_top = QUALITY_BAND + BASECALL_BAND;
+    let plot_bottom = height - AXIS_BAND;
+
+    // Malformed files can have channels of different lengths.
+    let region = |channel: &[u16]| {
+        let end = end.min(channel.len());
+        channel[start.min(end)..end].to_vec()
+    };
+    let signals = [
+        ('A', region(&channels.a)),
+        ('C', region(&channels.c)),
+        ('G', region(&channels.g)),
+        ('T', region(&channels.t)),
+    ];
+    let max = signals
+        .iter()
+        .flat_map(|(_, signal)| signal.iter().copied())
+        .max()
+        .unwrap_or(1)
+        .max(1) as f32;
+    let x = |scan: f32| (scan - start as f32) * width / (end - start - 1) as f32;
+    let y = |value: u16| plot_bottom - value as f32 / max * (plot_bottom - plot_top);
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="{w}" height="{h}" font-family="{font}"><rect width="{w}" height="{h}" fill="white"/>"#,
+        w = width,
+        h = height,
+        font = FONT_FAMILY
+    );
+    let _ = write!(
+        svg,
+        r##"<line x1="0" y1="{y}" x2="{w}" y2="{y}" stroke="#999" stroke-width="0.5"/>"##,
+        y = plot_bottom,
+        w = width
+    );
+
+    for (index, (&peak, base)) in trace
+        .peak_locations
+        .iter()
+        .zip(trace.basecalls.chars())
+        .enumerate()
+    {
+        let peak = peak as usize;
+        if !(start..end).contains(&peak) {
+            continue;
+        }
+        let px = x(peak as f32);
+        if let Some(&quality) = trace.quality.get(index) {
+            let bar = quality as f32 / MAX_QUALITY * (QUALITY_BAND - 4.0);
+            let bar = bar.min(QUALITY_BAND - 4.0);
+            let _ = write!(
+                svg,
+                r##"<rect x="{:.2}" y="{:.2}" width="4" height="{:.2}" fill="#9db4d6"/>"##,
+                px - 2.0,
+                QUALITY_BAND - 2.0 - bar,
+                bar
+            );
+        }
+        let _ = write!(
+            svg,
+            r#"<text x="{:.2}" y="{:.2}" font-size="11" text-anchor="middle" fill="{}">{}</text>"#,
+            px,
+            plot_top - 5.0,
+            base_color(base),
+            if base.is_ascii_alphabetic() {
+                base
+            } else {
+                'N'
+            }
+        );
+        if (index + 1) % LABEL_EVERY == 0 {
+            let _ = write!(
+                svg,
+                r##"<line x1="{x:.2}" y1="{y}" x2="{x:.2}" y2="{y2}" stroke="#999" stroke-width="0.5"/><text x="{x:.2}" y="{ty}" font-size="9" text-anchor="middle" fill="#555">{n}</text>"##,
+                x = px,
+                y = plot_bottom,
+                y2 = plot_bottom + 3.0,
+                ty = height - 3.0,
+                n = index + 1
+            );
+        }
+    }
+
+    for (base, signal) in &signals {
+        let points: Vec<String> = signal
+            .iter()
+            .enumerate()
+            .map(|(offset, &value)| format!("{:.2},{:.2}", x((start + offset) as f32), y(value)))
+            .collect();
+        let _ = write!(
+            svg,
+            r#"<polyline fill="none" stroke="{}" stroke-width="1" stroke-linejoin="round" points="{}"/>"#,
+            base_color(*base),
+            points.join(" ")
+        );
+    }
+    svg.push_str("</svg>");
+    Ok(svg)
+}
+
+/// `svg` rasterized at `scale` pixels per unit, as PNG data.
+fn png(svg: &str, scale: f32) -> Result<Vec<u8>, AppError> {
+    let mut options = usvg::Options::default();
+    options.fontdb_mut().load_font_data(SANS.to_vec());
+    let tree = usvg::Tree::from_str(svg, &options).map_err(|e| AppError::Image(e.to_string()))?;
+    let size = tree
+        .size()
+        .to_int_size()
+        .scale_by(scale)
+        .ok_or_else(|| AppError::Image("the image is too large".into()))?;
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| AppError::Image("the image is too large".into()))?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    pixmap
+        .encode_png()
+        .map_err(|e| AppError::Image(e.to_string()))
+}
+
+/// Writes the region of `trace` to `path` as `format`.
+pub fn write(
+    trace: &Trace,
+    path: &Path,
+    format: ImageFormat,
+    options: &TraceImageOptions,
+) -> Result<(), AppError> {
+    let svg = svg(trace, options)?;
+    match format {
+        ImageFormat::Svg => std::fs::write(path, svg)?,
+        ImageFormat::Png => {
+            let scale = options.scale.unwrap_or(DEFAULT_SCALE).clamp(1.0, MAX_SCALE);
+            std::fs::write(path, png(&svg, scale)?)?
+        }
+    }
+    Ok(())
+}
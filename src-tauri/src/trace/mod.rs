@@ -0,0 +1,290 @@
+//! Chromatogram readers, so the UI can draw a trace without a round-trip
+//! through the engine. Every format is normalised into a [`Trace`].
+
+mod abif;
+mod envelope;
+mod image;
+mod scf;
+mod trim;
+
+pub use envelope::TraceEnvelope;
+pub use image::{ImageFormat, TraceImageOptions};
+pub use trim::{TrimParams, TrimResult};
+
+use crate::blob::{BlobHandle, BlobStore};
+use crate::error::AppError;
+use crate::ipc::{self, BinaryWriter, Encoding};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager};
+use tracing::debug;
+
+/// Parsed traces kept for envelope requests while the user pans and zooms.
+const CACHED_TRACES: usize = 16;
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceFormat {
+    Abif,
+    Scf,
+}
+
+/// Raw signal per base, one sample per scan.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TraceChannels {
+    pub a: Vec<u16>,
+    pub c: Vec<u16>,
+    pub g: Vec<u16>,
+    pub t: Vec<u16>,
+}
+
+impl TraceChannels {
+    /// The channel for `base` (`A`, `C`, `G` or `T`, any case).
+    fn get_mut(&mut self, base: u8) -> Option<&mut Vec<u16>> {
+        match base.to_ascii_uppercase() {
+            b'A' => Some(&mut self.a),
+            b'C' => Some(&mut self.c),
+            b'G' => Some(&mut self.g),
+            b'T' => Some(&mut self.t),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Trace {
+    pub path: PathBuf,
+    pub format: TraceFormat,
+    pub sample_name: Option<String>,
+    pub basecalls: String,
+    /// Phred quality per base call; empty if the file has none.
+    pub quality: Vec<u8>,
+    /// Scan index of each base call's peak.
+    pub peak_locations: Vec<u32>,
+    pub channels: TraceChannels,
+}
+
+/// Reads the trace at `path`, detecting the format from its contents.
+pub fn read(path: &Path) -> Result<Trace, AppError> {
+    let data = std::fs::read(path)?;
+    let invalid = |detail: String| AppError::InvalidTrace(format!("{:?}: {}", path, detail));
+
+    let trace = if data.starts_with(abif::MAGIC) {
+        abif::parse(path, &data).map_err(invalid)?
+    } else if data.starts_with(scf::MAGIC) {
+        scf::parse(path, &data).map_err(invalid)?
+    } else {
+        return Err(invalid("not a recognised chromatogram format".into()));
+    };
+    debug!(
+        "Parsed {:?}: {} bases, {} scans",
+        path,
+        trace.basecalls.len(),
+        trace.channels.a.len()
+    );
+    Ok(trace)
+}
+
+struct CachedTrace {
+    modified: Option<SystemTime>,
+    trace: Arc<Trace>,
+}
+
+/// Recently parsed traces, most recent last.
+#[derive(Default)]
+pub struct TraceCache(Mutex<VecDeque<CachedTrace>>);
+
+impl TraceCache {
+    /// The parsed trace at `path`, re-read if the file changed since.
+    pub fn get(&self, path: &Path) -> Result<Arc<Trace>, AppError> {
+        let modified = std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+        {
+            let mut cache = self.0.lock().unwrap();
+            if let Some(index) = cache.iter().position(|cached| cached.trace.path == path) {
+                let cached = cache.remove(index).expect("index is in bounds");
+                if cached.modified == modified {
+                    let trace = cached.trace.clone();
+                    cache.push_back(cached);
+                    return Ok(trace);
+                }
+            }
+        }
+
+        let trace = Arc::new(read(path)?);
+        let mut cache = self.0.lock().unwrap();
+        if cache.len() >= CACHED_TRACES {
+            cache.pop_front();
+        }
+        cache.push_back(CachedTrace {
+            modified,
+            trace: trace.clone(),
+        });
+        Ok(trace)
+    }
+}
+
+fn join_error(e: impl std::fmt::Display) -> AppError {
+    AppError::Io(std::io::Error::other(e.to_string()))
+}
+
+/// Answers with a [`Trace`], in `encoding` if given.
+#[tauri::command]
+pub async fn parse_trace(
+    app_handle: AppHandle,
+    path: PathBuf,
+    encoding: Option<Encoding>,
+) -> Result<tauri::ipc::Response, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let trace = app_handle.state::<TraceCache>().get(&path)?;
+        ipc::encode(&*trace, encoding)
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// Per-pixel min/max of each channel over scans `start..end`, for drawing
+/// `width` pixels. The trace is parsed once and kept for later viewports.
+#[tauri::command]
+pub async fn get_trace_envelope(
+    app_handle: AppHandle,
+    path: PathBuf,
+    start: usize,
+    end: usize,
+    width: usize,
+) -> Result<TraceEnvelope, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let trace = app_handle.state::<TraceCache>().get(&path)?;
+        Ok(envelope::envelope(&trace, start, end, width))
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// Draws scans of the trace at `path` as an SVG or PNG figure at `target`.
+#[tauri::command]
+pub async fn export_trace_image(
+    app_handle: AppHandle,
+    path: PathBuf,
+    target: PathBuf,
+    format: ImageFormat,
+    options: Option<TraceImageOptions>,
+) -> Result<PathBuf, AppError> {
+    let written = target.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let trace = app_handle.state::<TraceCache>().get(&path)?;
+        image::write(&trace, &target, format, &options.unwrap_or_default())
+    })
+    .await
+    .map_err(join_error)??;
+    Ok(written)
+}
+
+/// Where quality trimming would cut the trace at `path`, and what is left.
+/// The trace is parsed once, so rerunning with new `params` is cheap.
+#[tauri::command]
+pub async fn trim_trace(
+    app_handle: AppHandle,
+    path: PathBuf,
+    params: Option<TrimParams>,
+) -> Result<TrimResult, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let trace = app_handle.state::<TraceCache>().get(&path)?;
+        trim::trim(&trace, &params.unwrap_or_default())
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// [`parse_trace`] as a binary buffer, version 1:
+///
+/// | offset | contents |
+/// | --- | --- |
+/// | 0 | `PSTR`, `u32` version, `u32` scans, `u32` bases |
+/// | 16 | `u16[scans]` each for A, C, G, T |
+/// | aligned to 4 | `u32[bases]` peak locations |
+/// | then | `u8[bases]` quality (zeros if the file has none), `u8[bases]` base calls (ASCII) |
+///
+/// Sample name and format are left to [`parse_trace`]'s metadata.
+fn trace_buffer(trace: &Trace) -> BinaryWriter {
+    let channels = &trace.channels;
+    let scans = channels.a.len();
+    let bases = trace.basecalls.len();
+    let mut quality = trace.quality.clone();
+    quality.resize(bases, 0);
+    let mut peaks = trace.peak_locations.clone();
+    peaks.resize(bases, 0);
+
+    let mut writer = BinaryWriter::new(b"PSTR", 1, 8 * scans + 6 * bases + 16);
+    writer
+        .u32(scans as u32)
+        .u32(bases as u32)
+        .u16s(&channels.a)
+        .u16s(&channels.c)
+        .u16s(&channels.g)
+        .u16s(&channels.t)
+        .u32s(&peaks)
+        .u8s(&quality)
+        .u8s(trace.basecalls.as_bytes());
+    writer
+}
+
+/// The trace in the layout of [`trace_buffer`].
+#[tauri::command]
+pub async fn get_trace_binary(app_handle: AppHandle, path: PathBuf) -> Result<tauri::ipc::Response, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let trace = app_handle.state::<TraceCache>().get(&path)?;
+        Ok(trace_buffer(&trace).finish())
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// Like [`get_trace_binary`], but written to a blob file, for traces from
+/// long runs that are too large to push through the bridge.
+#[tauri::command]
+pub async fn get_trace_blob(app_handle: AppHandle, path: PathBuf) -> Result<BlobHandle, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let trace = app_handle.state::<TraceCache>().get(&path)?;
+        trace_buffer(&trace).finish_to_blob(&app_handle.state::<BlobStore>())
+    })
+    .await
+    .map_err(join_error)?
+}
+
+/// [`get_trace_envelope`] as a binary buffer, version 1:
+///
+/// | offset | contents |
+/// | --- | --- |
+/// | 0 | `PSEV`, `u32` version, `u32` buckets, `u32` start, `u32` end |
+/// | 24 | `f64` scans per bucket |
+/// | 32 | `u16[buckets]` min then max, for A, C, G, T in turn |
+#[tauri::command]
+pub async fn get_trace_envelope_binary(
+    app_handle: AppHandle,
+    path: PathBuf,
+    start: usize,
+    end: usize,
+    width: usize,
+) -> Result<tauri::ipc::Response, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let trace = app_handle.state::<TraceCache>().get(&path)?;
+        let envelope = envelope::envelope(&trace, start, end, width);
+        let buckets = envelope.a.min.len();
+
+        let mut writer = BinaryWriter::new(b"PSEV", 1, 16 * buckets + 32);
+        writer
+            .u32(buckets as u32)
+            .u32(envelope.start as u32)
+            .u32(envelope.end as u32)
+            .f64(envelope.scans_per_bucket);
+        for channel in [&envelope.a, &envelope.c, &envelope.g, &envelope.t] {
+            writer.u16s(&channel.min).u16s(&channel.max);
+        }
+        Ok(writer.finish())
+    })
+    .await
+    .map_err(join_error)?
+}
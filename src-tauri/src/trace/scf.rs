@@ -0,0 +1,291 @@
+//! Staden SCF files (version 3, plus the older interleaved version 2), still
+//! written by some capillary sequencers and trace editors.
+
+use super::{Trace, TraceChannels, TraceFormat};
+use std::path::Path;
+
+pub const MAGIC: &[u8] = b".scf";
+
+const HEADER_LEN: usize = 128;
+/// Version 2 stores bases as fixed records: peak (4), probabilities (4), base (1), spare (3).
+const V2_BASE_LEN: usize = 12;
+
+struct Header {
+    samples: usize,
+    samples_offset: usize,
+    bases: usize,
+    bases_offset: usize,
+    comments_size: usize,
+    comments_offset: usize,
+    major_version: u8,
+    sample_size: usize,
+}
+
+fn be_u32(data: &[u8], at: usize) -> Option<usize> {
+    let bytes = data.get(at..at + 4)?.try_into().ok()?;
+    usize::try_from(u32::from_be_bytes(bytes)).ok()
+}
+
+fn read_header(data: &[u8]) -> Option<Header> {
+    if data.len() < HEADER_LEN {
+        return None;
+    }
+    Some(Header {
+        samples: be_u32(data, 4)?,
+        samples_offset: be_u32(data, 8)?,
+        bases: be_u32(data, 12)?,
+        bases_offset: be_u32(data, 24)?,
+        comments_size: be_u32(data, 28)?,
+        comments_offset: be_u32(data, 32)?,
+        // Stored as text, e.g. "3.00".
+        major_version: data[36].wrapping_sub(b'0'),
+        sample_size: be_u32(data, 40)?,
+    })
+}
+
+pub fn parse(path: &Path, data: &[u8]) -> Result<Trace, String> {
+    let header = read_header(data).ok_or("file is too short for an SCF header")?;
+    if header.sample_size != 1 && header.sample_size != 2 {
+        return Err(format!("unsupported sample size {}", header.sample_size));
+    }
+
+    let (channels, bases) = match header.major_version {
+        3 => (read_samples_v3(data, &header), read_bases_v3(data, &header)),
+        1 | 2 => (read_samples_v2(data, &header), read_bases_v2(data, &header)),
+        other => return Err(format!("unsupported SCF version {}", other)),
+    };
+    let channels = channels.ok_or("sample data is truncated")?;
+    let bases = bases.ok_or("base data is truncated")?;
+
+    let mut basecalls = String::with_capacity(bases.len());
+    let mut quality = Vec::with_capacity(bases.len());
+    let mut peak_locations = Vec::with_capacity(bases.len());
+    for base in bases {
+        basecalls.push(base.call as char);
+        quality.push(base.confidence());
+        peak_locations.push(base.peak);
+    }
+
+    Ok(Trace {
+        path: path.to_path_buf(),
+        format: TraceFormat::Scf,
+        sample_name: comment(data, &header, "NAME"),
+        basecalls,
+        quality,
+        peak_locations,
+        channels,
+    })
+}
+
+fn sample(data: &[u8], at: usize, size: usize) -> Option<u16> {
+    match size {
+        1 => data.get(at).map(|&value| u16::from(value)),
+        _ => Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?)),
+    }
+}
+
+/// Version 3 stores each channel in turn, delta-encoded twice.
+fn read_samples_v3(data: &[u8], header: &Header) -> Option<TraceChannels> {
+    let size = header.sample_size;
+    let channel = |index: usize| -> Option<Vec<u16>> {
+        let start = header.samples_offset + index * header.samples * size;
+        let mut values = (0..header.samples)
+            .map(|i| sample(data, start + i * size, size))
+            .collect::<Option<Vec<u16>>>()?;
+        for _ in 0..2 {
+            let mut previous = 0u16;
+            for value in &mut values {
+                *value = value.wrapping_add(previous);
+                previous = *value;
+            }
+        }
+        if size == 1 {
+            // Deltas wrap at a byte for 8-bit samples.
+            values.iter_mut().for_each(|value| *value &= 0xff);
+        }
+        Some(values)
+    };
+    Some(TraceChannels {
+        a: channel(0)?,
+        c: channel(1)?,
+        g: channel(2)?,
+        t: channel(3)?,
+    })
+}
+
+/// Version 2 interleaves the four channels per scan, unencoded.
+fn read_samples_v2(data: &[u8], header: &Header) -> Option<TraceChannels> {
+    let size = header.sample_size;
+    let mut channels = TraceChannels::default();
+    for i in 0..header.samples {
+        let at = header.samples_offset + i * 4 * size;
+        channels.a.push(sample(data, at, size)?);
+        channels.c.push(sample(data, at + size, size)?);
+        channels.g.push(sample(data, at + 2 * size, size)?);
+        channels.t.push(sample(data, at + 3 * size, size)?);
+    }
+    Some(channels)
+}
+
+struct Base {
+    peak: u32,
+    /// Probability of A, C, G and T.
+    probabilities: [u8; 4],
+    call: u8,
+}
+
+impl Base {
+    /// The called base's own probability, or the highest one for ambiguity codes.
+    fn confidence(&self) -> u8 {
+        match self.call.to_ascii_uppercase() {
+            b'A' => self.probabilities[0],
+            b'C' => self.probabilities[1],
+            b'G' => self.probabilities[2],
+            b'T' => self.probabilities[3],
+            _ => self.probabilities.iter().copied().max().unwrap_or(0),
+        }
+    }
+}
+
+/// Version 3 stores the peaks, then each probability, then the calls, as arrays.
+fn read_bases_v3(data: &[u8], header: &Header) -> Option<Vec<Base>> {
+    let count = header.bases;
+    let peaks = header.bases_offset;
+    let probabilities = peaks + 4 * count;
+    let calls = probabilities + 4 * count;
+    (0..count)
+        .map(|i| {
+            Some(Base {
+                peak: u32::try_from(be_u32(data, peaks + 4 * i)?).ok()?,
+                probabilities: [
+                    *data.get(probabilities + i)?,
+                    *data.get(probabilities + count + i)?,
+                    *data.get(probabilities + 2 * count + i)?,
+                    *data.get(probabilities + 3 * count + i)?,
+                ],
+                call: *data.get(calls + i)?,
+            })
+        })
+        .collect()
+}
+
+fn read_bases_v2(data: &[u8], header: &Header) -> Option<Vec<Base>> {
+    (0..header.bases)
+        .map(|i| {
+            let at = header.bases_offset + i * V2_BASE_LEN;
+            let record = data.get(at..at + V2_BASE_LEN)?;
+            Some(Base {
+                peak: u32::try_from(be_u32(record, 0)?).ok()?,
+                probabilities: record[4..8].try_into().ok()?,
+                call: record[8],
+            })
+        })
+        .collect()
+}
+
+/// A `KEY=value` line from the comments section.
+fn comment(data: &[u8], header: &Header, key: &str) -> Option<String> {
+    let comments = data.get(header.comments_offset..header.comments_offset + header.comments_size)?;
+    String::from_utf8_lossy(comments)
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(name, _)| name.trim() == key)
+        .map(|(_, value)| value.trim_matches(|c: char| c.is_whitespace() || c == '\0').to_string())
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn put(data: &mut [u8], at: usize, value: u32) {
+        data[at..at + 4].copy_from_slice(&value.to_be_bytes());
+    }
+
+    /// A header followed by `body`, with the sections at the given offsets.
+    fn scf(
+        version: &[u8; 4],
+        sample_size: u32,
+        counts: [u32; 2],
+        offsets: [u32; 2],
+        body: &[u8],
+    ) -> Vec<u8> {
+        let mut data = vec![0; HEADER_LEN];
+        data[..4].copy_from_slice(MAGIC);
+        put(&mut data, 4, counts[0]);
+        put(&mut data, 8, offsets[0]);
+        put(&mut data, 12, counts[1]);
+        put(&mut data, 24, offsets[1]);
+        data[36..40].copy_from_slice(version);
+        put(&mut data, 40, sample_size);
+        data.extend_from_slice(body);
+        data
+    }
+
+    #[test]
+    fn v3_decodes_delta_delta_samples_and_bases() {
+        let mut body = vec![
+            0x00, 0x05, 0x00, 0x00, 0x00, 0x05, 0xff, 0xf6, // A: 5, 10, 20, 20
+            0, 0, 0, 0, 0, 0, 0, 0, // C
+            0x00, 0x07, 0xff, 0xf9, 0x00, 0x00, 0x00, 0x00, // G: 7, 7, 7, 7
+            0, 0, 0, 0, 0, 0, 0, 0, // T
+        ];
+        body.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 3]);
+        body.extend_from_slice(&[40, 2, 1, 9, 0, 1, 0, 0]);
+        body.extend_from_slice(b"AN");
+        let comments_offset = HEADER_LEN + body.len();
+        body.extend_from_slice(b"NAME=sample1\n");
+        let mut data = scf(b"3.00", 2, [4, 2], [128, 160], &body);
+        put(&mut data, 28, 13);
+        put(&mut data, 32, comments_offset as u32);
+
+        let trace = parse(Path::new("a.scf"), &data).unwrap();
+        assert_eq!(trace.channels.a, [5, 10, 20, 20]);
+        assert_eq!(trace.channels.c, [0; 4]);
+        assert_eq!(trace.channels.g, [7; 4]);
+        assert_eq!(trace.basecalls, "AN");
+        assert_eq!(trace.quality, [40, 9]);
+        assert_eq!(trace.peak_locations, [1, 3]);
+        assert_eq!(trace.sample_name.as_deref(), Some("sample1"));
+    }
+
+    #[test]
+    fn v3_byte_samples_wrap() {
+        let mut body = vec![250, 16, 241, 6];
+        body.extend_from_slice(&[0; 12]);
+        let data = scf(b"3.00", 1, [4, 0], [128, 144], &body);
+
+        let trace = parse(Path::new("a.scf"), &data).unwrap();
+        assert_eq!(trace.channels.a, [250, 4, 255, 0]);
+        assert_eq!(trace.channels.t, [0; 4]);
+        assert!(trace.basecalls.is_empty());
+        assert_eq!(trace.sample_name, None);
+    }
+
+    #[test]
+    fn v2_reads_interleaved_samples_and_base_records() {
+        let body = [
+            0, 1, 0, 2, 0, 3, 0, 4, // scan 0
+            0, 5, 0, 6, 0, 7, 0, 8, // scan 1
+            0, 0, 0, 1, 0, 0, 30, 0, b'G', 0, 0, 0,
+        ];
+        let data = scf(b"2.00", 2, [2, 1], [128, 144], &body);
+
+        let trace = parse(Path::new("a.scf"), &data).unwrap();
+        assert_eq!(trace.channels.a, [1, 5]);
+        assert_eq!(trace.channels.c, [2, 6]);
+        assert_eq!(trace.channels.g, [3, 7]);
+        assert_eq!(trace.channels.t, [4, 8]);
+        assert_eq!(trace.basecalls, "G");
+        assert_eq!(trace.quality, [30]);
+        assert_eq!(trace.peak_locations, [1]);
+    }
+
+    #[test]
+    fn rejects_short_truncated_and_unknown_files() {
+        assert!(parse(Path::new("a.scf"), MAGIC).is_err());
+        assert!(parse(Path::new("a.scf"), &scf(b"4.00", 2, [0, 0], [128, 128], &[])).is_err());
+        assert!(parse(Path::new("a.scf"), &scf(b"3.00", 4, [0, 0], [128, 128], &[])).is_err());
+        assert!(parse(Path::new("a.scf"), &scf(b"3.00", 2, [4, 0], [128, 128], &[0; 8])).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+//! Quality trimming of a trace's base calls, cheap enough to rerun on every
+//! slider move of the trim preview.
+
+use super::Trace;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrimMethod {
+    /// Modified Mott: keeps the stretch where the summed
+    /// `error_limit - P(error)` is largest.
+    #[default]
+    Mott,
+    /// Cuts each end up to the first window whose mean quality reaches
+    /// `min_quality`.
+    Window,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrimParams {
+    pub method: TrimMethod,
+    /// Error probability above which a base counts against keeping it; Mott only.
+    pub error_limit: f64,
+    /// Bases per window; window only.
+    pub window: usize,
+    /// Mean Phred quality a window needs; window only.
+    pub min_quality: u8,
+}
+
+impl Default for TrimParams {
+    fn default() -> Self {
+        Self {
+            method: TrimMethod::default(),
+            error_limit: 0.05,
+            window: 10,
+            min_quality: 20,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TrimResult {
+    /// Kept base calls, 0-based, end exclusive; equal when nothing is kept.
+    pub start: usize,
+    pub end: usize,
+    /// Scan positions of the first and last kept base, for shading the
+    /// chromatogram.
+    pub start_scan: Option<u32>,
+    pub end_scan: Option<u32>,
+    pub sequence: String,
+    pub mean_quality: Option<f64>,
+}
+
+fn mott(quality: &[u8], error_limit: f64) -> (usize, usize) {
+    let (mut best, mut best_range) = (0.0, (0, 0));
+    let (mut sum, mut start) = (0.0, 0);
+    for (index, &q) in quality.iter().enumerate() {
+        sum += error_limit - 10f64.powf(-(q as f64) / 10.0);
+        if sum <= 0.0 {
+            sum = 0.0;
+            start = index + 1;
+        } else if sum > best {
+            best = sum;
+            best_range = (start, index + 1);
+        }
+    }
+    best_range
+}
+
+fn window(quality: &[u8], window: usize, min_quality: u8) -> (usize, usize) {
+    let window = window.clamp(1, quality.len().max(1));
+    let passes = |bases: &[u8]| {
+        bases.iter().map(|&q| q as usize).sum::<usize>() >= min_quality as usize * bases.len()
+    };
+    let Some(start) = quality.windows(window).position(passes) else {
+        return (0, 0);
+    };
+    let end = quality.len() - quality.windows(window).rev().position(passes).unwrap_or(0);
+    (start, end)
+}
+
+/// Trims `trace` by its base qualities.
+pub fn trim(trace: &Trace, params: &TrimParams) -> Result<TrimResult, AppError> {
+    let bases = trace.basecalls.len();
+    if trace.quality.len() != bases {
+        return Err(AppError::InvalidTrace(format!(
+            "{:?} has no quality values to trim by",
+            trace.path
+        )));
+    }
+    let quality = &trace.quality;
+    let (start, end) = match params.method {
+        TrimMethod::Mott => mott(quality, params.error_limit),
+        TrimMethod::Window => window(quality, params.window, params.min_quality),
+    };
+    let kept = &quality[start..end];
+    Ok(TrimResult {
+        start,
+        end,
+        start_scan: (start < end)
+            .then(|| trace.peak_locations.get(start).copied())
+            .flatten(),
+        end_scan: (start < end)
+            .then(|| trace.peak_locations.get(end - 1).copied())
+            .flatten(),
+        sequence: trace.basecalls[start..end].to_string(),
+        mean_quality: (!kept.is_empty())
+            .then(|| kept.iter().map(|&q| q as f64).sum::<f64>() / kept.len() as f64),
+    })
+}
@@ -0,0 +1,163 @@
+//! Runs the bundled tracy directly for single-trace work: basecalling,
+//! aligning a trace to a reference and decomposing a heterozygous trace.
+//! These skip the engine's queue, so they answer faster and still work while
+//! the engine is down; anything batch-sized still goes through a job.
+//!
+//! tracy writes its results to `<prefix>.json` (and, for decompose,
+//! `<prefix>.decomp.json`) in a scratch folder, which are read back into the
+//! types in [`model`].
+
+mod model;
+
+pub use model::{Alignment, Basecalls, Decomposition, Versioned};
+
+use crate::error::AppError;
+use crate::supervisor;
+use crate::temp::TempStore;
+use model::Validate;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+/// Name tracy is registered under with the supervisor.
+pub const TOOL_NAME: &str = "tracy";
+
+const PREFIX: &str = "out";
+
+/// Alignment settings shared by `align` and `decompose`; tracy's defaults
+/// for anything unset.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TracyOptions {
+    /// Gap open penalty, negative.
+    pub gap_open: Option<i32>,
+    /// Gap extension penalty, negative.
+    pub gap_extension: Option<i32>,
+    /// Trimming stringency from 1 (least) to 9; no trimming when unset.
+    pub trim: Option<u8>,
+    /// Secondary to primary peak ratio at which a base counts as double;
+    /// decompose only.
+    pub peak_ratio: Option<f64>,
+}
+
+impl TracyOptions {
+    fn args(&self, decompose: bool) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(gap_open) = self.gap_open {
+            args.extend(["-g".to_string(), gap_open.to_string()]);
+        }
+        if let Some(gap_extension) = self.gap_extension {
+            args.extend(["-e".to_string(), gap_extension.to_string()]);
+        }
+        if let Some(trim) = self.trim {
+            args.extend(["-t".to_string(), trim.clamp(1, 9).to_string()]);
+        }
+        if decompose {
+            if let Some(peak_ratio) = self.peak_ratio {
+                args.extend(["-p".to_string(), peak_ratio.to_string()]);
+            }
+        }
+        args
+    }
+}
+
+fn checked(path: &Path, what: &str) -> Result<String, AppError> {
+    if !path.is_file() {
+        return Err(AppError::Tool(format!(
+            "the {} {:?} does not exist",
+            what, path
+        )));
+    }
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Runs `tracy <subcommand>` with the arguments `args` builds from the
+/// output prefix, in a scratch folder, and reads back `<prefix><suffix>`.
+async fn run<T: DeserializeOwned + Validate>(
+    app_handle: &AppHandle,
+    subcommand: &str,
+    args: impl FnOnce(String) -> Vec<String>,
+    suffix: &str,
+) -> Result<Versioned<T>, AppError> {
+    let temp = app_handle.state::<TempStore>();
+    let scratch_name = format!(
+        "tracy-{}-{}",
+        subcommand,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos())
+    );
+    let scratch = temp.create(app_handle, &scratch_name)?;
+    let prefix = scratch.join(PREFIX).to_string_lossy().into_owned();
+    let mut command = vec![subcommand.to_string()];
+    command.extend(args(prefix));
+    let result = async {
+        supervisor::run(app_handle, TOOL_NAME, command).await?;
+        model::parse(&std::fs::read(
+            scratch.join(format!("{}{}", PREFIX, suffix)),
+        )?)
+    }
+    .await;
+    temp.remove(&scratch_name);
+    result
+}
+
+/// Basecalls the trace at `path`.
+#[tauri::command]
+pub async fn tracy_basecall(
+    app_handle: AppHandle,
+    path: PathBuf,
+) -> Result<Versioned<Basecalls>, AppError> {
+    let trace = checked(&path, "trace")?;
+    let args = |prefix: String| {
+        vec![
+            "-f".into(),
+            "json".into(),
+            "-o".into(),
+            format!("{}.json", prefix),
+            trace,
+        ]
+    };
+    run(&app_handle, "basecall", args, ".json").await
+}
+
+/// Aligns the trace at `path` to `reference`, a FASTA file or another trace.
+#[tauri::command]
+pub async fn tracy_align(
+    app_handle: AppHandle,
+    path: PathBuf,
+    reference: PathBuf,
+    options: Option<TracyOptions>,
+) -> Result<Versioned<Alignment>, AppError> {
+    let trace = checked(&path, "trace")?;
+    let reference = checked(&reference, "reference")?;
+    let args = |prefix: String| {
+        let mut args = vec!["-r".into(), reference, "-o".into(), prefix];
+        args.extend(options.unwrap_or_default().args(false));
+        args.push(trace);
+        args
+    };
+    run(&app_handle, "align", args, ".json").await
+}
+
+/// Separates the two alleles of a heterozygous trace at `path`, aligned to
+/// `reference`.
+#[tauri::command]
+pub async fn tracy_decompose(
+    app_handle: AppHandle,
+    path: PathBuf,
+    reference: PathBuf,
+    options: Option<TracyOptions>,
+) -> Result<Versioned<Decomposition>, AppError> {
+    let trace = checked(&path, "trace")?;
+    let reference = checked(&reference, "reference")?;
+    let args = |prefix: String| {
+        let mut args = vec!["-r".into(), reference, "-o".into(), prefix];
+        args.extend(options.unwrap_or_default().args(true));
+        args.push(trace);
+        args
+    };
+    run(&app_handle, "decompose", args, ".decomp.json").await
+}
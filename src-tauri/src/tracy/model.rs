@@ -0,0 +1,194 @@
+//! Typed versions of tracy's JSON outputs. Parsing them here means a tracy
+//! that writes something unexpected fails with a clear error instead of
+//! breaking the UI, and the frontend gets one stable, snake_case shape
+//! (versioned with [`MODEL_VERSION`]) whatever tracy release produced it.
+//!
+//! Field names are tracy's, taken as aliases; keys tracy writes that the app
+//! does not use are dropped.
+
+use crate::error::AppError;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+
+/// Bumped whenever a type below changes shape.
+pub const MODEL_VERSION: u32 = 1;
+
+/// A result as sent to the frontend.
+#[derive(Clone, Debug, Serialize)]
+pub struct Versioned<T> {
+    pub version: u32,
+    #[serde(flatten)]
+    pub data: T,
+}
+
+/// What every tracy output carries: the four channels and the basecalls.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TraceData {
+    /// Scan positions, one per sample of the channels.
+    #[serde(default)]
+    pub pos: Vec<u32>,
+    #[serde(alias = "peakA")]
+    pub peak_a: Vec<i32>,
+    #[serde(alias = "peakC")]
+    pub peak_c: Vec<i32>,
+    #[serde(alias = "peakG")]
+    pub peak_g: Vec<i32>,
+    #[serde(alias = "peakT")]
+    pub peak_t: Vec<i32>,
+    /// Scan position of every called base.
+    #[serde(alias = "basecallPos")]
+    pub basecall_pos: Vec<u32>,
+    /// Phred quality of every called base, when tracy reports it.
+    #[serde(alias = "basecallQual", default)]
+    pub basecall_qual: Vec<u32>,
+    /// Label per scan position, e.g. `"12:A"` or `"12:R|A"` for a double peak.
+    #[serde(default)]
+    pub basecalls: BTreeMap<String, String>,
+    #[serde(alias = "primarySeq", default)]
+    pub primary_seq: String,
+    #[serde(alias = "secondarySeq", default)]
+    pub secondary_seq: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Basecalls {
+    #[serde(flatten)]
+    pub trace: TraceData,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Alignment {
+    #[serde(flatten)]
+    pub trace: TraceData,
+    #[serde(alias = "refchr", alias = "ref1chr", default)]
+    pub ref_chr: Option<String>,
+    /// 1-based position of the alignment start on the reference.
+    #[serde(alias = "refpos", alias = "ref1pos", default)]
+    pub ref_pos: Option<u64>,
+    /// Whether the trace aligned to the forward strand.
+    #[serde(default, deserialize_with = "flag")]
+    pub forward: Option<bool>,
+    /// Reference and trace rows of the alignment, gapped to equal length.
+    #[serde(alias = "refalign", alias = "ref1align")]
+    pub ref_align: String,
+    #[serde(alias = "altalign", alias = "alt1align")]
+    pub alt_align: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DecompositionProfile {
+    /// Indel size tried.
+    pub x: Vec<i64>,
+    /// How well the trace decomposes with that indel; the best is the smallest.
+    pub y: Vec<f64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Decomposition {
+    #[serde(flatten)]
+    pub alignment: Alignment,
+    #[serde(default)]
+    pub decomposition: Option<DecompositionProfile>,
+    /// Size of the heterozygous indel found, 0 if none.
+    #[serde(alias = "hetindel", default)]
+    pub het_indel: Option<i64>,
+    /// Share of double peaks in the trace, in percent.
+    #[serde(alias = "hetper", default)]
+    pub het_percent: Option<f64>,
+    /// The two alleles aligned to the reference.
+    #[serde(alias = "allele1align", default)]
+    pub allele1_align: Option<String>,
+    #[serde(alias = "allele2align", default)]
+    pub allele2_align: Option<String>,
+}
+
+/// tracy writes flags as `true`/`false` or as `1`/`0` depending on version.
+fn flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<bool>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flag {
+        Bool(bool),
+        Number(i64),
+    }
+    Ok(
+        Option::<Flag>::deserialize(deserializer)?.map(|flag| match flag {
+            Flag::Bool(value) => value,
+            Flag::Number(value) => value != 0,
+        }),
+    )
+}
+
+/// Consistency checks beyond what the types enforce.
+pub trait Validate {
+    fn validate(&self) -> Result<(), String>;
+}
+
+impl Validate for TraceData {
+    fn validate(&self) -> Result<(), String> {
+        let samples = self.peak_a.len();
+        if self.peak_c.len() != samples
+            || self.peak_g.len() != samples
+            || self.peak_t.len() != samples
+        {
+            return Err("the four channels differ in length".to_string());
+        }
+        if !self.pos.is_empty() && self.pos.len() != samples {
+            return Err("positions and channels differ in length".to_string());
+        }
+        if !self.basecall_qual.is_empty() && self.basecall_qual.len() != self.basecall_pos.len() {
+            return Err("basecalls and qualities differ in number".to_string());
+        }
+        if let Some(&position) = self
+            .basecall_pos
+            .iter()
+            .find(|&&position| position as usize >= samples)
+        {
+            return Err(format!(
+                "basecall at scan {} is past the end of the trace",
+                position
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Validate for Basecalls {
+    fn validate(&self) -> Result<(), String> {
+        self.trace.validate()
+    }
+}
+
+impl Validate for Alignment {
+    fn validate(&self) -> Result<(), String> {
+        self.trace.validate()?;
+        if self.ref_align.len() != self.alt_align.len() {
+            return Err("the alignment rows differ in length".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Validate for Decomposition {
+    fn validate(&self) -> Result<(), String> {
+        self.alignment.validate()?;
+        if let Some(profile) = &self.decomposition {
+            if profile.x.len() != profile.y.len() {
+                return Err("the decomposition profile is malformed".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses and checks tracy's JSON output `contents`.
+pub fn parse<T: DeserializeOwned + Validate>(contents: &[u8]) -> Result<Versioned<T>, AppError> {
+    let data: T = serde_json::from_slice(contents)
+        .map_err(|e| AppError::Tool(format!("unexpected tracy output: {}", e)))?;
+    data.validate()
+        .map_err(|e| AppError::Tool(format!("inconsistent tracy output: {}", e)))?;
+    Ok(Versioned {
+        version: MODEL_VERSION,
+        data,
+    })
+}
@@ -0,0 +1,202 @@
+//! Tray icon showing the engine's state as a coloured dot on the app icon
+//! (green ready, amber busy starting or unwell, red down), with a menu to
+//! restart the engine, open the logs, pause the job queue and quit. Handy when
+//! the app sits minimised processing a watch folder.
+//!
+//! With the `close_to_tray` setting, closing the main window only hides it:
+//! the engine and the job queue carry on in the background until the window
+//! is brought back from the tray or by a deep link, or the app is quit from
+//! the tray.
+
+use crate::engine::{self, EngineManager, EngineState};
+use crate::error::AppError;
+use crate::instance;
+use crate::jobs;
+use crate::logging;
+use crate::settings::SettingsStore;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::image::Image;
+use tauri::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager, Window, WindowEvent, Wry};
+use tracing::{error, info, warn};
+
+const TRAY_ID: &str = "main";
+
+const GREEN: [u8; 3] = [0x2e, 0xb8, 0x5c];
+const AMBER: [u8; 3] = [0xf2, 0xa9, 0x00];
+const RED: [u8; 3] = [0xd9, 0x30, 0x25];
+
+/// Managed state: the menu items whose text or check mark follows the app.
+pub struct Tray {
+    status: MenuItem<Wry>,
+    pause_queue: CheckMenuItem<Wry>,
+    /// The main window was closed into the tray.
+    in_background: AtomicBool,
+}
+
+/// Creates the tray icon. Called once at startup.
+pub fn init(app_handle: &AppHandle) -> Result<(), AppError> {
+    let status = MenuItem::with_id(app_handle, "status", "Engine: stopped", false, None::<&str>)?;
+    let show = MenuItem::with_id(app_handle, "show", "Show PS Analyzer", true, None::<&str>)?;
+    let restart =
+        MenuItem::with_id(app_handle, "restart_engine", "Restart engine", true, None::<&str>)?;
+    let logs = MenuItem::with_id(app_handle, "open_logs", "Open logs", true, None::<&str>)?;
+    let pause_queue =
+        CheckMenuItem::with_id(app_handle, "pause_queue", "Pause queue", true, false, None::<&str>)?;
+    let quit = MenuItem::with_id(app_handle, "quit", "Quit PS Analyzer", true, None::<&str>)?;
+    let menu = Menu::with_items(
+        app_handle,
+        &[
+            &status,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &show,
+            &restart,
+            &logs,
+            &pause_queue,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &quit,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("PS Analyzer")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(on_menu_event)
+        .on_tray_icon_event(on_tray_icon_event);
+    if let Some(icon) = icon(app_handle, RED) {
+        builder = builder.icon(icon);
+    }
+    builder.build(app_handle)?;
+    app_handle.manage(Tray {
+        status,
+        pause_queue,
+        in_background: AtomicBool::new(false),
+    });
+    update(app_handle);
+    Ok(())
+}
+
+/// A left click brings the main window back; the menu is on the right button.
+fn on_tray_icon_event(tray: &TrayIcon, event: TrayIconEvent) {
+    if let TrayIconEvent::Click {
+        button: MouseButton::Left,
+        button_state: MouseButtonState::Up,
+        ..
+    } = event
+    {
+        show_main_window(tray.app_handle());
+    }
+}
+
+fn show_main_window(app_handle: &AppHandle) {
+    if let Some(tray) = app_handle.try_state::<Tray>() {
+        tray.in_background.store(false, Ordering::SeqCst);
+    }
+    instance::focus_main_window(app_handle);
+}
+
+/// Brings the main window back if it was closed into the tray, e.g. when a
+/// deep link arrives.
+pub fn restore_from_background(app_handle: &AppHandle) {
+    let in_background = app_handle
+        .try_state::<Tray>()
+        .is_some_and(|tray| tray.in_background.load(Ordering::SeqCst));
+    if in_background {
+        show_main_window(app_handle);
+    }
+}
+
+/// Window event hook: with `close_to_tray`, closing the main window hides it.
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    let WindowEvent::CloseRequested { api, .. } = event else {
+        return;
+    };
+    let app_handle = window.app_handle();
+    let Some(tray) = app_handle.try_state::<Tray>() else {
+        return;
+    };
+    if window.label() != "main" || !app_handle.state::<SettingsStore>().get().close_to_tray {
+        return;
+    }
+    api.prevent_close();
+    let _ = window.hide();
+    tray.in_background.store(true, Ordering::SeqCst);
+    info!("Main window closed into the tray; the engine and job queue keep running");
+}
+
+fn on_menu_event(app_handle: &AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        "show" => show_main_window(app_handle),
+        "restart_engine" => {
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = engine::restart(&app_handle) {
+                    error!("Failed to restart bio-engine from the tray: {}", e);
+                }
+            });
+        }
+        "open_logs" => {
+            if let Err(e) = logging::open_log_dir(app_handle) {
+                warn!("Could not open the log folder: {}", e);
+            }
+        }
+        "pause_queue" => {
+            let paused = !jobs::is_queue_paused(app_handle.state());
+            jobs::set_queue_paused(app_handle.clone(), paused);
+        }
+        "quit" => app_handle.exit(0),
+        _ => {}
+    }
+}
+
+/// The app icon with a dot of `color` in its lower right corner.
+fn icon(app_handle: &AppHandle, color: [u8; 3]) -> Option<Image<'static>> {
+    let base = app_handle.default_window_icon()?;
+    let (width, height) = (base.width(), base.height());
+    let mut rgba = base.rgba().to_vec();
+    let radius = width.min(height) as f32 / 4.0;
+    let (cx, cy) = (width as f32 - radius - 1.0, height as f32 - radius - 1.0);
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+            if dx * dx + dy * dy > radius * radius {
+                continue;
+            }
+            let offset = ((y * width + x) * 4) as usize;
+            rgba[offset..offset + 3].copy_from_slice(&color);
+            rgba[offset + 3] = 0xff;
+        }
+    }
+    Some(Image::new_owned(rgba, width, height))
+}
+
+/// Redraws the icon and menu from the engine state and the queue. Called
+/// whenever either changes.
+pub fn update(app_handle: &AppHandle) {
+    let (Some(tray), Some(state)) = (app_handle.tray_by_id(TRAY_ID), app_handle.try_state::<Tray>())
+    else {
+        return;
+    };
+    let engine = app_handle.state::<EngineManager>().state();
+    let (color, label) = match engine {
+        EngineState::Ready => (GREEN, "ready"),
+        EngineState::Starting => (AMBER, "starting"),
+        EngineState::Degraded => (AMBER, "not responding"),
+        EngineState::Paused => (AMBER, "paused"),
+        EngineState::Crashed => (RED, "crashed"),
+        EngineState::Stopped => (RED, "stopped"),
+    };
+    let paused = jobs::is_queue_paused(app_handle.state());
+
+    let _ = tray.set_icon(icon(app_handle, color));
+    let tooltip = if paused {
+        format!("PS Analyzer: engine {}, queue paused", label)
+    } else {
+        format!("PS Analyzer: engine {}", label)
+    };
+    let _ = tray.set_tooltip(Some(tooltip));
+    let _ = state.status.set_text(format!("Engine: {}", label));
+    let _ = state.pause_queue.set_checked(paused);
+}
@@ -0,0 +1,182 @@
+//! In-app updates through the Tauri updater, with a stable and a beta channel.
+
+use crate::engine;
+use crate::error::AppError;
+use crate::session::SessionManager;
+use crate::settings::SettingsStore;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_updater::{Update, UpdaterExt};
+use tracing::{info, warn};
+
+/// Emitted with an [`UpdateInfo`] when the launch check finds a newer release.
+pub const UPDATE_AVAILABLE_EVENT: &str = "update-available";
+
+/// Emitted with a [`DownloadProgress`] for every downloaded chunk.
+pub const UPDATE_PROGRESS_EVENT: &str = "update-download-progress";
+
+const STABLE_ENDPOINT: &str =
+    "https://github.com/lagosproject/ps-analyzer/releases/latest/download/latest.json";
+/// Beta builds publish their manifest on a rolling `beta` release.
+const BETA_ENDPOINT: &str =
+    "https://github.com/lagosproject/ps-analyzer/releases/download/beta/latest.json";
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    fn endpoint(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => STABLE_ENDPOINT,
+            UpdateChannel::Beta => BETA_ENDPOINT,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub notes: Option<String>,
+    pub channel: UpdateChannel,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DownloadProgress {
+    pub downloaded: u64,
+    pub content_length: Option<u64>,
+}
+
+/// The update found by the last check, kept until the user installs it.
+#[derive(Default)]
+pub struct PendingUpdate(Mutex<Option<Update>>);
+
+/// The minisign public key releases are signed with, shared by the app,
+/// sidecar and tool updates. Without one nothing downloaded can be verified,
+/// so none of them run.
+pub fn signing_key(app_handle: &AppHandle) -> Result<String, AppError> {
+    app_handle
+        .config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|updater| updater.get("pubkey"))
+        .and_then(|key| key.as_str())
+        .filter(|key| !key.trim().is_empty())
+        .map(str::to_string)
+        .ok_or(AppError::UpdatesNotConfigured)
+}
+
+fn load_channel(app_handle: &AppHandle) -> UpdateChannel {
+    app_handle.state::<SettingsStore>().get().update_channel
+}
+
+async fn check(app_handle: &AppHandle) -> Result<Option<UpdateInfo>, AppError> {
+    signing_key(app_handle)?;
+    let channel = load_channel(app_handle);
+    let endpoint = Url::parse(channel.endpoint()).expect("update endpoints are valid URLs");
+    let update = app_handle
+        .updater_builder()
+        .endpoints(vec![endpoint])?
+        .build()?
+        .check()
+        .await?;
+
+    let info = update.as_ref().map(|update| UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        notes: update.body.clone(),
+        channel,
+    });
+    *app_handle.state::<PendingUpdate>().0.lock().unwrap() = update;
+    Ok(info)
+}
+
+/// Checks for updates in the background right after launch.
+pub fn check_on_launch(app_handle: &AppHandle) {
+    if crate::portable::root().is_some() {
+        return;
+    }
+    if signing_key(app_handle).is_err() {
+        info!("Updates are not configured in this build, not checking for them");
+        return;
+    }
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        match check(&app_handle).await {
+            Ok(Some(info)) => {
+                info!("Update {} available on the {:?} channel", info.version, info.channel);
+                let _ = app_handle.emit(UPDATE_AVAILABLE_EVENT, info);
+            }
+            Ok(None) => info!("PS Analyzer is up to date"),
+            Err(e) => warn!("Update check failed: {}", e),
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn check_for_updates(app_handle: AppHandle) -> Result<Option<UpdateInfo>, AppError> {
+    check(&app_handle).await
+}
+
+/// Downloads and installs the update found by the last check, then relaunches.
+/// The engine is stopped first so its binaries can be replaced.
+#[tauri::command]
+pub async fn install_update(app_handle: AppHandle) -> Result<(), AppError> {
+    if crate::portable::root().is_some() {
+        return Err(AppError::PortableUnsupported("installing updates"));
+    }
+    signing_key(&app_handle)?;
+    let Some(update) = app_handle.state::<PendingUpdate>().0.lock().unwrap().take() else {
+        return Err(AppError::NoPendingUpdate);
+    };
+
+    info!("Downloading update {}", update.version);
+    let mut downloaded = 0u64;
+    let bytes = update
+        .download(
+            |chunk_length, content_length| {
+                downloaded += chunk_length as u64;
+                let _ = app_handle.emit(
+                    UPDATE_PROGRESS_EVENT,
+                    DownloadProgress {
+                        downloaded,
+                        content_length,
+                    },
+                );
+            },
+            || info!("Update downloaded, verifying signature"),
+        )
+        .await?;
+
+    let handle = app_handle.clone();
+    let _ = tauri::async_runtime::spawn_blocking(move || engine::shutdown(&handle)).await;
+
+    // On Windows this hands over to the installer and exits.
+    update.install(bytes)?;
+    info!("Update {} installed, restarting", update.version);
+    // Relaunching may not go through `RunEvent::Exit`, but this is a graceful exit.
+    app_handle.state::<SessionManager>().end();
+    app_handle.restart();
+}
+
+#[tauri::command]
+pub fn get_update_channel(app_handle: AppHandle) -> UpdateChannel {
+    load_channel(&app_handle)
+}
+
+#[tauri::command]
+pub fn set_update_channel(app_handle: AppHandle, channel: UpdateChannel) -> Result<(), AppError> {
+    app_handle
+        .state::<SettingsStore>()
+        .update(|settings| settings.update_channel = channel)?;
+    // An update found on the other channel no longer applies.
+    app_handle.state::<PendingUpdate>().0.lock().unwrap().take();
+    Ok(())
+}
@@ -0,0 +1,260 @@
+//! Streams large inputs (multi-GB FASTQ) to the engine in chunks, so the
+//! frontend never has to hold a whole file in memory to post it.
+//!
+//! Uses the engine's chunked upload API:
+//! - `POST /upload/sessions` `{file_name, size}` → `{upload_id}`
+//! - `GET /upload/sessions/{id}` → `{received}`, the bytes stored so far
+//! - `PUT /upload/sessions/{id}` with `Content-Range: bytes a-b/size` → `{received}`
+//! - `POST /upload/sessions/{id}/complete` → `{path}`, the server-side path
+//!
+//! Sessions are remembered in `uploads.json`, so uploading the same unchanged
+//! file again (after a failure or an app restart) continues where it stopped.
+
+use crate::engine::{EngineClient, EngineManager};
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_http::reqwest::{self, header, Method, StatusCode};
+use tracing::{info, warn};
+
+/// Emitted with an [`UploadProgress`] after every chunk.
+pub const UPLOAD_PROGRESS_EVENT: &str = "upload-progress";
+
+const CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+const CHUNK_ATTEMPTS: u32 = 3;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+const SESSIONS_FILE: &str = "uploads.json";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UploadProgress {
+    pub path: PathBuf,
+    pub uploaded: u64,
+    pub total: u64,
+}
+
+/// An unfinished upload, valid while the file is unchanged.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+struct SessionRecord {
+    path: PathBuf,
+    size: u64,
+    modified: u64,
+    upload_id: String,
+}
+
+#[derive(Deserialize)]
+struct CreatedSession {
+    upload_id: String,
+}
+
+#[derive(Deserialize)]
+struct SessionState {
+    received: u64,
+}
+
+#[derive(Deserialize)]
+struct CompletedUpload {
+    path: String,
+}
+
+/// Uploads the user asked to cancel, checked between chunks.
+#[derive(Default)]
+pub struct Uploads {
+    cancelled: Mutex<HashSet<PathBuf>>,
+}
+
+fn sessions_path(app_handle: &AppHandle) -> Option<PathBuf> {
+    crate::portable::app_data_dir(app_handle).ok().map(|dir| dir.join(SESSIONS_FILE))
+}
+
+fn load_sessions(app_handle: &AppHandle) -> Vec<SessionRecord> {
+    sessions_path(app_handle)
+        .and_then(|path| std::fs::read(path).ok())
+        .and_then(|contents| serde_json::from_slice(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_sessions(app_handle: &AppHandle, sessions: &[SessionRecord]) {
+    let Some(path) = sessions_path(app_handle) else {
+        return;
+    };
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, serde_json::to_vec_pretty(sessions).unwrap_or_default()));
+    if let Err(e) = written {
+        warn!("Could not save upload sessions, interrupted uploads will restart: {}", e);
+    }
+}
+
+fn remember(app_handle: &AppHandle, record: &SessionRecord) {
+    let mut sessions = load_sessions(app_handle);
+    sessions.retain(|session| session.path != record.path);
+    sessions.push(record.clone());
+    save_sessions(app_handle, &sessions);
+}
+
+fn forget(app_handle: &AppHandle, path: &Path) {
+    let mut sessions = load_sessions(app_handle);
+    sessions.retain(|session| session.path != path);
+    save_sessions(app_handle, &sessions);
+}
+
+fn upload_error(detail: impl std::fmt::Display) -> AppError {
+    AppError::Upload(detail.to_string())
+}
+
+async fn json<T: for<'de> Deserialize<'de>>(response: reqwest::Response) -> Result<T, AppError> {
+    let status = response.status();
+    let body = response.bytes().await?;
+    if !status.is_success() {
+        return Err(upload_error(format!(
+            "engine answered {}: {}",
+            status,
+            String::from_utf8_lossy(&body)
+        )));
+    }
+    serde_json::from_slice(&body).map_err(|e| upload_error(format!("unexpected engine response: {}", e)))
+}
+
+/// The stored session for this exact file and how much of it the engine has,
+/// if the engine still knows it.
+async fn resume(client: &EngineClient, record: &SessionRecord) -> Option<u64> {
+    let path = format!("/upload/sessions/{}", record.upload_id);
+    let request = client.request(Method::GET, &path).timeout(REQUEST_TIMEOUT);
+    let response = client.execute(request).await.ok()?;
+    json::<SessionState>(response).await.ok().map(|state| state.received)
+}
+
+fn read_chunk(file: &mut File, offset: u64, size: u64) -> Result<Vec<u8>, AppError> {
+    let length = CHUNK_SIZE.min(size - offset);
+    let mut chunk = Vec::with_capacity(length as usize);
+    file.seek(SeekFrom::Start(offset))?;
+    file.take(length).read_to_end(&mut chunk)?;
+    Ok(chunk)
+}
+
+/// Uploads `path` to the engine and returns its path on the engine side.
+#[tauri::command]
+pub async fn upload_file(app_handle: AppHandle, path: PathBuf) -> Result<String, AppError> {
+    let path = path.canonicalize()?;
+    let metadata = std::fs::metadata(&path)?;
+    let size = metadata.len();
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let manager = app_handle.state::<EngineManager>();
+    if !manager.is_ready() {
+        return Err(AppError::EngineNotRunning);
+    }
+    let client = app_handle.state::<EngineClient>();
+    app_handle.state::<Uploads>().cancelled.lock().unwrap().remove(&path);
+
+    let stored = load_sessions(&app_handle)
+        .into_iter()
+        .find(|session| session.path == path && session.size == size && session.modified == modified);
+    let resumed = match &stored {
+        Some(record) => resume(&client, record).await.map(|received| (record.clone(), received)),
+        None => None,
+    };
+    let (record, mut offset) = match resumed {
+        Some((record, received)) => {
+            info!("Resuming upload of {:?} at {} of {} bytes", path, received, size);
+            (record, received.min(size))
+        }
+        None => {
+            let request = client
+                .request(Method::POST, "/upload/sessions")
+                .timeout(REQUEST_TIMEOUT)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(serde_json::to_vec(&serde_json::json!({ "file_name": file_name, "size": size })).unwrap_or_default());
+            let response = client.execute(request).await?;
+            if response.status() == StatusCode::NOT_FOUND {
+                return Err(upload_error("this bio-engine version does not support chunked uploads"));
+            }
+            let created: CreatedSession = json(response).await?;
+            let record = SessionRecord {
+                path: path.clone(),
+                size,
+                modified,
+                upload_id: created.upload_id,
+            };
+            remember(&app_handle, &record);
+            info!("Uploading {:?} ({} bytes)", path, size);
+            (record, 0)
+        }
+    };
+
+    let session_path = format!("/upload/sessions/{}", record.upload_id);
+    let mut file = File::open(&path)?;
+    while offset < size {
+        if app_handle.state::<Uploads>().cancelled.lock().unwrap().remove(&path) {
+            info!("Upload of {:?} cancelled at {} bytes", path, offset);
+            return Err(upload_error("upload cancelled"));
+        }
+
+        let chunk = read_chunk(&mut file, offset, size)?;
+        let range = format!("bytes {}-{}/{}", offset, offset + chunk.len() as u64 - 1, size);
+        let mut attempt = 1;
+        let state: SessionState = loop {
+            let request = client
+                .request(Method::PUT, &session_path)
+                .timeout(REQUEST_TIMEOUT)
+                .header(header::CONTENT_RANGE, &range)
+                .header(header::CONTENT_TYPE, "application/octet-stream")
+                .body(chunk.clone());
+            let result = match client.execute(request).await {
+                Ok(response) => json(response).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(state) => break state,
+                Err(e) if attempt < CHUNK_ATTEMPTS => {
+                    warn!("Chunk {} of {:?} failed (attempt {}), retrying: {}", range, path, attempt, e);
+                    tokio::time::sleep(Duration::from_secs(u64::from(attempt))).await;
+                    attempt += 1;
+                }
+                // The session is kept, so the next attempt resumes from here.
+                Err(e) => return Err(e),
+            }
+        };
+        offset = state.received;
+        let _ = app_handle.emit(
+            UPLOAD_PROGRESS_EVENT,
+            UploadProgress {
+                path: path.clone(),
+                uploaded: offset,
+                total: size,
+            },
+        );
+    }
+
+    let request = client
+        .request(Method::POST, &format!("{}/complete", session_path))
+        .timeout(REQUEST_TIMEOUT);
+    let response = client.execute(request).await?;
+    let completed: CompletedUpload = json(response).await?;
+    forget(&app_handle, &path);
+    info!("Uploaded {:?} as {}", path, completed.path);
+    Ok(completed.path)
+}
+
+/// Stops an upload after its current chunk. It can be resumed later by
+/// uploading the same file again.
+#[tauri::command]
+pub fn cancel_upload(uploads: tauri::State<Uploads>, path: PathBuf) {
+    let path = path.canonicalize().unwrap_or(path);
+    uploads.cancelled.lock().unwrap().insert(path);
+}
@@ -0,0 +1,184 @@
+//! Watch folders: directories, such as where a sequencer drops its `.ab1`
+//! files, whose new files are analysed automatically with a chosen preset.
+//!
+//! Folders are polled rather than watched through OS events, which network
+//! shares (where sequencers usually write) do not deliver reliably. A file is
+//! only queued once it has stopped changing, since sequencers write traces in
+//! several steps. Files already there when a folder is first polled are left
+//! alone. The user hears about the results through [`notify`](crate::notify),
+//! and about a folder that has gone missing through [`WATCH_FOLDER_MISSING_EVENT`].
+
+use crate::jobs::{self, JobPriority};
+use crate::settings::{SettingsStore, WatchFolder};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{debug, info, warn};
+
+/// Emitted with the path when a watch folder cannot be found, once until it
+/// is back.
+pub const WATCH_FOLDER_MISSING_EVENT: &str = "watch-folder-missing";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How long a new file must stay the same size before it is analysed.
+const SETTLE_TIME: Duration = Duration::from_secs(10);
+
+/// A new file not yet settled: its size and modification time when last seen,
+/// and since when they have not changed.
+struct Pending {
+    size: u64,
+    modified: Option<SystemTime>,
+    since: Instant,
+}
+
+/// Managed state of the watcher.
+#[derive(Default)]
+pub struct FolderWatcher {
+    /// Folders polled at least once; their files at that time count as known.
+    baselined: Mutex<HashSet<PathBuf>>,
+    known: Mutex<HashSet<PathBuf>>,
+    pending: Mutex<HashMap<PathBuf, Pending>>,
+    /// Folders already reported missing.
+    missing: Mutex<HashSet<PathBuf>>,
+}
+
+/// Polls the folders in the `watch_folders` setting for as long as the app runs.
+pub fn start(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let folders = app_handle.state::<SettingsStore>().get().watch_folders;
+            for folder in &folders {
+                poll(&app_handle, folder);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+fn poll(app_handle: &AppHandle, folder: &WatchFolder) {
+    let watcher = app_handle.state::<FolderWatcher>();
+    // Settings only check that a folder exists when it is added, and shares
+    // come and go.
+    if !folder.path.is_dir() {
+        if watcher.missing.lock().unwrap().insert(folder.path.clone()) {
+            warn!("Watch folder {:?} does not exist or is not reachable", folder.path);
+            let _ = app_handle.emit(WATCH_FOLDER_MISSING_EVENT, &folder.path);
+        }
+        return;
+    }
+    if watcher.missing.lock().unwrap().remove(&folder.path) {
+        info!("Watch folder {:?} is reachable again", folder.path);
+    }
+    let files = list(&folder.path, folder.recursive, &folder.extensions);
+
+    if watcher.baselined.lock().unwrap().insert(folder.path.clone()) {
+        debug!("Watching {:?}, {} existing files ignored", folder.path, files.len());
+        watcher.known.lock().unwrap().extend(files.into_iter().map(|(path, _, _)| path));
+        return;
+    }
+
+    let mut known = watcher.known.lock().unwrap();
+    let mut pending = watcher.pending.lock().unwrap();
+    let mut settled = Vec::new();
+    for (path, size, modified) in files {
+        if known.contains(&path) {
+            continue;
+        }
+        match pending.get_mut(&path) {
+            Some(entry) if entry.size == size && entry.modified == modified => {
+                if entry.since.elapsed() >= SETTLE_TIME {
+                    pending.remove(&path);
+                    known.insert(path.clone());
+                    settled.push(path);
+                }
+            }
+            Some(entry) => {
+                entry.size = size;
+                entry.modified = modified;
+                entry.since = Instant::now();
+            }
+            None => {
+                pending.insert(
+                    path,
+                    Pending {
+                        size,
+                        modified,
+                        since: Instant::now(),
+                    },
+                );
+            }
+        }
+    }
+    drop(pending);
+    drop(known);
+
+    for path in settled {
+        submit(app_handle, folder, path);
+    }
+}
+
+/// Supported files under `dir` with their size and modification time.
+fn list(dir: &Path, recursive: bool, extensions: &[String]) -> Vec<(PathBuf, u64, Option<SystemTime>)> {
+    let mut files = Vec::new();
+    let mut folders = vec![dir.to_path_buf()];
+    while let Some(folder) = folders.pop() {
+        let Ok(entries) = std::fs::read_dir(&folder) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(kind) = entry.file_type() else {
+                continue;
+            };
+            let path = entry.path();
+            if kind.is_dir() {
+                if recursive {
+                    folders.push(path);
+                }
+                continue;
+            }
+            let matches = path
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+                .is_some_and(|extension| extensions.iter().any(|wanted| wanted.eq_ignore_ascii_case(&extension)));
+            if !kind.is_file() || !matches {
+                continue;
+            }
+            if let Ok(metadata) = entry.metadata() {
+                files.push((path, metadata.len(), metadata.modified().ok()));
+            }
+        }
+    }
+    files
+}
+
+/// Queues `path` for analysis with the folder's preset, as a single-read sample
+/// named after the file.
+fn submit(app_handle: &AppHandle, folder: &WatchFolder, path: PathBuf) {
+    let sample = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut request = folder.preset.clone();
+    if let Value::Object(object) = &mut request {
+        object
+            .entry("name")
+            .or_insert_with(|| Value::String(format!("Watch folder: {}", sample)));
+        object.insert(
+            "patients".to_string(),
+            json!([{
+                "id": sample,
+                "name": sample,
+                "reads": [{ "id": sample, "file": path }],
+            }]),
+        );
+    }
+
+    match jobs::submit_job(app_handle.clone(), request, Some(JobPriority::Batch)) {
+        Ok(job) => info!("Queued job {} for new file {:?}", job.id, path),
+        Err(e) => warn!("Could not queue {:?} for analysis: {}", path, e),
+    }
+}
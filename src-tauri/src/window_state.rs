@@ -0,0 +1,216 @@
+//! Window size, position, maximized state and monitor, saved when a window
+//! closes and when the app exits, and restored when it next opens.
+//!
+//! A saved position is only used if the window's title bar would land on a
+//! connected monitor, so unplugging the screen it was on does not leave the
+//! window off screen; otherwise it is centred on the primary monitor. The
+//! size is likewise shrunk to fit the monitor it opens on.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize, Window, WindowEvent};
+use tracing::{debug, info, warn};
+
+const STATE_FILE: &str = "window-state.json";
+
+/// Windows whose geometry is fixed by the config.
+const UNTRACKED: [&str; 1] = ["splashscreen"];
+
+/// Smallest size restored, in case a bad save shrank the window to nothing.
+const MIN_WIDTH: u32 = 400;
+const MIN_HEIGHT: u32 = 300;
+
+/// How much of the window's top edge must be on a monitor for the saved
+/// position to be used: enough of the title bar to grab it.
+const GRAB_WIDTH: i32 = 100;
+const GRAB_HEIGHT: i32 = 40;
+
+/// Geometry of one window in physical pixels. Position and size are those of
+/// the window when last neither maximized nor minimized, so unmaximizing
+/// after a restore returns to them.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct WindowGeometry {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    maximized: bool,
+    /// Name of the monitor the window was on.
+    monitor: Option<String>,
+}
+
+/// Managed state: the geometry of each window by label, kept current as
+/// windows move and written out on close and exit.
+pub struct WindowStates {
+    path: Option<PathBuf>,
+    windows: Mutex<HashMap<String, WindowGeometry>>,
+    /// Labels whose saved maximized state is still to be applied on first show.
+    pending_maximize: Mutex<Vec<String>>,
+}
+
+impl WindowStates {
+    /// Loads the saved state. A missing or unreadable file restores nothing.
+    pub fn open(app_handle: &AppHandle) -> Self {
+        let path = crate::portable::app_data_dir(app_handle)
+            .ok()
+            .map(|dir| dir.join(STATE_FILE));
+        let windows = path
+            .as_ref()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|contents| serde_json::from_slice(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            windows: Mutex::new(windows),
+            pending_maximize: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Writes the state of every window seen this session.
+    pub fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let contents =
+            serde_json::to_vec_pretty(&*self.windows.lock().unwrap()).unwrap_or_default();
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(path, contents));
+        if let Err(e) = written {
+            warn!("Could not save the window state: {}", e);
+        }
+    }
+}
+
+fn tracked(window: &Window) -> bool {
+    !UNTRACKED.contains(&window.label())
+}
+
+/// Whether the top `GRAB_WIDTH` × `GRAB_HEIGHT` of a window at `x, y` would
+/// be on `monitor`.
+fn grabbable_on(monitor: &Monitor, x: i32, y: i32, width: u32) -> bool {
+    let (mx, my) = (monitor.position().x, monitor.position().y);
+    let (mw, mh) = (monitor.size().width as i32, monitor.size().height as i32);
+    let grab_right = x + (width as i32).min(GRAB_WIDTH);
+    let overlap_x = grab_right.min(mx + mw) - x.max(mx);
+    let overlap_y = (y + GRAB_HEIGHT).min(my + mh) - y.max(my);
+    overlap_x >= GRAB_WIDTH.min(width as i32) / 2 && overlap_y >= GRAB_HEIGHT / 2
+}
+
+/// Applies the saved geometry of `window`, if any, before it is first shown.
+/// Maximizing waits for [`restore_maximized`], as maximizing a hidden window
+/// shows it on some platforms.
+pub fn restore(window: &Window) {
+    let app_handle = window.app_handle();
+    let Some(states) = app_handle.try_state::<WindowStates>() else {
+        return;
+    };
+    let Some(saved) = states.windows.lock().unwrap().get(window.label()).cloned() else {
+        return;
+    };
+    let monitors = window.available_monitors().unwrap_or_default();
+
+    let target = monitors
+        .iter()
+        .find(|monitor| grabbable_on(monitor, saved.x, saved.y, saved.width));
+    let fallback = || {
+        window
+            .primary_monitor()
+            .ok()
+            .flatten()
+            .or_else(|| monitors.first().cloned())
+    };
+    let monitor = target.cloned().or_else(fallback);
+
+    let (mut width, mut height) = (saved.width.max(MIN_WIDTH), saved.height.max(MIN_HEIGHT));
+    if let Some(monitor) = &monitor {
+        width = width.min(monitor.size().width);
+        height = height.min(monitor.size().height);
+    }
+    let _ = window.set_size(PhysicalSize::new(width, height));
+
+    match (target, &monitor) {
+        (Some(_), _) => {
+            let _ = window.set_position(PhysicalPosition::new(saved.x, saved.y));
+        }
+        (None, Some(monitor)) => {
+            info!(
+                "Monitor {} of window {} is no longer connected; centring it",
+                saved.monitor.as_deref().unwrap_or("(unnamed)"),
+                window.label()
+            );
+            let x = monitor.position().x + (monitor.size().width.saturating_sub(width) / 2) as i32;
+            let y =
+                monitor.position().y + (monitor.size().height.saturating_sub(height) / 2) as i32;
+            let _ = window.set_position(PhysicalPosition::new(x, y));
+        }
+        (None, None) => {}
+    }
+
+    if saved.maximized {
+        states
+            .pending_maximize
+            .lock()
+            .unwrap()
+            .push(window.label().to_string());
+    }
+    debug!("Restored window {} to {:?}", window.label(), saved);
+}
+
+/// Maximizes `window` if it was maximized when last closed. Called right after
+/// a restored window is first shown.
+pub fn restore_maximized(window: &Window) {
+    let Some(states) = window.app_handle().try_state::<WindowStates>() else {
+        return;
+    };
+    let mut pending = states.pending_maximize.lock().unwrap();
+    if let Some(index) = pending.iter().position(|label| label == window.label()) {
+        pending.remove(index);
+        let _ = window.maximize();
+    }
+}
+
+/// Records the current geometry of `window`.
+fn record(window: &Window, states: &WindowStates) {
+    if window.is_minimized().unwrap_or(false) || !window.is_visible().unwrap_or(false) {
+        return;
+    }
+    let maximized = window.is_maximized().unwrap_or(false);
+    let mut windows = states.windows.lock().unwrap();
+    let geometry = windows.entry(window.label().to_string()).or_default();
+    geometry.maximized = maximized;
+    if maximized {
+        return;
+    }
+    if let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) {
+        geometry.x = position.x;
+        geometry.y = position.y;
+        geometry.width = size.width;
+        geometry.height = size.height;
+    }
+    if let Ok(Some(monitor)) = window.current_monitor() {
+        geometry.monitor = monitor.name().cloned();
+    }
+}
+
+/// Window event hook: follows moves and resizes, and saves when a window closes.
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    if !tracked(window) {
+        return;
+    }
+    let app_handle = window.app_handle();
+    let Some(states) = app_handle.try_state::<WindowStates>() else {
+        return;
+    };
+    match event {
+        WindowEvent::Moved(_) | WindowEvent::Resized(_) => record(window, &states),
+        WindowEvent::CloseRequested { .. } => {
+            record(window, &states);
+            states.save();
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,225 @@
+//! Additional windows beside the main one, so analyses can sit side by side:
+//! one per project or per chromatogram, each with its own label
+//! (`project-3`, `trace-7`).
+//!
+//! A window is opened with the state it should start from, e.g. the view of a
+//! trace being popped out, which the new window collects once with
+//! `take_window_state`. Windows talk to each other with `send_to_window`,
+//! which delivers to that window only; [`WINDOW_CLOSED_EVENT`] tells the rest
+//! when one goes, so a popped-out trace can be docked again.
+
+use crate::error::AppError;
+use crate::window_state;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{
+    AppHandle, Emitter, Manager, PhysicalPosition, WebviewUrl, WebviewWindowBuilder, Window,
+    WindowEvent,
+};
+use tracing::info;
+
+/// Emitted to every window with the label of a window that has closed.
+pub const WINDOW_CLOSED_EVENT: &str = "window-closed";
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowKind {
+    Project,
+    Trace,
+}
+
+impl WindowKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            WindowKind::Project => "project",
+            WindowKind::Trace => "trace",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenWindowRequest {
+    pub kind: WindowKind,
+    /// What the window shows, e.g. the project or trace path. A second
+    /// request for an open key focuses that window instead.
+    pub key: Option<String>,
+    pub title: Option<String>,
+    /// Frontend route to load, e.g. `analysis/42`; the start page if omitted.
+    pub route: Option<String>,
+    /// Handed to the new window through `take_window_state`.
+    pub state: Option<Value>,
+    /// Open on a different monitor from the requesting window, if there is one.
+    #[serde(default)]
+    pub other_monitor: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct WindowInfo {
+    pub label: String,
+    pub kind: WindowKind,
+    pub key: Option<String>,
+    pub title: String,
+}
+
+/// A message from one window to another.
+#[derive(Clone, Debug, Serialize)]
+pub struct WindowMessage {
+    /// Label of the sending window.
+    pub from: String,
+    pub payload: Value,
+}
+
+/// Managed state: the additional windows and the state each is to start from.
+#[derive(Default)]
+pub struct Windows {
+    next_id: AtomicU64,
+    open: Mutex<Vec<WindowInfo>>,
+    handoff: Mutex<HashMap<String, Value>>,
+}
+
+/// Centres a window of `window`'s size on a monitor other than the one
+/// `source` is on. Returns false if there is no other monitor.
+fn move_to_other_monitor(window: &Window, source: &Window) -> bool {
+    let current = source
+        .current_monitor()
+        .ok()
+        .flatten()
+        .map(|monitor| *monitor.position());
+    let other = window
+        .available_monitors()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|monitor| Some(*monitor.position()) != current);
+    let (Some(monitor), Ok(size)) = (other, window.outer_size()) else {
+        return false;
+    };
+    let x = monitor.position().x + (monitor.size().width.saturating_sub(size.width) / 2) as i32;
+    let y = monitor.position().y + (monitor.size().height.saturating_sub(size.height) / 2) as i32;
+    window.set_position(PhysicalPosition::new(x, y)).is_ok()
+}
+
+/// Opens an additional window, or focuses the one already showing `key`.
+/// Returns the window's label.
+///
+/// Async, since creating a window from a synchronous command deadlocks on Windows.
+#[tauri::command]
+pub async fn open_window(
+    app_handle: AppHandle,
+    source: Window,
+    request: OpenWindowRequest,
+) -> Result<String, AppError> {
+    let windows = app_handle.state::<Windows>();
+    let existing = request.key.as_ref().and_then(|key| {
+        windows
+            .open
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|info| info.kind == request.kind && info.key.as_ref() == Some(key))
+            .map(|info| info.label.clone())
+    });
+    if let Some(label) = existing {
+        if let Some(window) = app_handle.get_webview_window(&label) {
+            let _ = window.unminimize();
+            let _ = window.set_focus();
+            return Ok(label);
+        }
+    }
+
+    let id = windows.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+    let label = format!("{}-{}", request.kind.as_str(), id);
+    let title = request.title.unwrap_or_else(|| {
+        request
+            .key
+            .clone()
+            .unwrap_or_else(|| "PS Analyzer".to_string())
+    });
+    if let Some(state) = request.state {
+        windows.handoff.lock().unwrap().insert(label.clone(), state);
+    }
+
+    let route = request.route.unwrap_or_default();
+    let created = WebviewWindowBuilder::new(&app_handle, &label, WebviewUrl::App(route.into()))
+        .title(&title)
+        .inner_size(1000.0, 700.0)
+        .visible(false)
+        .build();
+    let webview_window = match created {
+        Ok(window) => window,
+        Err(e) => {
+            windows.handoff.lock().unwrap().remove(&label);
+            return Err(e.into());
+        }
+    };
+    if let Some(window) = app_handle.get_window(&label) {
+        if !(request.other_monitor && move_to_other_monitor(&window, &source)) {
+            window_state::restore(&window);
+        }
+        let _ = webview_window.show();
+        window_state::restore_maximized(&window);
+    }
+    let _ = webview_window.set_focus();
+
+    windows.open.lock().unwrap().push(WindowInfo {
+        label: label.clone(),
+        kind: request.kind,
+        key: request.key,
+        title,
+    });
+    info!("Opened window {}", label);
+    Ok(label)
+}
+
+/// The state the calling window was opened with; `None` after the first call.
+#[tauri::command]
+pub fn take_window_state(window: Window, windows: tauri::State<Windows>) -> Option<Value> {
+    windows.handoff.lock().unwrap().remove(window.label())
+}
+
+/// Sends `payload` as `event` to the window labelled `target` only.
+#[tauri::command]
+pub fn send_to_window(
+    app_handle: AppHandle,
+    source: Window,
+    target: String,
+    event: String,
+    payload: Value,
+) -> Result<(), AppError> {
+    if app_handle.get_webview_window(&target).is_none() {
+        return Err(AppError::WindowNotFound(target));
+    }
+    let message = WindowMessage {
+        from: source.label().to_string(),
+        payload,
+    };
+    app_handle.emit_to(target.as_str(), &event, message)?;
+    Ok(())
+}
+
+/// The additional windows currently open.
+#[tauri::command]
+pub fn list_windows(windows: tauri::State<Windows>) -> Vec<WindowInfo> {
+    windows.open.lock().unwrap().clone()
+}
+
+/// Window event hook: forgets closed windows and tells the others.
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    if !matches!(event, WindowEvent::Destroyed) {
+        return;
+    }
+    let app_handle = window.app_handle();
+    let Some(windows) = app_handle.try_state::<Windows>() else {
+        return;
+    };
+    let label = window.label();
+    windows.handoff.lock().unwrap().remove(label);
+    let mut open = windows.open.lock().unwrap();
+    let before = open.len();
+    open.retain(|info| info.label != label);
+    if open.len() != before {
+        let _ = app_handle.emit(WINDOW_CLOSED_EVENT, label);
+    }
+}
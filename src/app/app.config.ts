@@ -6,43 +6,34 @@ import {
   importProvidersFrom
 } from "@angular/core";
 import { provideRouter } from "@angular/router";
-import { provideHttpClient } from '@angular/common/http';
+import { provideHttpClient, withInterceptors } from '@angular/common/http';
 import { provideTranslateService } from '@ngx-translate/core';
 import { provideTranslateHttpLoader } from '@ngx-translate/http-loader';
 import { invoke } from '@tauri-apps/api/core';
-import { API_CONFIG } from './core/config/api.config';
+import { engineInterceptor } from './core/interceptors/engine.interceptor';
 
 import { routes } from "./app.routes";
 
 export async function initializeApp() {
   try {
     if (typeof window !== 'undefined' && (window as any).__TAURI_INTERNALS__) {
-      const port: number = await invoke('get_backend_port');
-      if (port) {
-        API_CONFIG.baseUrl = `http://127.0.0.1:${port}`;
-        console.log(`Backend port dynamically set to ${port}`);
-
-        // Apply saved proxy config to the backend if any
-        const proxyDataStr = localStorage.getItem('ms_analyzer_proxy_config');
-        if (proxyDataStr) {
-          try {
-            const config = JSON.parse(proxyDataStr);
-            if (config.http_proxy || config.https_proxy) {
-              await fetch(`${API_CONFIG.baseUrl}/config/proxy`, {
-                method: 'POST',
-                headers: { 'Content-Type': 'application/json' },
-                body: JSON.stringify(config)
-              });
-              console.log('Applied saved proxy configuration to bio-engine.');
-            }
-          } catch (e) {
-            console.error('Failed to parse or apply proxy on startup', e);
+      // Apply saved proxy config to the backend if any. Goes through the
+      // engine_request command, which carries the engine's token.
+      const proxyDataStr = localStorage.getItem('ms_analyzer_proxy_config');
+      if (proxyDataStr) {
+        try {
+          const config = JSON.parse(proxyDataStr);
+          if (config.http_proxy || config.https_proxy) {
+            await invoke('engine_request', { method: 'POST', path: '/config/proxy', body: config });
+            console.log('Applied saved proxy configuration to bio-engine.');
           }
+        } catch (e) {
+          console.error('Failed to parse or apply proxy on startup', e);
         }
       }
     }
   } catch (e) {
-    console.warn('Could not fetch dynamic backend port. Defaulting to standard config.', e);
+    console.warn('Could not apply startup configuration to the bio-engine.', e);
   }
 }
 
@@ -51,7 +42,7 @@ export const appConfig: ApplicationConfig = {
     provideBrowserGlobalErrorListeners(),
     provideZonelessChangeDetection(),
     provideRouter(routes),
-    provideHttpClient(),
+    provideHttpClient(withInterceptors([engineInterceptor])),
     provideAppInitializer(initializeApp),
     provideTranslateService({
       loader: provideTranslateHttpLoader({
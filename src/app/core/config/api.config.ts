@@ -5,9 +5,11 @@
 export const API_CONFIG = {
     /** 
      * Base URL for the FastAPI server.
-     * In Tauri, it uses the local sidecar. In a web deployment, it uses the /api proxy.
+     * In Tauri, requests to it never leave the app: the engine interceptor hands
+     * them to the `engine_request` command, which adds the engine's token and
+     * knows its transport. In a web deployment, it uses the /api proxy.
      */
     baseUrl: (typeof window !== 'undefined' && (window as any).__TAURI_INTERNALS__) 
-        ? 'http://127.0.0.1:8000' 
+        ? 'http://bio-engine.invalid'
         : '/api'
 };
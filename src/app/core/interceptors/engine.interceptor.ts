@@ -0,0 +1,69 @@
+import {
+  HttpErrorResponse,
+  HttpEvent,
+  HttpInterceptorFn,
+  HttpRequest,
+  HttpResponse
+} from '@angular/common/http';
+import { invoke } from '@tauri-apps/api/core';
+import { Observable, from, map, catchError, throwError } from 'rxjs';
+import { API_CONFIG } from '../config/api.config';
+
+/**
+ * In the desktop app, sends requests for the analysis engine through the
+ * `engine_request` command instead of HTTP. The engine only accepts requests
+ * carrying its per-launch token, which the webview never sees, and may be
+ * listening on a socket or pipe rather than a port.
+ */
+export const engineInterceptor: HttpInterceptorFn = (req, next) => {
+  const isTauri = typeof window !== 'undefined' && (window as any).__TAURI_INTERNALS__;
+  if (!isTauri || !req.url.startsWith(API_CONFIG.baseUrl)) {
+    return next(req);
+  }
+  return proxy(req);
+};
+
+function proxy(req: HttpRequest<unknown>): Observable<HttpEvent<unknown>> {
+  const url = req.urlWithParams;
+  const path = url.slice(API_CONFIG.baseUrl.length) || '/';
+
+  if (req.body instanceof FormData || req.body instanceof Blob) {
+    return throwError(() => new HttpErrorResponse({
+      url,
+      status: 400,
+      statusText: 'Bad Request',
+      error: { message: 'Files are sent to the engine with the upload_file command in the desktop app.' }
+    }));
+  }
+
+  const request = invoke<unknown>('engine_request', {
+    method: req.method,
+    path: path.startsWith('/') ? path : `/${path}`,
+    body: req.body ?? null
+  });
+  return from(request).pipe(
+    map(body => new HttpResponse({ url, status: 200, statusText: 'OK', body: convert(body, req) })),
+    catchError(error => throwError(() => new HttpErrorResponse({
+      url,
+      status: 502,
+      statusText: 'Bad Gateway',
+      error: { message: typeof error === 'string' ? error : String(error) }
+    })))
+  );
+}
+
+/** Fits what the command returned (parsed JSON or raw bytes) to the requested response type. */
+function convert(body: unknown, req: HttpRequest<unknown>): unknown {
+  const bytes = body instanceof ArrayBuffer ? body : null;
+  switch (req.responseType) {
+    case 'blob':
+      return new Blob([bytes ?? JSON.stringify(body)]);
+    case 'arraybuffer':
+      return bytes ?? new TextEncoder().encode(JSON.stringify(body)).buffer;
+    case 'text':
+      if (bytes) return new TextDecoder().decode(bytes);
+      return typeof body === 'string' ? body : JSON.stringify(body);
+    default:
+      return bytes ? JSON.parse(new TextDecoder().decode(bytes)) : body;
+  }
+}